@@ -0,0 +1,13 @@
+def main() -> int:
+    total = 0
+
+    for i in range(10):
+        if i == 7:
+            break
+
+        total = total + i
+
+    for n in (1, 2, 3):
+        total = total - n
+
+    return total
@@ -61,6 +61,79 @@ class bool:
         ...
 
 
+@intrinsic
+class float:
+    """
+    float(x=0) -> float
+
+    Convert a number or string to an integer, or return 0 if no arguments
+    are given.  If x is a number, return x.__float__().
+    """
+
+    def __add__(self, x: float) -> float:
+        ...
+
+    def __sub__(self, x: float) -> float:
+        ...
+
+    def __mul__(self, x: float) -> float:
+        ...
+
+    def __truediv__(self, x: float) -> float:
+        ...
+
+    def __floordiv__(self, x: float) -> float:
+        ...
+
+    def __mod__(self, x: float) -> float:
+        ...
+
+    def __radd__(self, x: float) -> float:
+        ...
+
+    def __rsub__(self, x: float) -> float:
+        ...
+
+    def __rmul__(self, x: float) -> float:
+        ...
+
+    def __rtruediv__(self, x: float) -> float:
+        ...
+
+    def __rfloordiv__(self, x: float) -> float:
+        ...
+
+    def __rmod__(self, x: float) -> float:
+        ...
+
+    def __neg__(self) -> float:
+        ...
+
+    def __pos__(self) -> float:
+        ...
+
+    def __eq__(self, x: float) -> bool:
+        ...
+
+    def __ne__(self, x: float) -> bool:
+        ...
+
+    def __lt__(self, x: float) -> bool:
+        ...
+
+    def __le__(self, x: float) -> bool:
+        ...
+
+    def __gt__(self, x: float) -> bool:
+        ...
+
+    def __ge__(self, x: float) -> bool:
+        ...
+
+    def __bool__(self) -> bool:
+        ...
+
+
 @intrinsic
 class int:
     def __add__(self, x: int) -> int:
@@ -69,24 +142,65 @@ class int:
     def __sub__(self, x: int) -> int:
         ...
 
+    def __mul__(self, x: int) -> int:
+        ...
+
+    def __truediv__(self, x: int) -> float:
+        ...
+
+    def __floordiv__(self, x: int) -> int:
+        ...
+
+    def __mod__(self, x: int) -> int:
+        ...
+
+    def __pow__(self, x: int) -> int:
+        ...
+
+    def __lshift__(self, x: int) -> int:
+        ...
+
+    def __rshift__(self, x: int) -> int:
+        ...
+
+    def __and__(self, x: int) -> int:
+        ...
+
+    def __or__(self, x: int) -> int:
+        ...
+
+    def __xor__(self, x: int) -> int:
+        ...
+
+    def __invert__(self) -> int:
+        ...
+
+    def __neg__(self) -> int:
+        ...
+
+    def __pos__(self) -> int:
+        ...
+
     def __eq__(self, x: int) -> bool:
         ...
 
     def __ne__(self, x: int) -> bool:
         ...
 
-    def __bool__(self) -> bool:
+    def __lt__(self, x: int) -> bool:
         ...
 
+    def __le__(self, x: int) -> bool:
+        ...
 
-@intrinsic
-class float:
-    """
-    float(x=0) -> float
+    def __gt__(self, x: int) -> bool:
+        ...
 
-    Convert a number or string to an integer, or return 0 if no arguments
-    are given.  If x is a number, return x.__float__().
-    """
+    def __ge__(self, x: int) -> bool:
+        ...
+
+    def __bool__(self) -> bool:
+        ...
 
 
 @intrinsic
@@ -104,6 +218,9 @@ class str:
     errors defaults to 'strict'.
     """
 
+    def __contains__(self, x: str) -> bool:
+        ...
+
 
 @intrinsic
 class tuple:
@@ -114,11 +231,170 @@ class tuple:
         ...
 
 
+@intrinsic
+class list:
+    """
+    Built-in mutable sequence.
+
+    If no argument is given, the constructor creates a new empty list.
+    The argument must be an iterable if specified.
+    """
+
+    def append(self, _object: object) -> None:
+        ...
+
+
+@intrinsic
+class dict:
+    """
+    dict() -> new empty dictionary
+    dict(mapping) -> new dictionary initialized from a mapping object's
+        (key, value) pairs
+    """
+
+
+@intrinsic
+class set:
+    """
+    set() -> new empty set object
+    set(iterable) -> new set object
+
+    Build an unordered collection of unique elements.
+    """
+
+    def add(self, _element: object) -> None:
+        ...
+
+
+@intrinsic
+class range:
+    """
+    range(stop) -> range object
+    range(start, stop[, step]) -> range object
+
+    Return an object that produces a sequence of integers from start (inclusive)
+    to stop (exclusive) by step.
+    """
+
+
 @intrinsic
 def id(__obj: object) -> int:
     ...
 
 
+@intrinsic
+def len(__obj: object) -> int:
+    return __obj.__len__()
+
+
 @intrinsic
 def isinstance(__obj: object, __class_or_tuple: type) -> bool:
     ...
+
+
+class BaseException:
+    """Common base class for all exceptions"""
+
+
+class SystemExit(BaseException):
+    """Request to exit from the interpreter."""
+
+
+class KeyboardInterrupt(BaseException):
+    """Program interrupted by user."""
+
+
+class GeneratorExit(BaseException):
+    """Request that a generator exit."""
+
+
+class Exception(BaseException):
+    """Common base class for all non-exit exceptions."""
+
+
+class ArithmeticError(Exception):
+    """Base class for arithmetic errors."""
+
+
+class ZeroDivisionError(ArithmeticError):
+    """Second argument to a division or modulo operation was zero."""
+
+
+class OverflowError(ArithmeticError):
+    """Result too large to be represented."""
+
+
+class AssertionError(Exception):
+    """Assertion failed."""
+
+
+class AttributeError(Exception):
+    """Attribute not found."""
+
+
+class ImportError(Exception):
+    """Import can't find module, or can't find name in module."""
+
+
+class ModuleNotFoundError(ImportError):
+    """Module not found."""
+
+
+class LookupError(Exception):
+    """Base class for lookup errors."""
+
+
+class IndexError(LookupError):
+    """Sequence index out of range."""
+
+
+class KeyError(LookupError):
+    """Mapping key not found."""
+
+
+class MemoryError(Exception):
+    """Out of memory."""
+
+
+class NameError(Exception):
+    """Name not found globally."""
+
+
+class UnboundLocalError(NameError):
+    """Local name referenced but not bound to a value."""
+
+
+class OSError(Exception):
+    """Base class for I/O related errors."""
+
+
+class RuntimeError(Exception):
+    """Unspecified run-time error."""
+
+
+class NotImplementedError(RuntimeError):
+    """Method or function hasn't been implemented yet."""
+
+
+class RecursionError(RuntimeError):
+    """Recursion limit exceeded."""
+
+
+class StopIteration(Exception):
+    """Signal the end from iterator.__next__()."""
+
+
+class SystemError(Exception):
+    """Internal error in the Python interpreter.
+
+    Please report this to the Python maintainer, along with the traceback,
+    the Python version, and the hardware/OS platform and version.
+    """
+
+
+class TypeError(Exception):
+    """Inappropriate argument type."""
+
+
+class ValueError(Exception):
+    """Inappropriate argument value (of correct type)."""
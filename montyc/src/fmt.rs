@@ -0,0 +1,70 @@
+//! The `fmt` subcommand, formats source files with `montyc_parser::unparse`.
+
+use std::path::PathBuf;
+
+use montyc_core::diagnostic::DiagnosticSpan;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct FmtOptions {
+    /// Don't write anything, list the files that are not formatted and fail if there are any.
+    #[structopt(long)]
+    check: bool,
+
+    /// The files to format.
+    #[structopt(parse(from_os_str), required = true)]
+    files: Vec<PathBuf>,
+}
+
+/// Format every file, returning whether all of them were (or with `--check` already are) formatted.
+pub fn run(opts: &FmtOptions) -> anyhow::Result<bool> {
+    let mut success = true;
+
+    for path in opts.files.iter() {
+        let source = std::fs::read_to_string(path)?;
+
+        let formatted = match montyc_parser::unparse::format(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                for error in errors {
+                    let span = DiagnosticSpan::new(
+                        path.display().to_string(),
+                        &source,
+                        error.span.clone(),
+                        true,
+                        None,
+                    );
+
+                    let (line, column) = span.start;
+
+                    eprintln!(
+                        "error: {}:{}:{}: {}",
+                        span.file, line, column, error.message
+                    );
+
+                    match error.expected.as_slice() {
+                        [] => (),
+                        [expected] => eprintln!("  expected {}", expected),
+                        expected => eprintln!("  expected one of {}", expected.join(", ")),
+                    }
+                }
+
+                success = false;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+
+        if opts.check {
+            println!("{}", path.display());
+            success = false;
+        } else {
+            std::fs::write(path, formatted)?;
+        }
+    }
+
+    Ok(success)
+}
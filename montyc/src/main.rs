@@ -1,40 +1,93 @@
+use montyc_core::diagnostic::Diagnostic;
+use montyc_core::opts::MessageFormat;
+use montyc_core::MontyError;
 use montyc_driver::prelude::{CompilerOptions, SessionContext};
 
 use structopt::StructOpt;
 
+mod fmt;
+
+// parsed once from the arguments, so the size of the options doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, StructOpt)]
+enum Command {
+    #[structopt(flatten)]
+    Compiler(CompilerOptions),
+
+    /// Format the provided source files in place.
+    Fmt(fmt::FmtOptions),
+}
+
 fn main() -> anyhow::Result<()> {
     env_logger::init();
 
-    let opts = CompilerOptions::from_args();
+    let opts = match Command::from_args() {
+        Command::Compiler(opts) => opts,
+        Command::Fmt(opts) => match fmt::run(&opts)? {
+            true => return Ok(()),
+            false => std::process::exit(1),
+        },
+    };
+
+    let message_format = opts.message_format();
+
     let opts_verified = match opts.clone().verify() {
         Ok(i) => i,
         Err(mut errors) => {
             for err in errors.drain(..) {
-                eprintln!("error: {}", err);
+                match message_format {
+                    MessageFormat::Human => eprintln!("error: {}", err),
+                    MessageFormat::Json => {
+                        eprintln!("{}", Diagnostic::error("E0300", err).to_json())
+                    }
+                }
             }
 
             std::process::exit(1);
         }
     };
 
+    if opts_verified.print_search_path() {
+        for root in opts_verified.search_path() {
+            println!("{}\t({})", root.path.display(), root.origin);
+        }
+
+        return Ok(());
+    }
+
+    let report = |cx: &SessionContext, err: &MontyError| match message_format {
+        MessageFormat::Human => eprint!("{}", cx.fmt_error(err)),
+        MessageFormat::Json => {
+            for diagnostic in cx.diagnostics_of(err) {
+                eprintln!("{}", diagnostic.to_json());
+            }
+        }
+    };
+
     let gcx = match SessionContext::initialize(&opts_verified) {
         Ok(cx) => cx,
 
         Err((cx, err)) => {
-            // TODO: cx.fmt_error(err)
-            eprintln!("error: {}", err);
+            report(&cx, &err);
             std::process::exit(1)
         }
     };
 
-    match opts {
-        CompilerOptions::Check { input, .. } => {
-            gcx.include_module(input, "__main__")?;
-        }
+    let result = match opts {
+        CompilerOptions::Check { input, .. } => gcx
+            .include_module(input, "__main__")
+            .and_then(|(mref, _)| gcx.typecheck_module(mref))
+            .and_then(|errors| match errors.as_slice() {
+                [] => Ok(()),
+                _ => Err(MontyError::TypeErrors(errors)),
+            }),
 
-        CompilerOptions::Build { .. } => {
-            montyc_codegen::compile(&opts, &gcx)?;
-        }
+        CompilerOptions::Build { .. } => montyc_codegen::compile(&opts, &gcx).map(|_| ()),
+    };
+
+    if let Err(err) = result {
+        report(&gcx, &err);
+        std::process::exit(1);
     }
 
     Ok(())
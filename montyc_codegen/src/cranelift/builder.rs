@@ -7,15 +7,15 @@ use cranelift_codegen::ir::{self, ExternalName};
 use cranelift_codegen::ir::{
     FuncRef, InstBuilder, MemFlags, StackSlot, StackSlotData, StackSlotKind,
 };
-use cranelift_codegen::isa::TargetIsa;
+use cranelift_codegen::isa::{CallConv, TargetIsa};
 
 use cranelift_frontend::FunctionBuilder;
-use cranelift_module::{DataContext, FuncId, Module};
+use cranelift_module::{DataContext, FuncId, Module, ModuleError};
 use cranelift_object::ObjectModule;
 
 use montyc_core::ast::Constant;
 use montyc_core::codegen::{CgBlockId, CgInst, Field};
-use montyc_core::{patma, MapT, PythonType, TypeId, TypingConstants, ValueId};
+use montyc_core::{patma, MapT, PythonType, SpanRef, TypeId, TypingConstants, ValueId};
 
 use montyc_query::Queries;
 
@@ -26,6 +26,14 @@ use petgraph::EdgeDirection;
 use crate::cranelift::ir_type_of;
 use crate::tvalue::TValue;
 
+/// Lists, dicts and sets are a heap allocated `{ len: i64, capacity: i64, data: *mut u8 }` header
+/// pointing at a separately allocated buffer of `capacity` slots, the buffer is doubled when it's full.
+const COLLECTION_LEN: i32 = 0;
+const COLLECTION_CAPACITY: i32 = 8;
+const COLLECTION_DATA: i32 = 16;
+const COLLECTION_HEADER_SIZE: i64 = 24;
+const COLLECTION_INITIAL_CAPACITY: i64 = 8;
+
 trait Allocatable {
     fn alloc_uninit(&self, fx: &mut Builder) -> ir::Value;
 
@@ -36,6 +44,10 @@ trait Allocatable {
 
 impl Allocatable for TypeId {
     fn alloc_uninit(&self, fx: &mut Builder) -> ir::Value {
+        if let Some(stride) = fx.collection_stride(*self) {
+            return fx.collection_new(stride);
+        }
+
         let tcx = fx.host.tcx();
 
         let layout = match tcx.get_python_type_of(*self).unwrap() {
@@ -54,7 +66,9 @@ impl Allocatable for TypeId {
                 layout
             }
 
-            PythonType::List { inner } => todo!(),
+            PythonType::List { .. } | PythonType::Dict { .. } | PythonType::Set { .. } => {
+                unreachable!()
+            }
             PythonType::Union { members } => todo!(),
             PythonType::Type { of } => todo!(),
             PythonType::Instance { of: value } => fx.host.get_layout_of(value).unwrap(),
@@ -104,7 +118,26 @@ impl Allocatable for TypeId {
                         .store(MemFlags::new(), *elem.as_value(), addr, offset);
                 }
             }
-            PythonType::List { inner } => todo!(),
+            PythonType::List { .. } => {
+                for elem in values {
+                    fx.collection_push(addr, 8, &[*elem.as_value()]);
+                }
+            }
+            PythonType::Set { .. } => {
+                for elem in values {
+                    fx.collection_insert(addr, 8, *elem.as_value(), None);
+                }
+            }
+            PythonType::Dict { .. } => {
+                let values = values
+                    .into_iter()
+                    .map(|elem| *elem.as_value())
+                    .collect::<Vec<_>>();
+
+                for entry in values.chunks(2) {
+                    fx.collection_insert(addr, 16, entry[0], Some(entry[1]));
+                }
+            }
             PythonType::Union { members } => todo!(),
             PythonType::Type { of } => todo!(),
             PythonType::Instance { of } => {}
@@ -132,13 +165,59 @@ fn stack_alloc(builder: &mut FunctionBuilder, queries: &dyn Queries, tid: TypeId
     builder.create_stack_slot(StackSlotData::new(StackSlotKind::ExplicitSlot, slot_size))
 }
 
+/// Trap raised by `range(start, stop, step)` when `step` is zero.
+pub(crate) const RANGE_STEP_ZERO: ir::TrapCode = ir::TrapCode::User(3);
+
+/// The lowered state of a live `for` loop iterator.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum IterState {
+    /// A `range(start, stop, step)` whose next value is kept in `cursor`.
+    Range {
+        cursor: StackSlot,
+        stop: ir::Value,
+        step: ir::Value,
+    },
+
+    /// The elements of a homogeneous tuple at `base`, the index of the next element is kept in `cursor`.
+    Tuple {
+        cursor: StackSlot,
+        base: ir::Value,
+        len: i64,
+        stride: i64,
+        elem_t: TypeId,
+        elem_ir_t: ir::Type,
+    },
+
+    /// The elements of a list or set, or the keys of a dict, at `header`; the index of the next slot is kept in `cursor`.
+    Collection {
+        cursor: StackSlot,
+        header: ir::Value,
+        stride: i64,
+        elem_t: TypeId,
+    },
+}
+
+/// Where the cell of a variable captured by a closure is, cells are heap allocated so they're shared by reference.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Cell {
+    /// A local variable of this function, the address of its cell is kept in the stack slot.
+    Local(StackSlot),
+
+    /// A variable captured by this function, the address of its cell is at the offset into the closure environment.
+    Captured(i32),
+}
+
 pub(super) struct Builder<'a, 'b> {
     pub inner: FunctionBuilder<'a>,
     pub cfg: &'b montyc_core::codegen::CgBlockCFG,
 
     pub values: MapT<usize, TValue<ir::Value>>,
     pub locals: MapT<u32, (StackSlot, TypeId, ir::Type)>,
+    pub cells: MapT<u32, (Cell, TypeId)>,
+    /// The environment of the closure being built, `{ func: *const u8, cells: [*mut u8] }`.
+    pub env: Option<ir::Value>,
     pub f_refs: MapT<ValueId, FuncRef>,
+    pub iters: MapT<usize, IterState>,
 
     pub host: &'a dyn Queries,
     pub alloc: ExternalName,
@@ -147,6 +226,13 @@ pub(super) struct Builder<'a, 'b> {
 
 impl Builder<'_, '_> {
     pub fn heap_alloc(&mut self, size: i64) -> ir::Inst {
+        let size = self.inner.ins().iconst(ir::types::I64, size);
+
+        self.heap_alloc_dyn(size)
+    }
+
+    /// Like `heap_alloc` but for a size that is only known at runtime.
+    pub fn heap_alloc_dyn(&mut self, size: ir::Value) -> ir::Inst {
         let pointer_type = self.cg_settings.pointer_type();
         let signature = self.inner.import_signature(signature!(
             self.cg_settings.default_call_conv(),
@@ -160,15 +246,337 @@ impl Builder<'_, '_> {
             colocated: false,
         });
 
-        let size = self.inner.ins().iconst(ir::types::I64, size);
-
         self.ins().call(alloc, &[size])
     }
 
+    /// The size of the slots holding the elements of a list or set, or the entries of a dict.
+    pub fn collection_stride(&self, tid: TypeId) -> Option<i64> {
+        match self.host.tcx().get_python_type_of(tid)? {
+            PythonType::List { .. } | PythonType::Set { .. } => Some(8),
+            PythonType::Dict { .. } => Some(16),
+            _ => None,
+        }
+    }
+
+    /// Allocate the header and buffer of an empty list, dict or set.
+    pub fn collection_new(&mut self, stride: i64) -> ir::Value {
+        let header = self.heap_alloc(COLLECTION_HEADER_SIZE);
+        let header = self.inner.inst_results(header)[0];
+
+        let data = self.heap_alloc(COLLECTION_INITIAL_CAPACITY * stride);
+        let data = self.inner.inst_results(data)[0];
+
+        let len = self.inner.ins().iconst(ir::types::I64, 0);
+        let capacity = self
+            .inner
+            .ins()
+            .iconst(ir::types::I64, COLLECTION_INITIAL_CAPACITY);
+
+        let flags = MemFlags::new();
+
+        self.inner.ins().store(flags, len, header, COLLECTION_LEN);
+        self.inner
+            .ins()
+            .store(flags, capacity, header, COLLECTION_CAPACITY);
+        self.inner.ins().store(flags, data, header, COLLECTION_DATA);
+
+        header
+    }
+
+    /// Append a slot made of the words in `slot` to the collection at `header`, growing its buffer if it's full.
+    pub fn collection_push(&mut self, header: ir::Value, stride: i64, slot: &[ir::Value]) {
+        let flags = MemFlags::new();
+
+        let len = self
+            .inner
+            .ins()
+            .load(ir::types::I64, flags, header, COLLECTION_LEN);
+        let capacity = self
+            .inner
+            .ins()
+            .load(ir::types::I64, flags, header, COLLECTION_CAPACITY);
+
+        let grow_block = self.inner.create_block();
+        let store_block = self.inner.create_block();
+
+        let full = self
+            .inner
+            .ins()
+            .icmp(IntCC::SignedGreaterThanOrEqual, len, capacity);
+
+        self.inner.ins().brnz(full, grow_block, &[]);
+        self.inner.ins().jump(store_block, &[]);
+        self.inner.switch_to_block(grow_block);
+
+        let data = self
+            .inner
+            .ins()
+            .load(ir::types::I64, flags, header, COLLECTION_DATA);
+        let capacity = self.inner.ins().imul_imm(capacity, 2);
+        let size = self.inner.ins().imul_imm(capacity, stride);
+
+        let grown = self.heap_alloc_dyn(size);
+        let grown = self.inner.inst_results(grown)[0];
+
+        let used = self.inner.ins().imul_imm(len, stride);
+        let config = self.cg_settings.frontend_config();
+
+        self.inner.call_memcpy(config, grown, data, used);
+        self.inner
+            .ins()
+            .store(flags, capacity, header, COLLECTION_CAPACITY);
+        self.inner
+            .ins()
+            .store(flags, grown, header, COLLECTION_DATA);
+        self.inner.ins().jump(store_block, &[]);
+        self.inner.switch_to_block(store_block);
+
+        let data = self
+            .inner
+            .ins()
+            .load(ir::types::I64, flags, header, COLLECTION_DATA);
+        let offset = self.inner.ins().imul_imm(len, stride);
+        let addr = self.inner.ins().iadd(data, offset);
+
+        for (ix, word) in slot.iter().enumerate() {
+            let word = self.bool_as_i64(*word);
+            let offset = i32::try_from(ix * 8).unwrap();
+
+            self.inner.ins().store(flags, word, addr, offset);
+        }
+
+        let len = self.inner.ins().iadd_imm(len, 1);
+        self.inner.ins().store(flags, len, header, COLLECTION_LEN);
+    }
+
+    /// The index of the slot whose first word is `key` in the collection at `header`, or `-1` if there isn't one.
+    pub fn collection_find(&mut self, header: ir::Value, stride: i64, key: ir::Value) -> ir::Value {
+        let flags = MemFlags::new();
+        let key = self.bool_as_i64(key);
+
+        let len = self
+            .inner
+            .ins()
+            .load(ir::types::I64, flags, header, COLLECTION_LEN);
+        let data = self
+            .inner
+            .ins()
+            .load(ir::types::I64, flags, header, COLLECTION_DATA);
+
+        let head_block = self.inner.create_block();
+        let body_block = self.inner.create_block();
+        let exit_block = self.inner.create_block();
+
+        let cursor = self.inner.append_block_param(head_block, ir::types::I64);
+        let found = self.inner.append_block_param(exit_block, ir::types::I64);
+
+        let zero = self.inner.ins().iconst(ir::types::I64, 0);
+        self.inner.ins().jump(head_block, &[zero]);
+        self.inner.switch_to_block(head_block);
+
+        let done = self
+            .inner
+            .ins()
+            .icmp(IntCC::SignedGreaterThanOrEqual, cursor, len);
+        let missing = self.inner.ins().iconst(ir::types::I64, -1);
+
+        self.inner.ins().brnz(done, exit_block, &[missing]);
+        self.inner.ins().jump(body_block, &[]);
+        self.inner.switch_to_block(body_block);
+
+        let offset = self.inner.ins().imul_imm(cursor, stride);
+        let addr = self.inner.ins().iadd(data, offset);
+        let candidate = self.inner.ins().load(ir::types::I64, flags, addr, 0);
+        let matches = self.inner.ins().icmp(IntCC::Equal, candidate, key);
+
+        self.inner.ins().brnz(matches, exit_block, &[cursor]);
+
+        let next = self.inner.ins().iadd_imm(cursor, 1);
+        self.inner.ins().jump(head_block, &[next]);
+        self.inner.switch_to_block(exit_block);
+
+        found
+    }
+
+    /// Insert `key` into a set, or `key: value` into a dict, replacing the value of a dict entry if `key` is already present.
+    pub fn collection_insert(
+        &mut self,
+        header: ir::Value,
+        stride: i64,
+        key: ir::Value,
+        value: Option<ir::Value>,
+    ) {
+        let index = self.collection_find(header, stride, key);
+
+        let insert_block = self.inner.create_block();
+        let present_block = self.inner.create_block();
+        let after_block = self.inner.create_block();
+
+        let missing = self.inner.ins().icmp_imm(IntCC::SignedLessThan, index, 0);
+
+        self.inner.ins().brnz(missing, insert_block, &[]);
+        self.inner.ins().jump(present_block, &[]);
+        self.inner.switch_to_block(present_block);
+
+        if let Some(value) = value {
+            let value = self.bool_as_i64(value);
+            let data =
+                self.inner
+                    .ins()
+                    .load(ir::types::I64, MemFlags::new(), header, COLLECTION_DATA);
+            let offset = self.inner.ins().imul_imm(index, stride);
+            let addr = self.inner.ins().iadd(data, offset);
+
+            self.inner.ins().store(MemFlags::new(), value, addr, 8);
+        }
+
+        self.inner.ins().jump(after_block, &[]);
+        self.inner.switch_to_block(insert_block);
+
+        match value {
+            Some(value) => self.collection_push(header, stride, &[key, value]),
+            None => self.collection_push(header, stride, &[key]),
+        }
+
+        self.inner.ins().jump(after_block, &[]);
+        self.inner.switch_to_block(after_block);
+    }
+
+    /// Set up the cells of the variables captured by this function and by the closures it creates.
+    pub fn init_cells(&mut self, captures: Vec<(SpanRef, TypeId)>) {
+        for (ix, (var, type_id)) in captures.into_iter().enumerate() {
+            let offset = i32::try_from((ix + 1) * 8).unwrap();
+
+            self.cells
+                .insert(var.group(), (Cell::Captured(offset), type_id));
+        }
+
+        let captured = self
+            .cfg
+            .raw_nodes()
+            .iter()
+            .flat_map(|node| node.weight.iter())
+            .filter_map(|inst| match inst {
+                CgInst::MakeClosure { captures, .. } => Some(captures.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+
+        for (var, type_id) in captured {
+            if self.cells.contains_key(&var.group()) {
+                continue;
+            }
+
+            let cell = self.heap_alloc(8);
+            let cell = self.inner.inst_results(cell)[0];
+
+            let ss = self.stack_alloc(TypingConstants::Int);
+            self.inner.ins().stack_store(cell, ss, 0);
+
+            self.cells.insert(var.group(), (Cell::Local(ss), type_id));
+        }
+    }
+
+    /// The address of the cell of `var` and the type of its value, if the variable is captured.
+    pub fn cell_addr(&mut self, var: u32) -> Option<(ir::Value, TypeId)> {
+        let (cell, type_id) = *self.cells.get(&var)?;
+
+        let addr = match cell {
+            Cell::Local(ss) => self.inner.ins().stack_load(ir::types::I64, ss, 0),
+            Cell::Captured(offset) => {
+                let env = self
+                    .env
+                    .expect("captured variables are only read by closures.");
+
+                self.inner
+                    .ins()
+                    .load(ir::types::I64, MemFlags::new(), env, offset)
+            }
+        };
+
+        Some((addr, type_id))
+    }
+
+    fn func_ref(
+        &mut self,
+        module: &mut ObjectModule,
+        fids: &MapT<ValueId, FuncId>,
+        value: ValueId,
+    ) -> FuncRef {
+        match self.f_refs.get(&value) {
+            Some(f_ref) => *f_ref,
+            None => {
+                let fid = fids[&value];
+                let f_ref = module.declare_func_in_func(fid, self.inner.func);
+                self.f_refs.insert(value, f_ref);
+                f_ref
+            }
+        }
+    }
+
+    /// The address of the static closure of a function that captures nothing, a closure environment with no cells.
+    fn static_closure(
+        &mut self,
+        module: &mut ObjectModule,
+        fids: &MapT<ValueId, FuncId>,
+        value: ValueId,
+    ) -> ir::Value {
+        let data_id = module
+            .declare_data(
+                &format!("closure,{}", value.0),
+                cranelift_module::Linkage::Local,
+                false,
+                false,
+            )
+            .unwrap();
+
+        let mut dcx = DataContext::new();
+        dcx.define_zeroinit(8);
+
+        let f_ref = module.declare_func_in_data(fids[&value], &mut dcx);
+        dcx.write_function_addr(0, f_ref);
+
+        // every function referring to `value` declares the same data but it can only be defined once.
+        match module.define_data(data_id, &mut dcx) {
+            Ok(()) | Err(ModuleError::DuplicateDefinition(_)) => (),
+            Err(err) => panic!("{}", err),
+        }
+
+        let closure_gv = module.declare_data_in_func(data_id, self.inner.func);
+
+        self.inner.ins().global_value(ir::types::I64, closure_gv)
+    }
+
     pub fn stack_alloc(&mut self, tid: TypeId) -> StackSlot {
         stack_alloc(&mut self.inner, self.host, tid)
     }
 
+    /// Widen a `b1` produced by an intrinsic (i.e. `int.__lt__`) into the `i64` that bools are represented as.
+    pub fn bool_as_i64(&mut self, value: ir::Value) -> ir::Value {
+        if self.inner.func.dfg.value_type(value) == ir::types::B1 {
+            self.inner.ins().bint(ir::types::I64, value)
+        } else {
+            value
+        }
+    }
+
+    /// Coerce an argument to the type of the parameter it's passed as.
+    ///
+    /// Ints are promoted where a float is expected, and floats passed as `self`
+    /// to an intrinsic are passed as their raw bits.
+    pub fn coerce_arg(&mut self, value: ir::Value, param_t: ir::Type) -> ir::Value {
+        match (self.inner.func.dfg.value_type(value), param_t) {
+            (ir::types::I64, ir::types::F64) => {
+                self.inner.ins().fcvt_from_sint(ir::types::F64, value)
+            }
+
+            (ir::types::F64, ir::types::I64) => self.inner.ins().bitcast(ir::types::I64, value),
+
+            _ => value,
+        }
+    }
+
     pub fn lower(
         mut self,
         blocks: MapT<CgBlockId, ir::Block>,
@@ -262,10 +670,347 @@ impl Builder<'_, '_> {
                         }
                     }
 
+                    CgInst::RangeIter {
+                        start,
+                        stop,
+                        step,
+                        ret,
+                    } => {
+                        let start = match start {
+                            Some(start) => *self.values[start].as_value(),
+                            None => self.inner.ins().iconst(ir::types::I64, 0),
+                        };
+
+                        let step = match step {
+                            Some(step) => {
+                                let step = *self.values[step].as_value();
+
+                                self.inner.ins().trapz(step, RANGE_STEP_ZERO);
+
+                                step
+                            }
+
+                            None => self.inner.ins().iconst(ir::types::I64, 1),
+                        };
+
+                        let stop = *self.values[stop].as_value();
+                        let cursor = self.stack_alloc(TypingConstants::Int);
+
+                        self.inner.ins().stack_store(start, cursor, 0);
+                        self.iters
+                            .insert(*ret, IterState::Range { cursor, stop, step });
+                    }
+
+                    CgInst::TupleIter { orig, orig_t, ret } => {
+                        let members = match self.host.tcx().get_python_type_of(*orig_t).unwrap() {
+                            PythonType::Tuple {
+                                members: Some(members),
+                            } => members,
+                            _ => {
+                                unreachable!("only tuples with known members can be iterated over.")
+                            }
+                        };
+
+                        assert!(
+                            !members.contains(&TypingConstants::Unknown),
+                            "can not iterate over a tuple with unknown members."
+                        );
+
+                        let (_, offsets) = {
+                            let tcx = self.host.tcx();
+
+                            montyc_core::calculate_layout(
+                                &mut members.iter().map(|tid| tcx.layout_of(*tid)),
+                            )
+                        };
+
+                        let stride = match (offsets.as_slice(), members.first()) {
+                            ([first, second, ..], _) => i64::from(second - first),
+                            (_, Some(elem_t)) => {
+                                i64::try_from(self.host.tcx().layout_of(*elem_t).size()).unwrap()
+                            }
+                            (_, None) => 0,
+                        };
+
+                        // an empty tuple is exhausted straight away so its elements are never loaded.
+                        let elem_t = members.first().cloned().unwrap_or(TypingConstants::Never);
+                        let elem_ir_t = match members.first() {
+                            Some(elem_t) => ir_type_of(self.host.tcx(), *elem_t),
+                            None => ir::types::I64,
+                        };
+
+                        let cursor = self.stack_alloc(TypingConstants::Int);
+                        let zero = self.inner.ins().iconst(ir::types::I64, 0);
+
+                        self.inner.ins().stack_store(zero, cursor, 0);
+                        self.iters.insert(
+                            *ret,
+                            IterState::Tuple {
+                                cursor,
+                                base: *self.values[orig].as_value(),
+                                len: i64::try_from(members.len()).unwrap(),
+                                stride,
+                                elem_t,
+                                elem_ir_t,
+                            },
+                        );
+                    }
+
+                    CgInst::CollectionIter { orig, orig_t, ret } => {
+                        let elem_t = match self.host.tcx().get_python_type_of(*orig_t).unwrap() {
+                            PythonType::List { inner }
+                            | PythonType::Set { inner }
+                            | PythonType::Dict { key: inner, .. } => inner,
+                            _ => unreachable!(),
+                        };
+
+                        let cursor = self.stack_alloc(TypingConstants::Int);
+                        let zero = self.inner.ins().iconst(ir::types::I64, 0);
+
+                        self.inner.ins().stack_store(zero, cursor, 0);
+                        self.iters.insert(
+                            *ret,
+                            IterState::Collection {
+                                cursor,
+                                header: *self.values[orig].as_value(),
+                                stride: self.collection_stride(*orig_t).unwrap(),
+                                elem_t,
+                            },
+                        );
+                    }
+
+                    CgInst::ListAppend { list, value } => {
+                        let header = *self.values[list].as_value();
+                        let value = *self.values[value].as_value();
+
+                        self.collection_push(header, 8, &[value]);
+                    }
+
+                    CgInst::SetAdd { set, value } => {
+                        let header = *self.values[set].as_value();
+                        let value = *self.values[value].as_value();
+
+                        self.collection_insert(header, 8, value, None);
+                    }
+
+                    CgInst::DictInsert { dict, key, value } => {
+                        let header = *self.values[dict].as_value();
+                        let key = *self.values[key].as_value();
+                        let value = *self.values[value].as_value();
+
+                        self.collection_insert(header, 16, key, Some(value));
+                    }
+
+                    CgInst::Len { orig, ret, .. } => {
+                        let header = *self.values[orig].as_value();
+                        let len = self.inner.ins().load(
+                            ir::types::I64,
+                            MemFlags::new(),
+                            header,
+                            COLLECTION_LEN,
+                        );
+
+                        self.values
+                            .insert(*ret, TValue::imm(len, TypingConstants::Int));
+                    }
+
+                    CgInst::GetItem {
+                        orig,
+                        orig_t,
+                        index,
+                        ret,
+                    } => {
+                        let header = *self.values[orig].as_value();
+                        let index = self.bool_as_i64(*self.values[index].as_value());
+                        let flags = MemFlags::new();
+
+                        let (addr, elem_t) =
+                            match self.host.tcx().get_python_type_of(*orig_t).unwrap() {
+                                PythonType::List { inner } => {
+                                    let len = self.inner.ins().load(
+                                        ir::types::I64,
+                                        flags,
+                                        header,
+                                        COLLECTION_LEN,
+                                    );
+
+                                    // negative indices count from the end of the list.
+                                    let negative =
+                                        self.inner.ins().icmp_imm(IntCC::SignedLessThan, index, 0);
+                                    let wrapped = self.inner.ins().iadd(index, len);
+                                    let index = self.inner.ins().select(negative, wrapped, index);
+
+                                    // `IndexError`, compared unsigned so indices that are still negative are out of range too.
+                                    let out_of_range = self.inner.ins().icmp(
+                                        IntCC::UnsignedGreaterThanOrEqual,
+                                        index,
+                                        len,
+                                    );
+
+                                    self.inner.ins().trapnz(out_of_range, ir::TrapCode::User(0));
+
+                                    let data = self.inner.ins().load(
+                                        ir::types::I64,
+                                        flags,
+                                        header,
+                                        COLLECTION_DATA,
+                                    );
+                                    let offset = self.inner.ins().imul_imm(index, 8);
+
+                                    (self.inner.ins().iadd(data, offset), inner)
+                                }
+
+                                PythonType::Dict { value, .. } => {
+                                    let found = self.collection_find(header, 16, index);
+
+                                    // `KeyError`
+                                    let missing =
+                                        self.inner.ins().icmp_imm(IntCC::SignedLessThan, found, 0);
+
+                                    self.inner.ins().trapnz(missing, ir::TrapCode::User(0));
+
+                                    let data = self.inner.ins().load(
+                                        ir::types::I64,
+                                        flags,
+                                        header,
+                                        COLLECTION_DATA,
+                                    );
+                                    let offset = self.inner.ins().imul_imm(found, 16);
+                                    let entry = self.inner.ins().iadd(data, offset);
+
+                                    (self.inner.ins().iadd_imm(entry, 8), value)
+                                }
+
+                                _ => unreachable!(),
+                            };
+
+                        let elem_ir_t = ir_type_of(self.host.tcx(), elem_t);
+                        let elem = self.inner.ins().load(elem_ir_t, flags, addr, 0);
+
+                        self.values.insert(*ret, TValue::imm(elem, elem_t));
+                    }
+
+                    CgInst::IterNext {
+                        iter,
+                        exhausted,
+                        ret,
+                    } => {
+                        let state = self.iters[iter];
+                        let next_block = self.inner.create_block();
+
+                        match state {
+                            IterState::Range { cursor, stop, step } => {
+                                let current =
+                                    self.inner.ins().stack_load(ir::types::I64, cursor, 0);
+
+                                let ascending =
+                                    self.inner.ins().icmp_imm(IntCC::SignedGreaterThan, step, 0);
+                                let above = self.inner.ins().icmp(
+                                    IntCC::SignedGreaterThanOrEqual,
+                                    current,
+                                    stop,
+                                );
+                                let below = self.inner.ins().icmp(
+                                    IntCC::SignedLessThanOrEqual,
+                                    current,
+                                    stop,
+                                );
+                                let done = self.inner.ins().select(ascending, above, below);
+
+                                self.inner.ins().brnz(done, blocks[exhausted], &[]);
+                                self.inner.ins().jump(next_block, &[]);
+                                self.inner.switch_to_block(next_block);
+
+                                let next = self.inner.ins().iadd(current, step);
+                                self.inner.ins().stack_store(next, cursor, 0);
+
+                                self.values
+                                    .insert(*ret, TValue::imm(current, TypingConstants::Int));
+                            }
+
+                            IterState::Tuple {
+                                cursor,
+                                base,
+                                len,
+                                stride,
+                                elem_t,
+                                elem_ir_t,
+                            } => {
+                                let index = self.inner.ins().stack_load(ir::types::I64, cursor, 0);
+                                let done = self.inner.ins().icmp_imm(
+                                    IntCC::SignedGreaterThanOrEqual,
+                                    index,
+                                    len,
+                                );
+
+                                self.inner.ins().brnz(done, blocks[exhausted], &[]);
+                                self.inner.ins().jump(next_block, &[]);
+                                self.inner.switch_to_block(next_block);
+
+                                let offset = self.inner.ins().imul_imm(index, stride);
+                                let addr = self.inner.ins().iadd(base, offset);
+                                let elem =
+                                    self.inner.ins().load(elem_ir_t, MemFlags::new(), addr, 0);
+
+                                let next = self.inner.ins().iadd_imm(index, 1);
+                                self.inner.ins().stack_store(next, cursor, 0);
+
+                                self.values.insert(*ret, TValue::imm(elem, elem_t));
+                            }
+
+                            IterState::Collection {
+                                cursor,
+                                header,
+                                stride,
+                                elem_t,
+                            } => {
+                                let index = self.inner.ins().stack_load(ir::types::I64, cursor, 0);
+
+                                // the length is reloaded as the collection may grow while it's iterated over.
+                                let len = self.inner.ins().load(
+                                    ir::types::I64,
+                                    MemFlags::new(),
+                                    header,
+                                    COLLECTION_LEN,
+                                );
+                                let done = self.inner.ins().icmp(
+                                    IntCC::SignedGreaterThanOrEqual,
+                                    index,
+                                    len,
+                                );
+
+                                self.inner.ins().brnz(done, blocks[exhausted], &[]);
+                                self.inner.ins().jump(next_block, &[]);
+                                self.inner.switch_to_block(next_block);
+
+                                let data = self.inner.ins().load(
+                                    ir::types::I64,
+                                    MemFlags::new(),
+                                    header,
+                                    COLLECTION_DATA,
+                                );
+                                let offset = self.inner.ins().imul_imm(index, stride);
+                                let addr = self.inner.ins().iadd(data, offset);
+
+                                let elem_ir_t = ir_type_of(self.host.tcx(), elem_t);
+                                let elem =
+                                    self.inner.ins().load(elem_ir_t, MemFlags::new(), addr, 0);
+
+                                let next = self.inner.ins().iadd_imm(index, 1);
+                                self.inner.ins().stack_store(next, cursor, 0);
+
+                                self.values.insert(*ret, TValue::imm(elem, elem_t));
+                            }
+                        }
+                    }
+
                     CgInst::Jump { to, with } => {
                         let args = with
                             .into_iter()
-                            .map(|v| self.values[&v].clone().as_value().clone())
+                            .map(|v| {
+                                let value = self.values[&v].clone().as_value().clone();
+                                self.bool_as_i64(value)
+                            })
                             .collect::<Vec<_>>();
                         self.inner.ins().jump(blocks[&to], args.as_slice());
                         break;
@@ -273,57 +1018,133 @@ impl Builder<'_, '_> {
 
                     CgInst::JumpIfTrue { to, ctrl } => {
                         let one = self.inner.ins().iconst(ir::types::I64, 1);
-                        let ctrl = self.values[ctrl].as_value();
+                        let ctrl = self.bool_as_i64(*self.values[ctrl].as_value());
 
                         self.inner
                             .ins()
-                            .br_icmp(IntCC::Equal, *ctrl, one, blocks[to], &[]);
+                            .br_icmp(IntCC::Equal, ctrl, one, blocks[to], &[]);
                     }
 
                     CgInst::JumpIfFalse { to, ctrl } => {
                         let one = self.inner.ins().iconst(ir::types::I64, 0);
-                        let ctrl = self.values[ctrl].as_value();
+                        let ctrl = self.bool_as_i64(*self.values[ctrl].as_value());
 
                         self.inner
                             .ins()
-                            .br_icmp(IntCC::Equal, *ctrl, one, blocks[to], &[]);
+                            .br_icmp(IntCC::Equal, ctrl, one, blocks[to], &[]);
                     }
 
                     CgInst::Use { value, ret } => {
-                        let f_ref = match self.f_refs.get(value) {
-                            Some(f_ref) => *f_ref,
-                            None => {
-                                let fid = fids[value];
-                                let f_ref = module.declare_func_in_func(fid, self.inner.func);
-                                self.f_refs.insert(*value, f_ref);
-                                f_ref
-                            }
-                        };
+                        // functions are used as values through a closure, one with no cells.
+                        let closure = self.static_closure(module, fids, *value);
+                        let ty = self.host.get_type_of(*value).unwrap();
 
+                        self.values.insert(*ret, TValue::reference(closure, ty));
+                    }
+
+                    CgInst::MakeClosure {
+                        value,
+                        captures,
+                        ret,
+                    } => {
+                        let env = self.heap_alloc(i64::try_from((captures.len() + 1) * 8).unwrap());
+                        let env = self.inner.inst_results(env)[0];
+
+                        let f_ref = self.func_ref(module, fids, *value);
                         let addr = self
                             .inner
                             .ins()
                             .func_addr(cg_settings.pointer_type(), f_ref);
 
+                        self.inner.ins().store(MemFlags::new(), addr, env, 0);
+
+                        for (ix, (var, _)) in captures.iter().enumerate() {
+                            let (cell, _) = self
+                                .cell_addr(var.group())
+                                .expect("captured variables always have a cell.");
+                            let offset = i32::try_from((ix + 1) * 8).unwrap();
+
+                            self.inner.ins().store(MemFlags::new(), cell, env, offset);
+                        }
+
                         let ty = self.host.get_type_of(*value).unwrap();
 
-                        self.values.insert(*ret, TValue::reference(addr, ty));
+                        self.values.insert(*ret, TValue::reference(env, ty));
                     }
 
-                    CgInst::Call { value, args, ret } => {
-                        let func = match self.f_refs.get(value) {
-                            Some(f) => *f,
-                            None => {
-                                let fid = fids[value];
-                                let f_ref = module.declare_func_in_func(fid, self.inner.func);
-                                self.f_refs.insert(*value, f_ref);
-                                f_ref
-                            }
+                    CgInst::CallClosure {
+                        closure,
+                        closure_t,
+                        args,
+                        ret,
+                    } => {
+                        let (params, ret_t) =
+                            match self.host.tcx().get_python_type_of(*closure_t).unwrap() {
+                                PythonType::Callable { params, ret } => {
+                                    (params.unwrap_or_default(), ret)
+                                }
+                                _ => unreachable!(),
+                            };
+
+                        let param_types = params
+                            .iter()
+                            .map(|param_t| ir_type_of(self.host.tcx(), *param_t))
+                            .collect::<Vec<_>>();
+
+                        // the environment is passed last, functions that don't take one ignore it.
+                        let sig = {
+                            let mut params = param_types.clone();
+                            params.push(ir::types::I64);
+
+                            signature!(
+                                CallConv::SystemV,
+                                params.as_slice(),
+                                ir_type_of(self.host.tcx(), ret_t)
+                            )
                         };
 
+                        let sig_ref = self.inner.import_signature(sig);
+
+                        let env = *self.values[closure].as_value();
+                        let callee = self
+                            .inner
+                            .ins()
+                            .load(ir::types::I64, MemFlags::new(), env, 0);
+
+                        let mut args = args
+                            .iter()
+                            .zip(param_types)
+                            .map(|(v, param_t)| {
+                                let value = *self.values[v].as_value();
+                                self.coerce_arg(value, param_t)
+                            })
+                            .collect::<Vec<_>>();
+
+                        args.push(env);
+
+                        let inst = self.inner.ins().call_indirect(sig_ref, callee, &args);
+                        let rval = self.inner.inst_results(inst)[0];
+
+                        self.values.insert(*ret, TValue::imm(rval, ret_t));
+                    }
+
+                    CgInst::Call { value, args, ret } => {
+                        let func = self.func_ref(module, fids, *value);
+
+                        let sig_ref = self.inner.func.dfg.ext_funcs[func].signature;
+                        let param_types = self.inner.func.dfg.signatures[sig_ref]
+                            .params
+                            .iter()
+                            .map(|param| param.value_type)
+                            .collect::<Vec<_>>();
+
                         let args = args
                             .iter()
-                            .map(|v| *self.values[v].as_value())
+                            .zip(param_types)
+                            .map(|(v, param_t)| {
+                                let value = *self.values[v].as_value();
+                                self.coerce_arg(value, param_t)
+                            })
                             .collect::<Vec<_>>();
 
                         let inst = self.inner.ins().call(func, &args);
@@ -354,10 +1175,9 @@ impl Builder<'_, '_> {
                                 TypingConstants::Int,
                             ),
 
-                            Constant::Float(n) => (
-                                self.inner.ins().iconst(ir::types::F64, *n as i64),
-                                TypingConstants::Float,
-                            ),
+                            Constant::Float(n) => {
+                                (self.inner.ins().f64const(*n), TypingConstants::Float)
+                            }
 
                             Constant::Bool(b) => (
                                 self.inner.ins().iconst(ir::types::I64, *b as i64),
@@ -404,16 +1224,62 @@ impl Builder<'_, '_> {
                         self.values.insert(*ret, TValue::imm(ssa, const_t));
                     }
 
+                    CgInst::Not { value, ret } => {
+                        let value = self.bool_as_i64(*self.values[value].as_value());
+                        let negated = self.inner.ins().icmp_imm(IntCC::Equal, value, 0);
+                        let negated = self.bool_as_i64(negated);
+
+                        self.values
+                            .insert(*ret, TValue::imm(negated, TypingConstants::Bool));
+                    }
+
+                    CgInst::Is { left, right, ret } => {
+                        let left = *self.values[left].as_value();
+                        let right = *self.values[right].as_value();
+
+                        let same = self.inner.ins().icmp(IntCC::Equal, left, right);
+                        let same = self.bool_as_i64(same);
+
+                        self.values
+                            .insert(*ret, TValue::imm(same, TypingConstants::Bool));
+                    }
+
+                    CgInst::BlockParam { type_id, ret } => {
+                        let ty = ir_type_of(self.host.tcx(), *type_id);
+                        let param = self.inner.append_block_param(current_block, ty);
+
+                        self.values.insert(*ret, TValue::imm(param, *type_id));
+                    }
+
                     CgInst::ReadLocalVar { var, ret } => {
-                        let (ss, ty, _) = self.locals[&var.group()];
-                        let v = self.inner.ins().stack_load(ir::types::I64, ss, 0);
+                        if let Some((cell, ty)) = self.cell_addr(var.group()) {
+                            let ir_t = ir_type_of(self.host.tcx(), ty);
+                            let v = self.inner.ins().load(ir_t, MemFlags::new(), cell, 0);
+
+                            self.values.insert(*ret, TValue::imm(v, ty));
+                            continue;
+                        }
+
+                        let (ss, ty, ir_t) = self.locals[&var.group()];
+                        let v = self.inner.ins().stack_load(ir_t, ss, 0);
 
                         self.values.insert(*ret, TValue::imm(v, ty));
                     }
 
                     CgInst::WriteLocalVar { var, orig } => {
+                        if let Some((cell, _)) = self.cell_addr(var.group()) {
+                            let value = self.bool_as_i64(*self.values[orig].as_value());
+
+                            self.inner.ins().store(MemFlags::new(), value, cell, 0);
+                            continue;
+                        }
+
                         if !self.locals.contains_key(&var.group()) {
-                            let slot_t = TypingConstants::Int;
+                            let slot_t = if self.values[orig].type_id == TypingConstants::Float {
+                                TypingConstants::Float
+                            } else {
+                                TypingConstants::Int
+                            };
                             let ss = stack_alloc(&mut self.inner, self.host, slot_t);
 
                             self.locals.insert(
@@ -429,7 +1295,17 @@ impl Builder<'_, '_> {
                         let val = self.values[orig];
                         let src = *self.values[orig].as_value();
 
-                        if self.host.tcx().is_integer(val.type_id) {
+                        let is_closure = matches!(
+                            self.host.tcx().get_python_type_of(val.type_id),
+                            Some(PythonType::Callable { .. })
+                        );
+
+                        // lists, dicts, sets and closures are stored by their address.
+                        if self.host.tcx().is_integer(val.type_id)
+                            || val.type_id == TypingConstants::Float
+                            || self.collection_stride(val.type_id).is_some()
+                            || is_closure
+                        {
                             self.inner.ins().stack_store(src, ss, 0);
                         } else {
                             let size = self.host.tcx().size_of(slot_t);
@@ -452,8 +1328,6 @@ impl Builder<'_, '_> {
                 }
             }
 
-            self.inner.seal_block(current_block);
-
             blocks_processed.push(block_ix);
 
             for edge in self.cfg.edges_directed(block_ix, EdgeDirection::Outgoing) {
@@ -469,6 +1343,8 @@ impl Builder<'_, '_> {
             }
         }
 
+        // loops introduce back edges so blocks can only be sealed once every predecessor is known.
+        self.inner.seal_all_blocks();
         self.inner.finalize();
     }
 }
@@ -0,0 +1,255 @@
+use cranelift_codegen::ir::{
+    self, condcodes::FloatCC, AbiParam, Function, InstBuilder, Signature, TrapCode, Value,
+};
+use cranelift_codegen::isa::CallConv;
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_object::ObjectModule;
+
+use montyc_core::{TypeId, TypingConstants};
+
+use crate::cranelift::BackendImpl;
+
+/// Build an intrinsic function with the given signature, `self` is passed as the raw bits of the float.
+fn float_intrinsic(
+    name: &'static str,
+    params: &[ir::Type],
+    ret: ir::Type,
+    build: impl FnOnce(&mut FunctionBuilder, &[Value]) -> Value,
+) -> (Function, TypeId, &'static str) {
+    let mut sig = Signature::new(CallConv::SystemV);
+
+    sig.params.push(AbiParam::new(ir::types::I64));
+    sig.params.extend(params.iter().cloned().map(AbiParam::new));
+    sig.returns.push(AbiParam::new(ret));
+
+    let mut func = Function::with_name_signature(
+        ir::ExternalName::User {
+            namespace: 0,
+            index: 0,
+        },
+        sig,
+    );
+    let mut f_cx = FunctionBuilderContext::new();
+    let mut fx = FunctionBuilder::new(&mut func, &mut f_cx);
+
+    let start = fx.create_block();
+
+    fx.append_block_params_for_function_params(start);
+    fx.seal_block(start);
+    fx.switch_to_block(start);
+
+    let mut args = fx.block_params(start).to_vec();
+
+    args[0] = fx.ins().bitcast(ir::types::F64, args[0]);
+
+    let ret = build(&mut fx, args.as_slice());
+
+    fx.ins().return_(&[ret]);
+
+    (func, TypingConstants::Float, name)
+}
+
+/// Build an intrinsic taking `self` and another float and producing a float.
+fn float_binary(
+    name: &'static str,
+    build: fn(&mut FunctionBuilder, Value, Value) -> Value,
+) -> (Function, TypeId, &'static str) {
+    float_intrinsic(name, &[ir::types::F64], ir::types::F64, |fx, args| {
+        build(fx, args[0], args[1])
+    })
+}
+
+/// Build an intrinsic taking only `self` and producing a float.
+fn float_unary(
+    name: &'static str,
+    build: fn(&mut FunctionBuilder, Value) -> Value,
+) -> (Function, TypeId, &'static str) {
+    float_intrinsic(name, &[], ir::types::F64, |fx, args| build(fx, args[0]))
+}
+
+/// Build an intrinsic comparing `self` to another float with `cond`.
+fn float_fcmp(cond: FloatCC, name: &'static str) -> (Function, TypeId, &'static str) {
+    float_intrinsic(name, &[ir::types::F64], ir::types::B1, |fx, args| {
+        fx.ins().fcmp(cond, args[0], args[1])
+    })
+}
+
+/// Trap with a division by zero if the divisor `b` is zero.
+fn trap_if_zero(fx: &mut FunctionBuilder, b: Value) {
+    let zero = fx.ins().f64const(0.0);
+    let is_zero = fx.ins().fcmp(FloatCC::Equal, b, zero);
+
+    fx.ins().trapnz(is_zero, TrapCode::IntegerDivisionByZero);
+}
+
+fn truediv(fx: &mut FunctionBuilder, a: Value, b: Value) -> Value {
+    trap_if_zero(fx, b);
+
+    fx.ins().fdiv(a, b)
+}
+
+fn floordiv(fx: &mut FunctionBuilder, a: Value, b: Value) -> Value {
+    let q = truediv(fx, a, b);
+
+    fx.ins().floor(q)
+}
+
+/// `a - b * floor(a / b)`, the result takes the sign of the divisor.
+fn modulo(fx: &mut FunctionBuilder, a: Value, b: Value) -> Value {
+    let q = floordiv(fx, a, b);
+    let m = fx.ins().fmul(b, q);
+
+    fx.ins().fsub(a, m)
+}
+
+pub(crate) fn float_add(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__add__", |fx, a, b| fx.ins().fadd(a, b))
+}
+
+pub(crate) fn float_sub(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__sub__", |fx, a, b| fx.ins().fsub(a, b))
+}
+
+pub(crate) fn float_mul(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__mul__", |fx, a, b| fx.ins().fmul(a, b))
+}
+
+pub(crate) fn float_truediv(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__truediv__", truediv)
+}
+
+pub(crate) fn float_floordiv(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__floordiv__", floordiv)
+}
+
+pub(crate) fn float_mod(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__mod__", modulo)
+}
+
+pub(crate) fn float_radd(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__radd__", |fx, a, b| fx.ins().fadd(b, a))
+}
+
+pub(crate) fn float_rsub(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__rsub__", |fx, a, b| fx.ins().fsub(b, a))
+}
+
+pub(crate) fn float_rmul(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__rmul__", |fx, a, b| fx.ins().fmul(b, a))
+}
+
+pub(crate) fn float_rtruediv(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__rtruediv__", |fx, a, b| truediv(fx, b, a))
+}
+
+pub(crate) fn float_rfloordiv(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__rfloordiv__", |fx, a, b| floordiv(fx, b, a))
+}
+
+pub(crate) fn float_rmod(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_binary("__rmod__", |fx, a, b| modulo(fx, b, a))
+}
+
+pub(crate) fn float_neg(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_unary("__neg__", |fx, a| fx.ins().fneg(a))
+}
+
+pub(crate) fn float_pos(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_unary("__pos__", |_, a| a)
+}
+
+pub(crate) fn float_eq(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_fcmp(FloatCC::Equal, "__eq__")
+}
+
+pub(crate) fn float_ne(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_fcmp(FloatCC::NotEqual, "__ne__")
+}
+
+pub(crate) fn float_lt(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_fcmp(FloatCC::LessThan, "__lt__")
+}
+
+pub(crate) fn float_le(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_fcmp(FloatCC::LessThanOrEqual, "__le__")
+}
+
+pub(crate) fn float_gt(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_fcmp(FloatCC::GreaterThan, "__gt__")
+}
+
+pub(crate) fn float_ge(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    float_fcmp(FloatCC::GreaterThanOrEqual, "__ge__")
+}
+
+pub(crate) fn float_bool(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    // `NotEqual` is also true for unordered operands, `bool(nan)` is `True`.
+    float_intrinsic("__bool__", &[], ir::types::B1, |fx, args| {
+        let zero = fx.ins().f64const(0.0);
+
+        fx.ins().fcmp(FloatCC::NotEqual, args[0], zero)
+    })
+}
@@ -1,4 +1,6 @@
-use cranelift_codegen::ir::{self, condcodes::IntCC, AbiParam, Function, InstBuilder, Signature};
+use cranelift_codegen::ir::{
+    self, condcodes::IntCC, AbiParam, Function, InstBuilder, Signature, TrapCode, Value,
+};
 use cranelift_codegen::isa::CallConv;
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
 use cranelift_object::ObjectModule;
@@ -207,3 +209,609 @@ pub(crate) fn int_bool(
 
     (func, TypingConstants::Int, "__bool__")
 }
+
+/// Build an intrinsic comparing `self` to another int with `cond`.
+fn int_icmp(cond: IntCC, name: &'static str) -> (Function, TypeId, &'static str) {
+    let mut sig = Signature::new(CallConv::SystemV);
+
+    sig.params
+        .extend_from_slice(&[AbiParam::new(ir::types::R64), AbiParam::new(ir::types::I64)]);
+    sig.returns.push(AbiParam::new(ir::types::B1));
+
+    let mut func = Function::with_name_signature(
+        ir::ExternalName::User {
+            namespace: 0,
+            index: 0,
+        },
+        sig,
+    );
+    let mut f_cx = FunctionBuilderContext::new();
+    let mut fx = FunctionBuilder::new(&mut func, &mut f_cx);
+
+    let start = fx.create_block();
+
+    fx.append_block_params_for_function_params(start);
+    fx.seal_block(start);
+    fx.switch_to_block(start);
+
+    let (a, b) = match fx.block_params(start) {
+        [a, b] => (*a, *b),
+        _ => unreachable!(),
+    };
+
+    let a = fx.ins().raw_bitcast(ir::types::I64, a);
+
+    let ret = fx.ins().icmp(cond, a, b);
+
+    fx.ins().return_(&[ret]);
+
+    (func, TypingConstants::Int, name)
+}
+
+pub(crate) fn int_lt(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_icmp(IntCC::SignedLessThan, "__lt__")
+}
+
+pub(crate) fn int_le(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_icmp(IntCC::SignedLessThanOrEqual, "__le__")
+}
+
+pub(crate) fn int_gt(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_icmp(IntCC::SignedGreaterThan, "__gt__")
+}
+
+pub(crate) fn int_ge(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_icmp(IntCC::SignedGreaterThanOrEqual, "__ge__")
+}
+
+/// Trap raised by `x ** n` when `n` is negative, the result would be a float.
+pub(crate) const NEGATIVE_EXPONENT: TrapCode = TrapCode::User(1);
+
+/// Trap raised by `x << n` and `x >> n` when `n` is negative.
+pub(crate) const NEGATIVE_SHIFT_COUNT: TrapCode = TrapCode::User(2);
+
+/// Build an intrinsic taking `self` and another int and producing an int.
+fn int_binary(
+    name: &'static str,
+    build: fn(&mut FunctionBuilder, Value, Value) -> Value,
+) -> (Function, TypeId, &'static str) {
+    let mut sig = Signature::new(CallConv::SystemV);
+
+    sig.params
+        .extend_from_slice(&[AbiParam::new(ir::types::R64), AbiParam::new(ir::types::I64)]);
+    sig.returns.push(AbiParam::new(ir::types::I64));
+
+    let mut func = Function::with_name_signature(
+        ir::ExternalName::User {
+            namespace: 0,
+            index: 0,
+        },
+        sig,
+    );
+    let mut f_cx = FunctionBuilderContext::new();
+    let mut fx = FunctionBuilder::new(&mut func, &mut f_cx);
+
+    let start = fx.create_block();
+
+    fx.append_block_params_for_function_params(start);
+    fx.switch_to_block(start);
+
+    let (a, b) = match fx.block_params(start) {
+        [a, b] => (*a, *b),
+        _ => unreachable!(),
+    };
+
+    let a = fx.ins().raw_bitcast(ir::types::I64, a);
+
+    let ret = build(&mut fx, a, b);
+
+    fx.ins().return_(&[ret]);
+
+    // `build` may have introduced a loop, blocks can only be sealed once all of them are known.
+    fx.seal_all_blocks();
+
+    (func, TypingConstants::Int, name)
+}
+
+/// Build an intrinsic taking only `self` and producing an int.
+fn int_unary(
+    name: &'static str,
+    build: fn(&mut FunctionBuilder, Value) -> Value,
+) -> (Function, TypeId, &'static str) {
+    let mut sig = Signature::new(CallConv::SystemV);
+
+    sig.params.push(AbiParam::new(ir::types::R64));
+    sig.returns.push(AbiParam::new(ir::types::I64));
+
+    let mut func = Function::with_name_signature(
+        ir::ExternalName::User {
+            namespace: 0,
+            index: 0,
+        },
+        sig,
+    );
+    let mut f_cx = FunctionBuilderContext::new();
+    let mut fx = FunctionBuilder::new(&mut func, &mut f_cx);
+
+    let start = fx.create_block();
+
+    fx.append_block_params_for_function_params(start);
+    fx.seal_block(start);
+    fx.switch_to_block(start);
+
+    let this = match fx.block_params(start) {
+        [this] => *this,
+        _ => unreachable!(),
+    };
+
+    let this = fx.ins().raw_bitcast(ir::types::I64, this);
+
+    let ret = build(&mut fx, this);
+
+    fx.ins().return_(&[ret]);
+
+    (func, TypingConstants::Int, name)
+}
+
+/// 1 if the non-zero remainder `r` of a division by `b` must be rounded towards negative infinity, otherwise 0.
+///
+/// Python rounds the quotient down so the remainder always takes the sign of the divisor.
+fn needs_floor(fx: &mut FunctionBuilder, r: Value, b: Value) -> Value {
+    let nonzero = fx.ins().icmp_imm(IntCC::NotEqual, r, 0);
+    let nonzero = fx.ins().bint(ir::types::I64, nonzero);
+
+    let signs = fx.ins().bxor(r, b);
+    let opposite = fx.ins().icmp_imm(IntCC::SignedLessThan, signs, 0);
+    let opposite = fx.ins().bint(ir::types::I64, opposite);
+
+    fx.ins().band(nonzero, opposite)
+}
+
+pub(crate) fn int_mul(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_binary("__mul__", |fx, a, b| fx.ins().imul(a, b))
+}
+
+pub(crate) fn int_floordiv(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_binary("__floordiv__", |fx, a, b| {
+        fx.ins().trapz(b, TrapCode::IntegerDivisionByZero);
+
+        let q = fx.ins().sdiv(a, b);
+        let r = fx.ins().srem(a, b);
+        let floor = needs_floor(fx, r, b);
+
+        fx.ins().isub(q, floor)
+    })
+}
+
+pub(crate) fn int_truediv(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    let mut sig = Signature::new(CallConv::SystemV);
+
+    sig.params
+        .extend_from_slice(&[AbiParam::new(ir::types::R64), AbiParam::new(ir::types::I64)]);
+    sig.returns.push(AbiParam::new(ir::types::F64));
+
+    let mut func = Function::with_name_signature(
+        ir::ExternalName::User {
+            namespace: 0,
+            index: 0,
+        },
+        sig,
+    );
+    let mut f_cx = FunctionBuilderContext::new();
+    let mut fx = FunctionBuilder::new(&mut func, &mut f_cx);
+
+    let start = fx.create_block();
+
+    fx.append_block_params_for_function_params(start);
+    fx.seal_block(start);
+    fx.switch_to_block(start);
+
+    let (a, b) = match fx.block_params(start) {
+        [a, b] => (*a, *b),
+        _ => unreachable!(),
+    };
+
+    let a = fx.ins().raw_bitcast(ir::types::I64, a);
+
+    fx.ins().trapz(b, TrapCode::IntegerDivisionByZero);
+
+    let a = fx.ins().fcvt_from_sint(ir::types::F64, a);
+    let b = fx.ins().fcvt_from_sint(ir::types::F64, b);
+
+    let ret = fx.ins().fdiv(a, b);
+
+    fx.ins().return_(&[ret]);
+
+    (func, TypingConstants::Int, "__truediv__")
+}
+
+pub(crate) fn int_mod(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_binary("__mod__", |fx, a, b| {
+        fx.ins().trapz(b, TrapCode::IntegerDivisionByZero);
+
+        let r = fx.ins().srem(a, b);
+        let floor = needs_floor(fx, r, b);
+        let adjust = fx.ins().imul(floor, b);
+
+        fx.ins().iadd(r, adjust)
+    })
+}
+
+pub(crate) fn int_pow(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    // exponentiation by squaring:
+    //
+    //   result = 1
+    //   while n:
+    //       if n & 1:
+    //           result *= x
+    //       x *= x
+    //       n >>= 1
+    //
+    int_binary("__pow__", |fx, x, n| {
+        let negative = fx.ins().icmp_imm(IntCC::SignedLessThan, n, 0);
+        fx.ins().trapnz(negative, NEGATIVE_EXPONENT);
+
+        let header = fx.create_block();
+        let body = fx.create_block();
+        let exit = fx.create_block();
+
+        for _ in 0..3 {
+            fx.append_block_param(header, ir::types::I64);
+        }
+
+        fx.append_block_param(exit, ir::types::I64);
+
+        let one = fx.ins().iconst(ir::types::I64, 1);
+        fx.ins().jump(header, &[one, x, n]);
+
+        fx.switch_to_block(header);
+
+        let (result, x, n) = match fx.block_params(header) {
+            [result, x, n] => (*result, *x, *n),
+            _ => unreachable!(),
+        };
+
+        fx.ins().brz(n, exit, &[result]);
+        fx.ins().jump(body, &[]);
+
+        fx.switch_to_block(body);
+
+        let odd = fx.ins().band_imm(n, 1);
+        let product = fx.ins().imul(result, x);
+        let result = fx.ins().select(odd, product, result);
+        let x = fx.ins().imul(x, x);
+        let n = fx.ins().ushr_imm(n, 1);
+
+        fx.ins().jump(header, &[result, x, n]);
+
+        fx.switch_to_block(exit);
+
+        fx.block_params(exit)[0]
+    })
+}
+
+pub(crate) fn int_lshift(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_binary("__lshift__", |fx, a, b| {
+        let negative = fx.ins().icmp_imm(IntCC::SignedLessThan, b, 0);
+        fx.ins().trapnz(negative, NEGATIVE_SHIFT_COUNT);
+
+        // cranelift masks the shift amount, every bit is shifted out past the width of an int.
+        let shifted = fx.ins().ishl(a, b);
+        let overflow = fx.ins().icmp_imm(IntCC::SignedGreaterThanOrEqual, b, 64);
+        let zero = fx.ins().iconst(ir::types::I64, 0);
+
+        fx.ins().select(overflow, zero, shifted)
+    })
+}
+
+pub(crate) fn int_rshift(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_binary("__rshift__", |fx, a, b| {
+        let negative = fx.ins().icmp_imm(IntCC::SignedLessThan, b, 0);
+        fx.ins().trapnz(negative, NEGATIVE_SHIFT_COUNT);
+
+        // shifting by the width of an int or more leaves only the sign.
+        let overflow = fx.ins().icmp_imm(IntCC::SignedGreaterThanOrEqual, b, 64);
+        let max = fx.ins().iconst(ir::types::I64, 63);
+        let amount = fx.ins().select(overflow, max, b);
+
+        fx.ins().sshr(a, amount)
+    })
+}
+
+pub(crate) fn int_and(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_binary("__and__", |fx, a, b| fx.ins().band(a, b))
+}
+
+pub(crate) fn int_or(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_binary("__or__", |fx, a, b| fx.ins().bor(a, b))
+}
+
+pub(crate) fn int_xor(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_binary("__xor__", |fx, a, b| fx.ins().bxor(a, b))
+}
+
+pub(crate) fn int_invert(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_unary("__invert__", |fx, this| fx.ins().bnot(this))
+}
+
+pub(crate) fn int_neg(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_unary("__neg__", |fx, this| fx.ins().ineg(this))
+}
+
+pub(crate) fn int_pos(
+    _cx: &BackendImpl,
+    _obj: &mut ObjectModule,
+) -> (Function, TypeId, &'static str) {
+    int_unary("__pos__", |_, this| this)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use cranelift_codegen::ir::{Function, InstructionData, Opcode, TrapCode};
+    use cranelift_codegen::settings;
+    use cranelift_object::{ObjectBuilder, ObjectModule};
+
+    use super::*;
+    use crate::cranelift::builtins::BuiltinStub;
+
+    /// Build the intrinsic and check that it passes the verifier.
+    fn build(stub: BuiltinStub) -> Function {
+        let cx = BackendImpl {
+            settings: settings::Flags::new(settings::builder()),
+            data: Default::default(),
+            cc: "cc".into(),
+            output: "a.out".into(),
+        };
+
+        let mut object_module = ObjectModule::new(
+            ObjectBuilder::new(
+                cx.codegen_settings(),
+                "test",
+                cranelift_module::default_libcall_names(),
+            )
+            .unwrap(),
+        );
+
+        let (func, _, name) = stub(&cx, &mut object_module);
+
+        if let Err(err) = cranelift_codegen::verify_function(&func, &cx.settings) {
+            panic!("{} does not verify: {}", name, err);
+        }
+
+        func
+    }
+
+    /// Interpret an intrinsic, implementing just the instructions the int intrinsics are made of.
+    fn eval(func: &Function, args: &[i64]) -> Result<i64, TrapCode> {
+        let mut values = HashMap::new();
+        let mut block = func.layout.entry_block().unwrap();
+        let mut params = args.to_vec();
+
+        loop {
+            for (param, arg) in func.dfg.block_params(block).iter().zip(params.drain(..)) {
+                values.insert(*param, arg);
+            }
+
+            let mut next = None;
+
+            for inst in func.layout.block_insts(block) {
+                let data = &func.dfg[inst];
+                let args: Vec<i64> = func.dfg.inst_args(inst).iter().map(|v| values[v]).collect();
+
+                let imm = match *data {
+                    InstructionData::UnaryImm { imm, .. }
+                    | InstructionData::BinaryImm64 { imm, .. }
+                    | InstructionData::IntCompareImm { imm, .. } => imm.bits(),
+                    _ => 0,
+                };
+
+                let cmp = |a: i64, b: i64| match data.cond_code().unwrap() {
+                    IntCC::Equal => a == b,
+                    IntCC::NotEqual => a != b,
+                    IntCC::SignedLessThan => a < b,
+                    IntCC::SignedLessThanOrEqual => a <= b,
+                    IntCC::SignedGreaterThan => a > b,
+                    IntCC::SignedGreaterThanOrEqual => a >= b,
+                    cond => unimplemented!("{:?}", cond),
+                };
+
+                let result = match data.opcode() {
+                    Opcode::Return => return Ok(args[0]),
+                    Opcode::Trapz if args[0] == 0 => return Err(data.trap_code().unwrap()),
+                    Opcode::Trapnz if args[0] != 0 => return Err(data.trap_code().unwrap()),
+                    Opcode::Trapz | Opcode::Trapnz => continue,
+
+                    Opcode::Jump => {
+                        next = data.branch_destination();
+                        params.extend(func.dfg.inst_variable_args(inst).iter().map(|v| values[v]));
+                        break;
+                    }
+
+                    Opcode::Brz if args[0] == 0 => {
+                        next = data.branch_destination();
+                        params.extend(func.dfg.inst_variable_args(inst).iter().map(|v| values[v]));
+                        break;
+                    }
+
+                    Opcode::Brz => continue,
+
+                    Opcode::RawBitcast => args[0],
+                    Opcode::Iconst => imm,
+                    Opcode::Bint => args[0],
+                    Opcode::Icmp => cmp(args[0], args[1]) as i64,
+                    Opcode::IcmpImm => cmp(args[0], imm) as i64,
+                    Opcode::Select => {
+                        if args[0] != 0 {
+                            args[1]
+                        } else {
+                            args[2]
+                        }
+                    }
+                    Opcode::Iadd => args[0].wrapping_add(args[1]),
+                    Opcode::Isub => args[0].wrapping_sub(args[1]),
+                    Opcode::Imul => args[0].wrapping_mul(args[1]),
+                    Opcode::Ineg => args[0].wrapping_neg(),
+                    Opcode::Sdiv => args[0]
+                        .checked_div(args[1])
+                        .ok_or(TrapCode::IntegerOverflow)?,
+                    Opcode::Srem => args[0]
+                        .checked_rem(args[1])
+                        .ok_or(TrapCode::IntegerOverflow)?,
+                    Opcode::Band => args[0] & args[1],
+                    Opcode::BandImm => args[0] & imm,
+                    Opcode::Bor => args[0] | args[1],
+                    Opcode::Bxor => args[0] ^ args[1],
+                    Opcode::Bnot => !args[0],
+                    // the shift amount is taken modulo the width of the type.
+                    Opcode::Ishl => args[0].wrapping_shl(args[1] as u32),
+                    Opcode::Sshr => args[0].wrapping_shr(args[1] as u32),
+                    Opcode::UshrImm => ((args[0] as u64).wrapping_shr(imm as u32)) as i64,
+                    opcode => unimplemented!("{:?}", opcode),
+                };
+
+                values.insert(func.dfg.first_result(inst), result);
+            }
+
+            block = next.expect("the block does not end in a jump or return");
+        }
+    }
+
+    fn assert_cases(stub: BuiltinStub, cases: &[(i64, i64, Result<i64, TrapCode>)]) {
+        let func = build(stub);
+
+        for (a, b, expected) in cases.iter().cloned() {
+            assert_eq!(eval(&func, &[a, b]), expected, "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn floordiv_rounds_towards_negative_infinity() {
+        assert_cases(
+            int_floordiv,
+            &[
+                (7, 2, Ok(3)),
+                (-7, 2, Ok(-4)),
+                (7, -2, Ok(-4)),
+                (-7, -2, Ok(3)),
+                (-6, 2, Ok(-3)),
+                (0, -3, Ok(0)),
+                (1, 0, Err(TrapCode::IntegerDivisionByZero)),
+            ],
+        );
+    }
+
+    #[test]
+    fn mod_takes_the_sign_of_the_divisor() {
+        assert_cases(
+            int_mod,
+            &[
+                (7, 2, Ok(1)),
+                (-7, 2, Ok(1)),
+                (7, -2, Ok(-1)),
+                (-7, -2, Ok(-1)),
+                (-6, 2, Ok(0)),
+                (1, 0, Err(TrapCode::IntegerDivisionByZero)),
+            ],
+        );
+    }
+
+    #[test]
+    fn truediv_by_zero_traps() {
+        let func = build(int_truediv);
+
+        assert_eq!(eval(&func, &[1, 0]), Err(TrapCode::IntegerDivisionByZero));
+    }
+
+    #[test]
+    fn pow() {
+        assert_cases(
+            int_pow,
+            &[
+                (2, 10, Ok(1024)),
+                (-3, 3, Ok(-27)),
+                (-3, 2, Ok(9)),
+                (7, 1, Ok(7)),
+                (7, 0, Ok(1)),
+                (0, 0, Ok(1)),
+                (0, 5, Ok(0)),
+                (2, -1, Err(NEGATIVE_EXPONENT)),
+            ],
+        );
+    }
+
+    #[test]
+    fn shifts() {
+        assert_cases(
+            int_lshift,
+            &[
+                (1, 3, Ok(8)),
+                (-1, 1, Ok(-2)),
+                (1, 63, Ok(i64::MIN)),
+                (1, 64, Ok(0)),
+                (1, 100, Ok(0)),
+                (1, -1, Err(NEGATIVE_SHIFT_COUNT)),
+            ],
+        );
+
+        assert_cases(
+            int_rshift,
+            &[
+                (8, 3, Ok(1)),
+                (-8, 1, Ok(-4)),
+                (-1, 100, Ok(-1)),
+                (8, 64, Ok(0)),
+                (8, -1, Err(NEGATIVE_SHIFT_COUNT)),
+            ],
+        );
+    }
+}
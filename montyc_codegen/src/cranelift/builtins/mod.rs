@@ -1,4 +1,5 @@
 mod bool;
+mod float;
 mod int;
 
 use cranelift_object::ObjectModule;
@@ -25,6 +26,45 @@ decl_builtins![
     int::int_bool,
     int::int_eq,
     int::int_ne,
+    int::int_lt,
+    int::int_le,
+    int::int_gt,
+    int::int_ge,
+    int::int_mul,
+    int::int_truediv,
+    int::int_floordiv,
+    int::int_mod,
+    int::int_pow,
+    int::int_lshift,
+    int::int_rshift,
+    int::int_and,
+    int::int_or,
+    int::int_xor,
+    int::int_invert,
+    int::int_neg,
+    int::int_pos,
+    // float
+    float::float_add,
+    float::float_sub,
+    float::float_mul,
+    float::float_truediv,
+    float::float_floordiv,
+    float::float_mod,
+    float::float_radd,
+    float::float_rsub,
+    float::float_rmul,
+    float::float_rtruediv,
+    float::float_rfloordiv,
+    float::float_rmod,
+    float::float_neg,
+    float::float_pos,
+    float::float_eq,
+    float::float_ne,
+    float::float_lt,
+    float::float_le,
+    float::float_gt,
+    float::float_ge,
+    float::float_bool,
     // bool
     bool::bool_bool
 ];
@@ -14,7 +14,8 @@ use tempfile::NamedTempFile;
 use montyc_core::codegen::{CgBlockId, CgInst};
 use montyc_core::opts::CompilerOptions;
 use montyc_core::{
-    BuiltinType, MapT, MontyResult, PythonType, TaggedValueId, TypeId, ValueId, FUNCTION,
+    BuiltinType, MapT, MontyResult, Property, PropertyValue, PythonType, TaggedValueId, TypeId,
+    TypingConstants, ValueId, FUNCTION,
 };
 use montyc_query::Queries;
 
@@ -75,6 +76,7 @@ fn ir_type_of(tcx: &dyn montyc_core::TypingContext, type_id: TypeId) -> ir::Type
             BuiltinType::I16 => ir::types::I16,
             BuiltinType::I32 => ir::types::I32,
             BuiltinType::I64 => ir::types::I64,
+            BuiltinType::Float => ir::types::F64,
             _ => ir::types::I64,
         },
 
@@ -208,7 +210,25 @@ impl BackendImpl {
 
             if let PythonType::Callable { ret, .. } = tcx.get_python_type_of(func_t).unwrap() {
                 let name = queries.spanref_to_str(queries.get_function(*ix)?.name)?;
-                let key = (ret, name);
+
+                // intrinsics are keyed by the class they're a method of, i.e. `float.__lt__` and `int.__lt__`.
+                let receiver = [
+                    TypingConstants::Int,
+                    TypingConstants::Float,
+                    TypingConstants::Bool,
+                    TypingConstants::Str,
+                ]
+                .iter()
+                .cloned()
+                .find(|class_t| {
+                    matches!(
+                        tcx.get_property(*class_t, name),
+                        Some(Property { value: PropertyValue::Id(id), .. }) if id == *ix
+                    )
+                })
+                .unwrap_or(ret);
+
+                let key = (receiver, name);
 
                 let tcx = queries.tcx();
 
@@ -283,7 +303,10 @@ impl BackendImpl {
                 inner: FunctionBuilder::new(&mut clir, builder_cx),
                 values: MapT::with_capacity(cfg.raw_nodes().iter().map(|n| n.weight.len()).sum()),
                 locals: MapT::new(),
+                cells: MapT::new(),
+                env: None,
                 f_refs: MapT::new(),
+                iters: MapT::new(),
                 host: queries,
                 alloc,
                 cfg,
@@ -313,9 +336,16 @@ impl BackendImpl {
                 _ => unreachable!(),
             };
 
-            if let Some(params) = func_params {
-                let entry_block_params = fx.block_params(start).to_owned().into_boxed_slice();
+            let entry_block_params = fx.block_params(start).to_owned().into_boxed_slice();
+            let captures = fx.host.get_function(func.value_id.0).unwrap().captures;
+
+            if captures.is_some() {
+                fx.env = entry_block_params.last().copied();
+            }
+
+            fx.init_cells(captures.unwrap_or_default());
 
+            if let Some(params) = func_params {
                 let param_names = match fx
                     .host
                     .get_function_flatcode(func.value_id)
@@ -327,17 +357,23 @@ impl BackendImpl {
                     _ => unreachable!(),
                 };
 
-                for (((param_name, _), param_t), param_v) in param_names
+                for ((param, param_t), param_v) in param_names
                     .iter()
+                    .filter(|param| !param.kind.is_variadic())
                     .zip(params.iter())
                     .zip(entry_block_params.iter())
                 {
+                    if let Some((cell, _)) = fx.cell_addr(param.name.group()) {
+                        fx.inner.ins().store(ir::MemFlags::new(), *param_v, cell, 0);
+                        continue;
+                    }
+
                     let param_ss = fx.stack_alloc(*param_t);
 
                     ir::InstBuilder::stack_store(fx.inner.ins(), *param_v, param_ss, 0);
 
                     fx.locals.insert(
-                        param_name.group(),
+                        param.name.group(),
                         (param_ss, *param_t, ir_type_of(fx.host.tcx(), *param_t)),
                     );
                 }
@@ -510,12 +546,17 @@ impl BackendImpl {
                 montyc_core::PythonType::Callable { params, ret } => {
                     let ret = ir_type_of(tcx, ret);
 
-                    let params = params
+                    let mut params = params
                         .unwrap_or_default()
                         .iter()
                         .map(|t| ir_type_of(tcx, *t))
                         .collect::<Vec<_>>();
 
+                    // nested functions are passed the environment of their closure last.
+                    if func.captures.is_some() {
+                        params.push(ir::types::I64);
+                    }
+
                     signature!(CallConv::SystemV, params.as_slice(), ret)
                 }
 
@@ -20,7 +20,7 @@ impl fmt::Display for Constant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Constant::Int(n) => write!(f, "{}", n),
-            Constant::Float(n) => write!(f, "{}", n),
+            Constant::Float(n) => write!(f, "{:?}", n),
             Constant::Bool(b) => write!(f, "{}", b),
             Constant::String(s) => write!(f, "{:?}", s),
             Constant::None => write!(f, "None"),
@@ -28,3 +28,30 @@ impl fmt::Display for Constant {
         }
     }
 }
+
+/// How arguments are bound to a function parameter, mirroring `inspect.Parameter.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterKind {
+    /// Parameters before a `/`, they can only be passed positionally.
+    PositionalOnly,
+    /// Regular parameters that can be passed positionally or by keyword.
+    PositionalOrKeyword,
+    /// `*args`, collects excess positional arguments.
+    VarPositional,
+    /// Parameters after a `*` or `*args`, they can only be passed by keyword.
+    KeywordOnly,
+    /// `**kwargs`, collects excess keyword arguments.
+    VarKeyword,
+}
+
+impl ParameterKind {
+    /// Whether this is `*args` or `**kwargs`.
+    pub fn is_variadic(&self) -> bool {
+        matches!(self, Self::VarPositional | Self::VarKeyword)
+    }
+
+    /// Whether a positional argument may be bound to this parameter.
+    pub fn is_positional(&self) -> bool {
+        matches!(self, Self::PositionalOnly | Self::PositionalOrKeyword)
+    }
+}
@@ -20,8 +20,18 @@ pub enum CgInst {
 
     Use { value: ValueId, ret: usize, },
     Call { value: ValueId, args: Vec<usize>, ret: usize },
+
+    /// Function values are closures: a pointer to the function followed by the cells of the variables it captures.
+    MakeClosure { value: ValueId, captures: Vec<(SpanRef, TypeId)>, ret: usize },
+    CallClosure { closure: usize, closure_t: TypeId, args: Vec<usize>, ret: usize },
     Const { cst: crate::ast::Constant, ret: usize },
 
+    Not { value: usize, ret: usize },
+    Is { left: usize, right: usize, ret: usize },
+
+    /// The value passed along by the `Jump`s into the current block.
+    BlockParam { type_id: TypeId, ret: usize },
+
     FieldLoad { orig: usize, orig_t: TypeId, field: Field, field_t: TypeId, ret: usize },
 
     ReadLocalVar { var: SpanRef, ret: usize },
@@ -29,6 +39,19 @@ pub enum CgInst {
 
     Alloc { type_id: TypeId, ilist: Vec<usize>, ret: usize },
 
+    /// Lists, dicts and sets grow in place, dicts are initialized by `Alloc` from a flat list of keys and values.
+    ListAppend { list: usize, value: usize },
+    SetAdd { set: usize, value: usize },
+    DictInsert { dict: usize, key: usize, value: usize },
+
+    Len { orig: usize, orig_t: TypeId, ret: usize },
+    GetItem { orig: usize, orig_t: TypeId, index: usize, ret: usize },
+
+    RangeIter { start: Option<usize>, stop: usize, step: Option<usize>, ret: usize },
+    TupleIter { orig: usize, orig_t: TypeId, ret: usize },
+    CollectionIter { orig: usize, orig_t: TypeId, ret: usize },
+    IterNext { iter: usize, exhausted: CgBlockId, ret: usize },
+
     Return(usize),
 }
 
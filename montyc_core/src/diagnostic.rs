@@ -0,0 +1,275 @@
+//! Structured, machine-readable diagnostics.
+//!
+//! These mirror what gets rendered for humans but are meant to be emitted
+//! as JSON (one object per line) for editors and CI to consume.
+//!
+//! Error codes are stable and grouped by where they come from: `E00xx` parsing,
+//! `E01xx` typechecking, `E02xx` comptime evaluation, `E03xx` options and IO,
+//! and `E09xx` for internal errors.
+//!
+
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+/// How bad a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A labelled region of some source file.
+#[derive(Debug, Clone)]
+pub struct DiagnosticSpan {
+    pub file: String,
+    pub bytes: Range<usize>,
+
+    /// 1-based (line, column) of the start of the span.
+    pub start: (usize, usize),
+
+    /// 1-based (line, column) of the end of the span.
+    pub end: (usize, usize),
+
+    pub is_primary: bool,
+    pub label: Option<String>,
+}
+
+impl DiagnosticSpan {
+    /// Create a span for the byte range `bytes` of `source`, computing the lines and columns.
+    pub fn new(
+        file: impl Into<String>,
+        source: &str,
+        bytes: Range<usize>,
+        is_primary: bool,
+        label: Option<String>,
+    ) -> Self {
+        fn line_col(source: &str, offset: usize) -> (usize, usize) {
+            let offset = offset.min(source.len());
+            let before = source.get(..offset).unwrap_or(source);
+
+            let line = before.matches('\n').count() + 1;
+            let line_start = before.rfind('\n').map(|ix| ix + 1).unwrap_or(0);
+            let column = before[line_start..].chars().count() + 1;
+
+            (line, column)
+        }
+
+        Self {
+            file: file.into(),
+            start: line_col(source, bytes.start),
+            end: line_col(source, bytes.end),
+            bytes,
+            is_primary,
+            label,
+        }
+    }
+}
+
+/// A single diagnostic message.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// A stable identifier for the kind of diagnostic, i.e. `E0105`.
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+
+    /// The file the diagnostic is about, if any.
+    pub file: Option<String>,
+    pub spans: Vec<DiagnosticSpan>,
+    pub notes: Vec<String>,
+
+    /// Rendered names of the types involved, keyed by their role (i.e. "expected".)
+    pub types: Vec<(&'static str, String)>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity: Severity::Error,
+            message: message.into(),
+            file: None,
+            spans: vec![],
+            notes: vec![],
+            types: vec![],
+        }
+    }
+
+    /// Serialize the diagnostic into a single line JSON object.
+    pub fn to_json(&self) -> String {
+        let mut out = String::with_capacity(256);
+
+        self.write_json(&mut out)
+            .expect("writing into a String can not fail.");
+
+        out
+    }
+
+    fn write_json(&self, out: &mut String) -> fmt::Result {
+        write!(
+            out,
+            "{{\"code\":{},\"severity\":{},\"message\":{},\"file\":",
+            JsonStr(self.code),
+            JsonStr(self.severity.as_str()),
+            JsonStr(&self.message),
+        )?;
+
+        match &self.file {
+            Some(file) => write!(out, "{}", JsonStr(file))?,
+            None => out.push_str("null"),
+        }
+
+        out.push_str(",\"spans\":[");
+
+        for (ix, span) in self.spans.iter().enumerate() {
+            if ix > 0 {
+                out.push(',');
+            }
+
+            write!(
+                out,
+                "{{\"file\":{},\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{},\"is_primary\":{},\"label\":",
+                JsonStr(&span.file),
+                span.bytes.start,
+                span.bytes.end,
+                span.start.0,
+                span.start.1,
+                span.end.0,
+                span.end.1,
+                span.is_primary,
+            )?;
+
+            match &span.label {
+                Some(label) => write!(out, "{}}}", JsonStr(label))?,
+                None => out.push_str("null}"),
+            }
+        }
+
+        out.push_str("],\"notes\":[");
+
+        for (ix, note) in self.notes.iter().enumerate() {
+            if ix > 0 {
+                out.push(',');
+            }
+
+            write!(out, "{}", JsonStr(note))?;
+        }
+
+        out.push_str("],\"types\":{");
+
+        for (ix, (role, name)) in self.types.iter().enumerate() {
+            if ix > 0 {
+                out.push(',');
+            }
+
+            write!(out, "{}:{}", JsonStr(role), JsonStr(name))?;
+        }
+
+        out.push_str("}}");
+
+        Ok(())
+    }
+}
+
+/// Formats a string as a quoted and escaped JSON string.
+struct JsonStr<'a>(&'a str);
+
+impl fmt::Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+
+        for ch in self.0.chars() {
+            match ch {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+                ch => f.write_char(ch)?,
+            }
+        }
+
+        f.write_char('"')
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diagnostic, DiagnosticSpan, JsonStr};
+
+    #[test]
+    fn json_strings_are_escaped() {
+        let escaped = |st: &str| JsonStr(st).to_string();
+
+        assert_eq!(escaped("plain"), r#""plain""#);
+        assert_eq!(escaped(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(escaped(r"C:\path\"), r#""C:\\path\\""#);
+        assert_eq!(escaped("a\nb\r\tc"), r#""a\nb\r\tc""#);
+        assert_eq!(escaped("\u{0}\u{8}\u{1f}"), r#""\u0000\u0008\u001f""#);
+
+        // everything else, including non-ASCII, is written as is.
+        assert_eq!(escaped("naïve → λ \u{7f}"), "\"naïve → λ \u{7f}\"");
+    }
+
+    #[test]
+    fn spans_count_lines_and_characters() {
+        let source = "x = 1\ny = \"λλ\" + z\n";
+        let start = source.find('z').unwrap();
+
+        let span = DiagnosticSpan::new("main.py", source, start..start + 1, true, None);
+
+        assert_eq!(span.start, (2, 12));
+        assert_eq!(span.end, (2, 13));
+    }
+
+    #[test]
+    fn json_field_layout() {
+        let mut diagnostic = Diagnostic::error("E0101", "Incompatible return type.");
+
+        assert_eq!(
+            diagnostic.to_json(),
+            r#"{"code":"E0101","severity":"error","message":"Incompatible return type.","file":null,"spans":[],"notes":[],"types":{}}"#
+        );
+
+        let source = "def f() -> int:\n    return \"s\"\n";
+        let ret = source.find("return").unwrap();
+
+        diagnostic.file = Some(String::from("main.py"));
+        diagnostic.spans = vec![
+            DiagnosticSpan::new(
+                "main.py",
+                source,
+                ret..source.len() - 1,
+                true,
+                Some(String::from("returns str")),
+            ),
+            // a span nested inside of the primary one.
+            DiagnosticSpan::new("main.py", source, ret + 7..ret + 10, false, None),
+        ];
+        diagnostic.notes = vec![String::from("expected \"int\"")];
+        diagnostic.types = vec![
+            ("expected", String::from("int")),
+            ("actual", String::from("str")),
+        ];
+
+        assert_eq!(
+            diagnostic.to_json(),
+            concat!(
+                r#"{"code":"E0101","severity":"error","message":"Incompatible return type.","file":"main.py","spans":["#,
+                r#"{"file":"main.py","byte_start":20,"byte_end":30,"line_start":2,"column_start":5,"line_end":2,"column_end":15,"is_primary":true,"label":"returns str"},"#,
+                r#"{"file":"main.py","byte_start":27,"byte_end":30,"line_start":2,"column_start":12,"line_end":2,"column_end":15,"is_primary":false,"label":null}"#,
+                r#"],"notes":["expected \"int\""],"types":{"expected":"int","actual":"str"}}"#,
+            )
+        );
+    }
+}
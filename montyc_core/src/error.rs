@@ -1,4 +1,5 @@
 use std::ops::Range;
+use std::time::Duration;
 
 use crate::{ModuleRef, SpanRef, TypeId, ValueId};
 
@@ -59,7 +60,7 @@ pub enum TypeError {
     UnknownType { sref: String },
 
     #[error("Could not find the definition of this variable.")]
-    UndefinedVariable { sref: SpanRef },
+    UndefinedVariable { sref: SpanRef, span: LocatedSpan },
 
     #[error("Incompatible argument type.")]
     BadArgumentType {
@@ -100,12 +101,140 @@ pub enum TypeError {
 
     #[error("Unsupported feature")]
     Unsupported { span: LocatedSpan, message: String },
+
+    #[error("Arguments do not match the parameters of the callee.")]
+    BadCallArguments {
+        callsite: LocatedSpan,
+        message: String,
+    },
+}
+
+impl TypeError {
+    /// A stable code identifying the kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeError::UnboundLocal { .. } => "E0101",
+            TypeError::DuplicateParameters => "E0102",
+            TypeError::ReturnOutsideFunction(_) => "E0103",
+            TypeError::IncompatibleReassignment { .. } => "E0104",
+            TypeError::BadReturnType { .. } => "E0105",
+            TypeError::BadConditionalType { .. } => "E0106",
+            TypeError::MissingReturn { .. } => "E0107",
+            TypeError::NotCallable { .. } => "E0108",
+            TypeError::UnknownType { .. } => "E0109",
+            TypeError::UndefinedVariable { .. } => "E0110",
+            TypeError::BadArgumentType { .. } => "E0111",
+            TypeError::SusArgumentLength { .. } => "E0112",
+            TypeError::IncompatibleTypes { .. } => "E0113",
+            TypeError::BadBinaryOp { .. } => "E0114",
+            TypeError::InvalidAttributeAccess { .. } => "E0115",
+            TypeError::NotAFunction => "E0116",
+            TypeError::Unsupported { .. } => "E0117",
+            TypeError::BadCallArguments { .. } => "E0118",
+        }
+    }
+
+    /// The primary location of the error, if it has one.
+    pub fn span(&self) -> Option<&LocatedSpan> {
+        match self {
+            TypeError::UnboundLocal { defined: span, .. }
+            | TypeError::ReturnOutsideFunction(span)
+            | TypeError::IncompatibleReassignment {
+                bad_reassignment: span,
+                ..
+            }
+            | TypeError::BadReturnType { ret_node: span, .. }
+            | TypeError::MissingReturn { ret_span: span, .. }
+            | TypeError::NotCallable { callsite: span, .. }
+            | TypeError::UndefinedVariable { span, .. }
+            | TypeError::BadArgumentType { arg_node: span, .. }
+            | TypeError::SusArgumentLength { callsite: span, .. }
+            | TypeError::IncompatibleTypes {
+                left_span: span, ..
+            }
+            | TypeError::BadBinaryOp { span, .. }
+            | TypeError::InvalidAttributeAccess { access: span, .. }
+            | TypeError::Unsupported { span, .. }
+            | TypeError::BadCallArguments { callsite: span, .. } => Some(span),
+
+            TypeError::DuplicateParameters
+            | TypeError::BadConditionalType { .. }
+            | TypeError::UnknownType { .. }
+            | TypeError::NotAFunction => None,
+        }
+    }
+}
+
+/// A frame of a comptime traceback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceFrame {
+    pub module: ModuleRef,
+
+    /// The function or class being executed, or None for module level code.
+    pub name: Option<String>,
+
+    /// The span of the code being executed in the frame.
+    pub span: Option<Span>,
+}
+
+/// A limit on comptime evaluation that can be exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComptimeLimit {
+    /// The number of instructions that may be executed.
+    Ticks(u64),
+
+    /// The wall-clock time evaluation may take.
+    Timeout(Duration),
+}
+
+impl std::fmt::Display for ComptimeLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComptimeLimit::Ticks(ticks) => write!(f, "budget of {} ticks", ticks),
+            ComptimeLimit::Timeout(timeout) => write!(f, "time limit of {:?}", timeout),
+        }
+    }
+}
+
+/// A syntax error found while parsing some source code.
+#[derive(Debug, Clone, Error)]
+#[error("{message}")]
+pub struct ParseError {
+    /// The span of the offending token.
+    pub span: Span,
+    pub message: String,
+
+    /// Descriptions of what would have been accepted instead, i.e. "`:`" or "an identifier".
+    pub expected: Vec<String>,
 }
 
 #[derive(Debug, Error, derive_more::From)]
 pub enum MontyError {
-    #[error("An interpreter exception occured.")]
-    InterpreterException(),
+    #[error("An interpreter exception occured: {kind}")]
+    InterpreterException {
+        module: ModuleRef,
+        kind: String,
+        message: Option<String>,
+
+        /// The frames the exception escaped from, innermost first.
+        traceback: Vec<TraceFrame>,
+    },
+
+    #[error("Comptime evaluation of module `{name}` exceeded its {limit}.")]
+    ComptimeLimitExceeded {
+        module: ModuleRef,
+        name: String,
+        limit: ComptimeLimit,
+
+        /// The frames that were executing when the limit was hit, innermost first.
+        frames: Vec<TraceFrame>,
+    },
+
+    #[error("{} syntax error(s) were found while parsing.", .errors.len())]
+    Parse {
+        module: ModuleRef,
+        errors: Vec<ParseError>,
+    },
 
     #[error("Any query error.")]
     QueryError,
@@ -121,4 +250,22 @@ pub enum MontyError {
 
     #[error("An error has occured during typechecking.")]
     TypeError { module: ModuleRef, error: TypeError },
+
+    #[error("{} errors have occured during typechecking.", .0.len())]
+    TypeErrors(Vec<(ModuleRef, TypeError)>),
+}
+
+impl MontyError {
+    /// A stable code identifying the kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            MontyError::Parse { .. } => "E0001",
+            MontyError::TypeError { error, .. } => error.code(),
+            MontyError::TypeErrors(_) => "E0100",
+            MontyError::InterpreterException { .. } => "E0200",
+            MontyError::ComptimeLimitExceeded { .. } => "E0201",
+            MontyError::IO(_) => "E0301",
+            MontyError::QueryError | MontyError::None | MontyError::ValueDoesNotExist(_) => "E0900",
+        }
+    }
 }
@@ -17,4 +17,7 @@ pub struct Function {
 
     /// if the function is defined externally
     pub is_extern: bool,
+
+    /// The variables of the enclosing function captured by reference, `None` if this isn't a nested function.
+    pub captures: Option<Vec<(SpanRef, TypeId)>>,
 }
@@ -39,6 +39,7 @@ pub(crate) mod macros {
 
 pub mod ast;
 pub mod codegen;
+pub mod diagnostic;
 pub mod dict;
 pub mod error;
 pub mod func;
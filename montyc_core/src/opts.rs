@@ -1,9 +1,160 @@
+use std::ffi::OsStr;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::result::Result;
+use std::time::Duration;
 
 use structopt::*;
 
+/// How diagnostics are emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Rendered reports with source snippets.
+    Human,
+
+    /// One JSON object per diagnostic, per line.
+    Json,
+}
+
+impl std::str::FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown message format {:?}, expected one of: human, json",
+                s
+            )),
+        }
+    }
+}
+
+/// Which parser turns source into an AST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParserBackend {
+    /// `montyc_parser`, built on `nom`.
+    Nom,
+
+    /// `montyc_parser2`, built on `chumsky`.
+    Chumsky,
+}
+
+impl std::str::FromStr for ParserBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nom" => Ok(Self::Nom),
+            "chumsky" => Ok(Self::Chumsky),
+            _ => Err(format!(
+                "unknown parser {:?}, expected one of: nom, chumsky",
+                s
+            )),
+        }
+    }
+}
+
+/// How I/O performed by compile-time code outside of the allowed paths is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxMode {
+    /// Every access is granted.
+    Allow,
+
+    /// Every access is refused.
+    Deny,
+
+    /// Ask on the terminal before granting an access.
+    Prompt,
+}
+
+impl std::str::FromStr for SandboxMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "deny" => Ok(Self::Deny),
+            "prompt" => Ok(Self::Prompt),
+            _ => Err(format!(
+                "unknown sandbox mode {:?}, expected one of: allow, deny, prompt",
+                s
+            )),
+        }
+    }
+}
+
+/// A per-module override of the comptime tick budget, given as `<module>=<ticks>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickOverride {
+    pub module: String,
+    pub ticks: u64,
+}
+
+impl std::str::FromStr for TickOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (module, ticks) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected `<module>=<ticks>` but got {:?}", s))?;
+
+        let ticks = ticks.parse().map_err(|err| {
+            format!(
+                "bad tick count {:?} for module {:?}: {}",
+                ticks, module, err
+            )
+        })?;
+
+        Ok(Self {
+            module: module.to_owned(),
+            ticks,
+        })
+    }
+}
+
+/// Where an entry of the module search path came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchPathOrigin {
+    /// The directory of the input file.
+    Input,
+
+    /// An `-I <dir>` flag.
+    Include,
+
+    /// The `MONTYPATH` environment variable.
+    Environment,
+
+    /// A line of the `--search-path-file`.
+    File,
+
+    /// The standard library.
+    Libstd,
+}
+
+impl std::fmt::Display for SearchPathOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Input => write!(f, "input"),
+            Self::Include => write!(f, "-I"),
+            Self::Environment => write!(f, "{}", SEARCH_PATH_ENV),
+            Self::File => write!(f, "search path file"),
+            Self::Libstd => write!(f, "libstd"),
+        }
+    }
+}
+
+/// A directory modules are imported from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchRoot {
+    pub path: PathBuf,
+    pub origin: SearchPathOrigin,
+}
+
+/// The environment variable holding extra search roots, separated like `PATH`.
+pub const SEARCH_PATH_ENV: &str = "MONTYPATH";
+
 #[derive(Debug)]
 pub struct VerifiedCompilerOptions(pub CompilerOptions);
 
@@ -29,6 +180,59 @@ pub enum CompilerOptions {
         /// The input file to check.
         #[structopt(parse(from_os_str))]
         input: PathBuf,
+
+        /// The format diagnostics are emitted in, either `human` or `json`.
+        #[structopt(long, default_value = "human")]
+        message_format: MessageFormat,
+
+        /// The parser modules are parsed with, either `nom` or `chumsky`.
+        #[structopt(long, default_value = "nom")]
+        parser: ParserBackend,
+
+        /// How comptime I/O outside of the allowed paths is handled, one of `allow`, `deny` or `prompt`.
+        #[structopt(long, default_value = "prompt")]
+        comptime_io: SandboxMode,
+
+        /// A path prefix comptime code may always read from, can be given more than once.
+        #[structopt(long = "allow-path", parse(from_os_str), number_of_values = 1)]
+        allow_paths: Vec<PathBuf>,
+
+        /// The number of instructions comptime code may execute per module, 0 for unbounded.
+        #[structopt(long, default_value = "1000000")]
+        comptime_ticks: u64,
+
+        /// Override the tick budget of a single module with `<module>=<ticks>`, can be given more than once.
+        #[structopt(long = "comptime-ticks-for", number_of_values = 1)]
+        comptime_tick_overrides: Vec<TickOverride>,
+
+        /// The number of seconds comptime code may run for per module, unbounded if not given.
+        #[structopt(long)]
+        comptime_timeout: Option<u64>,
+
+        /// The most objects comptime code may keep alive at once, exceeding it raises a `MemoryError`.
+        #[structopt(long)]
+        comptime_heap_limit: Option<usize>,
+
+        /// Add a directory to the module search path, can be given more than once.
+        #[structopt(
+            short = "I",
+            long = "include",
+            parse(from_os_str),
+            number_of_values = 1
+        )]
+        include_paths: Vec<PathBuf>,
+
+        /// A file listing module search path directories, one per line.
+        #[structopt(long, parse(from_os_str))]
+        search_path_file: Option<PathBuf>,
+
+        /// Print the module search path, in order of precedence, and exit.
+        #[structopt(long)]
+        print_search_path: bool,
+
+        /// The resolved module search path, filled in by `verify`.
+        #[structopt(skip)]
+        search_path: Vec<SearchRoot>,
     },
 
     /// Compile the provided input.
@@ -64,6 +268,59 @@ pub enum CompilerOptions {
         /// Low level codegen settings to pass to Cranelift.
         #[structopt(multiple = true, short = "C", long = "codegen")]
         cranelift_settings: Vec<String>,
+
+        /// The format diagnostics are emitted in, either `human` or `json`.
+        #[structopt(long, default_value = "human")]
+        message_format: MessageFormat,
+
+        /// The parser modules are parsed with, either `nom` or `chumsky`.
+        #[structopt(long, default_value = "nom")]
+        parser: ParserBackend,
+
+        /// How comptime I/O outside of the allowed paths is handled, one of `allow`, `deny` or `prompt`.
+        #[structopt(long, default_value = "prompt")]
+        comptime_io: SandboxMode,
+
+        /// A path prefix comptime code may always read from, can be given more than once.
+        #[structopt(long = "allow-path", parse(from_os_str), number_of_values = 1)]
+        allow_paths: Vec<PathBuf>,
+
+        /// The number of instructions comptime code may execute per module, 0 for unbounded.
+        #[structopt(long, default_value = "1000000")]
+        comptime_ticks: u64,
+
+        /// Override the tick budget of a single module with `<module>=<ticks>`, can be given more than once.
+        #[structopt(long = "comptime-ticks-for", number_of_values = 1)]
+        comptime_tick_overrides: Vec<TickOverride>,
+
+        /// The number of seconds comptime code may run for per module, unbounded if not given.
+        #[structopt(long)]
+        comptime_timeout: Option<u64>,
+
+        /// The most objects comptime code may keep alive at once, exceeding it raises a `MemoryError`.
+        #[structopt(long)]
+        comptime_heap_limit: Option<usize>,
+
+        /// Add a directory to the module search path, can be given more than once.
+        #[structopt(
+            short = "I",
+            long = "include",
+            parse(from_os_str),
+            number_of_values = 1
+        )]
+        include_paths: Vec<PathBuf>,
+
+        /// A file listing module search path directories, one per line.
+        #[structopt(long, parse(from_os_str))]
+        search_path_file: Option<PathBuf>,
+
+        /// Print the module search path, in order of precedence, and exit.
+        #[structopt(long)]
+        print_search_path: bool,
+
+        /// The resolved module search path, filled in by `verify`.
+        #[structopt(skip)]
+        search_path: Vec<SearchRoot>,
     },
 }
 
@@ -94,14 +351,236 @@ impl CompilerOptions {
         }
     }
 
-    pub fn verify(mut self) -> Result<VerifiedCompilerOptions, Vec<String>> {
-        let mut errors = vec![];
+    pub fn input(&self) -> PathBuf {
+        match self {
+            CompilerOptions::Check { input, .. } | CompilerOptions::Build { input, .. } => {
+                input.clone()
+            }
+        }
+    }
+
+    pub fn comptime_io(&self) -> SandboxMode {
+        match self {
+            CompilerOptions::Check { comptime_io, .. }
+            | CompilerOptions::Build { comptime_io, .. } => *comptime_io,
+        }
+    }
+
+    pub fn allow_paths(&self) -> &[PathBuf] {
+        match self {
+            CompilerOptions::Check { allow_paths, .. }
+            | CompilerOptions::Build { allow_paths, .. } => allow_paths.as_slice(),
+        }
+    }
+
+    /// The directories modules are imported from, in order of precedence.
+    ///
+    /// This is only populated once the options have been verified.
+    ///
+    pub fn search_path(&self) -> &[SearchRoot] {
+        match self {
+            CompilerOptions::Check { search_path, .. }
+            | CompilerOptions::Build { search_path, .. } => search_path.as_slice(),
+        }
+    }
+
+    pub fn print_search_path(&self) -> bool {
+        match self {
+            CompilerOptions::Check {
+                print_search_path, ..
+            }
+            | CompilerOptions::Build {
+                print_search_path, ..
+            } => *print_search_path,
+        }
+    }
+
+    /// Resolve the module search path.
+    ///
+    /// The directory of the input comes first, then every `-I` directory, then
+    /// `MONTYPATH`, then the lines of the search path file, and libstd is always last.
+    /// A directory only appears once, at its most important position.
+    ///
+    fn resolve_search_path(
+        input: &Path,
+        libstd: &Path,
+        include_paths: &[PathBuf],
+        montypath: Option<&OsStr>,
+        search_path_file: Option<&Path>,
+        errors: &mut Vec<String>,
+    ) -> Vec<SearchRoot> {
+        let mut roots = vec![];
+
+        if let Some(parent) = input
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+        {
+            roots.push((parent, SearchPathOrigin::Input));
+        }
+
+        for path in include_paths {
+            match Self::check_if_path_exists(path, "an include directory") {
+                Ok(path) => roots.push((path, SearchPathOrigin::Include)),
+                Err(st) => errors.push(st),
+            }
+        }
 
-        let (libstd, input) = match &mut self {
-            CompilerOptions::Check { libstd, input }
-            | CompilerOptions::Build { libstd, input, .. } => (libstd, input),
+        // like `PYTHONPATH`, entries of the environment that do not exist are ignored.
+        if let Some(paths) = montypath {
+            for path in std::env::split_paths(paths) {
+                if let Ok(path) = path.canonicalize() {
+                    roots.push((path, SearchPathOrigin::Environment));
+                }
+            }
+        }
+
+        if let Some(file) = search_path_file {
+            match std::fs::read_to_string(file) {
+                Ok(contents) => {
+                    let base = file.parent().unwrap_or_else(|| Path::new(""));
+
+                    for line in contents.lines().map(str::trim) {
+                        if line.is_empty() || line.starts_with('#') {
+                            continue;
+                        }
+
+                        // relative entries are relative to the file and not the working directory.
+                        match Self::check_if_path_exists(&base.join(line), "a search path entry") {
+                            Ok(path) => roots.push((path, SearchPathOrigin::File)),
+                            Err(st) => errors.push(st),
+                        }
+                    }
+                }
+
+                Err(err) => errors.push(format!(
+                    "Failed to read the search path file. (path={:?}, error={:?})",
+                    file,
+                    err.kind()
+                )),
+            }
+        }
+
+        roots.push((libstd.to_path_buf(), SearchPathOrigin::Libstd));
+
+        let mut search_path: Vec<SearchRoot> = Vec::with_capacity(roots.len());
+
+        for (path, origin) in roots {
+            if !search_path.iter().any(|root| root.path == path) {
+                search_path.push(SearchRoot { path, origin });
+            }
+        }
+
+        search_path
+    }
+
+    /// The tick budget for evaluating the module named `module`, or None if unbounded.
+    pub fn comptime_ticks(&self, module: &str) -> Option<u64> {
+        let (ticks, overrides) = match self {
+            CompilerOptions::Check {
+                comptime_ticks,
+                comptime_tick_overrides,
+                ..
+            }
+            | CompilerOptions::Build {
+                comptime_ticks,
+                comptime_tick_overrides,
+                ..
+            } => (*comptime_ticks, comptime_tick_overrides),
         };
 
+        let ticks = overrides
+            .iter()
+            .rev()
+            .find(|o| o.module == module)
+            .map_or(ticks, |o| o.ticks);
+
+        Some(ticks).filter(|ticks| *ticks != 0)
+    }
+
+    /// The wall-clock limit for evaluating a single module, or None if unbounded.
+    pub fn comptime_timeout(&self) -> Option<Duration> {
+        match self {
+            CompilerOptions::Check {
+                comptime_timeout, ..
+            }
+            | CompilerOptions::Build {
+                comptime_timeout, ..
+            } => comptime_timeout.map(Duration::from_secs),
+        }
+    }
+
+    /// The most objects comptime code may keep alive at once, or None if unbounded.
+    pub fn comptime_heap_limit(&self) -> Option<usize> {
+        match self {
+            CompilerOptions::Check {
+                comptime_heap_limit,
+                ..
+            }
+            | CompilerOptions::Build {
+                comptime_heap_limit,
+                ..
+            } => *comptime_heap_limit,
+        }
+    }
+
+    pub fn message_format(&self) -> MessageFormat {
+        match self {
+            CompilerOptions::Check { message_format, .. }
+            | CompilerOptions::Build { message_format, .. } => *message_format,
+        }
+    }
+
+    pub fn parser(&self) -> ParserBackend {
+        match self {
+            CompilerOptions::Check { parser, .. } | CompilerOptions::Build { parser, .. } => {
+                *parser
+            }
+        }
+    }
+
+    pub fn verify(self) -> Result<VerifiedCompilerOptions, Vec<String>> {
+        let montypath = std::env::var_os(SEARCH_PATH_ENV);
+
+        self.verify_with(montypath.as_deref())
+    }
+
+    /// Verify the options with `montypath` as the value of `MONTYPATH`.
+    pub fn verify_with(
+        mut self,
+        montypath: Option<&OsStr>,
+    ) -> Result<VerifiedCompilerOptions, Vec<String>> {
+        let mut errors = vec![];
+
+        let (libstd, input, allow_paths, include_paths, search_path_file, search_path) =
+            match &mut self {
+                CompilerOptions::Check {
+                    libstd,
+                    input,
+                    allow_paths,
+                    include_paths,
+                    search_path_file,
+                    search_path,
+                    ..
+                }
+                | CompilerOptions::Build {
+                    libstd,
+                    input,
+                    allow_paths,
+                    include_paths,
+                    search_path_file,
+                    search_path,
+                    ..
+                } => (
+                    libstd,
+                    input,
+                    allow_paths,
+                    include_paths,
+                    search_path_file,
+                    search_path,
+                ),
+            };
+
         match Self::check_if_path_exists(&libstd, "the standard library") {
             Err(st) => {
                 errors.push(st);
@@ -120,6 +599,22 @@ impl CompilerOptions {
             errors.push(st);
         }
 
+        for path in allow_paths.iter_mut() {
+            match Self::check_if_path_exists(&path, "an allowed path") {
+                Err(st) => errors.push(st),
+                Ok(canonical) => (*path) = canonical,
+            }
+        }
+
+        *search_path = Self::resolve_search_path(
+            input,
+            libstd,
+            include_paths,
+            montypath,
+            search_path_file.as_deref(),
+            &mut errors,
+        );
+
         if let CompilerOptions::Build { cc, ld, .. } = &self {
             if let Some(cc) = cc {
                 if let Err(st) = Self::check_if_path_exists(&cc, "the specified C compiler") {
@@ -141,3 +636,60 @@ impl CompilerOptions {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LIBSTD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../libstd");
+
+    #[test]
+    fn search_path_precedence() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        // search roots are canonical, the temporary directory may be behind a symlink.
+        let dir = tmp.path().canonicalize().unwrap();
+
+        for sub in ["inc", "env", "file"] {
+            std::fs::create_dir(dir.join(sub)).unwrap();
+        }
+
+        // entries already on the search path are only kept at their first, highest precedence, position.
+        std::fs::write(dir.join("main.py"), "").unwrap();
+        std::fs::write(dir.join("search.txt"), "# comment\n\nfile\ninc\n").unwrap();
+
+        let montypath =
+            std::env::join_paths([dir.join("env"), dir.join("missing"), dir.join("inc")]).unwrap();
+
+        let opts = CompilerOptions::from_iter([
+            "montyc",
+            "check",
+            "--libstd",
+            LIBSTD,
+            "--search-path-file",
+            dir.join("search.txt").to_str().unwrap(),
+            "-I",
+            dir.join("inc").to_str().unwrap(),
+            dir.join("main.py").to_str().unwrap(),
+        ])
+        .verify_with(Some(&montypath))
+        .expect("options should verify.");
+
+        let search_path: Vec<_> = opts
+            .search_path()
+            .iter()
+            .map(|root| (root.path.strip_prefix(&dir).ok(), root.origin))
+            .collect();
+
+        assert_eq!(
+            search_path,
+            [
+                (Some(Path::new("")), SearchPathOrigin::Input),
+                (Some(Path::new("inc")), SearchPathOrigin::Include),
+                (Some(Path::new("env")), SearchPathOrigin::Environment),
+                (Some(Path::new("file")), SearchPathOrigin::File),
+                (None, SearchPathOrigin::Libstd),
+            ]
+        );
+    }
+}
@@ -129,6 +129,10 @@ builtins!(
     .13 = UntypedTuple { "tuple", 8 },
     .14 = AnyType { "Any", 8 },
     .15 = Bytes { "bytes", 8 },
+    .16 = Range { "range", 8 },
+    .17 = UntypedList { "list", 8 },
+    .18 = UntypedDict { "dict", 8 },
+    .19 = UntypedSet { "set", 8 },
     // Primitive, lower-level types.
     .100 = U8 { "u8", 1 },
     .101 = U16 { "u16", 2 },
@@ -171,6 +175,12 @@ pub enum PythonType {
     /// A list type.
     List { inner: TypeId },
 
+    /// A dict type, `Dict[K, V]` maps keys of type K to values of type V.
+    Dict { key: TypeId, value: TypeId },
+
+    /// A set type.
+    Set { inner: TypeId },
+
     /// Union type; `Union[X, Y]` means either X or Y.
     Union { members: Option<Vec<TypeId>> },
 
@@ -327,6 +337,19 @@ pub fn calculate_layout(fields: impl Iterator<Item = Layout>) -> (Layout, Vec<i3
     (layout, offsets)
 }
 
+/// Give `base` the natively implemented methods `(name, params, ret)`.
+fn insert_builtin_methods<T>(tcx: &T, base: TypeId, methods: &[(&str, Vec<TypeId>, TypeId)])
+where
+    T: TypingContext + ?Sized,
+{
+    for (name, params, ret) in methods {
+        let type_id = tcx.callable(Some(params.clone()), *ret);
+        let property = Property::new(type_id, PropertyValue::Builtin(base, name.to_string()));
+
+        tcx.insert_property(base, name.to_string(), property);
+    }
+}
+
 pub enum UnifyFailure {
     NotCallable,
     UnequalArity(usize, usize),
@@ -405,12 +428,15 @@ pub trait TypingContext {
             | PythonType::Instance { .. }
             | PythonType::Type { .. }
             | PythonType::TypeVar { .. }
-            | PythonType::List { .. }
             | PythonType::Generic { .. } => {
                 todo!()
             }
 
-            PythonType::Any => I64_LAYOUT,
+            // containers are pointers to their heap allocated storage.
+            PythonType::Any
+            | PythonType::List { .. }
+            | PythonType::Dict { .. }
+            | PythonType::Set { .. } => I64_LAYOUT,
 
             PythonType::Class { members, .. }
             | PythonType::Tuple { members }
@@ -507,9 +533,60 @@ pub trait TypingContext {
         tuple_type_id
     }
 
+    /// Create a list type of `inner` elements.
     #[inline]
     fn list(&self, inner: TypeId) -> TypeId {
-        self.insert(Type::from(PythonType::List { inner }))
+        let list_type_id = self.insert(Type::from(PythonType::List { inner }));
+
+        insert_builtin_methods(
+            self,
+            list_type_id,
+            &[
+                (
+                    "__getitem__",
+                    vec![list_type_id, TypingConstants::Int],
+                    inner,
+                ),
+                ("__len__", vec![list_type_id], TypingConstants::Int),
+                ("append", vec![inner], TypingConstants::None),
+            ],
+        );
+
+        list_type_id
+    }
+
+    /// Create a dict type mapping `key`s to `value`s.
+    #[inline]
+    fn dict(&self, key: TypeId, value: TypeId) -> TypeId {
+        let dict_type_id = self.insert(Type::from(PythonType::Dict { key, value }));
+
+        insert_builtin_methods(
+            self,
+            dict_type_id,
+            &[
+                ("__getitem__", vec![dict_type_id, key], value),
+                ("__len__", vec![dict_type_id], TypingConstants::Int),
+            ],
+        );
+
+        dict_type_id
+    }
+
+    /// Create a set type of `inner` elements.
+    #[inline]
+    fn set(&self, inner: TypeId) -> TypeId {
+        let set_type_id = self.insert(Type::from(PythonType::Set { inner }));
+
+        insert_builtin_methods(
+            self,
+            set_type_id,
+            &[
+                ("__len__", vec![set_type_id], TypingConstants::Int),
+                ("add", vec![inner], TypingConstants::None),
+            ],
+        );
+
+        set_type_id
     }
 
     /// Create a callable type from a signature.
@@ -597,7 +674,10 @@ pub trait TypingContext {
         let mut errors = Vec::with_capacity(params.len());
 
         for (ix, (l, r)) in params.iter().cloned().zip(args.iter().cloned()).enumerate() {
-            if (l != TypingConstants::Unknown) && (l != r) {
+            // ints are implicitly promoted where a float is expected.
+            let promoted = l == TypingConstants::Float && r == TypingConstants::Int;
+
+            if (l != TypingConstants::Unknown) && (l != r) && !promoted {
                 errors.push((ix, l, r))
             }
         }
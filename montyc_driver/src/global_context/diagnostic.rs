@@ -0,0 +1,683 @@
+use std::ops::Range;
+
+use ariadne::{Color, Label, Report, ReportKind};
+use montyc_core::diagnostic::{Diagnostic, DiagnosticSpan};
+use montyc_core::error::{ComptimeLimit, ParseError, TraceFrame, TypeError};
+
+use super::*;
+
+type LocatedSpan = (ModuleRef, montyc_core::Span);
+
+/// A labelled span: the location, the color to render it in, and its message.
+type SpanLabel = (LocatedSpan, Color, String);
+
+/// A note listing what the parser would have accepted where a syntax error occurred.
+fn expected_note(error: &ParseError) -> Option<String> {
+    match error.expected.as_slice() {
+        [] => None,
+        [expected] => Some(format!("expected {}", expected)),
+        expected => Some(format!("expected one of {}", expected.join(", "))),
+    }
+}
+
+impl SessionContext {
+    /// The on-disk path of a module, used as the source id in rendered reports.
+    fn module_path(&self, mref: ModuleRef) -> String {
+        self.modules
+            .lock()
+            .get(mref)
+            .map(|module| module.path.display().to_string())
+            .unwrap_or_else(|| format!("<module {}>", mref.0))
+    }
+
+    fn display_type_of(&self, type_id: TypeId) -> String {
+        self.typing_context
+            .display_type(type_id, &|v| self.value_store.type_id_of(v))
+            .unwrap_or_else(|| String::from("<unknown>"))
+    }
+
+    /// Format an error into a human readable diagnostic.
+    ///
+    /// Type and parse errors are rendered as reports with the relevant source code highlighted,
+    /// every other kind of error falls back to its `Display` message.
+    ///
+    pub fn fmt_error(&self, error: &MontyError) -> String {
+        match error {
+            MontyError::TypeError { module, error } => self.fmt_type_error(*module, error),
+            MontyError::TypeErrors(errors) => errors
+                .iter()
+                .map(|(module, error)| self.fmt_type_error(*module, error))
+                .collect(),
+
+            MontyError::Parse { module, errors } => errors
+                .iter()
+                .map(|error| {
+                    self.render_report(
+                        "Failed to parse the module.",
+                        Some(&(*module, error.span.clone())),
+                        vec![(
+                            (*module, error.span.clone()),
+                            Color::Red,
+                            error.message.clone(),
+                        )],
+                        expected_note(error),
+                    )
+                })
+                .collect(),
+
+            MontyError::InterpreterException {
+                module,
+                kind,
+                message,
+                traceback,
+            } if !traceback.is_empty() => format!(
+                "error: {} raised while evaluating {}\n{}{}{}\n",
+                kind,
+                self.module_path(*module),
+                self.fmt_traceback(traceback),
+                kind,
+                message
+                    .as_ref()
+                    .map(|m| format!(": {}", m))
+                    .unwrap_or_default()
+            ),
+
+            MontyError::InterpreterException {
+                module,
+                kind,
+                message,
+                ..
+            } => format!(
+                "error: {} raised while evaluating {}{}\n",
+                kind,
+                self.module_path(*module),
+                message
+                    .as_ref()
+                    .map(|m| format!(": {}", m))
+                    .unwrap_or_default()
+            ),
+
+            MontyError::ComptimeLimitExceeded { .. } => {
+                let (primary, labels, note) = self.describe_limit_exceeded(error);
+
+                self.render_report(error, primary.as_ref(), labels, note)
+            }
+
+            error => format!("error: {}\n", error),
+        }
+    }
+
+    /// Format the frames of a traceback, innermost first, like CPython does with the most recent call last.
+    fn fmt_traceback(&self, frames: &[TraceFrame]) -> String {
+        let mut out = String::from("Traceback (most recent call last):\n");
+
+        for frame in frames.iter().rev() {
+            let path = self.module_path(frame.module);
+            let name = frame.name.as_deref().unwrap_or("<module>");
+
+            let span = match &frame.span {
+                Some(span) => span,
+                None => {
+                    out.push_str(&format!("  File {:?}, in {}\n", path, name));
+                    continue;
+                }
+            };
+
+            let source = self
+                .module_sources
+                .get(&frame.module)
+                .map(|s| s.value().to_string())
+                .unwrap_or_default();
+
+            let (line, _) =
+                DiagnosticSpan::new(path.as_str(), &source, span.clone(), false, None).start;
+
+            out.push_str(&format!("  File {:?}, line {}, in {}\n", path, line, name));
+
+            if let Some(text) = source.lines().nth(line - 1) {
+                out.push_str(&format!("    {}\n", text.trim()));
+            }
+        }
+
+        out
+    }
+
+    /// The located spans of traceback frames labelled with the function they are in, innermost first.
+    fn frame_labels(frames: &[TraceFrame]) -> Vec<SpanLabel> {
+        frames
+            .iter()
+            .filter_map(|frame| {
+                let span = (frame.module, frame.span.clone()?);
+                let name = frame.name.as_deref().unwrap_or("<module>");
+
+                Some((span, Color::Yellow, format!("in {}", name)))
+            })
+            .collect()
+    }
+
+    /// The primary span, frame labels, and a hint on raising the limit for a `ComptimeLimitExceeded` error.
+    fn describe_limit_exceeded(
+        &self,
+        error: &MontyError,
+    ) -> (Option<LocatedSpan>, Vec<SpanLabel>, Option<String>) {
+        /// How many of the innermost frames are shown.
+        const MAX_FRAMES: usize = 5;
+
+        let (module, name, limit, frames) = match error {
+            MontyError::ComptimeLimitExceeded {
+                module,
+                name,
+                limit,
+                frames,
+            } => (*module, name, limit, frames),
+            _ => unreachable!(),
+        };
+
+        let mut labels = Self::frame_labels(frames);
+        let omitted = labels.len().saturating_sub(MAX_FRAMES);
+
+        labels.truncate(MAX_FRAMES);
+
+        if let Some((_, color, message)) = labels.first_mut() {
+            *color = Color::Red;
+            *message = format!("executing this {}", message);
+        }
+
+        let mut note = match limit {
+            ComptimeLimit::Ticks(_) => format!(
+                "raise the budget with `--comptime-ticks`, or for this module only with `--comptime-ticks-for {}=<ticks>`",
+                name
+            ),
+            ComptimeLimit::Timeout(_) => String::from("raise the time limit with `--comptime-timeout`"),
+        };
+
+        if omitted > 0 {
+            note.push_str(&format!("\n{} outer frames were omitted", omitted));
+        }
+
+        let primary = labels
+            .first()
+            .map(|(span, _, _)| span.clone())
+            .or(Some((module, 0..0)));
+
+        (primary, labels, Some(note))
+    }
+
+    /// Render a `TypeError` found while checking code in the module `mref`.
+    pub fn fmt_type_error(&self, mref: ModuleRef, error: &TypeError) -> String {
+        let (primary, labels, note) = self.describe_type_error(error, true);
+
+        self.render_report(
+            error,
+            primary.as_ref().or(Some(&(mref, 0..0))),
+            labels,
+            note,
+        )
+    }
+
+    fn render_report(
+        &self,
+        message: impl ToString,
+        primary: Option<&LocatedSpan>,
+        labels: Vec<SpanLabel>,
+        note: Option<String>,
+    ) -> String {
+        let (report_mref, offset) = primary
+            .map(|(m, span)| (*m, span.start))
+            .unwrap_or((ModuleRef(0), 0));
+
+        let mut modules = vec![report_mref];
+
+        let mut report = Report::<(String, Range<usize>)>::build(
+            ReportKind::Error,
+            self.module_path(report_mref),
+            offset,
+        )
+        .with_message(message);
+
+        for ((m, span), color, message) in labels {
+            modules.push(m);
+
+            report = report.with_label(
+                Label::new((self.module_path(m), span))
+                    .with_message(message)
+                    .with_color(color),
+            );
+        }
+
+        if let Some(note) = note {
+            report = report.with_note(note);
+        }
+
+        modules.sort();
+        modules.dedup();
+
+        let sources = modules.into_iter().filter_map(|m| {
+            let source = self.module_sources.get(&m)?.value().to_string();
+            Some((self.module_path(m), source))
+        });
+
+        let mut buf = Vec::new();
+
+        report
+            .finish()
+            .write(ariadne::sources(sources), &mut buf)
+            .expect("writing a report into a buffer can not fail.");
+
+        String::from_utf8_lossy(&buf).into_owned()
+    }
+
+    /// The primary span, labels, and an optional note describing a type error.
+    ///
+    /// When `paint` is set type names in messages are colored for terminal output.
+    ///
+    fn describe_type_error(
+        &self,
+        error: &TypeError,
+        paint: bool,
+    ) -> (Option<LocatedSpan>, Vec<SpanLabel>, Option<String>) {
+        let label =
+            |span: &LocatedSpan, color: Color, message: String| (span.clone(), color, message);
+
+        let sref_str = |sref: SpanRef| self.resolve_sref_as_str(sref).unwrap_or("<unknown>");
+        let ty = |type_id: TypeId| {
+            let name = self.display_type_of(type_id);
+
+            if paint {
+                yansi::Paint::yellow(name).to_string()
+            } else {
+                name
+            }
+        };
+
+        let primary = error.span().cloned();
+
+        let (labels, note) = match error {
+            TypeError::UnboundLocal { name, defined, .. } => (
+                vec![label(
+                    defined,
+                    Color::Yellow,
+                    format!("`{}` is only assigned here", sref_str(*name)),
+                )],
+                Some(String::from(
+                    "local variables must be assigned a value before they are used.",
+                )),
+            ),
+
+            TypeError::DuplicateParameters | TypeError::NotAFunction => (vec![], None),
+
+            TypeError::ReturnOutsideFunction(span) => (
+                vec![label(
+                    span,
+                    Color::Red,
+                    String::from("this `return` is not in a function"),
+                )],
+                None,
+            ),
+
+            TypeError::IncompatibleReassignment {
+                name,
+                first_assignment,
+                bad_reassignment,
+                expected_type,
+                actual_type,
+            } => (
+                vec![
+                    label(
+                        bad_reassignment,
+                        Color::Red,
+                        format!("this assigns a value with a type of: {}", ty(*actual_type)),
+                    ),
+                    label(
+                        first_assignment,
+                        Color::Yellow,
+                        format!(
+                            "`{}` was first assigned a value with a type of: {}",
+                            sref_str(*name),
+                            ty(*expected_type)
+                        ),
+                    ),
+                ],
+                None,
+            ),
+
+            TypeError::BadReturnType {
+                expected,
+                actual,
+                ret_node,
+                def_node,
+            } => {
+                let mut labels = vec![label(
+                    ret_node,
+                    Color::Red,
+                    format!("This returns an object with a type of: {}", ty(*actual)),
+                )];
+
+                let annotation = format!(
+                    "This function was annotated to return a type of: {}",
+                    ty(*expected)
+                );
+
+                if def_node != ret_node {
+                    labels.push(label(def_node, Color::Yellow, annotation));
+                    (labels, None)
+                } else {
+                    (labels, Some(annotation))
+                }
+            }
+
+            TypeError::BadConditionalType { actual, .. } => (
+                vec![],
+                Some(format!(
+                    "conditions must be of type {} but this one has a type of: {}",
+                    ty(TypingConstants::Bool),
+                    ty(*actual)
+                )),
+            ),
+
+            TypeError::MissingReturn {
+                expected,
+                def_span,
+                ret_span,
+            } => (
+                vec![
+                    label(
+                        ret_span,
+                        Color::Red,
+                        String::from("this implicitly returns `None`"),
+                    ),
+                    label(
+                        def_span,
+                        Color::Yellow,
+                        format!(
+                            "This function was annotated to return a type of: {}",
+                            ty(*expected)
+                        ),
+                    ),
+                ],
+                None,
+            ),
+
+            TypeError::NotCallable { kind, callsite } => (
+                vec![label(
+                    callsite,
+                    Color::Red,
+                    format!("objects with a type of {} can not be called", ty(*kind)),
+                )],
+                None,
+            ),
+
+            TypeError::UnknownType { sref } => (
+                vec![],
+                Some(format!("could not infer the type of `{}`", sref)),
+            ),
+
+            TypeError::UndefinedVariable { sref, span } => (
+                vec![label(
+                    span,
+                    Color::Red,
+                    format!(
+                        "`{}` is not defined in this module or in builtins",
+                        sref_str(*sref)
+                    ),
+                )],
+                None,
+            ),
+
+            TypeError::BadArgumentType {
+                expected,
+                actual,
+                arg_node,
+                def_node,
+            } => {
+                let mut labels = vec![label(
+                    arg_node,
+                    Color::Red,
+                    format!("This argument has a type of: {}", ty(*actual)),
+                )];
+
+                let expectation = format!("A value of type {} was expected", ty(*expected));
+
+                if def_node != arg_node {
+                    labels.push(label(def_node, Color::Yellow, expectation));
+                    (labels, None)
+                } else {
+                    (labels, Some(expectation))
+                }
+            }
+
+            TypeError::SusArgumentLength {
+                callsite,
+                expected,
+                actual,
+            } => (
+                vec![label(
+                    callsite,
+                    Color::Red,
+                    format!(
+                        "expected {} arguments but {} were supplied",
+                        expected, actual
+                    ),
+                )],
+                None,
+            ),
+
+            TypeError::IncompatibleTypes {
+                left_span,
+                left,
+                right_span,
+                right,
+            } => (
+                vec![
+                    label(
+                        left_span,
+                        Color::Yellow,
+                        format!("This has a type of: {}", ty(*left)),
+                    ),
+                    label(
+                        right_span,
+                        Color::Red,
+                        format!("But this has a type of: {}", ty(*right)),
+                    ),
+                ],
+                None,
+            ),
+
+            TypeError::BadBinaryOp { span, left, right } => (
+                vec![label(
+                    span,
+                    Color::Red,
+                    format!(
+                        "No implementation of this operator for {} and {}",
+                        ty(*left),
+                        ty(*right)
+                    ),
+                )],
+                None,
+            ),
+
+            TypeError::InvalidAttributeAccess { base, access } => (
+                vec![label(
+                    access,
+                    Color::Red,
+                    format!("No such attribute on a value with a type of: {}", ty(*base)),
+                )],
+                None,
+            ),
+
+            TypeError::Unsupported { span, message } => {
+                (vec![label(span, Color::Red, message.clone())], None)
+            }
+
+            TypeError::BadCallArguments { callsite, message } => {
+                (vec![label(callsite, Color::Red, message.clone())], None)
+            }
+        };
+
+        (primary, labels, note)
+    }
+
+    /// Types involved in a type error, keyed by their role.
+    fn type_error_types(error: &TypeError) -> Vec<(&'static str, TypeId)> {
+        match error {
+            TypeError::IncompatibleReassignment {
+                expected_type,
+                actual_type,
+                ..
+            } => vec![("expected", *expected_type), ("actual", *actual_type)],
+
+            TypeError::BadReturnType {
+                expected, actual, ..
+            }
+            | TypeError::BadArgumentType {
+                expected, actual, ..
+            } => vec![("expected", *expected), ("actual", *actual)],
+
+            TypeError::BadConditionalType { actual, .. } => vec![("actual", *actual)],
+            TypeError::MissingReturn { expected, .. } => vec![("expected", *expected)],
+            TypeError::NotCallable { kind, .. } => vec![("actual", *kind)],
+
+            TypeError::IncompatibleTypes { left, right, .. }
+            | TypeError::BadBinaryOp { left, right, .. } => {
+                vec![("left", *left), ("right", *right)]
+            }
+
+            TypeError::InvalidAttributeAccess { base, .. } => vec![("base", *base)],
+
+            TypeError::UnboundLocal { .. }
+            | TypeError::DuplicateParameters
+            | TypeError::ReturnOutsideFunction(_)
+            | TypeError::UnknownType { .. }
+            | TypeError::UndefinedVariable { .. }
+            | TypeError::SusArgumentLength { .. }
+            | TypeError::NotAFunction
+            | TypeError::Unsupported { .. }
+            | TypeError::BadCallArguments { .. } => vec![],
+        }
+    }
+
+    fn diagnostic_span(
+        &self,
+        (mref, span): &LocatedSpan,
+        is_primary: bool,
+        label: Option<String>,
+    ) -> DiagnosticSpan {
+        let source = self
+            .module_sources
+            .get(mref)
+            .map(|s| s.value().to_string())
+            .unwrap_or_default();
+
+        DiagnosticSpan::new(
+            self.module_path(*mref),
+            &source,
+            span.clone(),
+            is_primary,
+            label,
+        )
+    }
+
+    /// Describe an error as machine-readable diagnostics, there is one per type or syntax error.
+    pub fn diagnostics_of(&self, error: &MontyError) -> Vec<Diagnostic> {
+        match error {
+            MontyError::TypeError { module, error } => {
+                vec![self.type_error_diagnostic(*module, error)]
+            }
+
+            MontyError::TypeErrors(errors) => errors
+                .iter()
+                .map(|(module, error)| self.type_error_diagnostic(*module, error))
+                .collect(),
+
+            MontyError::Parse { module, errors } => errors
+                .iter()
+                .map(|parse_error| {
+                    let mut diagnostic =
+                        Diagnostic::error(error.code(), parse_error.message.clone());
+
+                    diagnostic.file = Some(self.module_path(*module));
+                    diagnostic.spans = vec![self.diagnostic_span(
+                        &(*module, parse_error.span.clone()),
+                        true,
+                        Some(parse_error.message.clone()),
+                    )];
+                    diagnostic.notes.extend(expected_note(parse_error));
+
+                    diagnostic
+                })
+                .collect(),
+
+            MontyError::InterpreterException {
+                module,
+                kind,
+                message,
+                traceback,
+            } => {
+                let mut diagnostic = Diagnostic::error(
+                    error.code(),
+                    match message {
+                        Some(message) => format!("{}: {}", kind, message),
+                        None => kind.clone(),
+                    },
+                );
+
+                diagnostic.file = Some(self.module_path(*module));
+                diagnostic.spans = Self::frame_labels(traceback)
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, (span, _, message))| {
+                        self.diagnostic_span(span, ix == 0, Some(message.clone()))
+                    })
+                    .collect();
+
+                vec![diagnostic]
+            }
+
+            MontyError::ComptimeLimitExceeded { module, .. } => {
+                let (primary, labels, note) = self.describe_limit_exceeded(error);
+
+                let mut diagnostic = Diagnostic::error(error.code(), error.to_string());
+
+                diagnostic.file = Some(self.module_path(*module));
+                diagnostic.notes = note.into_iter().collect();
+                diagnostic.spans = labels
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, (span, _, message))| {
+                        let is_primary = primary.as_ref() == Some(span) && ix == 0;
+                        self.diagnostic_span(span, is_primary, Some(message.clone()))
+                    })
+                    .collect();
+
+                vec![diagnostic]
+            }
+
+            error => vec![Diagnostic::error(error.code(), error.to_string())],
+        }
+    }
+
+    fn type_error_diagnostic(&self, mref: ModuleRef, error: &TypeError) -> Diagnostic {
+        let (primary, labels, note) = self.describe_type_error(error, false);
+
+        let mut diagnostic = Diagnostic::error(error.code(), error.to_string());
+
+        diagnostic.file = Some(self.module_path(primary.as_ref().map_or(mref, |(m, _)| *m)));
+        diagnostic.notes = note.into_iter().collect();
+
+        diagnostic.spans = labels
+            .iter()
+            .enumerate()
+            .map(|(ix, (span, _, message))| {
+                let is_primary = primary.as_ref() == Some(span) && ix == 0;
+                self.diagnostic_span(span, is_primary, Some(message.clone()))
+            })
+            .collect();
+
+        diagnostic.types = Self::type_error_types(error)
+            .into_iter()
+            .map(|(role, type_id)| (role, self.display_type_of(type_id)))
+            .collect();
+
+        diagnostic
+    }
+}
@@ -1,5 +1,6 @@
 use montyc_hlirt::PyResultExt;
 
+use super::sandbox::Access;
 use super::*;
 
 impl RuntimeHost for &SessionContext {
@@ -30,15 +31,23 @@ impl RuntimeHost for &SessionContext {
     }
 
     fn try_read_file(&self, path: &Path) -> io::Result<String> {
+        self.io_policy.check(Access::ReadFile, path)?;
+
         std::fs::read_to_string(path)
     }
 
     fn try_read_directory(&self, path: &Path) -> io::Result<Box<[io::Result<std::fs::DirEntry>]>> {
+        self.io_policy.check(Access::ReadDirectory, path)?;
+
         path.read_dir().map(|dir| dir.collect())
     }
 
     fn try_get_cwd(&self) -> io::Result<PathBuf> {
-        std::env::current_dir()
+        let cwd = std::env::current_dir()?;
+
+        self.io_policy.check(Access::GetCwd, &cwd)?;
+
+        Ok(cwd)
     }
 }
 
@@ -57,7 +66,7 @@ impl RuntimeHostExt for &SessionContext {
 
     fn import_module_spec(
         &mut self,
-        _mref: ModuleRef,
+        mref: ModuleRef,
         path: &[SpanRef],
         relative: usize,
     ) -> Box<
@@ -74,21 +83,7 @@ impl RuntimeHostExt for &SessionContext {
             .collect::<Vec<_>>()
             .join(".");
 
-        if relative != 0 {
-            match path {
-                // ignore the relative part of an import that looks at __monty.
-                [head, ..] if self.static_names[&head.group()] == "__monty" => (),
-
-                _ => {
-                    return Box::new(move |_| {
-                        PyException::import_error()
-                            .set_message("relative imports are not supported.")
-                            .into()
-                    });
-                }
-            }
-        }
-
+        // the relative part of an import that looks at __monty is ignored.
         if name == "__monty" {
             return Box::new(
                 |ecx: &mut montyc_hlirt::ctx::EvaluationContext<'_, '_, Self>| {
@@ -97,115 +92,14 @@ impl RuntimeHostExt for &SessionContext {
             );
         }
 
-        fn find_spec(
-            ecx: &mut montyc_hlirt::ctx::EvaluationContext<&SessionContext>,
-            name: String,
-        ) -> PyResult<Option<ObjectId>> {
-            let sys = ecx.rt.singletons.sys;
-            let none_v = ecx.rt.singletons.none_v;
-
-            let sys_dict = ecx.rt.objects.with_object(sys, |sys| match sys {
-                PyValue::Module { inner, .. } => inner.__dict__.clone(),
-                _ => unreachable!(),
-            });
-
-            let sys_meta_path = sys_dict.get(ecx.rt.hash("meta_path")).unwrap().1;
-            let mut meta_path = ecx.iter_object(sys_meta_path);
-
-            let find_spec = ecx.new_string("find_spec").trace()?;
-            let name_s = ecx.new_string(&name).trace()?;
-
-            while let Some(elem) = meta_path.next(ecx) {
-                let elem = elem.trace()?;
-
-                let spec = ecx
-                    .call_method_object(elem, find_spec, &[name_s, none_v, none_v])
-                    .trace()?;
-
-                if spec == none_v {
-                    continue;
-                }
-
-                return Ok(Some(spec));
-            }
-
-            return Ok(None);
-        }
-
-        fn gcd_import<S>(
-            name: S,
-        ) -> impl FnOnce(&mut montyc_hlirt::ctx::EvaluationContext<&SessionContext>) -> PyResult<ObjectId>
-        where
-            S: ToString,
-        {
-            let name = name.to_string();
-
-            move |ecx| {
-                let sys = ecx.rt.singletons.sys;
-
-                let sys_dict = ecx.rt.objects.with_object(sys, |sys| match sys {
-                    PyValue::Module { inner, .. } => inner.__dict__.clone(),
-                    _ => unreachable!(),
-                });
-
-                let sys_modules = sys_dict.get(ecx.rt.hash("modules")).unwrap().1;
-
-                match name.rsplit_once(".") {
-                    Some((parent, _child)) => {
-                        // import the parent module first.
-
-                        let parent_in_sys_modules = {
-                            let rt = ecx.runtime();
-                            let parent_h = rt.hash(parent);
-
-                            rt.objects
-                                .with_object(sys_modules, |this| match this {
-                                    PyValue::Dict(dct) => dct.get(parent_h),
-                                    _ => unreachable!(),
-                                })
-                                .map(|kv| kv.1)
-                        };
-
-                        let _parent_module = match parent_in_sys_modules {
-                            Some(module) => module,
-                            None => gcd_import(parent)(ecx).trace()?,
-                        };
-
-                        {
-                            let rt = ecx.runtime();
-                            let name_h = rt.hash(name);
-
-                            if let Some(module) = rt
-                                .objects
-                                .with_object(sys_modules, |this| match this {
-                                    PyValue::Dict(dct) => dct.get(name_h),
-                                    _ => None,
-                                })
-                                .map(|kv| kv.1)
-                            {
-                                return Ok(module);
-                            }
-                        }
-
-                        todo!()
-                    }
-
-                    None => {
-                        // no dots, no parent module.
-                        match find_spec(ecx, name).trace()? {
-                            Some(spec) => Ok(spec),
-                            None => {
-                                return PyException::import_error()
-                                    .set_message("Module not found.")
-                                    .into()
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        Box::new(move |ecx| {
+            let name = match relative {
+                0 => name,
+                level => crate::import::resolve_name(ecx, mref, &name, level).trace()?,
+            };
 
-        Box::new(gcd_import(name))
+            crate::import::import_module(ecx, &name).trace()
+        })
     }
 }
 
@@ -214,17 +108,12 @@ impl AcceptInput<&str, FlatCode> for &SessionContext {
         let mut modules = self.modules.lock();
         let mref = (modules.reserve() as u32).into();
 
-        let module_ast = montyc_parser::parse(
-            &input,
-            montyc_parser::comb::module,
-            Some(self.spanner.clone()),
-            mref,
-        );
-
         let _ = self
             .module_sources
             .insert(mref, input.to_string().into_boxed_str());
 
+        let module_ast = self.parse_ast(input, mref)?;
+
         let _ = self.module_asts.insert(mref, Rc::new(module_ast));
 
         self.accept_input(mref)
@@ -1,7 +1,8 @@
 use std::cell::{RefCell, RefMut};
-use std::convert::TryInto;
+use std::num::NonZeroU64;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 use std::{fmt, io, panic, rc::Rc};
 
 use ahash::AHashSet;
@@ -15,15 +16,16 @@ use montyc_hlirt::rt::singletons::Singletons;
 use parking_lot::Mutex;
 
 use montyc_core::dict::PyDictRaw;
+use montyc_core::opts::ParserBackend;
 use montyc_core::utils::SSAMap;
 use montyc_core::{
-    patma, BuiltinType, LocalTypeId, MapT, ModuleData, ModuleRef, MontyError, MontyResult,
-    PythonType, SpanRef, TaggedValueId, Type, TypeId, TypingConstants, TypingContext, Value,
-    ValueId, FUNCTION, MODULE,
+    patma, BuiltinType, ComptimeLimit, LocalTypeId, MapT, ModuleData, ModuleRef, MontyError,
+    MontyResult, PythonType, SpanRef, TaggedValueId, TraceFrame, Type, TypeId, TypingConstants,
+    TypingContext, Value, ValueId, FUNCTION, MODULE,
 };
-use montyc_flatcode::FlatCode;
-use montyc_hlirt::ctx::{CallCx, EvalGlue};
-use montyc_hlirt::object::{FuncLike, IntoPyValue, ObjectBuilder, PyValue, ReadyCallable};
+use montyc_flatcode::{raw_inst::Parameter, FlatCode};
+use montyc_hlirt::ctx::CallCx;
+use montyc_hlirt::object::{AnyFunc, FuncLike, IntoPyValue, ObjectBuilder, PyValue, ReadyCallable};
 use montyc_hlirt::rt::{AcceptInput, Runtime, RuntimeHost, RuntimeHostExt};
 use montyc_hlirt::{argparse, ObjectId, PyException, PyResult, PyResultExt};
 use montyc_parser::{AstObject, SpanInterner};
@@ -32,8 +34,12 @@ use montyc_query::Queries;
 use crate::prelude::*;
 use crate::value_store::{GVKey, GlobalValueStore};
 
+mod diagnostic;
+mod parser2;
 pub mod host;
+pub mod native;
 pub mod query;
+pub mod sandbox;
 
 /// Statically known identifiers used to seed the span interner.
 const MAGICAL_NAMES: &[&'static str] = &[
@@ -49,6 +55,7 @@ const MAGICAL_NAMES: &[&'static str] = &[
     "type",
     "id",
     "isinstance",
+    "len",
     "list",
     "str",
     "bool",
@@ -65,8 +72,8 @@ const MAGICAL_NAMES: &[&'static str] = &[
     "__ror__",
     "__and__",
     "__rand__",
-    "__div__",
-    "__rdiv__",
+    "__truediv__",
+    "__rtruediv__",
     "__ne__",
     "__rne__",
     "__getitem__",
@@ -325,7 +332,24 @@ impl TypingContext for TypingData {
                         )
                     ),
 
-                    PythonType::List { .. } => todo!(),
+                    PythonType::List { inner } => write!(
+                        f,
+                        "List[{}]",
+                        TypeFormatter::display(*inner, *tcx, *type_id_of_val)
+                    ),
+
+                    PythonType::Dict { key, value } => write!(
+                        f,
+                        "Dict[{}, {}]",
+                        TypeFormatter::display(*key, *tcx, *type_id_of_val),
+                        TypeFormatter::display(*value, *tcx, *type_id_of_val)
+                    ),
+
+                    PythonType::Set { inner } => write!(
+                        f,
+                        "Set[{}]",
+                        TypeFormatter::display(*inner, *tcx, *type_id_of_val)
+                    ),
 
                     PythonType::Union { members } => write!(
                         f,
@@ -367,6 +391,7 @@ impl TypingContext for TypingData {
                     PythonType::Generic { .. } => todo!(),
                     PythonType::Builtin { inner } => match inner {
                         BuiltinType::Bytes => write!(f, "<bytes>"),
+                        BuiltinType::Range => write!(f, "<range>"),
                         BuiltinType::Int => write!(f, "<int>"),
                         BuiltinType::Float => write!(f, "<float>"),
                         BuiltinType::Str => write!(f, "<str>"),
@@ -380,6 +405,9 @@ impl TypingContext for TypingData {
                         BuiltinType::TSelf => write!(f, "<self>"),
                         BuiltinType::UntypedFunc => write!(f, "<callable(unknown) -> unknown>"),
                         BuiltinType::UntypedTuple => write!(f, "<tuple[unknown, ...]>"),
+                        BuiltinType::UntypedList => write!(f, "<list[unknown]>"),
+                        BuiltinType::UntypedDict => write!(f, "<dict[unknown, unknown]>"),
+                        BuiltinType::UntypedSet => write!(f, "<set[unknown]>"),
                         BuiltinType::AnyType => write!(f, "<any>"),
                         BuiltinType::U8 => write!(f, "<u8>"),
                         BuiltinType::U16 => write!(f, "<u16>"),
@@ -438,6 +466,9 @@ pub struct SessionContext {
     /// An interpreter runtime for consteval.
     pub(crate) const_runtime: Rc<RefCell<montyc_hlirt::rt::Runtime>>,
 
+    /// The policy consulted whenever consteval code performs I/O.
+    pub(crate) io_policy: sandbox::IoPolicy,
+
     /// Used to keep track of type information.
     pub(crate) typing_context: TypingData,
 
@@ -453,6 +484,7 @@ impl SessionContext {
         let const_runtime = Rc::new(RefCell::new(Runtime::new_uninit()));
 
         Self {
+            io_policy: sandbox::IoPolicy::new(&opts),
             opts,
             spanner: SpanInterner::new(),
             static_names: Default::default(),
@@ -617,6 +649,10 @@ impl SessionContext {
                 (TypingConstants::Str, |s| &mut s.string_class),
                 (TypingConstants::UntypedTuple, |s| &mut s.tuple_class),
                 (TypingConstants::UntypedFunc, |s| &mut s.function_class),
+                (TypingConstants::Range, |s| &mut s.range_class),
+                (TypingConstants::UntypedList, |s| &mut s.list_class),
+                (TypingConstants::UntypedDict, |s| &mut s.dict_class),
+                (TypingConstants::UntypedSet, |s| &mut s.set_class),
             ];
 
             log::trace!("[SessionContext::initialize_hlirt] proceeding to overwrite builtin singletons with classes from builtins.py");
@@ -691,9 +727,8 @@ impl SessionContext {
         // then take its module object and register that in `sys.modules` under the qualname `importlib._bootstrap`
         // once that's done we "patch" the module by associating some native procedures defined here with the module.
         //
-        // why do we do this? well because currently there are two major deficiencies at play here:
+        // why do we do this? well because currently there is a major deficiency at play here:
         //
-        //  * the current montyc_parser can NOT handle try/except blocks, (and I think the raise statement isn't a thing in the ast.)
         //  * montyc_hlirt is seriously lacking in defining methods for builtin types so there's a bunch of stuff we cant do.
         //
         // until this gets fixed we gotta do this.
@@ -705,7 +740,14 @@ impl SessionContext {
                 .map_err(RuntimeError::Host)?
         };
 
-        crate::import::setup(rt, bootstrap)?;
+        let search_path: Vec<_> = self
+            .opts
+            .search_path()
+            .iter()
+            .map(|root| root.path.clone())
+            .collect();
+
+        crate::import::setup(rt, bootstrap, &search_path)?;
 
         Ok(())
     }
@@ -722,20 +764,16 @@ impl SessionContext {
     }
 
     #[inline]
-    fn parse_module(&self, source: &str, path: &Path, module_name: &str) -> ModuleRef {
+    pub(crate) fn parse_module(
+        &self,
+        source: &str,
+        path: &Path,
+        module_name: &str,
+    ) -> MontyResult<ModuleRef> {
         let mut modules = self.modules.lock();
         let mref = (modules.reserve() as u32).into();
 
-        let module_ast = montyc_parser::parse(
-            &source,
-            montyc_parser::comb::module,
-            Some(self.spanner.clone()),
-            mref,
-        );
-
-        // if module_ast.is_err() {
-        //     self.modules.cancel_reserve(mref).unwrap();
-        // }
+        let module_ast = self.parse_ast(source, mref);
 
         let module = ModuleData {
             path: path.to_path_buf(),
@@ -748,10 +786,36 @@ impl SessionContext {
             .module_sources
             .insert(mref, source.to_string().into_boxed_str());
 
-        let _ = self.module_asts.insert(mref, Rc::new(module_ast));
+        // the module data and source are kept around even if parsing failed so the error can be rendered.
         let _ = modules.try_set_value(mref, module).unwrap();
 
-        mref
+        let _ = self.module_asts.insert(mref, Rc::new(module_ast?));
+
+        Ok(mref)
+    }
+
+    /// Parse `source` as the module `mref` with the backend selected by `--parser`.
+    #[inline]
+    pub(crate) fn parse_ast(
+        &self,
+        source: &str,
+        mref: ModuleRef,
+    ) -> MontyResult<montyc_parser::ast::Module> {
+        let module_ast = match self.opts.parser() {
+            ParserBackend::Nom => montyc_parser::try_parse(
+                source,
+                montyc_parser::comb::module,
+                Some(self.spanner.clone()),
+                mref,
+            ),
+
+            ParserBackend::Chumsky => parser2::parse_module(source, &self.spanner, mref),
+        };
+
+        module_ast.map_err(|errors| MontyError::Parse {
+            module: mref,
+            errors,
+        })
     }
 
     #[inline]
@@ -759,8 +823,8 @@ impl SessionContext {
         &self,
         path: impl AsRef<Path>,
         module_name: impl AsRef<str>,
-        f: impl Fn(&Self, ModuleRef) -> T,
-    ) -> io::Result<T> {
+        f: impl Fn(&Self, ModuleRef) -> MontyResult<T>,
+    ) -> MontyResult<T> {
         let path = path.as_ref();
 
         if let Some(_) = self
@@ -772,10 +836,10 @@ impl SessionContext {
         {
             log::error!("[global_context:load_module_with] Found a module with the same path as one we're trying to load! path={:?}", path);
 
-            return Err(io::Error::new(
+            return Err(MontyError::IO(io::Error::new(
                 io::ErrorKind::AlreadyExists,
                 "Attempted to load a module with a path that is already loaded.",
-            ));
+            )));
         }
 
         log::debug!(
@@ -783,20 +847,21 @@ impl SessionContext {
             path
         );
 
-        let source = match std::fs::read_to_string(path) {
+        // modules are read through the host so the sandbox applies to imports as well.
+        let source = match self.try_read_file(path) {
             Ok(st) => st.into_boxed_str(),
             Err(why) => {
                 log::error!(
                     "[global_context:load_module_with] Failed to read path contents! {:?}",
                     why
                 );
-                return Err(why);
+                return Err(MontyError::IO(why));
             }
         };
 
-        let mref = self.parse_module(&source, path, module_name.as_ref());
+        let mref = self.parse_module(&source, path, module_name.as_ref())?;
 
-        Ok(f(self, mref))
+        f(self, mref)
     }
 
     /// Subject a module to evaluation.
@@ -806,29 +871,101 @@ impl SessionContext {
         let rt = Rc::clone(&self.const_runtime);
         let mut rt = rt.borrow_mut();
 
+        rt.set_heap_limit(self.opts.comptime_heap_limit());
+
         let mut this = self;
         let ecx = match rt.eval(&mut this, mref) {
             Ok(cx) => cx,
             Err(err) => match err {
-                RuntimeError::Host(_) => todo!(),
-                RuntimeError::Py(_) => todo!(),
+                RuntimeError::Host(err) => return Err(err),
+                RuntimeError::Py(exc) => return Err(self.exception_to_error(mref, &exc)),
                 RuntimeError::ModuleAlreadyLoaded => todo!(),
             },
         };
 
         *ecx.host.pot.dirty.lock() = true;
 
+        let name = self.module_name(mref);
+        let ticks = self.opts.comptime_ticks(&name).and_then(NonZeroU64::new);
+        let deadline = self.opts.comptime_timeout().map(|t| Instant::now() + t);
+
         let module = match ecx
-            .set_upper_tick_bound(0x1000.try_into().ok())
+            .set_upper_tick_bound(ticks)
+            .set_deadline(deadline)
             .run_until_complete()
         {
             Ok(module) => module,
-            Err(exc) => todo!("runtime exception! {:#?}", exc),
+            Err(exc) => return Err(self.exception_to_error(mref, &exc)),
         };
 
+        // nothing is executing anymore, so everything that isn't reachable from a module or
+        // pinned by the value store is garbage.
+        rt.collect_garbage();
+
         Ok(module)
     }
 
+    /// The `__name__` of a module.
+    fn module_name(&self, mref: ModuleRef) -> String {
+        self.modules
+            .lock()
+            .get(mref)
+            .map(|module| module.name.clone())
+            .unwrap_or_default()
+    }
+
+    /// Convert an exception raised while evaluating `mref` into an error.
+    fn exception_to_error(&self, mref: ModuleRef, exc: &PyException) -> MontyError {
+        log::debug!(
+            "[SessionContext::eval_module] runtime exception! {:#?}",
+            exc
+        );
+
+        let name = self.module_name(mref);
+
+        let limit = if exc.is_exceeded_ticks() {
+            self.opts.comptime_ticks(&name).map(ComptimeLimit::Ticks)
+        } else if exc.is_exceeded_deadline() {
+            self.opts.comptime_timeout().map(ComptimeLimit::Timeout)
+        } else {
+            None
+        };
+
+        let frames = exc
+            .frames()
+            .iter()
+            .map(|frame| TraceFrame {
+                module: frame.mref,
+                name: frame.name.as_ref().map(|name| name.to_string()),
+                span: frame.span.clone(),
+            })
+            .collect();
+
+        if let Some(limit) = limit {
+            return MontyError::ComptimeLimitExceeded {
+                module: mref,
+                name,
+                limit,
+                frames,
+            };
+        }
+
+        let message = match (exc.message(), exc.missing_name()) {
+            (Some(message), _) => Some(message.to_string()),
+            (None, Some(name)) => self
+                .resolve_sref_as_str(name)
+                .map(|name| format!("name {:?} is not defined", name)),
+            (None, None) => None,
+        };
+
+        MontyError::InterpreterException {
+            module: mref,
+            kind: exc.kind().to_string(),
+            message,
+            traceback: frames,
+        }
+    }
+
     /// Add a new module, by its path, to the current context.
     ///
     /// This will load the module, parse it, and consteval it before returning.
@@ -852,7 +989,6 @@ impl SessionContext {
 
             Ok((mref, module))
         })
-        .map_err(|err| MontyError::IO(err))?
     }
 
     /// A "fancy path" is any colon-deliminated string describing a path to a function through it's modules.
@@ -974,6 +1110,75 @@ impl SessionContext {
         Ok(TaggedValueId::func(func))
     }
 
+    /// Typecheck every function defined in the module `mref`, including the methods of classes defined there.
+    ///
+    /// Unlike `call_graph_of` this does not require an entry point and no `CgInst`s are
+    /// emitted (when the options are `Check`.) Type errors from every function are collected
+    /// and returned sorted by module and location, any other error is propagated immediately.
+    ///
+    pub fn typecheck_module(
+        &self,
+        mref: ModuleRef,
+    ) -> MontyResult<Vec<(ModuleRef, montyc_core::TypeError)>> {
+        let store = &self.value_store;
+        let rt = Rc::clone(&self.const_runtime);
+
+        let functions = {
+            let rt = rt.borrow();
+            let module = store
+                .alloc_id_of(mref.resolve(store).ok_or(MontyError::None)?)
+                .ok_or(MontyError::None)?;
+
+            let module_dict = rt.objects.with_object(module, |val| match val {
+                PyValue::Module { inner, .. } => inner.__dict__.clone(),
+                _ => unreachable!(),
+            });
+
+            let mut functions = vec![];
+            let mut dicts = vec![module_dict];
+
+            while let Some(dict) = dicts.pop() {
+                for (_, (_, value)) in dict.iter() {
+                    rt.objects.with_object(*value, |val| match val {
+                        PyValue::Function {
+                            body: AnyFunc::Code { module, seq_id },
+                            ..
+                        } if module.mref == mref && !module.sequences()[*seq_id].is_stubbed() => {
+                            functions.push(*value)
+                        }
+
+                        PyValue::Class { inner, .. } => dicts.push(inner.__dict__.clone()),
+                        _ => (),
+                    });
+                }
+            }
+
+            functions.sort();
+            functions.dedup();
+            functions
+        };
+
+        let mut errors = vec![];
+
+        for object in functions {
+            let value = store.get_by_assoc(object).ok_or(MontyError::None)?;
+
+            if self.get_function(value)?.is_extern {
+                continue;
+            }
+
+            match self.get_function_cg_cfg(TaggedValueId::func(value)) {
+                Ok(_) => (),
+                Err(MontyError::TypeErrors(mut errs)) => errors.append(&mut errs),
+                Err(err) => return Err(err),
+            }
+        }
+
+        crate::typeck::sort_type_errors(&mut errors);
+
+        Ok(errors)
+    }
+
     /// Serialize the runtime object graph into a consumable format for type checking and analysis.
     fn stir_the_pot(&self) -> MontyResult<()> {
         let mut dirty_guard = match self.pot.dirty.try_lock() {
@@ -1047,6 +1252,9 @@ impl SessionContext {
             let value_id = store.insert(Value::default());
 
             store.assoc(*id, value_id);
+
+            // the store now refers to the object so it must outlive any garbage collection.
+            rt.borrow_mut().pin(*id);
         }
 
         for (id, refs) in new_objects.drain() {
@@ -1188,7 +1396,12 @@ impl SessionContext {
                             let mut param_t = vec![];
                             let mut params = params.into_iter().peekable();
 
-                            if let Some((var, None)) = params.peek() {
+                            if let Some(Parameter {
+                                name: var,
+                                annotation: None,
+                                ..
+                            }) = params.peek()
+                            {
                                 if Queries::spanref_to_str(self, *var)? == "self" {
                                     param_t.push(TypingConstants::TSelf);
                                     let _ = params.next();
@@ -1199,14 +1412,15 @@ impl SessionContext {
                                 "[SessionContext::object_type]  resolving parameter annotations",
                             );
 
-                            for (name, ann) in params {
+                            // `*args` and `**kwargs` only take excess arguments, they're not part of the signature.
+                            for param in params.filter(|param| !param.kind.is_variadic()) {
                                 log::trace!(
                                     "[SessionContext::object_type]    resolving {:?} : {:?}",
-                                    name,
-                                    ann,
+                                    param.name,
+                                    param.annotation,
                                 );
 
-                                let type_id = match ann {
+                                let type_id = match &param.annotation {
                                     Some(o) => self.resolve_type_annotation(rt, *o)?,
                                     None => TypingConstants::None,
                                 };
@@ -1294,6 +1508,7 @@ impl SessionContext {
             | type_class -> Type
             | object_class -> Object
             | bytes_class -> Bytes
+            | range_class -> Range
         ];
 
         Err(MontyError::None)
@@ -0,0 +1,152 @@
+//! Rust-backed modules that embedders provide to comptime code.
+//!
+//! A `NativeModule` is a set of functions, classes and constants that gets
+//! registered into `sys.modules` with `SessionContext::register_native_module`
+//! and is then importable like any other module, i.e. a build system can expose
+//! its version, git hash and feature flags to module-level code.
+//!
+
+use montyc_core::{CLASS, MODULE};
+use montyc_hlirt::object::{IntoPyValue, ObjectBuilder, PyValue, ReadyCallable};
+use montyc_hlirt::rt::Runtime;
+use montyc_hlirt::{ObjectId, ObjectSpace, PyException, PyResult};
+
+use super::SessionContext;
+
+type Deferred = Box<dyn FnOnce(&mut Runtime) -> PyResult<ObjectId>>;
+
+/// A named module whose attributes are implemented natively, see `SessionContext::register_native_module`.
+pub struct NativeModule {
+    name: String,
+    builder: ObjectBuilder<{ MODULE }>,
+    deferred: Vec<(String, Deferred)>,
+}
+
+impl NativeModule {
+    /// An empty module that will be importable as `name`.
+    pub fn new<S>(name: S) -> Self
+    where
+        S: ToString,
+    {
+        Self {
+            name: name.to_string(),
+            builder: ObjectBuilder::new(),
+            deferred: Vec::new(),
+        }
+    }
+
+    /// The name the module is registered under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Define a function, its arguments are usually parsed with the `argparse` combinators.
+    pub fn function<S, F>(mut self, name: S, func: F) -> Self
+    where
+        S: ToString,
+        F: Into<ReadyCallable>,
+    {
+        self.builder = self.builder.setattr(name, func.into());
+        self
+    }
+
+    /// Define a class.
+    pub fn class<S>(mut self, name: S, class: ObjectBuilder<{ CLASS }>) -> Self
+    where
+        S: ToString,
+    {
+        self.deferred.push((
+            name.to_string(),
+            Box::new(move |rt| class.synthesise_within(rt)),
+        ));
+
+        self
+    }
+
+    /// Define a constant, i.e. a `String`, `i64`, `f64` or `bool`.
+    pub fn constant<S, V>(self, name: S, value: V) -> Self
+    where
+        S: ToString,
+        V: IntoPyValue + 'static,
+    {
+        // strings are interned so that they compare like the literals in module code.
+        self.setattr_with(name, move |rt| match value.into_py_val() {
+            PyValue::Str(st) => rt.new_string(&st),
+            value => rt.objects.insert(value),
+        })
+    }
+
+    /// Define an attribute whose value is created with the runtime, i.e. a list of strings.
+    pub fn setattr_with<S, F>(mut self, name: S, ctor: F) -> Self
+    where
+        S: ToString,
+        F: FnOnce(&mut Runtime) -> ObjectId + 'static,
+    {
+        self.deferred
+            .push((name.to_string(), Box::new(move |rt| Ok(ctor(rt)))));
+
+        self
+    }
+}
+
+impl SessionContext {
+    /// Register a native module so that comptime code can import it by its name.
+    ///
+    /// Registering a name that is already in `sys.modules` is an `ImportError`.
+    ///
+    pub fn register_native_module(&mut self, module: NativeModule) -> PyResult<ObjectId> {
+        let NativeModule {
+            name,
+            builder,
+            deferred,
+        } = module;
+
+        let mut rt = self.const_runtime.borrow_mut();
+
+        let sys = rt.singletons.sys;
+        let modules_hash = rt.hash("modules");
+        let (_, sys_modules) = rt
+            .objects
+            .with_object(sys, |this| match this {
+                PyValue::Module { inner, .. } => inner.__dict__.get(modules_hash),
+                _ => unreachable!(),
+            })
+            .unwrap();
+
+        let hash = rt.hash(name.as_str());
+
+        let registered = rt.objects.with_object(sys_modules, |this| match this {
+            PyValue::Dict(dict) => dict.get(hash).is_some(),
+            _ => unreachable!(),
+        });
+
+        if registered {
+            return PyException::import_error()
+                .set_message(format!("a module named '{}' is already registered", name))
+                .into();
+        }
+
+        let object = builder
+            .setattr("__name__", name.clone())
+            .synthesise_within(&mut *rt)?;
+
+        let mut attrs = Vec::with_capacity(deferred.len());
+
+        for (attr, ctor) in deferred {
+            let value = ctor(&mut *rt)?;
+            attrs.push(rt.make_kv_pair(&attr, |_| value));
+        }
+
+        rt.setattrs(object, attrs.into_iter())?;
+
+        // sys.modules[name] = object
+        let (hash, key, value) = rt.make_kv_pair(&name, |_| object);
+
+        rt.objects.with_object_mut(sys_modules, |this| match this {
+            PyValue::Dict(dict) => dict.insert(hash, (key, value)),
+            _ => unreachable!(),
+        });
+
+        Ok(object)
+    }
+}
@@ -0,0 +1,544 @@
+//! Parsing modules with `montyc_parser2`, selected with `--parser chumsky`.
+//!
+//! The trees it produces are translated into the ones of `montyc_parser` so
+//! the rest of the driver does not care which of the two parsed a module.
+//!
+
+use montyc_core::{ModuleRef, ParseError, SpanRef};
+use montyc_parser::{ast, span_interner::BoundMutInterner, spanned::Spanned, SpanInterner};
+use montyc_parser2::{ast as p2, parser::Parseable};
+
+/// Parse `source` as the module `mref`, interning its names into `spanner`.
+pub(super) fn parse_module(
+    source: &str,
+    spanner: &SpanInterner,
+    mref: ModuleRef,
+) -> Result<ast::Module, Vec<ParseError>> {
+    let interner = montyc_parser2::span_interner::SpanInterner::new();
+
+    let module = p2::Module::parse_with(source, &interner, mref).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|error| ParseError {
+                span: error.span(),
+                message: error.message(source),
+                expected: error.expected(),
+            })
+            .collect::<Vec<_>>()
+    })?;
+
+    let mut translate = Translate {
+        interner,
+        bound: spanner
+            .get(source, mref)
+            .expect("span interner is already borrowed."),
+    };
+
+    Ok(ast::Module {
+        body: translate.block(module.body),
+    })
+}
+
+macro_rules! same_variants {
+    ($value:expr, $ty:ident; $($variant:ident),* $(,)?) => {
+        match $value {
+            $(p2::$ty::$variant => ast::$ty::$variant,)*
+        }
+    };
+}
+
+struct Translate<'source, 'data> {
+    interner: montyc_parser2::span_interner::SpanInterner,
+    bound: BoundMutInterner<'source, 'data>,
+}
+
+impl Translate<'_, '_> {
+    /// Re-intern a span reference of `montyc_parser2` into the session's interner.
+    fn sref(&mut self, sref: SpanRef) -> SpanRef {
+        let data = self
+            .interner
+            .span_data(sref)
+            .expect("span reference was interned by the parser.");
+
+        self.bound.insert(data.range)
+    }
+
+    fn spanned<T, U>(
+        &mut self,
+        spanned: p2::Spanned<T>,
+        f: impl FnOnce(&mut Self, T) -> U,
+    ) -> Spanned<U> {
+        Spanned {
+            span: spanned.span,
+            inner: f(self, spanned.inner),
+        }
+    }
+
+    fn boxed<T, U>(
+        &mut self,
+        spanned: Box<p2::Spanned<T>>,
+        f: impl FnOnce(&mut Self, T) -> U,
+    ) -> Box<Spanned<U>> {
+        Box::new(self.spanned(*spanned, f))
+    }
+
+    fn block(&mut self, body: Vec<p2::Spanned<p2::Statement>>) -> Vec<Spanned<ast::Statement>> {
+        body.into_iter()
+            .map(|stmt| self.spanned(stmt, Self::statement))
+            .collect()
+    }
+
+    fn exprs(&mut self, exprs: Vec<p2::Spanned<p2::Expr>>) -> Vec<Spanned<ast::Expr>> {
+        exprs
+            .into_iter()
+            .map(|expr| self.spanned(expr, Self::expr))
+            .collect()
+    }
+
+    fn statement(&mut self, stmt: p2::Statement) -> ast::Statement {
+        match stmt {
+            p2::Statement::Expr(expr) => ast::Statement::Expr(self.expr(expr)),
+            p2::Statement::FnDef(funcdef) => ast::Statement::FnDef(self.funcdef(funcdef)),
+            p2::Statement::Ret(ret) => ast::Statement::Ret(ast::Return {
+                value: match ret.value {
+                    Ok(value) => Ok(self.spanned(value, Self::expr)),
+                    Err(span) => Err(Spanned {
+                        span: span.span,
+                        inner: (),
+                    }),
+                },
+            }),
+            p2::Statement::Asn(assign) => ast::Statement::Asn(ast::Assign {
+                name: self.spanned(assign.name, Self::primary),
+                value: self.spanned(assign.value, Self::expr),
+                kind: assign.kind.map(|kind| self.spanned(kind, Self::expr)),
+            }),
+            p2::Statement::Ann(ann) => ast::Statement::Ann(ast::Annotation {
+                name: self.spanned(ann.name, Self::atom),
+                kind: self.spanned(ann.kind, Self::expr),
+            }),
+            p2::Statement::Import(import) => ast::Statement::Import(self.import(import)),
+            p2::Statement::Class(klass) => ast::Statement::Class(ast::ClassDef {
+                name: self.spanned(klass.name, Self::atom),
+                bases: self.exprs(klass.bases),
+                decorator_list: klass
+                    .decorator_list
+                    .into_iter()
+                    .map(|dec| self.spanned(dec, Self::primary))
+                    .collect(),
+                body: self.block(klass.body),
+            }),
+            p2::Statement::If(chain) => ast::Statement::If(ast::IfChain {
+                branches: chain
+                    .branches
+                    .into_iter()
+                    .map(|branch| {
+                        self.spanned(branch, |this, branch| ast::If {
+                            test: this.spanned(branch.test, Self::expr),
+                            body: this.block(branch.body),
+                        })
+                    })
+                    .collect(),
+                orelse: chain.orelse.map(|body| self.block(body)),
+            }),
+            p2::Statement::While(while_) => ast::Statement::While(ast::While {
+                test: self.spanned(while_.test, Self::expr),
+                body: self.block(while_.body),
+            }),
+            p2::Statement::For(for_) => ast::Statement::For(ast::For {
+                target: self.spanned(for_.target, Self::primary),
+                iter: self.spanned(for_.iter, Self::expr),
+                body: self.block(for_.body),
+                orelse: for_.orelse.map(|body| self.block(body)),
+            }),
+            p2::Statement::Try(try_) => ast::Statement::Try(ast::Try {
+                body: self.block(try_.body),
+                handlers: try_
+                    .handlers
+                    .into_iter()
+                    .map(|handler| {
+                        self.spanned(handler, |this, handler| ast::ExceptHandler {
+                            kind: handler.kind.map(|kind| this.spanned(kind, Self::expr)),
+                            name: handler.name.map(|name| this.spanned(name, Self::atom)),
+                            body: this.block(handler.body),
+                        })
+                    })
+                    .collect(),
+                orelse: try_.orelse.map(|body| self.block(body)),
+                finalbody: try_.finalbody.map(|body| self.block(body)),
+            }),
+            p2::Statement::With(with) => ast::Statement::With(ast::With {
+                items: with
+                    .items
+                    .into_iter()
+                    .map(|item| {
+                        self.spanned(item, |this, item| ast::WithItem {
+                            context_expr: this.spanned(item.context_expr, Self::expr),
+                            target: item
+                                .target
+                                .map(|target| this.spanned(target, Self::primary)),
+                        })
+                    })
+                    .collect(),
+                body: self.block(with.body),
+                is_async: with.is_async,
+            }),
+            p2::Statement::Raise(raise) => ast::Statement::Raise(ast::Raise {
+                exc: raise.exc.map(|exc| self.spanned(exc, Self::expr)),
+            }),
+            p2::Statement::Pass => ast::Statement::Pass,
+            p2::Statement::Break => ast::Statement::Break,
+            p2::Statement::Continue => ast::Statement::Continue,
+        }
+    }
+
+    fn funcdef(&mut self, funcdef: p2::FunctionDef) -> ast::FunctionDef {
+        ast::FunctionDef {
+            reciever: funcdef.reciever.map(|recv| self.spanned(recv, Self::atom)),
+            name: self.spanned(funcdef.name, Self::atom),
+            args: funcdef.args.map(|args| self.parameters(args)),
+            body: self.block(funcdef.body),
+            decorator_list: funcdef
+                .decorator_list
+                .into_iter()
+                .map(|dec| self.spanned(dec, Self::primary))
+                .collect(),
+            returns: funcdef.returns.map(|ret| self.spanned(ret, Self::expr)),
+        }
+    }
+
+    fn parameters(&mut self, params: Vec<p2::Parameter>) -> Vec<ast::Parameter> {
+        params
+            .into_iter()
+            .map(|param| ast::Parameter {
+                name: self.sref(param.name),
+                annotation: param.annotation.map(|ann| self.spanned(ann, Self::expr)),
+                default: param
+                    .default
+                    .map(|default| self.spanned(default, Self::expr)),
+                kind: param.kind,
+            })
+            .collect()
+    }
+
+    fn import(&mut self, import: p2::Import) -> ast::Import {
+        let mut names = |this: &mut Self, names: Vec<p2::Spanned<p2::Primary>>| {
+            names
+                .into_iter()
+                .map(|name| this.spanned(name, Self::primary))
+                .collect()
+        };
+
+        match import {
+            p2::Import::Names(targets) => ast::Import::Names(names(self, targets)),
+            p2::Import::From {
+                module,
+                names: targets,
+                level,
+            } => ast::Import::From {
+                module: module.map(|module| self.spanned(module, Self::primary)),
+                names: names(self, targets),
+                level,
+            },
+        }
+    }
+
+    fn expr(&mut self, expr: p2::Expr) -> ast::Expr {
+        match expr {
+            p2::Expr::If { test, body, orelse } => ast::Expr::If {
+                test: self.boxed(test, Self::expr),
+                body: self.boxed(body, Self::expr),
+                orelse: self.boxed(orelse, Self::expr),
+            },
+            p2::Expr::BinOp { left, op, right } => ast::Expr::BinOp {
+                left: self.boxed(left, Self::expr),
+                op: Self::infix_op(op),
+                right: self.boxed(right, Self::expr),
+            },
+            p2::Expr::Compare { left, ops } => ast::Expr::Compare {
+                left: self.boxed(left, Self::expr),
+                ops: ops
+                    .into_iter()
+                    .map(|(op, right)| (Self::infix_op(op), self.spanned(right, Self::expr)))
+                    .collect(),
+            },
+            p2::Expr::Unary { op, value } => ast::Expr::Unary {
+                op: same_variants!(op, UnaryOp; Invert, Not, Add, Sub),
+                value: self.boxed(value, Self::expr),
+            },
+            p2::Expr::Named { target, value } => ast::Expr::Named {
+                target: self.spanned(target, Self::atom),
+                value: self.boxed(value, Self::expr),
+            },
+            p2::Expr::Lambda { args, body } => ast::Expr::Lambda {
+                args: self.parameters(args),
+                body: self.boxed(body, Self::expr),
+            },
+            p2::Expr::Primary(primary) => ast::Expr::Primary(self.spanned(primary, Self::primary)),
+        }
+    }
+
+    fn infix_op(op: p2::InfixOp) -> ast::InfixOp {
+        same_variants!(
+            op, InfixOp;
+            Add, Sub, Power, Invert, FloorDiv, MatMult, Mod, Div, Mult, LeftShift, RightShift,
+            NotEq, Eq, And, Or, Xor, Lt, LtE, Gt, GtE, In, NotIn, Is, IsNot,
+        )
+    }
+
+    fn primary(&mut self, primary: p2::Primary) -> ast::Primary {
+        match primary {
+            p2::Primary::Atomic(atom) => ast::Primary::Atomic(self.spanned(atom, Self::atom)),
+            p2::Primary::Subscript { value, index } => ast::Primary::Subscript {
+                value: self.boxed(value, Self::primary),
+                index: self.boxed(index, Self::expr),
+            },
+            p2::Primary::Call { func, args } => ast::Primary::Call {
+                func: self.boxed(func, Self::primary),
+                args: args.map(|args| {
+                    args.into_iter()
+                        .map(|arg| self.spanned(arg, Self::argument))
+                        .collect()
+                }),
+            },
+            p2::Primary::Attribute { left, attr } => ast::Primary::Attribute {
+                left: self.boxed(left, Self::primary),
+                attr: self.spanned(attr, Self::atom),
+            },
+            p2::Primary::Await(inner) => ast::Primary::Await(self.boxed(inner, Self::primary)),
+        }
+    }
+
+    fn argument(&mut self, arg: p2::Argument) -> ast::Argument {
+        match arg {
+            p2::Argument::Positional(value) => {
+                ast::Argument::Positional(self.spanned(value, Self::expr))
+            }
+            p2::Argument::Starred(value) => ast::Argument::Starred(self.spanned(value, Self::expr)),
+            p2::Argument::Keyword(name, value) => {
+                ast::Argument::Keyword(self.sref(name), self.spanned(value, Self::expr))
+            }
+            p2::Argument::DoubleStarred(value) => {
+                ast::Argument::DoubleStarred(self.spanned(value, Self::expr))
+            }
+        }
+    }
+
+    fn comprehensions(&mut self, clauses: Vec<p2::Comprehension>) -> Vec<ast::Comprehension> {
+        clauses
+            .into_iter()
+            .map(|clause| ast::Comprehension {
+                target: self.spanned(clause.target, Self::primary),
+                iter: self.spanned(clause.iter, Self::expr),
+                ifs: self.exprs(clause.ifs),
+            })
+            .collect()
+    }
+
+    fn atom(&mut self, atom: p2::Atom) -> ast::Atom {
+        match atom {
+            p2::Atom::None => ast::Atom::None,
+            p2::Atom::Ellipsis => ast::Atom::Ellipsis,
+            p2::Atom::Int(n) => ast::Atom::Int(n),
+            p2::Atom::Str(sref) => ast::Atom::Str(self.sref(sref)),
+            p2::Atom::Bool(b) => ast::Atom::Bool(b),
+            p2::Atom::Float(n) => ast::Atom::Float(n),
+            p2::Atom::Tuple(values) => ast::Atom::Tuple(self.exprs(values)),
+            p2::Atom::List(values) => ast::Atom::List(self.exprs(values)),
+            p2::Atom::Set(values) => ast::Atom::Set(self.exprs(values)),
+            p2::Atom::Dict(items) => ast::Atom::Dict(
+                items
+                    .into_iter()
+                    .map(|(key, value)| {
+                        (
+                            self.spanned(key, Self::expr),
+                            self.spanned(value, Self::expr),
+                        )
+                    })
+                    .collect(),
+            ),
+            p2::Atom::ListComp(elt, clauses) => {
+                ast::Atom::ListComp(self.boxed(elt, Self::expr), self.comprehensions(clauses))
+            }
+            p2::Atom::SetComp(elt, clauses) => {
+                ast::Atom::SetComp(self.boxed(elt, Self::expr), self.comprehensions(clauses))
+            }
+            p2::Atom::DictComp(item, clauses) => {
+                let (key, value) = *item;
+                let item = (
+                    self.spanned(key, Self::expr),
+                    self.spanned(value, Self::expr),
+                );
+
+                ast::Atom::DictComp(Box::new(item), self.comprehensions(clauses))
+            }
+            p2::Atom::Comment(sref) => ast::Atom::Comment(self.sref(sref)),
+            p2::Atom::Name(sref) => ast::Atom::Name(self.sref(sref)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use montyc_core::{ModuleRef, SpanRef};
+    use montyc_parser::{ast, SpanInterner};
+
+    /// Source using every kind of statement and expression both parsers understand.
+    const CORPUS: &str = r#"# a comment
+import a, b.c
+from . import d
+from ..e.f import g, h
+
+@decorator
+@decorator.attr(1, key=2)
+class C(Base, other.Base):
+    x: int
+    y = 1
+    z: int = 2
+
+    def method(self, a, b: int = 1, *args, c, d=2, **kwargs) -> None:
+        return
+
+    def positional(a, b, /, c):
+        return a
+
+def f():
+    pass
+
+if a:
+    pass
+elif b:
+    x = 1
+else:
+    y = 2
+
+while x < 10:
+    x = x + 1
+    if x == 5:
+        break
+    continue
+
+for i in range(10):
+    pass
+else:
+    pass
+
+for k, (a, b) in items:
+    pass
+
+try:
+    raise ValueError("bad")
+except ValueError as exc:
+    raise
+except (KeyError, IndexError):
+    pass
+except:
+    pass
+else:
+    pass
+finally:
+    pass
+
+with a as b, c:
+    pass
+
+async with a:
+    pass
+
+x = 1 if y else 2
+x = a + b - c * d / e // f % g ** h @ i
+x = a << b >> c & d | e ^ f
+x = a or b
+x = a < b <= c > d >= e == f != g
+x = a in b
+x = a not in b
+x = a is b
+x = a is not b
+x = -a + +b + ~c
+x = not a
+f = lambda: 0
+f = lambda a, b=1, *args, c, **kwargs: a
+x = f(a, *b, c=d, **e)
+x = a[0]
+x = a.b.c
+x = a.b(c)[d]
+x = await a
+x = None
+x = ...
+x = 1
+x = "string"
+x = True
+x = False
+x = 1.5
+x = (1,)
+x = (1, 2)
+x = []
+x = [1, 2]
+x = {1, 2}
+x = {}
+x = {1: 2, 3: 4}
+x = [i for i in range(10) if i if i > 2 for j in i]
+x = {i for i in a}
+x = {k: v for k in a}
+x = {k: v for k, v in a}
+"#;
+
+    /// Debug-print `module` with every span reference replaced by the text it points at.
+    ///
+    /// The two parsers intern names in different orders, so the references themselves differ.
+    ///
+    fn normalized(module: &ast::Module, spanner: &SpanInterner, source: &str) -> String {
+        let debug = format!("{:?}", module);
+        let mut output = String::with_capacity(debug.len());
+        let mut rest = debug.as_str();
+
+        while let Some(start) = rest.find("SpanRef(") {
+            output.push_str(&rest[..start]);
+            rest = &rest[start + "SpanRef(".len()..];
+
+            let end = rest.find(')').unwrap();
+            let (group, distinct) = rest[..end].split_once(", ").unwrap();
+            let sref = SpanRef::from((
+                group.parse::<u32>().unwrap(),
+                distinct.parse::<u32>().unwrap(),
+            ));
+
+            let text = spanner
+                .spanref_to_str(sref, |_, range| source.get(range))
+                .unwrap();
+
+            output.push_str(&format!("{:?}", text));
+            rest = &rest[end + 1..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    #[test]
+    fn both_parsers_agree() {
+        let mref = ModuleRef(1);
+
+        let nom = SpanInterner::new();
+        let expected =
+            montyc_parser::try_parse(CORPUS, montyc_parser::comb::module, Some(nom.clone()), mref)
+                .expect("corpus should parse with nom.");
+
+        let chumsky = SpanInterner::new();
+        let actual =
+            super::parse_module(CORPUS, &chumsky, mref).expect("corpus should parse with chumsky.");
+
+        let expected = normalized(&expected, &nom, CORPUS);
+        let actual = normalized(&actual, &chumsky, CORPUS);
+
+        // compare node by node first so a failure points at the node that differs.
+        for (expected, actual) in expected
+            .split("Spanned { span: ")
+            .zip(actual.split("Spanned { span: "))
+        {
+            assert_eq!(expected, actual);
+        }
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -1,4 +1,6 @@
-use montyc_core::{codegen::CgInst, patma, Function, Property, TaggedValueId, TypeId, ValueId};
+use montyc_core::{
+    codegen::CgInst, patma, Function, Property, PropertyValue, TaggedValueId, TypeId, ValueId,
+};
 use montyc_hlirt::object::AnyFunc;
 use montyc_parser::AstNode;
 
@@ -35,11 +37,22 @@ impl Queries for SessionContext {
                         .unwrap()
                         .unwrap();
 
+                    let rt = self.const_runtime.borrow();
+
+                    // builtin classes are typed as `Type[<builtin>]` so their instances can be constructed.
+                    if let Ok(of) = self.klass_to_instance_type(&*rt, object_id) {
+                        let ty = self.typing_context.insert(PythonType::Type { of }.into());
+
+                        self.value_store.with_metadata_mut(val, |m| {
+                            m.type_id.replace(ty);
+                        });
+
+                        return Ok(ty);
+                    }
+
                     let mut ty = Type::new_class(object_id, None);
 
-                    for (_, (k, v)) in self
-                        .const_runtime
-                        .borrow()
+                    for (_, (k, v)) in rt
                         .objects
                         .with_object(object_id, |val| match val {
                             PyValue::Class { inner, .. } => inner.__dict__.clone(),
@@ -47,13 +60,14 @@ impl Queries for SessionContext {
                         })
                         .iter()
                     {
-                        let attr_name = self
-                            .const_runtime
-                            .borrow()
+                        let attr_name = rt
                             .objects
                             .with_object(*k, |m| m.as_str().unwrap().to_string());
 
-                        let property = Property::new(type_id, value);
+                        let value = self.value_store.get_by_assoc(*v).ok_or(MontyError::None)?;
+                        let type_id = self.compute_type_of(&*rt, value)?;
+
+                        let property = Property::new(type_id, PropertyValue::Id(value));
 
                         ty.properties.insert(attr_name, property);
                     }
@@ -114,13 +128,19 @@ impl Queries for SessionContext {
     }
 
     fn get_module_flatcode(&self, mref: ModuleRef) -> MontyResult<montyc_flatcode::FlatCode> {
-        fn ast_to_flatcode<T: AstObject>(mref: ModuleRef, ast: &T) -> FlatCode {
+        fn ast_to_flatcode<T: AstObject>(mref: ModuleRef, ast: &T) -> MontyResult<FlatCode> {
             let span = ast.span().unwrap_or(0..0);
             let mut code = montyc_flatcode::FlatCode::new((mref.clone(), span));
 
             ast.visit_with(&mut code, None);
 
-            code
+            match code.errors() {
+                [] => Ok(code),
+                errors => Err(MontyError::Parse {
+                    module: mref,
+                    errors: errors.to_vec(),
+                }),
+            }
         }
 
         match self.value_store.contains(mref) {
@@ -130,9 +150,7 @@ impl Queries for SessionContext {
                     if let Some((_, Some(code))) = &metadata.flatcode {
                         Ok(code.as_ref().clone())
                     } else if let Some(ast) = &metadata.ast {
-                        let code = ast_to_flatcode(mref, ast);
-
-                        Ok(code)
+                        ast_to_flatcode(mref, ast)
                     } else {
                         panic!("module_flatcode Mref has no associated ast/flatcode metadata.")
                     }
@@ -141,9 +159,7 @@ impl Queries for SessionContext {
 
             false => {
                 let ast = self.module_asts.get(&mref).unwrap();
-                let code = ast_to_flatcode(mref, ast.as_ref());
-
-                Ok(code)
+                ast_to_flatcode(mref, ast.as_ref())
             }
         }
     }
@@ -172,14 +188,8 @@ impl Queries for SessionContext {
         {
             Some(cg_flowgraph) => Ok(cg_flowgraph),
             None => {
-                let _ = self.get_function(fid.0)?;
-                let cg_flowgraph = self
-                    .value_store
-                    .with_metadata(fid.0, |m| {
-                        let mut function = m.function.clone().unwrap();
-                        crate::typeck::typecheck(self, &mut function)
-                    })
-                    .unwrap()?;
+                let mut function = self.get_function(fid.0)?;
+                let cg_flowgraph = crate::typeck::typecheck(self, &mut function)?;
 
                 self.value_store.with_metadata_mut(fid.0, |m| {
                     m.cg_flowgraph.replace(cg_flowgraph.clone());
@@ -194,7 +204,9 @@ impl Queries for SessionContext {
 
                     for value in cg_flowgraph.raw_nodes().iter().flat_map(|n| {
                         n.weight.iter().filter_map(|inst| match inst {
-                            CgInst::Use { value, .. } | CgInst::Call { value, .. } => Some(*value),
+                            CgInst::Use { value, .. }
+                            | CgInst::Call { value, .. }
+                            | CgInst::MakeClosure { value, .. } => Some(*value),
                             _ => None,
                         })
                     }) {
@@ -256,6 +268,7 @@ impl Queries for SessionContext {
                     mref: module.mref,
                     name,
                     is_extern,
+                    captures: None,
                 }
             }
 
@@ -309,6 +322,7 @@ impl Queries for SessionContext {
         }
 
         let mut collected_functions = MapT::with_capacity(1024);
+        let mut type_errors = vec![];
         let mut functions_to_process = vec![entry_func];
 
         loop {
@@ -326,7 +340,11 @@ impl Queries for SessionContext {
                 panic!("untyped function in call graph {:#?}", func_type_id);
             }
 
-            let _ = self.get_function_cg_cfg(func_ix)?;
+            match self.get_function_cg_cfg(func_ix) {
+                Ok(_) => (),
+                Err(MontyError::TypeErrors(mut errors)) => type_errors.append(&mut errors),
+                Err(err) => return Err(err),
+            }
             let refs = self
                 .value_store
                 .with_metadata(func_ix.0, |m| m.internal_refs.clone())
@@ -365,6 +383,11 @@ impl Queries for SessionContext {
             collected_functions.insert(func_ix, func_type_id);
         }
 
+        if !type_errors.is_empty() {
+            crate::typeck::sort_type_errors(&mut type_errors);
+            return Err(MontyError::TypeErrors(type_errors));
+        }
+
         collected_functions.insert(entry_func, self.get_type_of(entry_func.0)?);
         collected_functions.shrink_to_fit();
 
@@ -0,0 +1,316 @@
+//! The capability policy consulted whenever comptime code asks the host for I/O.
+//!
+//! The standard library, the tree of the input file and any `--allow-path`
+//! prefixes are always accessible. Everything else, including the rest of the
+//! module search path, is granted, refused, or prompted for on the terminal
+//! depending on the `--comptime-io` mode.
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use montyc_core::opts::{CompilerOptions, SandboxMode};
+use montyc_core::MapT;
+
+/// A kind of I/O comptime code may attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    ReadFile,
+    ReadDirectory,
+    GetCwd,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::ReadFile => write!(f, "read the file"),
+            Access::ReadDirectory => write!(f, "list the directory"),
+            Access::GetCwd => write!(f, "get the current directory"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct IoPolicy {
+    mode: SandboxMode,
+
+    /// Path prefixes that are always accessible.
+    allowed: Vec<PathBuf>,
+
+    /// Answers given to earlier prompts, so the same access is only asked about once.
+    answers: RefCell<MapT<(Access, PathBuf), bool>>,
+}
+
+impl IoPolicy {
+    pub fn new(opts: &CompilerOptions) -> Self {
+        let mut allowed = vec![opts.libstd()];
+
+        let input = opts.input();
+        let input = input.canonicalize().unwrap_or(input);
+
+        if let Some(tree) = input.parent() {
+            allowed.push(tree.to_owned());
+        }
+
+        allowed.extend(opts.allow_paths().iter().cloned());
+
+        Self {
+            mode: opts.comptime_io(),
+            allowed,
+            answers: RefCell::default(),
+        }
+    }
+
+    /// Check whether `access` of `path` is permitted, producing a `PermissionDenied` error if it isn't.
+    pub fn check(&self, access: Access, path: &Path) -> io::Result<()> {
+        // resolve symlinks and `..` so they can't be used to escape an allowed prefix,
+        // a path that can't be resolved can't be checked so only `allow` grants it.
+        let (path, granted) = match path.canonicalize() {
+            Ok(path) => {
+                let granted = access == Access::GetCwd
+                    || self.allowed.iter().any(|prefix| path.starts_with(prefix))
+                    || match self.mode {
+                        SandboxMode::Allow => true,
+                        SandboxMode::Deny => false,
+                        SandboxMode::Prompt => self.prompt(access, &path),
+                    };
+
+                (path, granted)
+            }
+
+            Err(_) => (
+                path.to_owned(),
+                access == Access::GetCwd || self.mode == SandboxMode::Allow,
+            ),
+        };
+
+        if granted {
+            log::info!(
+                "[IoPolicy::check] granted comptime access to {} {:?}",
+                access,
+                path
+            );
+            Ok(())
+        } else {
+            log::warn!(
+                "[IoPolicy::check] refused comptime access to {} {:?}",
+                access,
+                path
+            );
+
+            Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("comptime code is not allowed to {} {:?}", access, path),
+            ))
+        }
+    }
+
+    fn prompt(&self, access: Access, path: &Path) -> bool {
+        let key = (access, path.to_owned());
+
+        if let Some(answer) = self.answers.borrow().get(&key) {
+            return *answer;
+        }
+
+        let answer = {
+            let mut stderr = io::stderr();
+
+            let _ = write!(
+                stderr,
+                "comptime code wants to {} {:?}, allow? [y/N] ",
+                access, path
+            );
+
+            let _ = stderr.flush();
+
+            // no terminal to answer (i.e. EOF on stdin) is taken as a refusal.
+            let mut line = String::new();
+
+            match io::stdin().lock().read_line(&mut line) {
+                Ok(0) | Err(_) => false,
+                Ok(_) => matches!(line.trim(), "y" | "Y" | "yes"),
+            }
+        };
+
+        self.answers.borrow_mut().insert(key, answer);
+
+        answer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use structopt::StructOpt;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const LIBSTD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../libstd");
+
+    /// A project with its input in `src/` and files in the sibling `data/` and `other/` directories.
+    fn project() -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+
+        for path in ["src/main.py", "data/file.txt", "other/file.txt"] {
+            let path = dir.path().join(path);
+
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        dir
+    }
+
+    /// The policy of `montyc check <args> <project>/src/main.py`.
+    fn policy(dir: &Path, args: &[&str]) -> IoPolicy {
+        let input = dir.join("src/main.py");
+
+        let mut argv = vec!["montyc", "check", "--libstd", LIBSTD];
+        argv.extend_from_slice(args);
+        argv.push(input.to_str().unwrap());
+
+        let opts = CompilerOptions::from_iter(argv)
+            .verify()
+            .expect("options should verify.");
+
+        IoPolicy::new(&opts)
+    }
+
+    fn is_denied(result: io::Result<()>) -> bool {
+        matches!(result, Err(err) if err.kind() == io::ErrorKind::PermissionDenied)
+    }
+
+    #[test]
+    fn deny_refuses_everything_outside_the_allowed_roots() {
+        let dir = project();
+        let policy = policy(dir.path(), &["--comptime-io", "deny"]);
+
+        policy
+            .check(Access::ReadFile, &dir.path().join("src/main.py"))
+            .unwrap();
+
+        policy
+            .check(Access::ReadFile, &Path::new(LIBSTD).join("builtins.py"))
+            .unwrap();
+
+        policy
+            .check(Access::GetCwd, &dir.path().join("data"))
+            .unwrap();
+
+        assert!(is_denied(
+            policy.check(Access::ReadFile, &dir.path().join("data/file.txt"))
+        ));
+
+        assert!(is_denied(
+            policy.check(Access::ReadDirectory, &dir.path().join("data"))
+        ));
+
+        // `..` is resolved before the path is compared to the allowed roots.
+        assert!(is_denied(policy.check(
+            Access::ReadFile,
+            &dir.path().join("src/../data/file.txt")
+        )));
+
+        // a path that can't be resolved is never matched against the allowed roots.
+        assert!(is_denied(policy.check(
+            Access::ReadFile,
+            &dir.path().join("src/missing/../../data/file.txt")
+        )));
+    }
+
+    #[test]
+    fn the_search_path_is_not_trusted() {
+        let dir = project();
+        let other = dir.path().join("other");
+
+        let montypath = std::env::join_paths([dir.path().join("data")]).unwrap();
+        let opts = CompilerOptions::from_iter([
+            "montyc",
+            "check",
+            "--libstd",
+            LIBSTD,
+            "--comptime-io",
+            "deny",
+            "-I",
+            other.to_str().unwrap(),
+            dir.path().join("src/main.py").to_str().unwrap(),
+        ])
+        .verify_with(Some(&montypath))
+        .expect("options should verify.");
+
+        let policy = IoPolicy::new(&opts);
+
+        assert!(is_denied(
+            policy.check(Access::ReadFile, &other.join("file.txt"))
+        ));
+
+        assert!(is_denied(
+            policy.check(Access::ReadFile, &dir.path().join("data/file.txt"))
+        ));
+    }
+
+    #[test]
+    fn allow_grants_everything() {
+        let dir = project();
+        let policy = policy(dir.path(), &["--comptime-io", "allow"]);
+
+        policy
+            .check(Access::ReadFile, &dir.path().join("data/file.txt"))
+            .unwrap();
+
+        policy
+            .check(Access::ReadDirectory, &dir.path().join("other"))
+            .unwrap();
+    }
+
+    #[test]
+    fn allow_path_grants_only_its_prefix() {
+        let dir = project();
+        let data = dir.path().join("data");
+        let policy = policy(
+            dir.path(),
+            &[
+                "--comptime-io",
+                "deny",
+                "--allow-path",
+                data.to_str().unwrap(),
+            ],
+        );
+
+        policy
+            .check(Access::ReadFile, &data.join("file.txt"))
+            .unwrap();
+
+        assert!(is_denied(
+            policy.check(Access::ReadFile, &dir.path().join("other/file.txt"))
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinks_can_not_escape_the_allowed_roots() {
+        let dir = project();
+        let src = dir.path().join("src");
+
+        std::os::unix::fs::symlink(dir.path().join("data/file.txt"), src.join("file.txt")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("data"), src.join("data")).unwrap();
+
+        let policy = policy(dir.path(), &["--comptime-io", "deny"]);
+
+        assert!(is_denied(
+            policy.check(Access::ReadFile, &src.join("file.txt"))
+        ));
+
+        assert!(is_denied(
+            policy.check(Access::ReadFile, &src.join("data/file.txt"))
+        ));
+
+        assert!(is_denied(
+            policy.check(Access::ReadDirectory, &src.join("data"))
+        ));
+    }
+}
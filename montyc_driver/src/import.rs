@@ -5,13 +5,15 @@ use std::{
     rc::Rc,
 };
 
-use montyc_core::MapT;
+use montyc_core::{MapT, ModuleRef, MontyError};
 use montyc_hlirt::{
     ctx::{CallCx, EvalGlue},
     object::{AnyFunc, GlobalsHook, IntoPyValue, PyObject, PyValue, RawObject, SharedObject},
     ObjectId, ObjectSpace, PyException, PyResult, PyResultExt,
 };
 
+use montyc_query::Queries;
+
 use crate::prelude::SessionContext;
 
 // -- ModuleSpec
@@ -20,11 +22,61 @@ use crate::prelude::SessionContext;
 pub(crate) struct ModuleSpec {
     name: ObjectId,
     loader: ObjectId,
+    origin: ObjectId,
+    submodule_search_locations: ObjectId,
 }
 
 impl ModuleSpec {
-    pub fn from_file_location(name: ObjectId, loader: ObjectId) -> Self {
-        Self { name, loader }
+    pub fn from_file_location(
+        name: ObjectId,
+        loader: ObjectId,
+        origin: ObjectId,
+        submodule_search_locations: ObjectId,
+    ) -> Self {
+        Self {
+            name,
+            loader,
+            origin,
+            submodule_search_locations,
+        }
+    }
+
+    /// The spec of a namespace package (PEP 420) whose portions are the directories in `submodule_search_locations`.
+    ///
+    /// Namespace packages have neither a loader nor an origin, there is no file to execute.
+    ///
+    pub fn namespace(
+        name: ObjectId,
+        none_v: ObjectId,
+        submodule_search_locations: ObjectId,
+    ) -> Self {
+        Self {
+            name,
+            loader: none_v,
+            origin: none_v,
+            submodule_search_locations,
+        }
+    }
+
+    /// The name of the package this module belongs to, packages are their own parent.
+    fn parent(&self, ecx: &mut dyn EvalGlue) -> PyResult<ObjectId> {
+        let none_v = ecx.runtime().singletons.none_v;
+
+        let name = ecx
+            .runtime()
+            .objects
+            .with_object(self.name, |this| match this {
+                PyValue::Str(st) => Ok(st.to_string()),
+                _ => PyException::type_error().into(),
+            })?;
+
+        if self.submodule_search_locations != none_v {
+            Ok(self.name)
+        } else {
+            let parent = name.rsplit_once('.').map_or("", |(parent, _)| parent);
+
+            ecx.new_string(parent)
+        }
     }
 }
 
@@ -39,14 +91,32 @@ impl PyObject for ModuleSpec {
         std::any::TypeId::of::<Self>()
     }
 
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {
+        bucket.extend([
+            self.name,
+            self.loader,
+            self.origin,
+            self.submodule_search_locations,
+        ]);
+    }
+
     fn get_attribute(&self, ecx: &mut dyn EvalGlue, attr: ObjectId) -> PyResult<ObjectId> {
         let loader = ecx.new_string("loader").trace()?;
         let name = ecx.new_string("name").trace()?;
+        let origin = ecx.new_string("origin").trace()?;
+        let parent = ecx.new_string("parent").trace()?;
+        let submodule_search_locations = ecx.new_string("submodule_search_locations").trace()?;
 
         if attr == loader {
             Ok(self.loader)
         } else if attr == name {
             Ok(self.name)
+        } else if attr == origin {
+            Ok(self.origin)
+        } else if attr == parent {
+            self.parent(ecx).trace()
+        } else if attr == submodule_search_locations {
+            Ok(self.submodule_search_locations)
         } else {
             PyException::attribute_error(attr, 0).into()
         }
@@ -79,7 +149,8 @@ impl FileFinder {
         _path: Option<ObjectId>,
         _target: Option<ObjectId>,
     ) -> PyResult<ObjectId> {
-        let mut _is_namespace = false;
+        let none_v = ecx.runtime().singletons.none_v;
+        let mut namespace_path = None;
 
         let fullname_st = ecx
             .runtime_mut()
@@ -100,7 +171,12 @@ impl FileFinder {
 
         let mut cache = MapT::new();
 
-        for entry in self.base_path.read_dir().map_err(|e| e.into())? {
+        let entries = ecx
+            .runtime_host()
+            .try_read_directory(&self.base_path)
+            .map_err(|e| e.into())?;
+
+        for entry in entries.into_vec() {
             if let Ok(entry) = entry {
                 let path = entry.path();
                 let name = path
@@ -109,30 +185,35 @@ impl FileFinder {
                     .unwrap_or_else(|| path.to_string_lossy())
                     .to_string();
 
-                if name == tail {
+                if name == tail && path.is_dir() {
                     log::trace!("[FileFinder::find_spec] found tail={:?} in cache", tail);
 
                     // check if the module is the name of a directory and thus a package.
-                    let base_path = path.join(tail);
-
-                    log::trace!(
-                        "[FileFinder::find_spec] base_path ++ tail = {:?}",
-                        base_path
-                    );
-
-                    for (suffix, _loader) in self.loaders.iter() {
+                    for (suffix, loader) in self.loaders.iter() {
                         let init_filename = format!("__init__{}", suffix);
-                        let full_path = base_path.join(init_filename);
+                        let full_path = path.join(init_filename);
 
                         if full_path.is_file() {
-                            todo!(
-                                "return self._get_spec(loader, fullname, {:?}, [base_path], target)",
+                            log::trace!(
+                                "[FileFinder::find_spec]  found package at {:?}",
                                 full_path
                             );
+
+                            let package_path =
+                                ecx.new_string(path.to_string_lossy().as_ref()).trace()?;
+
+                            let search_locations = ecx
+                                .runtime_mut()
+                                .objects
+                                .insert(PyValue::List(vec![package_path]));
+
+                            return self
+                                .get_spec(ecx, *loader, fullname, &full_path, search_locations)
+                                .trace();
                         }
                     }
 
-                    _is_namespace = self.base_path.is_dir();
+                    namespace_path = Some(path.clone());
                 }
 
                 log::trace!(
@@ -154,38 +235,47 @@ impl FileFinder {
 
             let partial = format!("{}{}", tail, suffix);
 
-            if let Some(path) = cache.get(&partial) {
+            if let Some(path) = cache.get(&partial).filter(|path| path.is_file()) {
                 log::trace!("[FileFinder::find_spec]  cache hit for path={:?}", path);
 
-                let full_path = self.base_path.join(partial);
-                let path = ecx
-                    .new_string(full_path.to_string_lossy().as_ref())
-                    .trace()?;
+                return self.get_spec(ecx, *loader, fullname, path, none_v).trace();
+            }
+        }
+
+        match namespace_path {
+            Some(path) => {
+                log::trace!(
+                    "[FileFinder::find_spec] Potential namespace package {:?}",
+                    path
+                );
 
-                let loader = ecx.call_object(*loader, &[fullname, path]).trace()?;
-                let spec = ModuleSpec::from_file_location(fullname, loader);
+                let path = ecx.new_string(path.to_string_lossy().as_ref()).trace()?;
+                let search_locations = ecx.runtime_mut().objects.insert(PyValue::List(vec![path]));
 
-                return Ok(ecx.runtime_mut().objects.insert(spec.into_py_val()));
+                let spec = ModuleSpec::namespace(fullname, none_v, search_locations);
+
+                Ok(ecx.runtime_mut().objects.insert(spec.into_py_val()))
             }
-        }
 
-        todo!("is_namespace");
+            None => Ok(none_v),
+        }
+    }
 
-        // if is_namespace {
-        //     log::trace!(
-        //         "[FileFinder::find_space] Potential namespace package {}",
-        //         fullname
-        //     );
+    fn get_spec(
+        &self,
+        ecx: &mut dyn EvalGlue,
+        loader: ObjectId,
+        fullname: ObjectId,
+        path: &Path,
+        submodule_search_locations: ObjectId,
+    ) -> PyResult<ObjectId> {
+        let path = ecx.new_string(path.to_string_lossy().as_ref()).trace()?;
+        let loader = ecx.call_object(loader, &[fullname, path]).trace()?;
 
-        //     let spec = ModuleSpec {
-        //         paths: vec![self.base_path.clone()],
-        //         loader: todo!(),
-        //     };
+        let spec =
+            ModuleSpec::from_file_location(fullname, loader, path, submodule_search_locations);
 
-        //     Some(spec)
-        // } else {
-        //     None
-        // }
+        Ok(ecx.runtime_mut().objects.insert(spec.into_py_val()))
     }
 }
 
@@ -194,6 +284,10 @@ impl PyObject for FileFinder {
         std::any::TypeId::of::<Self>()
     }
 
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {
+        bucket.extend(self.loaders.values());
+    }
+
     fn call_method(
         &self,
         ecx: &mut dyn EvalGlue,
@@ -245,6 +339,10 @@ impl PyObject for SourceFileLoader {
         std::any::TypeId::of::<Self>()
     }
 
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {
+        bucket.push(self.path);
+    }
+
     fn call_method(
         &self,
         ecx: &mut dyn EvalGlue,
@@ -262,7 +360,10 @@ impl PyObject for SourceFileLoader {
                     val => unreachable!("{:?}", val),
                 });
 
-            let source_bytes = std::fs::read_to_string(path).unwrap();
+            let source_bytes = ecx
+                .runtime_host()
+                .try_read_file(&path)
+                .map_err(|e| e.into())?;
 
             return ecx.new_string(&*source_bytes).trace();
         }
@@ -368,7 +469,7 @@ mod path_finder {
         log::trace!("[PathFinder::path_hooks] finding path hook for {:?}", path);
 
         let sys_path_hooks = ecx.getattr(sys, &"path_hooks").trace()?;
-        let mut sys_path_hooks_iter = ecx.iter_object(sys_path_hooks);
+        let mut sys_path_hooks_iter = ecx.iter_object(sys_path_hooks).trace()?;
 
         while let Some(hook) = sys_path_hooks_iter.next(ecx) {
             let hook = hook.trace()?;
@@ -407,7 +508,8 @@ mod path_finder {
         let none_v = ecx.runtime_mut().singletons.none_v;
         let find_spec = ecx.new_string("find_spec").trace()?;
 
-        let mut path_iter = ecx.iter_object(path);
+        let mut namespace_path = vec![];
+        let mut path_iter = ecx.iter_object(path).trace()?;
 
         while let Some(entry) = path_iter.next(ecx) {
             log::trace!("[PathFinder::get_spec]   path_iter -> {:?}", entry);
@@ -454,20 +556,43 @@ mod path_finder {
                 return Ok(spec);
             }
 
-            // todo!();
+            // a namespace portion, keep looking for a regular package or module.
+            let portions = ecx.getattr(spec, &"submodule_search_locations").trace()?;
+
+            if portions == none_v {
+                return PyException::import_error()
+                    .set_message("spec missing loader")
+                    .into();
+            }
+
+            let portions = ecx
+                .runtime()
+                .objects
+                .with_object(portions, |this| match this {
+                    PyValue::List(lst) => lst.clone(),
+                    val => unreachable!("{:?}", val),
+                });
 
-            // let spec_portions = ecx.getattr(spec, &"submodule_search_locations").trace()?;
+            log::trace!(
+                "[PathFinder::get_spec]     spec is a namespace portion, portions={:?}",
+                portions
+            );
 
-            // if spec_portions == none_v {
-            //     return PyException::import_error()
-            //         .set_message("spec missing loader")
-            //         .into();
-            // }
+            namespace_path.extend(portions);
+        }
 
-            todo!("namespace_path.extend(portions)");
+        if namespace_path.is_empty() {
+            return Ok(none_v);
         }
 
-        todo!("spec = _bootstrap.ModuleSpec(fullname, None)")
+        let namespace_path = ecx
+            .runtime_mut()
+            .objects
+            .insert(PyValue::List(namespace_path));
+
+        let spec = ModuleSpec::namespace(fullname, none_v, namespace_path);
+
+        Ok(ecx.runtime_mut().objects.insert(spec.into_py_val()))
     }
 
     pub fn find_spec(cx: CallCx) -> PyResult<ObjectId> {
@@ -506,26 +631,302 @@ mod path_finder {
         let loader = cx.ecx.getattr(spec, &"loader").trace()?;
 
         if loader == none_v {
-            todo!("spec.loader is None, go do namespace logic")
-            // namespace_path = spec.submodule_search_locations
-            // if namespace_path:
-            //     # We found at least one namespace path.  Return a spec which
-            //     # can create the namespace package.
-            //     spec.origin = None
-            //     spec.submodule_search_locations = _NamespacePath(fullname, namespace_path, cls._get_spec)
-            //     return spec
-            // else:
-            //     return None
+            // only portions of a namespace package were found, unlike `_NamespacePath` the
+            // search locations are computed once and not recomputed when `sys.path` changes.
+            let namespace_path = cx
+                .ecx
+                .getattr(spec, &"submodule_search_locations")
+                .trace()?;
+
+            if namespace_path == none_v {
+                Ok(none_v)
+            } else {
+                Ok(spec)
+            }
         } else {
-            return Ok(spec);
+            Ok(spec)
         }
     }
 }
 
+// -- import machinery
+
+type Ecx<'a, 'b, 'c> = montyc_hlirt::ctx::EvaluationContext<'a, 'b, &'c SessionContext>;
+
+fn sys_modules(ecx: &mut dyn EvalGlue) -> PyResult<ObjectId> {
+    let sys = ecx.runtime().singletons.sys;
+
+    ecx.getattr(sys, &"modules").trace()
+}
+
+/// `sys.modules.get(name)`
+fn sys_modules_get(ecx: &mut dyn EvalGlue, name: &str) -> PyResult<Option<ObjectId>> {
+    let sys_modules = sys_modules(ecx).trace()?;
+    let rt = ecx.runtime();
+    let hash = rt.hash(name);
+
+    Ok(rt.objects.with_object(sys_modules, |this| match this {
+        PyValue::Dict(dict) => dict.get(hash).map(|(_, module)| module),
+        _ => unreachable!(),
+    }))
+}
+
+/// `sys.modules[name] = module`
+fn sys_modules_insert(ecx: &mut dyn EvalGlue, name: &str, module: ObjectId) -> PyResult<()> {
+    let sys_modules = sys_modules(ecx).trace()?;
+    let rt = ecx.runtime_mut();
+    let (hash, key, value) = rt.make_kv_pair(name, |_| module);
+
+    rt.objects.with_object_mut(sys_modules, |this| match this {
+        PyValue::Dict(dict) => dict.insert(hash, (key, value)),
+        _ => unreachable!(),
+    });
+
+    Ok(())
+}
+
+/// `del sys.modules[name]`
+fn sys_modules_remove(ecx: &mut dyn EvalGlue, name: &str) -> PyResult<()> {
+    let sys_modules = sys_modules(ecx).trace()?;
+    let rt = ecx.runtime_mut();
+    let hash = rt.hash(name);
+
+    rt.objects.with_object_mut(sys_modules, |this| match this {
+        PyValue::Dict(dict) => dict.remove(&hash),
+        _ => unreachable!(),
+    });
+
+    Ok(())
+}
+
+/// The `__package__` of the module `mref`, if it has one.
+fn package_of(ecx: &mut dyn EvalGlue, mref: ModuleRef) -> Option<String> {
+    let rt = ecx.runtime();
+    let module = rt.module_objects.get(&mref)?.alloc;
+    let hash = rt.hash("__package__");
+
+    let (_, package) = ecx.getattr_direct_hash(module, hash).ok()?;
+
+    ecx.runtime()
+        .objects
+        .with_object(package, |this| match this {
+            PyValue::Str(st) if !st.is_empty() => Some(st.to_string()),
+            _ => None,
+        })
+}
+
+/// Resolve a relative module name to an absolute one, see `importlib._bootstrap._resolve_name`.
+///
+/// `name` is relative to the package of the module `mref`, `level` being the number of leading dots.
+///
+pub(crate) fn resolve_name(
+    ecx: &mut dyn EvalGlue,
+    mref: ModuleRef,
+    name: &str,
+    level: usize,
+) -> PyResult<String> {
+    let package = match package_of(ecx, mref) {
+        Some(package) => package,
+        None => {
+            return PyException::import_error()
+                .set_message("attempted relative import with no known parent package")
+                .into()
+        }
+    };
+
+    // the leftmost piece is what remains after going `level - 1` packages up.
+    let bits: Vec<&str> = package.rsplitn(level, '.').collect();
+
+    if bits.len() < level {
+        return PyException::import_error()
+            .set_message("attempted relative import beyond top-level package")
+            .into();
+    }
+
+    let base = bits.last().unwrap();
+
+    if name.is_empty() {
+        Ok(base.to_string())
+    } else {
+        Ok(format!("{}.{}", base, name))
+    }
+}
+
+/// Ask every finder on `sys.meta_path` for the spec of `name`, searching in `path` if it is a submodule.
+fn find_spec(
+    ecx: &mut dyn EvalGlue,
+    name: &str,
+    path: Option<ObjectId>,
+) -> PyResult<Option<ObjectId>> {
+    let sys = ecx.runtime().singletons.sys;
+    let none_v = ecx.runtime().singletons.none_v;
+
+    let sys_meta_path = ecx.getattr(sys, &"meta_path").trace()?;
+    let mut meta_path = ecx.iter_object(sys_meta_path).trace()?;
+
+    let find_spec = ecx.new_string("find_spec").trace()?;
+    let name = ecx.new_string(name).trace()?;
+
+    while let Some(finder) = meta_path.next(ecx) {
+        let finder = finder.trace()?;
+
+        let spec = ecx
+            .call_method_object(finder, find_spec, &[name, path.unwrap_or(none_v), none_v])
+            .trace()?;
+
+        if spec != none_v {
+            return Ok(Some(spec));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Create the module for `spec` and execute it, it is in `sys.modules` for as long as that takes.
+fn load(ecx: &mut Ecx, spec: ObjectId, name: &str) -> PyResult<ObjectId> {
+    let none_v = ecx.rt.singletons.none_v;
+    let loader = ecx.getattr(spec, &"loader").trace()?;
+
+    // namespace packages have no loader, their module is made from an empty body in the first portion.
+    let (source, origin) = if loader == none_v {
+        let portions = ecx.getattr(spec, &"submodule_search_locations").trace()?;
+        let portion = ecx.rt.objects.with_object(portions, |this| match this {
+            PyValue::List(lst) => lst.first().copied(),
+            _ => None,
+        });
+
+        let origin = match portion {
+            Some(portion) => ecx.rt.objects.with_object(portion, |this| match this {
+                PyValue::Str(st) => PathBuf::from(st.to_string()),
+                _ => unreachable!(),
+            }),
+
+            None => {
+                return PyException::import_error()
+                    .set_message(format!("the spec of '{}' has no loader", name))
+                    .into()
+            }
+        };
+
+        (String::new(), origin)
+    } else {
+        let origin = ecx.getattr(spec, &"origin").trace()?;
+        let origin = ecx.rt.objects.with_object(origin, |this| match this {
+            PyValue::Str(st) => Ok(PathBuf::from(st.to_string())),
+            _ => PyException::import_error()
+                .set_message(format!("the spec of '{}' has no origin", name))
+                .into(),
+        })?;
+
+        let get_data = ecx.new_string("get_data").trace()?;
+        let source = ecx.call_method_object(loader, get_data, &[]).trace()?;
+        let source = ecx.rt.objects.with_object(source, |this| match this {
+            PyValue::Str(st) => st.to_string(),
+            _ => unreachable!(),
+        });
+
+        (source, origin)
+    };
+
+    let host: &SessionContext = *ecx.host;
+
+    let code = host
+        .parse_module(&source, &origin, name)
+        .and_then(|mref| Queries::get_module_flatcode(host, mref))
+        .map_err(|err| {
+            let message = match err {
+                MontyError::Parse { errors, .. } => errors
+                    .iter()
+                    .map(|error| error.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                err => err.to_string(),
+            };
+
+            PyException::import_error().set_message(format!("{}: {}", origin.display(), message))
+        })?;
+
+    let module = ecx.module_from_spec(spec, Rc::new(code)).trace()?;
+
+    // the module is visible before it is executed so that circular imports find it.
+    sys_modules_insert(ecx, name, module).trace()?;
+
+    if let Err(exc) = ecx.exec_module(module) {
+        sys_modules_remove(ecx, name).trace()?;
+        return Err(exc);
+    }
+
+    Ok(module)
+}
+
+/// Import the absolute module `name`, and every package above it, unless it is already in `sys.modules`.
+///
+/// Submodules are searched for in the `__path__` of their parent package and are bound as
+/// attributes of it once they have been loaded, see `importlib._bootstrap._find_and_load`.
+///
+pub(crate) fn import_module(ecx: &mut Ecx, name: &str) -> PyResult<ObjectId> {
+    if let Some(module) = sys_modules_get(ecx, name).trace()? {
+        return Ok(module);
+    }
+
+    let (parent, path) = match name.rsplit_once('.') {
+        Some((parent, child)) => {
+            let parent_module = import_module(ecx, parent).trace()?;
+
+            // importing the parent may have imported this module as well.
+            if let Some(module) = sys_modules_get(ecx, name).trace()? {
+                return Ok(module);
+            }
+
+            let path = match ecx.getattr(parent_module, &"__path__") {
+                Ok(path) => path,
+                Err(exc) if exc.is_attribute_arror() => {
+                    return PyException::import_error()
+                        .set_message(format!(
+                            "No module named '{}'; '{}' is not a package",
+                            name, parent
+                        ))
+                        .into()
+                }
+                Err(exc) => return Err(exc),
+            };
+
+            (Some((parent_module, child)), Some(path))
+        }
+
+        None => (None, None),
+    };
+
+    let spec = match find_spec(ecx, name, path).trace()? {
+        Some(spec) => spec,
+        None => {
+            return PyException::import_error()
+                .set_message(format!("No module named '{}'", name))
+                .into()
+        }
+    };
+
+    let module = load(ecx, spec, name).trace()?;
+
+    if let Some((parent_module, child)) = parent {
+        let (hash, key, value) = ecx.rt.make_kv_pair(child, |_| module);
+
+        ecx.rt
+            .objects
+            .with_object_mut(parent_module, |this| match this {
+                PyValue::Module { inner, .. } => inner.__dict__.insert(hash, (key, value)),
+                _ => unreachable!(),
+            });
+    }
+
+    Ok(module)
+}
+
 /// Called by the session context to initialize the importlib._bootstrap module.
 pub(crate) fn setup<'a>(
     rt: Rc<RefCell<montyc_hlirt::rt::Runtime>>,
     (_mref, module): (montyc_core::ModuleRef, montyc_hlirt::ObjectId),
+    search_path: &[PathBuf],
 ) -> Result<(), montyc_hlirt::rt::RuntimeError<&'a SessionContext>> {
     let mut rt = rt.borrow_mut();
 
@@ -535,8 +936,35 @@ pub(crate) fn setup<'a>(
         _ => unreachable!(),
     });
 
+    // sys.path.extend(search_path)
+    {
+        let sys_path = sys_dict.get(rt.hash("path")).unwrap().1;
+
+        let entries: Vec<_> = search_path
+            .iter()
+            .map(|path| rt.new_string(path.to_string_lossy().as_ref()))
+            .collect();
+
+        rt.objects.with_object_mut(sys_path, |this| match this {
+            PyValue::List(ref mut lst) => lst.extend(entries),
+            _ => unreachable!(),
+        });
+    }
+
     let sys_modules = sys_dict.get(rt.hash("modules")).unwrap().1;
 
+    // sys.modules["__monty"] = {monty}, it is native and can not be found on the search path.
+    {
+        let monty = rt.singletons.monty;
+        let (hash, a, b) = rt.make_kv_pair("__monty", |_| monty);
+
+        rt.objects
+            .with_object_mut(sys_modules, move |this| match this {
+                PyValue::Dict(dict) => dict.insert(hash, (a, b)),
+                _ => unreachable!(),
+            });
+    }
+
     // sys.modules["importlib._bootstrap"] = {module}
     {
         let (hash, a, b) = rt.make_kv_pair("importlib._bootstrap", |_| module);
@@ -0,0 +1,447 @@
+//! End to end tests running source through a `SessionContext`, the way `montyc` does.
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+use montyc_core::{opts::CompilerOptions, ModuleRef, MontyResult, TypeError};
+use montyc_hlirt::{
+    argparse, ctx::CallCx, object::PyValue, ObjectId, ObjectSpace, PyException, PyResult,
+};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+use crate::prelude::{NativeModule, SessionContext};
+
+const LIBSTD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../libstd");
+
+/// Write `files` into a fresh directory, paths are relative to it.
+fn project(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+
+    for (path, contents) in files {
+        let path = dir.path().join(path);
+
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    dir
+}
+
+/// A session for running `montyc <command> <args>` with the standard library of this repository.
+fn session(command: &str, args: &[&str]) -> SessionContext {
+    let mut argv = vec!["montyc", command, "--libstd", LIBSTD];
+    argv.extend_from_slice(args);
+
+    let opts = CompilerOptions::from_iter(argv)
+        .verify()
+        .expect("options should verify.");
+
+    match SessionContext::initialize(&opts) {
+        Ok(cx) => cx,
+        Err((cx, err)) => panic!("{}", cx.fmt_error(&err)),
+    }
+}
+
+/// Include `input` as `__main__` and typecheck it, like `montyc check` does.
+fn check(cx: &SessionContext, input: &Path) -> MontyResult<Vec<(ModuleRef, TypeError)>> {
+    let (mref, _) = cx.include_module(input, "__main__")?;
+    cx.typecheck_module(mref)
+}
+
+#[test]
+fn check_reports_errors_from_every_function() {
+    let dir = project(&[(
+        "main.py",
+        "def f() -> int:\n    return \"f\"\n\n\
+         def g() -> int:\n    return 1\n\n\
+         class A:\n    def h(self) -> str:\n        return 1\n",
+    )]);
+
+    let input = dir.path().join("main.py");
+    let cx = session("check", &[input.to_str().unwrap()]);
+
+    let errors = check(&cx, &input).unwrap();
+
+    assert_eq!(errors.len(), 2, "{:#?}", errors);
+    assert!(errors
+        .iter()
+        .all(|(_, err)| matches!(err, TypeError::BadReturnType { .. })));
+}
+
+#[test]
+fn check_evaluates_module_level_statements() {
+    let dir = project(&[(
+        "main.py",
+        "x = 0\n\
+         for i in [1, 2, 3]:\n    x = x + i\n\n\
+         while x < 10:\n    x = x + 1\n\n\
+         if x == 10:\n    label = \"ten\"\nelse:\n    label = \"other\"\n\n\n\
+         def get() -> int:\n    return 1\n\n\n\
+         y = get()\n",
+    )]);
+
+    let input = dir.path().join("main.py");
+    let cx = session("check", &[input.to_str().unwrap()]);
+
+    assert!(check(&cx, &input).unwrap().is_empty());
+}
+
+/// Remove the ANSI escape sequences coloring a rendered report.
+fn strip_colors(rendered: &str) -> String {
+    let mut plain = String::with_capacity(rendered.len());
+    let mut chars = rendered.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\x1b' => {
+                chars.by_ref().find(|ch| ch.is_ascii_alphabetic());
+            }
+
+            ch => plain.push(ch),
+        }
+    }
+
+    plain
+}
+
+#[test]
+fn type_errors_render_with_source_snippets() {
+    let dir = project(&[("main.py", "def f() -> int:\n    return \"f\"\n")]);
+
+    let input = dir.path().join("main.py");
+    let cx = session("check", &[input.to_str().unwrap()]);
+
+    let errors = check(&cx, &input).unwrap();
+    let rendered = strip_colors(&cx.fmt_error(&montyc_core::MontyError::TypeErrors(errors)));
+
+    assert!(
+        rendered.contains("Incompatible return type."),
+        "{}",
+        rendered
+    );
+    assert!(
+        rendered.contains(&format!("{}:2:", input.display())),
+        "{}",
+        rendered
+    );
+    assert!(rendered.contains("return \"f\""), "{}", rendered);
+    assert!(
+        rendered.contains("This returns an object with a type of: <str>"),
+        "{}",
+        rendered
+    );
+    assert!(
+        rendered.contains("This function was annotated to return a type of: <int>"),
+        "{}",
+        rendered
+    );
+}
+
+#[test]
+fn type_errors_are_collected_without_cascading() {
+    let dir = project(&[(
+        "main.py",
+        "def f(a: int) -> int:\n    b = a.nope\n    c = b + 1\n    d = \"x\" + 1\n    return \"s\"\n",
+    )]);
+
+    let input = dir.path().join("main.py");
+    let cx = session("check", &[input.to_str().unwrap()]);
+
+    let errors = check(&cx, &input).unwrap();
+
+    // `b` is poisoned by the bad attribute access, so using it is not another error.
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [
+                (_, TypeError::InvalidAttributeAccess { .. }),
+                (_, TypeError::InvalidAttributeAccess { .. }),
+                (_, TypeError::BadReturnType { .. }),
+            ]
+        ),
+        "{:#?}",
+        errors
+    );
+}
+
+#[test]
+fn poisoned_blocks_are_not_compiled() {
+    let dir = project(&[("main.py", "def f() -> int:\n    return int\n")]);
+
+    let input = dir.path().join("main.py");
+    let cx = session("build", &[input.to_str().unwrap(), "main"]);
+
+    let errors = check(&cx, &input).unwrap();
+
+    assert!(
+        matches!(errors.as_slice(), [(_, TypeError::BadReturnType { .. })]),
+        "{:#?}",
+        errors
+    );
+}
+
+#[test]
+fn ints_and_floats_do_not_share_a_variable() {
+    let dir = project(&[(
+        "main.py",
+        "def f() -> float:\n    x = 1\n    x = x / 2\n    return 0.5\n",
+    )]);
+
+    let input = dir.path().join("main.py");
+    let cx = session("check", &[input.to_str().unwrap()]);
+
+    let errors = check(&cx, &input).unwrap();
+
+    match errors.as_slice() {
+        [(_, TypeError::Unsupported { message, .. })] => assert_eq!(
+            message,
+            "can not rebind a variable of type <int> to a value of type <float>."
+        ),
+        _ => panic!("{:#?}", errors),
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn imports_go_through_the_sandbox() {
+    let dir = project(&[
+        ("src/main.py", "import secret\n"),
+        ("other/secret.py", "x = 1\n"),
+    ]);
+
+    let input = dir.path().join("src/main.py");
+    let other = dir.path().join("other");
+
+    // the module is found in the tree of the input but its source lives outside of it.
+    std::os::unix::fs::symlink(other.join("secret.py"), dir.path().join("src/secret.py")).unwrap();
+
+    let cx = session("check", &["--comptime-io", "deny", input.to_str().unwrap()]);
+
+    let err = check(&cx, &input).unwrap_err();
+    let rendered = cx.fmt_error(&err);
+
+    assert!(
+        rendered.contains("comptime code is not allowed to read the file"),
+        "{}",
+        rendered
+    );
+
+    let other = other.to_str().unwrap();
+
+    // directories on the search path are not trusted just for being searched.
+    let cx = session(
+        "check",
+        &[
+            "--comptime-io",
+            "deny",
+            "-I",
+            other,
+            input.to_str().unwrap(),
+        ],
+    );
+
+    assert!(check(&cx, &input).is_err());
+
+    let granted: [&[&str]; 2] = [
+        &["--comptime-io", "allow"],
+        &["--comptime-io", "deny", "--allow-path", other],
+    ];
+
+    for args in granted {
+        let mut args = args.to_vec();
+        args.push(input.to_str().unwrap());
+
+        let cx = session("check", &args);
+
+        assert!(check(&cx, &input).unwrap().is_empty(), "{:?}", args);
+    }
+}
+
+/// The value passed as the only argument of a native function, formatted like `str()` would.
+fn str_arg(cx: &CallCx) -> PyResult<String> {
+    let [value] = cx.parse_args_with(argparse::args_unboxed(["value"]))?;
+
+    Ok(cx
+        .ecx
+        .runtime()
+        .objects
+        .with_object(value, |value| match value {
+            PyValue::Int(n) => n.to_string(),
+            PyValue::Str(st) => st.to_string(),
+            val => panic!("expected an int or a str instead got {:?}", val),
+        }))
+}
+
+#[test]
+fn native_modules_are_importable_from_comptime_code() {
+    let dir = project(&[(
+        "main.py",
+        "import build\n\
+         from build import double\n\n\
+         build.report(build.version)\n\n\
+         if build.debug:\n    build.report(double(21))\n",
+    )]);
+
+    let input = dir.path().join("main.py");
+    let mut cx = session("check", &[input.to_str().unwrap()]);
+
+    let reported = Rc::new(RefCell::new(vec![]));
+
+    let module = NativeModule::new("build")
+        .constant("version", String::from("1.2.3"))
+        .constant("debug", true)
+        .function("double", |cx: CallCx| -> PyResult<ObjectId> {
+            let [n] = cx.parse_args_with(argparse::args_unboxed(["n"]))?;
+
+            match cx.ecx.runtime().objects.with_object(n, |n| n.clone()) {
+                PyValue::Int(n) => cx.ecx.new_int(n * 2),
+                _ => PyException::type_error().into(),
+            }
+        })
+        .function("report", {
+            let reported = Rc::clone(&reported);
+
+            move |cx: CallCx| -> PyResult<ObjectId> {
+                reported.borrow_mut().push(str_arg(&cx)?);
+                Ok(cx.ecx.runtime().singletons.none_v)
+            }
+        });
+
+    cx.register_native_module(module).unwrap();
+
+    assert!(check(&cx, &input).unwrap().is_empty());
+    assert_eq!(*reported.borrow(), ["1.2.3", "42"]);
+
+    match cx.register_native_module(NativeModule::new("build")) {
+        Err(exc) => assert_eq!(exc.kind(), "ImportError"),
+        Ok(_) => panic!("a module can only be registered once."),
+    }
+}
+
+/// Register a native `probe` module whose `report` function records its argument.
+fn probe(cx: &mut SessionContext) -> Rc<RefCell<Vec<String>>> {
+    let reported = Rc::new(RefCell::new(vec![]));
+
+    let module = NativeModule::new("probe").function("report", {
+        let reported = Rc::clone(&reported);
+
+        move |cx: CallCx| -> PyResult<ObjectId> {
+            reported.borrow_mut().push(str_arg(&cx)?);
+            Ok(cx.ecx.runtime().singletons.none_v)
+        }
+    });
+
+    cx.register_native_module(module).unwrap();
+
+    reported
+}
+
+#[test]
+fn packages_are_imported_from_their_init_module() {
+    let dir = project(&[
+        (
+            "main.py",
+            "import probe\n\
+             import pkg\n\n\
+             probe.report(pkg.__package__)\n\
+             probe.report(pkg.__path__[0])\n\
+             probe.report(pkg.value)\n",
+        ),
+        ("pkg/__init__.py", "value = 1\n"),
+    ]);
+
+    let input = dir.path().join("main.py");
+    let mut cx = session("check", &[input.to_str().unwrap()]);
+    let reported = probe(&mut cx);
+
+    assert!(check(&cx, &input).unwrap().is_empty());
+
+    let reported = reported.borrow();
+
+    assert_eq!(reported[0], "pkg");
+    assert!(
+        Path::new(&reported[1]).ends_with("pkg"),
+        "{:?}",
+        reported[1]
+    );
+    assert_eq!(reported[2], "1");
+}
+
+#[test]
+fn dotted_imports_bind_the_top_level_package() {
+    let dir = project(&[
+        (
+            "main.py",
+            "import probe\n\
+             import a.b.c\n\
+             from a.b import c\n\n\
+             probe.report(a.b.c.value)\n\
+             probe.report(c.value)\n\
+             probe.report(c.__package__)\n",
+        ),
+        ("a/__init__.py", ""),
+        ("a/b/__init__.py", ""),
+        ("a/b/c.py", "value = 3\n"),
+    ]);
+
+    let input = dir.path().join("main.py");
+    let mut cx = session("check", &[input.to_str().unwrap()]);
+    let reported = probe(&mut cx);
+
+    assert!(check(&cx, &input).unwrap().is_empty());
+    assert_eq!(*reported.borrow(), ["3", "3", "a.b"]);
+}
+
+#[test]
+fn relative_imports_resolve_against_the_package() {
+    let dir = project(&[
+        (
+            "main.py",
+            "import probe\n\
+             import a.b.c\n\n\
+             probe.report(a.b.c.total)\n",
+        ),
+        ("a/__init__.py", ""),
+        ("a/top.py", "top_value = 1\n"),
+        ("a/b/__init__.py", ""),
+        ("a/b/sibling.py", "value = 2\n"),
+        (
+            "a/b/c.py",
+            "from . import sibling\n\
+             from .sibling import value\n\
+             from ..top import top_value\n\
+             from .. import top\n\n\
+             total = sibling.value + value + top_value + top.top_value\n",
+        ),
+    ]);
+
+    let input = dir.path().join("main.py");
+    let mut cx = session("check", &[input.to_str().unwrap()]);
+    let reported = probe(&mut cx);
+
+    assert!(check(&cx, &input).unwrap().is_empty());
+    assert_eq!(*reported.borrow(), ["6"]);
+}
+
+#[test]
+fn relative_imports_can_not_go_beyond_the_top_level_package() {
+    let dir = project(&[
+        ("main.py", "import a.b\n"),
+        ("a/__init__.py", ""),
+        ("a/b.py", "from ... import c\n"),
+    ]);
+
+    let input = dir.path().join("main.py");
+    let cx = session("check", &[input.to_str().unwrap()]);
+
+    let err = check(&cx, &input).unwrap_err();
+    let rendered = cx.fmt_error(&err);
+
+    assert!(
+        rendered.contains("attempted relative import beyond top-level package"),
+        "{}",
+        rendered
+    );
+}
@@ -47,6 +47,14 @@ impl BlockCFGBuilder {
                         }
                     }
 
+                    RawInst::IterNext { exhausted, .. } => {
+                        edges.push((block_index, exhausted, BlockCFGEdge::IfFalse(inst_ix)));
+                    }
+
+                    RawInst::PhiJump { recv, .. } => {
+                        edges.push((block_index, recv, BlockCFGEdge::Direct(inst_ix)));
+                    }
+
                     RawInst::Br { to } => {
                         if inst_ix > 0
                             && matches!(block[inst_ix - 1].op, RawInst::If { falsey: None, .. })
@@ -24,7 +24,15 @@ pub(crate) trait CFGReducer {
         errors: &mut Vec<montyc_core::error::TypeError>,
     ) -> MontyResult<Vec<Self::IndexT>>;
 
-    fn visit(&mut self, cx: &SessionContext, entry: Self::IndexT) -> MontyResult<Self::OutputT> {
+    /// Walk the graph starting from `entry`, any type errors found are collected into `type_errors`.
+    ///
+    /// Finding an error in a block does not stop the walk, every block reachable from `entry` is visited.
+    fn visit(
+        &mut self,
+        cx: &SessionContext,
+        entry: Self::IndexT,
+        type_errors: &mut Vec<montyc_core::error::TypeError>,
+    ) -> MontyResult<Self::OutputT> {
         let cfg_capacity = self.cfg_ref().n_nodes();
 
         let mut blocks_processed = Vec::with_capacity(cfg_capacity);
@@ -32,40 +40,33 @@ pub(crate) trait CFGReducer {
 
         blocks_to_analyze.push_front(entry);
 
-        let mut errors = Vec::with_capacity(16);
         let mut output = self.make_output();
 
         while let Some(ix) = blocks_to_analyze.pop_front() {
-            errors.clear();
-
             let insert_at = match blocks_processed.binary_search(&ix) {
                 Ok(_) => continue,
                 Err(index) => index,
             };
 
-            let output_nodes = self.visit_block(cx, &mut output, ix, &mut errors)?;
+            let output_nodes = self.visit_block(cx, &mut output, ix, type_errors)?;
 
-            if let [] = errors.as_slice() {
-                blocks_processed.insert(insert_at, ix);
+            blocks_processed.insert(insert_at, ix);
 
-                for node in output_nodes {
-                    match blocks_processed.binary_search(&node) {
-                        Ok(_) => continue,
-                        Err(_) => {
-                            if let Err(index) = blocks_to_analyze.binary_search(&node) {
-                                blocks_to_analyze.insert(index, node)
-                            }
+            for node in output_nodes {
+                match blocks_processed.binary_search(&node) {
+                    Ok(_) => continue,
+                    Err(_) => {
+                        if let Err(index) = blocks_to_analyze.binary_search(&node) {
+                            blocks_to_analyze.insert(index, node)
                         }
                     }
                 }
-
-                log::trace!(
-                    "[typeck::typecheck] blocks to analyze: {:?}",
-                    blocks_to_analyze
-                );
-            } else {
-                todo!("{:#?}", errors)
             }
+
+            log::trace!(
+                "[typeck::typecheck] blocks to analyze: {:?}",
+                blocks_to_analyze
+            );
         }
 
         Ok(output)
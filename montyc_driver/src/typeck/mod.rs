@@ -65,13 +65,25 @@ pub(crate) struct Variable(Vec<Binding>);
 
 type ErrorTy = TypeError;
 
+/// Sort type errors by the module they were found in and then by their location.
+pub(crate) fn sort_type_errors(errors: &mut Vec<(ModuleRef, TypeError)>) {
+    errors.sort_by_key(|(mref, err)| match err.span() {
+        Some((module, span)) => (*module, span.start, span.end),
+        None => (*mref, 0, 0),
+    });
+}
+
 /// Run the TypingMachine on the supplied function `fun`.
 ///
 /// This routine performs abstract interpretation of the provided function
 /// whilst analyzing and performing type correctess verification (typechecking)
 ///
+/// All type errors found in the function are reported together, values that
+/// failed to typecheck are poisoned with `Unknown` to avoid cascading errors.
+///
 /// As a side effect if the context build options are set to `Build` then `CgInst`
-/// instructions will be emitted for later codegen.
+/// instructions will be emitted for later codegen, when they are set to `Check`
+/// the returned graph has the same shape but its blocks are left empty.
 ///
 pub fn typecheck(
     cx: &SessionContext,
@@ -84,46 +96,72 @@ pub fn typecheck(
 
     let code = cx.get_function_flatcode(fun.value_id)?;
 
-    match &cx.opts {
-        CompilerOptions::Check { libstd, input } => todo!(),
-        CompilerOptions::Build { .. } => {
-            let (return_t, params_t) = match cx.tcx().get_python_type_of(fun.type_id).unwrap() {
-                PythonType::Callable { ret, params } => (ret, params),
-                _ => unreachable!(),
-            };
+    let emit_cg = match &cx.opts {
+        CompilerOptions::Check { .. } => false,
+        CompilerOptions::Build { .. } => true,
+    };
 
-            let mut tm = TypingMachine::new(flatseq_to_blocks(code.inst()), return_t, fun.mref);
-            let entry = match tm.entry {
-                Some(entry) => entry,
-                None => unreachable!("code should always have one block."),
-            };
+    let (return_t, params_t) = match cx.tcx().get_python_type_of(fun.type_id).unwrap() {
+        PythonType::Callable { ret, params } => (ret, params),
+        _ => unreachable!(),
+    };
 
-            if let Some(montyc_parser::AstNode::FuncDef(f)) = code.ast {
-                for ((sref, _), type_id) in f
-                    .args
-                    .unwrap_or_default()
-                    .into_iter()
-                    .zip(params_t.unwrap_or_default().into_iter())
-                {
-                    let var = tm.locals.entry(sref.group()).or_default();
-
-                    var.0.push(Binding {
-                        block: entry,
-                        inst: 0,
-                        type_id,
-                    });
-                }
-            }
+    let mut tm = TypingMachine::new(flatseq_to_blocks(code.inst()), return_t, fun.mref, emit_cg);
 
-            let mut cg_cfg = tm.visit(cx, entry)?;
+    tm.code = cx
+        .value_store
+        .with_metadata(fun.value_id.0, |m| m.flatcode.clone())
+        .flatten()
+        .and_then(|(_, module)| module);
 
-            for edge in tm.cfg.edge_indices() {
-                if let Some((start, end)) = tm.cfg.edge_endpoints(edge) {
-                    cg_cfg.update_edge(start, end, ());
+    for (var, type_id) in fun.captures.iter().flatten() {
+        tm.captures.insert(var.group(), *type_id);
+    }
+
+    let entry = match tm.entry {
+        Some(entry) => entry,
+        None => unreachable!("code should always have one block."),
+    };
+
+    if let Some(montyc_parser::AstNode::FuncDef(f)) = code.ast {
+        let mut params_t = params_t.unwrap_or_default().into_iter();
+
+        for param in f.args.unwrap_or_default() {
+            // `*args` and `**kwargs` aren't part of the signature, their uses are left unchecked.
+            let type_id = if param.kind.is_variadic() {
+                TypingConstants::Unknown
+            } else {
+                match params_t.next() {
+                    Some(type_id) => type_id,
+                    None => break,
                 }
-            }
+            };
+
+            let var = tm.locals.entry(param.name.group()).or_default();
+
+            var.0.push(Binding {
+                block: entry,
+                inst: 0,
+                type_id,
+            });
+        }
+    }
 
-            Ok(cg_cfg)
+    let mut errors = vec![];
+    let mut cg_cfg = tm.visit(cx, entry, &mut errors)?;
+
+    if !errors.is_empty() {
+        let mut errors = errors.into_iter().map(|err| (fun.mref, err)).collect();
+        sort_type_errors(&mut errors);
+
+        return Err(MontyError::TypeErrors(errors));
+    }
+
+    for edge in tm.cfg.edge_indices() {
+        if let Some((start, end)) = tm.cfg.edge_endpoints(edge) {
+            cg_cfg.update_edge(start, end, ());
         }
     }
+
+    Ok(cg_cfg)
 }
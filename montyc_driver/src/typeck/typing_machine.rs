@@ -1,5 +1,14 @@
-use montyc_core::{BuiltinType, TypingContext};
-use montyc_flatcode::FlatInst;
+use std::rc::Rc;
+
+use montyc_core::ast::ParameterKind;
+use montyc_core::{BuiltinType, PropertyValue, SpanRef, TaggedValueId, TypingContext, Value};
+use montyc_flatcode::{
+    raw_inst::{Argument, Dunder},
+    FlatCode, FlatInst,
+};
+use montyc_parser::ast::{Atom, Expr, InfixOp, Primary, UnaryOp};
+use montyc_parser::spanned::Spanned;
+use montyc_parser::AstNode;
 use montyc_query::Queries;
 use petgraph::graph::NodeIndex;
 use petgraph::visit::EdgeRef;
@@ -9,6 +18,77 @@ use crate::global_context::SessionContext;
 use super::*;
 use super::{block_cfg::BlockCFGBuilder, cfg_reducer::CFGReducer};
 
+/// A parameter of a statically known callee.
+struct CalleeParam {
+    name: String,
+    kind: ParameterKind,
+    /// `Some(None)` when the parameter has a default that isn't a constant.
+    default: Option<Option<Constant>>,
+}
+
+/// The type of a constant.
+fn constant_type(cst: &Constant) -> TypeId {
+    match cst {
+        Constant::Int(_) => TypingConstants::Int,
+        Constant::Float(_) => TypingConstants::Float,
+        Constant::Bool(_) => TypingConstants::Bool,
+        Constant::String(_) => TypingConstants::Str,
+        Constant::None => TypingConstants::None,
+        Constant::Ellipsis => TypingConstants::Ellipsis,
+    }
+}
+
+/// The constant a default value expression evaluates to, if it is one.
+fn constant_of(expr: &Spanned<Expr>) -> Option<Constant> {
+    match &expr.inner {
+        Expr::Primary(Spanned {
+            inner: Primary::Atomic(atom),
+            ..
+        }) => match atom.inner {
+            Atom::None => Some(Constant::None),
+            Atom::Ellipsis => Some(Constant::Ellipsis),
+            Atom::Int(n) => Some(Constant::Int(n)),
+            Atom::Str(st) => Some(Constant::String(st)),
+            Atom::Bool(b) => Some(Constant::Bool(b)),
+            Atom::Float(f) => Some(Constant::Float(f)),
+            _ => None,
+        },
+
+        Expr::Unary {
+            op: UnaryOp::Sub,
+            value,
+        } => match constant_of(value)? {
+            Constant::Int(n) => Some(Constant::Int(-n)),
+            Constant::Float(f) => Some(Constant::Float(-f)),
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+/// A method of a list, dict or set, these are compiled inline instead of called.
+enum ContainerMethod {
+    Len,
+    GetItem,
+    Append,
+    Add,
+}
+
+/// Collects the `CgInst`s generated for a block, or drops them when only typechecking.
+struct CgSink {
+    insts: Vec<montyc_core::codegen::CgInst>,
+    enabled: bool,
+}
+
+impl CgSink {
+    fn push(&mut self, inst: montyc_core::codegen::CgInst) {
+        if self.enabled {
+            self.insts.push(inst);
+        }
+    }
+}
+
 /// Akin to Pytype's VM this is a virtual machine for abstract interpretation of code.
 #[derive(Debug)]
 pub struct TypingMachine {
@@ -17,12 +97,31 @@ pub struct TypingMachine {
     pub(crate) entry: Option<NodeIndex>,
     pub(crate) mref: ModuleRef,
     pub(crate) locals: MapT<u32, Variable>,
+    /// The variables of the enclosing function captured by a nested function, read through its closure.
+    pub(crate) captures: MapT<u32, TypeId>,
+    /// The code of the module the function is defined in, nested functions are sequences of it.
+    pub(crate) code: Option<Rc<FlatCode>>,
     pub(crate) nonlocals: MapT<usize, ValueId>,
     pub(crate) values: MapT<usize, TypeId>,
+    /// The next value available for values that don't come from an instruction, i.e. default arguments.
+    pub(crate) next_value: usize,
+    pub(crate) emit_cg: bool,
 }
 
 impl TypingMachine {
-    pub fn new(blocks: Vec<Vec<FlatInst>>, return_t: TypeId, mref: ModuleRef) -> Self {
+    pub fn new(
+        blocks: Vec<Vec<FlatInst>>,
+        return_t: TypeId,
+        mref: ModuleRef,
+        emit_cg: bool,
+    ) -> Self {
+        let next_value = blocks
+            .iter()
+            .flatten()
+            .map(|inst| inst.value + 1)
+            .max()
+            .unwrap_or(0);
+
         let (bcfg, entry) = BlockCFGBuilder::new_from_blocks(blocks);
 
         Self {
@@ -31,8 +130,12 @@ impl TypingMachine {
             return_t,
             mref,
             locals: MapT::new(),
+            captures: MapT::new(),
+            code: None,
             nonlocals: MapT::new(),
             values: MapT::new(),
+            next_value,
+            emit_cg,
         }
     }
 
@@ -52,13 +155,21 @@ impl TypingMachine {
             entry: _,
             mref,
             locals,
+            captures,
+            code,
             nonlocals,
             return_t,
             values: value_types,
+            next_value,
+            emit_cg,
         } = self;
 
         let block = cfg.node_weight(block_ix).ok_or(MontyError::None)?;
-        let mut cg_block = vec![];
+        let errors_before = errors.len();
+        let mut cg_block = CgSink {
+            insts: vec![],
+            enabled: *emit_cg,
+        };
 
         let mref = *mref;
 
@@ -79,7 +190,102 @@ impl TypingMachine {
                     }
                 }
 
-                RawInst::Defn { .. } | RawInst::Class { .. } | RawInst::Import { .. } => {
+                RawInst::Defn {
+                    params,
+                    returns,
+                    sequence_id,
+                    ..
+                } => {
+                    let span = (mref, inst.attrs.span.clone().unwrap_or_default());
+
+                    let code = match code {
+                        Some(code) => Rc::clone(code),
+                        None => unreachable!(
+                            "functions are always typechecked with their module's code."
+                        ),
+                    };
+
+                    let annotations = params
+                        .iter()
+                        .filter(|param| !param.kind.is_variadic())
+                        .map(|param| param.annotation)
+                        .chain(Some(*returns));
+
+                    let mut types = vec![];
+
+                    for annotation in annotations {
+                        // like module level functions, missing annotations are `None`.
+                        let type_id = match annotation {
+                            None => Some(TypingConstants::None),
+                            Some(ann) => Self::annotation_type(cx, value_types, ann),
+                        };
+
+                        match type_id {
+                            Some(type_id) => types.push(type_id),
+                            None => {
+                                errors.push(TypeError::Unsupported {
+                                    span: span.clone(),
+                                    message: "the annotations of nested functions can only refer to classes.".into(),
+                                });
+
+                                break;
+                            }
+                        }
+                    }
+
+                    if types.len() != params.iter().filter(|p| !p.kind.is_variadic()).count() + 1 {
+                        value_types.insert(inst.value, TypingConstants::Unknown);
+                        continue;
+                    }
+
+                    let ret = types.pop().unwrap();
+                    let params_t = if types.is_empty() { None } else { Some(types) };
+                    let func_t = cx.tcx().callable(params_t, ret);
+
+                    let captures = code
+                        .free_variables(*sequence_id)
+                        .into_iter()
+                        .filter_map(|var| {
+                            let type_id = match locals.get(&var.group()) {
+                                Some(Variable(bindings)) => Self::variable_type(cx, bindings),
+                                None => *captures.get(&var.group())?,
+                            };
+
+                            Some((var, type_id))
+                        })
+                        .collect::<Vec<_>>();
+
+                    let value =
+                        Self::nested_function(cx, &code, *sequence_id, func_t, captures.clone());
+
+                    // the body is checked here, nested functions aren't reachable from the module.
+                    match cx.get_function_cg_cfg(TaggedValueId::func(value)) {
+                        Ok(_) => (),
+                        Err(MontyError::TypeErrors(errs)) => {
+                            errors.extend(errs.into_iter().map(|(_, err)| err))
+                        }
+                        Err(err) => return Err(err),
+                    }
+
+                    value_types.insert(inst.value, func_t);
+
+                    cg_block.push(CgInst::MakeClosure {
+                        value,
+                        captures,
+                        ret: inst.value,
+                    });
+                }
+
+                RawInst::Lambda { .. } => {
+                    errors.push(TypeError::Unsupported {
+                        span: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                        message: "lambdas can only be used during comptime evaluation, use a nested function with annotated parameters instead.".into(),
+                    });
+
+                    value_types.insert(inst.value, TypingConstants::Unknown);
+                }
+
+                RawInst::Class { .. } | RawInst::Import { .. } | RawInst::ImportFrom { .. } => {
                     todo!("{:#?}", inst)
                 }
 
@@ -136,8 +342,38 @@ impl TypingMachine {
                 RawInst::Undefined
                 | RawInst::Nop
                 | RawInst::BuildClass { .. }
+                | RawInst::PopBlock
+                | RawInst::PopExcept
                 | RawInst::JumpTarget => continue,
 
+                RawInst::SetupExcept { .. } | RawInst::Raise { .. } => {
+                    errors.push(TypeError::Unsupported {
+                        span: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                        message:
+                            "exceptions can only be raised and handled during comptime evaluation."
+                                .into(),
+                    });
+
+                    // the block can not be lowered, so stop emitting code for it.
+                    cg_block.enabled = false;
+                }
+
+                RawInst::Unsupported { message } => {
+                    errors.push(TypeError::Unsupported {
+                        span: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                        message: (*message).into(),
+                    });
+
+                    cg_block.enabled = false;
+                }
+
+                // compiled code never raises, so only the path leaving a `with` body normally is reachable.
+                RawInst::SetupWith { .. } => continue,
+
+                RawInst::GetException | RawInst::ExcMatch { .. } | RawInst::ClassOf { .. } => {
+                    value_types.insert(inst.value, TypingConstants::Unknown);
+                }
+
                 RawInst::SetVar { variable, value } => {
                     let sref = variable.clone();
                     let type_id = value_types[value];
@@ -150,6 +386,33 @@ impl TypingMachine {
 
                     let var = locals.entry(sref.group()).or_default();
 
+                    // the slot of a variable is sized by its first binding, floats and ints can not share one.
+                    if let Some(previous) = var.0.first().map(|binding| binding.type_id) {
+                        let is_float = |t| t == TypingConstants::Float;
+
+                        if previous != TypingConstants::Unknown
+                            && type_id != TypingConstants::Unknown
+                            && is_float(previous) != is_float(type_id)
+                        {
+                            let display = |t| {
+                                cx.tcx()
+                                    .display_type(t, &|v| cx.get_type_of(v).ok())
+                                    .unwrap_or_else(|| String::from("<unknown>"))
+                            };
+
+                            errors.push(TypeError::Unsupported {
+                                span: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                                message: format!(
+                                    "can not rebind a variable of type {} to a value of type {}.",
+                                    display(previous),
+                                    display(type_id)
+                                ),
+                            });
+
+                            continue;
+                        }
+                    }
+
                     var.0.push(Binding {
                         block: block_ix,
                         inst: inst.value,
@@ -164,16 +427,39 @@ impl TypingMachine {
 
                 RawInst::UseVar { variable } => {
                     match locals.get(&variable.group()) {
+                        None if captures.contains_key(&variable.group()) => {
+                            value_types.insert(inst.value, captures[&variable.group()]);
+
+                            cg_block.push(CgInst::ReadLocalVar {
+                                var: *variable,
+                                ret: inst.value,
+                            });
+                        }
+
                         None => {
-                            // NOT a local variable.
+                            // NOT a local variable, check the module globals and then the builtins.
                             let module = cx.value_store.get_by_assoc(mref).unwrap();
-                            let value = cx
+                            let builtins = cx
                                 .value_store
-                                .with_value(module, |m| {
-                                    m.properties.get(&variable.group()).cloned()
-                                })
-                                .unwrap()
-                                .unwrap();
+                                .get_by_assoc(cx.const_runtime.borrow().singletons.builtins);
+
+                            let value = Some(module).into_iter().chain(builtins).find_map(|m| {
+                                cx.value_store
+                                    .with_value(m, |m| m.properties.get(&variable.group()).cloned())
+                                    .flatten()
+                            });
+
+                            let value = match value {
+                                Some(value) => value,
+                                None => {
+                                    errors.push(TypeError::UndefinedVariable {
+                                        sref: *variable,
+                                        span: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                                    });
+                                    value_types.insert(inst.value, TypingConstants::Unknown);
+                                    continue;
+                                }
+                            };
 
                             log::trace!(
                                 "[TypingMaching::analyze_block::use_var]   variable {:?} references {:?}",
@@ -187,6 +473,13 @@ impl TypingMachine {
 
                             nonlocals.insert(inst.value, value);
 
+                            // classes have no runtime representation, they're only ever called.
+                            if let Some(PythonType::Type { .. }) =
+                                cx.tcx().get_python_type_of(value_t)
+                            {
+                                continue;
+                            }
+
                             cg_block.push(CgInst::Use {
                                 value,
                                 ret: inst.value,
@@ -194,85 +487,9 @@ impl TypingMachine {
                         }
 
                         Some(Variable(bindings)) => {
-                            let vfg = BlockCFGBuilder(cfg.clone()).variable_flowgraph(cx); // TODO(mental): build this once and cache that.
-
-                            panic!("{:#?}", vfg);
-
-                            // let defs: Vec<Defs> = vfg.block(block_ix).unwrap().def_blocks(variable);
-
-                            // defs.foo;
-                            //                             let mut bindings_it = Some(block_ix)
-                            //                                 .into_iter()
-                            //                                 .chain(
-                            //                                     cfg.edges_directed(block_ix, EdgeDirection::Incoming)
-                            //                                         .map(|e| e.source())
-                            //                                         .filter(|ix| *ix < block_ix),
-                            //                                 )
-                            //                                 .map(|ix| (ix, bindings.iter().find(|b| b.block == ix).cloned()));
-                            //
-                            //                             let variable_types = if let Some((index, binding)) = bindings_it.next()
-                            //                             {
-                            //                                 assert_eq!(index, block_ix);
-                            //
-                            //                                 match binding {
-                            //                                     Some(Binding { type_id, .. }) => vec![type_id],
-                            //
-                            //                                     None => {
-                            //                                         // no binding in current block, inspect the immediate predecessors.
-                            //
-                            //                                         let mut types = ahash::AHashSet::with_capacity(
-                            //                                             bindings_it.size_hint().0,
-                            //                                         );
-                            //
-                            //                                         for (ix, binding) in bindings_it {
-                            //                                             log::trace!("{ix:?} {block_ix:?}");
-                            //
-                            //                                             match binding {
-                            //                                                 Some(Binding { type_id, .. }) => {
-                            //                                                     types.insert(type_id);
-                            //                                                 }
-                            //                                                 None => {
-                            //                                                     log::trace!("no binding in {ix:?} checking predecessors.");
-                            //
-                            //                                                     for ix_pred in cfg
-                            //                                                         .edges_directed(ix, EdgeDirection::Incoming)
-                            //                                                         .map(|e| e.source())
-                            //                                                         .filter(|i| *i < ix)
-                            //                                                     {
-                            //                                                         log::trace!("{ix_pred:?} is a predecessor to {ix:?}");
-                            //                                                         if let Some(binding) = dbg!(bindings
-                            //                                                             .iter()
-                            //                                                             .find(|b| b.block == ix_pred)
-                            //                                                             .cloned())
-                            //                                                         {
-                            //                                                             types.insert(binding.type_id);
-                            //                                                         }
-                            //                                                     }
-                            //                                                 }
-                            //                                             }
-                            //                                         }
-                            //
-                            //                                         // assert!(n_bound != 0);
-                            //                                         types.into_iter().collect()
-                            //                                     }
-                            //                                 }
-                            //                             } else {
-                            //                                 todo!("unbound local variable.");
-                            //                             };
-                            //
-                            //                             let var_t = match variable_types.as_slice() {
-                            //                                 [] => todo!("unbound local variable."),
-                            //
-                            //                                 [one] => *one,
-                            //
-                            //                                 _ => cx.tcx().insert(
-                            //                                     PythonType::Union {
-                            //                                         members: Some(variable_types),
-                            //                                     }
-                            //                                     .into(),
-                            //                                 ),
-                            //                             };
-                            value_types.insert(inst.value, todo!());
+                            let var_t = Self::variable_type(cx, bindings);
+
+                            value_types.insert(inst.value, var_t);
 
                             cg_block.push(CgInst::ReadLocalVar {
                                 var: variable.clone(),
@@ -282,15 +499,325 @@ impl TypingMachine {
                     }
                 }
 
+                RawInst::GetIter { object } => {
+                    let object_t = value_types[object];
+                    let span = (mref, inst.attrs.span.clone().unwrap_or_default());
+
+                    if object_t == TypingConstants::Unknown {
+                        value_types.insert(inst.value, TypingConstants::Unknown);
+                        continue;
+                    }
+
+                    match cx.tcx().get_python_type_of(object_t).unwrap() {
+                        PythonType::Builtin {
+                            inner: BuiltinType::Range,
+                        } => {
+                            let arguments = match &Self::find_inst(cfg, *object).unwrap().0.op {
+                                RawInst::Call { arguments, .. } => arguments.clone(),
+                                _ => {
+                                    errors.push(TypeError::Unsupported {
+                                        span,
+                                        message: "range objects can only be iterated over directly, i.e. `for i in range(n)`.".into(),
+                                    });
+
+                                    value_types.insert(inst.value, TypingConstants::Unknown);
+                                    continue;
+                                }
+                            };
+
+                            let (start, stop, step) = match arguments.as_slice() {
+                                [stop] => (None, *stop, None),
+                                [start, stop] => (Some(*start), *stop, None),
+                                [start, stop, step] => (Some(*start), *stop, Some(*step)),
+                                _ => {
+                                    // a bad call to `range` has already been reported.
+                                    value_types.insert(inst.value, TypingConstants::Unknown);
+                                    continue;
+                                }
+                            };
+
+                            cg_block.push(CgInst::RangeIter {
+                                start,
+                                stop,
+                                step,
+                                ret: inst.value,
+                            });
+                        }
+
+                        PythonType::Tuple {
+                            members: Some(members),
+                        } if members.windows(2).all(|w| w[0] == w[1])
+                            && !members.contains(&TypingConstants::Unknown) =>
+                        {
+                            cg_block.push(CgInst::TupleIter {
+                                orig: *object,
+                                orig_t: object_t,
+                                ret: inst.value,
+                            });
+                        }
+
+                        PythonType::List { .. }
+                        | PythonType::Set { .. }
+                        | PythonType::Dict { .. } => {
+                            cg_block.push(CgInst::CollectionIter {
+                                orig: *object,
+                                orig_t: object_t,
+                                ret: inst.value,
+                            });
+                        }
+
+                        _ => {
+                            errors.push(TypeError::Unsupported {
+                                span,
+                                message: format!(
+                                    "can not iterate over a value of type {}.",
+                                    cx.tcx()
+                                        .display_type(object_t, &|v| cx.get_type_of(v).ok())
+                                        .unwrap_or_else(|| String::from("<unknown>"))
+                                ),
+                            });
+
+                            value_types.insert(inst.value, TypingConstants::Unknown);
+                            continue;
+                        }
+                    }
+
+                    value_types.insert(inst.value, object_t);
+                }
+
+                RawInst::IterNext { iter, exhausted } => {
+                    let iter_t = value_types[iter];
+
+                    let elem_t = match cx.tcx().get_python_type_of(iter_t).unwrap() {
+                        PythonType::Builtin {
+                            inner: BuiltinType::Range,
+                        } => TypingConstants::Int,
+
+                        PythonType::Tuple { members } => members
+                            .and_then(|m| m.first().cloned())
+                            .unwrap_or(TypingConstants::Never),
+
+                        // iterating over a dict produces its keys.
+                        PythonType::List { inner }
+                        | PythonType::Set { inner }
+                        | PythonType::Dict { key: inner, .. } => inner,
+
+                        _ => {
+                            value_types.insert(inst.value, TypingConstants::Unknown);
+                            continue;
+                        }
+                    };
+
+                    value_types.insert(inst.value, elem_t);
+
+                    let (_, block) = Self::find_inst(cfg, *exhausted).unwrap();
+
+                    cg_block.push(CgInst::IterNext {
+                        iter: *iter,
+                        exhausted: CgBlockId(block.index()),
+                        ret: inst.value,
+                    });
+                }
+
                 RawInst::RefAsStr { .. } => {
                     value_types.insert(inst.value, TypingConstants::Str);
                 }
 
-                RawInst::Call {
-                    callable,
-                    arguments,
-                } => {
+                RawInst::Call { .. } | RawInst::CallEx { .. } => {
+                    let (callable, arguments, keywords) = match &inst.op {
+                        RawInst::Call {
+                            callable,
+                            arguments,
+                        } => (callable, arguments.clone(), vec![]),
+
+                        RawInst::CallEx {
+                            callable,
+                            arguments,
+                        } => {
+                            let mut positional = vec![];
+                            let mut keywords = vec![];
+
+                            for argument in arguments.iter() {
+                                match argument {
+                                    Argument::Positional(value) => positional.push(*value),
+                                    Argument::Keyword(name, value) => keywords
+                                        .push((cx.spanref_to_str(*name)?.to_string(), *value)),
+
+                                    Argument::Starred(_) | Argument::DoubleStarred(_) => {
+                                        errors.push(TypeError::Unsupported {
+                                            span: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                                            message: "argument unpacking is only supported in comptime code.".into(),
+                                        });
+
+                                        break;
+                                    }
+                                }
+                            }
+
+                            if positional.len() + keywords.len() != arguments.len() {
+                                value_types.insert(inst.value, TypingConstants::Unknown);
+                                continue;
+                            }
+
+                            (callable, positional, keywords)
+                        }
+
+                        _ => unreachable!(),
+                    };
+
                     let callable_t = value_types[callable];
+
+                    // an error has already been reported for the callable, don't cascade.
+                    if callable_t == TypingConstants::Unknown {
+                        value_types.insert(inst.value, TypingConstants::Unknown);
+                        continue;
+                    }
+
+                    // the methods of lists, dicts and sets, and `len` of one, are compiled inline.
+                    if let Some((receiver, method, rest)) = Self::container_method(
+                        cx,
+                        cfg,
+                        *callable,
+                        &arguments,
+                        nonlocals,
+                        value_types,
+                    ) {
+                        let callsite = (mref, inst.attrs.span.clone().unwrap_or_default());
+                        let receiver_t = value_types[&receiver];
+                        let receiver_pytype = cx.tcx().get_python_type_of(receiver_t).unwrap();
+
+                        let expected = match (&method, &receiver_pytype) {
+                            (ContainerMethod::Len, _) => vec![],
+                            (ContainerMethod::GetItem, PythonType::Dict { key, .. }) => vec![*key],
+                            (ContainerMethod::GetItem, _) => vec![TypingConstants::Int],
+                            (
+                                ContainerMethod::Append | ContainerMethod::Add,
+                                PythonType::List { inner } | PythonType::Set { inner },
+                            ) => vec![*inner],
+                            _ => unreachable!(),
+                        };
+
+                        if !keywords.is_empty() || rest.len() != expected.len() {
+                            errors.push(TypeError::SusArgumentLength {
+                                callsite,
+                                expected: expected.len(),
+                                actual: rest.len() + keywords.len(),
+                            });
+
+                            value_types.insert(inst.value, TypingConstants::Unknown);
+                            continue;
+                        }
+
+                        let actual = rest.iter().map(|v| value_types[v]).collect::<Vec<_>>();
+
+                        // an empty container takes the type of the first element put into it.
+                        let refine =
+                            matches!(method, ContainerMethod::Append | ContainerMethod::Add)
+                                && expected[0] == TypingConstants::Never
+                                && actual[0] != TypingConstants::Unknown;
+
+                        let mismatch = expected.iter().zip(actual.iter()).zip(rest.iter()).find(
+                            |((expected, actual), _)| {
+                                expected != actual
+                                    && **actual != TypingConstants::Unknown
+                                    && !refine
+                            },
+                        );
+
+                        if let Some(((expected, actual), arg)) = mismatch {
+                            let arg_span = Self::find_inst(cfg, *arg)
+                                .and_then(|(arg, _)| arg.attrs.span.clone())
+                                .unwrap_or_else(|| callsite.1.clone());
+
+                            errors.push(TypeError::BadArgumentType {
+                                expected: *expected,
+                                actual: *actual,
+                                arg_node: (mref, arg_span),
+                                def_node: callsite,
+                            });
+
+                            value_types.insert(inst.value, TypingConstants::Unknown);
+                            continue;
+                        }
+
+                        if matches!(method, ContainerMethod::Add)
+                            && !Self::check_hashable(actual[0], &callsite, errors)
+                        {
+                            value_types.insert(inst.value, TypingConstants::Unknown);
+                            continue;
+                        }
+
+                        let ret_t = match (method, receiver_pytype) {
+                            (ContainerMethod::Len, _) => {
+                                cg_block.push(CgInst::Len {
+                                    orig: receiver,
+                                    orig_t: receiver_t,
+                                    ret: inst.value,
+                                });
+
+                                TypingConstants::Int
+                            }
+
+                            (ContainerMethod::GetItem, pytype) => {
+                                cg_block.push(CgInst::GetItem {
+                                    orig: receiver,
+                                    orig_t: receiver_t,
+                                    index: rest[0],
+                                    ret: inst.value,
+                                });
+
+                                match pytype {
+                                    PythonType::List { inner } => inner,
+                                    PythonType::Dict { value, .. } => value,
+                                    _ => unreachable!(),
+                                }
+                            }
+
+                            (ContainerMethod::Append, _) => {
+                                if refine {
+                                    let refined_t = cx.tcx().list(actual[0]);
+                                    Self::refine_container(
+                                        cfg,
+                                        locals,
+                                        value_types,
+                                        receiver,
+                                        refined_t,
+                                    );
+                                }
+
+                                cg_block.push(CgInst::ListAppend {
+                                    list: receiver,
+                                    value: rest[0],
+                                });
+
+                                TypingConstants::None
+                            }
+
+                            (ContainerMethod::Add, _) => {
+                                if refine {
+                                    let refined_t = cx.tcx().set(actual[0]);
+                                    Self::refine_container(
+                                        cfg,
+                                        locals,
+                                        value_types,
+                                        receiver,
+                                        refined_t,
+                                    );
+                                }
+
+                                cg_block.push(CgInst::SetAdd {
+                                    set: receiver,
+                                    value: rest[0],
+                                });
+
+                                TypingConstants::None
+                            }
+                        };
+
+                        value_types.insert(inst.value, ret_t);
+                        continue;
+                    }
+
                     let callable_pytype = cx.tcx().get_python_type_of(callable_t).unwrap();
 
                     match callable_pytype {
@@ -304,23 +831,159 @@ impl TypingMachine {
 
                         PythonType::Union { .. }
                         | PythonType::Tuple { .. }
-                        | PythonType::List { .. } => todo!("not callable"),
+                        | PythonType::List { .. }
+                        | PythonType::Dict { .. }
+                        | PythonType::Set { .. } => {
+                            errors.push(TypeError::NotCallable {
+                                kind: callable_t,
+                                callsite: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                            });
+
+                            value_types.insert(inst.value, TypingConstants::Unknown);
+                        }
+
+                        PythonType::Type { of } if of == TypingConstants::Range => {
+                            let callsite = (mref, inst.attrs.span.clone().unwrap_or_default());
+
+                            if !keywords.is_empty() {
+                                errors.push(TypeError::BadCallArguments {
+                                    callsite,
+                                    message: "range() takes no keyword arguments".into(),
+                                });
+
+                                value_types.insert(inst.value, TypingConstants::Unknown);
+                                continue;
+                            }
+
+                            if !(1..=3).contains(&arguments.len()) {
+                                errors.push(TypeError::SusArgumentLength {
+                                    callsite: callsite.clone(),
+                                    expected: 3,
+                                    actual: arguments.len(),
+                                });
+                            }
+
+                            for arg in arguments.iter() {
+                                let actual = value_types[arg];
+
+                                if actual != TypingConstants::Int
+                                    && actual != TypingConstants::Unknown
+                                {
+                                    let arg_span = Self::find_inst(cfg, *arg)
+                                        .and_then(|(arg, _)| arg.attrs.span.clone())
+                                        .unwrap_or_else(|| callsite.1.clone());
+
+                                    errors.push(TypeError::BadArgumentType {
+                                        expected: TypingConstants::Int,
+                                        actual,
+                                        arg_node: (mref, arg_span),
+                                        def_node: callsite.clone(),
+                                    });
+                                }
+                            }
+
+                            value_types.insert(inst.value, TypingConstants::Range);
+                        }
 
                         PythonType::Type { .. } => todo!(),
                         PythonType::TypeVar { .. } => todo!(),
 
                         PythonType::Callable { params: _, ret } => {
+                            let callsite = (mref, inst.attrs.span.clone().unwrap_or_default());
+                            let callee =
+                                Self::callee_of(cx, cfg, *callable, nonlocals, value_types)
+                                    .and_then(|callee| Self::signature_of(cx, callee));
+
+                            let arguments = match callee {
+                                Some(params) => {
+                                    match Self::bind_arguments(
+                                        &params, &arguments, &keywords, &callsite,
+                                    ) {
+                                        Ok(bound) => bound
+                                            .into_iter()
+                                            .map(|arg| match arg {
+                                                Ok(value) => value,
+                                                Err(cst) => {
+                                                    let value = *next_value;
+                                                    *next_value += 1;
+
+                                                    value_types.insert(value, constant_type(&cst));
+                                                    cg_block
+                                                        .push(CgInst::Const { cst, ret: value });
+
+                                                    value
+                                                }
+                                            })
+                                            .collect::<Vec<_>>(),
+
+                                        Err(err) => {
+                                            errors.push(err);
+                                            value_types
+                                                .insert(inst.value, TypingConstants::Unknown);
+                                            continue;
+                                        }
+                                    }
+                                }
+
+                                None if !keywords.is_empty() => {
+                                    errors.push(TypeError::Unsupported {
+                                        span: callsite,
+                                        message: "keyword arguments can only be passed to functions defined in Python.".into(),
+                                    });
+
+                                    value_types.insert(inst.value, TypingConstants::Unknown);
+                                    continue;
+                                }
+
+                                None => arguments,
+                            };
+
                             let argument_types =
                                 arguments.iter().map(|a| value_types[a]).collect::<Vec<_>>();
 
-                            if let Err(failure) =
-                                cx.tcx().unify_func(callable_t, &argument_types, ret)
-                            {
+                            let unified = cx.tcx().unify_func(callable_t, &argument_types, ret);
+
+                            // `a op b` falls back to `b.__rop__(a)` when `a.__op__` won't take a `b`, i.e. `1 + 2.5`.
+                            if unified.is_err() {
+                                if let Some((value, ret)) = Self::reflected_operator(
+                                    cx,
+                                    cfg,
+                                    *callable,
+                                    &arguments,
+                                    value_types,
+                                ) {
+                                    value_types.insert(inst.value, ret);
+
+                                    cg_block.push(CgInst::Call {
+                                        value,
+                                        args: arguments.iter().rev().cloned().collect(),
+                                        ret: inst.value,
+                                    });
+
+                                    continue;
+                                }
+                            }
+
+                            if let Err(failure) = unified {
                                 match failure {
-                                    montyc_core::UnifyFailure::UnequalArity(_, _) => todo!(),
+                                    montyc_core::UnifyFailure::UnequalArity(expected, actual) => {
+                                        errors.push(TypeError::SusArgumentLength {
+                                            callsite: (
+                                                mref,
+                                                inst.attrs.span.clone().unwrap_or_default(),
+                                            ),
+                                            expected,
+                                            actual,
+                                        });
+                                    }
 
                                     montyc_core::UnifyFailure::BadArgumentTypes(mismatch) => {
                                         for (n, expected, actual) in mismatch {
+                                            // the argument is already poisoned by an earlier error.
+                                            if actual == TypingConstants::Unknown {
+                                                continue;
+                                            }
+
                                             log::error!(
                                                 "{:?} != {:?}",
                                                 cx.tcx().display_type(expected, &|v| cx
@@ -344,8 +1007,6 @@ impl TypingMachine {
                                                 ),
                                             })
                                         }
-
-                                        break;
                                     }
 
                                     montyc_core::UnifyFailure::NotCallable => unreachable!(),
@@ -374,12 +1035,20 @@ impl TypingMachine {
                                 }
 
                                 RawInst::UseVar { .. } => {
-                                    if let Some(value) = nonlocals.get(callable) {
-                                        cg_block.push(CgInst::Call {
+                                    match nonlocals.get(callable) {
+                                        Some(value) => cg_block.push(CgInst::Call {
                                             value: *value,
                                             args: arguments.clone(),
                                             ret: inst.value,
-                                        });
+                                        }),
+
+                                        // a function held by a variable is called through its closure.
+                                        None => cg_block.push(CgInst::CallClosure {
+                                            closure: *callable,
+                                            closure_t: callable_t,
+                                            args: arguments.clone(),
+                                            ret: inst.value,
+                                        }),
                                     }
 
                                     continue;
@@ -444,9 +1113,9 @@ impl TypingMachine {
                                             })
                                         }
 
-                                        montyc_core::PropertyValue::Builtin(kind, slot_name) => {
-                                            todo!()
-                                        }
+                                        // the builtin methods of lists, dicts and sets are compiled
+                                        // inline above, any other call of one is already an error.
+                                        montyc_core::PropertyValue::Builtin(..) => (),
                                     }
                                 }
                             }
@@ -467,6 +1136,11 @@ impl TypingMachine {
                 RawInst::GetAttribute { object, attr } => {
                     let object_t = value_types[object];
 
+                    if object_t == TypingConstants::Unknown {
+                        value_types.insert(inst.value, TypingConstants::Unknown);
+                        continue;
+                    }
+
                     match Self::find_inst(cfg, *attr).unwrap().0.op {
                         RawInst::RefAsStr { r } => {
                             let attr = cx.spanref_to_str(r)?;
@@ -482,6 +1156,7 @@ impl TypingMachine {
                                 Ok(p) => p.type_id,
                                 Err(exc) => {
                                     errors.push(exc);
+                                    value_types.insert(inst.value, TypingConstants::Unknown);
                                     continue;
                                 }
                             };
@@ -498,20 +1173,35 @@ impl TypingMachine {
                 RawInst::GetDunder { object, dunder } => {
                     let object_t = value_types[object];
 
-                    let dunder = format!("{}", dunder);
-                    let property = cx
-                        .typing_context
-                        .get_property(object_t, &dunder)
-                        .ok_or_else(|| TypeError::InvalidAttributeAccess {
-                            base: object_t,
-                            access: (mref, inst.attrs.span.clone().unwrap_or_default()),
-                        });
+                    if object_t == TypingConstants::Unknown {
+                        value_types.insert(inst.value, TypingConstants::Unknown);
+                        continue;
+                    }
+
+                    let name = format!("{}", dunder);
+                    let property =
+                        cx.typing_context
+                            .get_property(object_t, &name)
+                            .ok_or_else(|| TypeError::InvalidAttributeAccess {
+                                base: object_t,
+                                access: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                            });
 
                     let dunder_t = match property {
                         Ok(p) => p.type_id,
                         Err(exc) => {
-                            todo!("{:?}", exc);
-                            errors.push(exc);
+                            // a `with` statement looks up both dunders, only report the missing `__enter__` once.
+                            let reported = matches!(dunder, Dunder::Exit)
+                                && cx
+                                    .typing_context
+                                    .get_property(object_t, "__enter__")
+                                    .is_none();
+
+                            if !reported {
+                                errors.push(exc);
+                            }
+
+                            value_types.insert(inst.value, TypingConstants::Unknown);
                             continue;
                         }
                     };
@@ -521,17 +1211,27 @@ impl TypingMachine {
 
                 RawInst::SetDunder { .. } => todo!(),
 
-                RawInst::Const(cst) => {
-                    let cst_t = match cst {
-                        Constant::Int(_) => TypingConstants::Int,
-                        Constant::Float(_) => TypingConstants::Float,
-                        Constant::Bool(_) => TypingConstants::Bool,
-                        Constant::String(_) => TypingConstants::Str,
-                        Constant::None => TypingConstants::None,
-                        Constant::Ellipsis => TypingConstants::Ellipsis,
-                    };
+                RawInst::Not { value } => {
+                    value_types.insert(inst.value, TypingConstants::Bool);
 
-                    value_types.insert(inst.value, cst_t);
+                    cg_block.push(CgInst::Not {
+                        value: *value,
+                        ret: inst.value,
+                    });
+                }
+
+                RawInst::Is { left, right } => {
+                    value_types.insert(inst.value, TypingConstants::Bool);
+
+                    cg_block.push(CgInst::Is {
+                        left: *left,
+                        right: *right,
+                        ret: inst.value,
+                    });
+                }
+
+                RawInst::Const(cst) => {
+                    value_types.insert(inst.value, constant_type(cst));
 
                     let cst = cst.clone();
 
@@ -555,6 +1255,202 @@ impl TypingMachine {
                     value_types.insert(inst.value, type_id);
                 }
 
+                // only tuples of a known length can be unpacked in compiled code.
+                RawInst::Unpack { value, index, len } => {
+                    let value_t = value_types[value];
+
+                    if value_t == TypingConstants::Unknown {
+                        value_types.insert(inst.value, TypingConstants::Unknown);
+                        continue;
+                    }
+
+                    let field_t = match cx.tcx().get_python_type_of(value_t).unwrap() {
+                        PythonType::Tuple {
+                            members: Some(members),
+                        } if members.len() == *len => members[*index],
+
+                        _ => {
+                            errors.push(TypeError::Unsupported {
+                                span: (mref, inst.attrs.span.clone().unwrap_or_default()),
+                                message: format!(
+                                    "can not unpack a value of type {} into {} targets.",
+                                    cx.tcx()
+                                        .display_type(value_t, &|v| cx.get_type_of(v).ok())
+                                        .unwrap_or_else(|| String::from("<unknown>")),
+                                    len
+                                ),
+                            });
+
+                            value_types.insert(inst.value, TypingConstants::Unknown);
+                            continue;
+                        }
+                    };
+
+                    value_types.insert(inst.value, field_t);
+
+                    cg_block.push(CgInst::FieldLoad {
+                        orig: *value,
+                        orig_t: value_t,
+                        field: montyc_core::codegen::Field::Imm(*index as i64),
+                        field_t,
+                        ret: inst.value,
+                    });
+                }
+
+                RawInst::List(elems) | RawInst::Set(elems) => {
+                    let span = (mref, inst.attrs.span.clone().unwrap_or_default());
+                    let elem_t = Self::display_element_type(cfg, mref, value_types, elems, errors);
+
+                    let type_id = match &inst.op {
+                        RawInst::Set(_) if !Self::check_hashable(elem_t, &span, errors) => {
+                            TypingConstants::Unknown
+                        }
+
+                        _ if elem_t == TypingConstants::Unknown => TypingConstants::Unknown,
+                        RawInst::Set(_) => cx.tcx().set(elem_t),
+                        _ => cx.tcx().list(elem_t),
+                    };
+
+                    value_types.insert(inst.value, type_id);
+
+                    cg_block.push(CgInst::Alloc {
+                        type_id,
+                        ilist: elems.to_vec(),
+                        ret: inst.value,
+                    });
+                }
+
+                RawInst::Dict(items) => {
+                    let span = (mref, inst.attrs.span.clone().unwrap_or_default());
+                    let (keys, values): (Vec<_>, Vec<_>) = items.iter().cloned().unzip();
+
+                    let key_t = Self::display_element_type(cfg, mref, value_types, &keys, errors);
+                    let value_t =
+                        Self::display_element_type(cfg, mref, value_types, &values, errors);
+
+                    let type_id = if !Self::check_hashable(key_t, &span, errors)
+                        || key_t == TypingConstants::Unknown
+                        || value_t == TypingConstants::Unknown
+                    {
+                        TypingConstants::Unknown
+                    } else {
+                        cx.tcx().dict(key_t, value_t)
+                    };
+
+                    value_types.insert(inst.value, type_id);
+
+                    cg_block.push(CgInst::Alloc {
+                        type_id,
+                        ilist: items.iter().flat_map(|(k, v)| [*k, *v]).collect(),
+                        ret: inst.value,
+                    });
+                }
+
+                RawInst::ListAppend { list, value } | RawInst::SetAdd { set: list, value } => {
+                    let span = (mref, inst.attrs.span.clone().unwrap_or_default());
+                    let (container_t, value_t) = (value_types[list], value_types[value]);
+
+                    if container_t == TypingConstants::Unknown
+                        || value_t == TypingConstants::Unknown
+                    {
+                        value_types.insert(*list, TypingConstants::Unknown);
+                        continue;
+                    }
+
+                    let is_set = matches!(inst.op, RawInst::SetAdd { .. });
+
+                    if is_set && !Self::check_hashable(value_t, &span, errors) {
+                        value_types.insert(*list, TypingConstants::Unknown);
+                        continue;
+                    }
+
+                    // comprehensions start out empty, their elements decide their type.
+                    let refined_t = match cx.tcx().get_python_type_of(container_t).unwrap() {
+                        PythonType::List { inner } | PythonType::Set { inner }
+                            if inner == TypingConstants::Never || inner == value_t =>
+                        {
+                            match is_set {
+                                true => cx.tcx().set(value_t),
+                                false => cx.tcx().list(value_t),
+                            }
+                        }
+
+                        PythonType::List { inner } | PythonType::Set { inner } => {
+                            errors.push(TypeError::BadArgumentType {
+                                expected: inner,
+                                actual: value_t,
+                                arg_node: span.clone(),
+                                def_node: span,
+                            });
+
+                            value_types.insert(*list, TypingConstants::Unknown);
+                            continue;
+                        }
+
+                        _ => unreachable!(),
+                    };
+
+                    Self::refine_container(cfg, locals, value_types, *list, refined_t);
+
+                    cg_block.push(match is_set {
+                        true => CgInst::SetAdd {
+                            set: *list,
+                            value: *value,
+                        },
+                        false => CgInst::ListAppend {
+                            list: *list,
+                            value: *value,
+                        },
+                    });
+                }
+
+                RawInst::DictInsert { dict, key, value } => {
+                    let span = (mref, inst.attrs.span.clone().unwrap_or_default());
+                    let (dict_t, key_t, value_t) =
+                        (value_types[dict], value_types[key], value_types[value]);
+
+                    if [dict_t, key_t, value_t].contains(&TypingConstants::Unknown)
+                        || !Self::check_hashable(key_t, &span, errors)
+                    {
+                        value_types.insert(*dict, TypingConstants::Unknown);
+                        continue;
+                    }
+
+                    match cx.tcx().get_python_type_of(dict_t).unwrap() {
+                        PythonType::Dict { key, value }
+                            if (key == TypingConstants::Never || key == key_t)
+                                && (value == TypingConstants::Never || value == value_t) => {}
+
+                        PythonType::Dict { key, value } => {
+                            let (expected, actual) = match key == key_t {
+                                true => (value, value_t),
+                                false => (key, key_t),
+                            };
+
+                            errors.push(TypeError::BadArgumentType {
+                                expected,
+                                actual,
+                                arg_node: span.clone(),
+                                def_node: span,
+                            });
+
+                            value_types.insert(*dict, TypingConstants::Unknown);
+                            continue;
+                        }
+
+                        _ => unreachable!(),
+                    }
+
+                    let refined_t = cx.tcx().dict(key_t, value_t);
+                    Self::refine_container(cfg, locals, value_types, *dict, refined_t);
+
+                    cg_block.push(CgInst::DictInsert {
+                        dict: *dict,
+                        key: *key,
+                        value: *value,
+                    });
+                }
+
                 RawInst::PhiJump { recv, value } => {
                     let (_, block) = Self::find_inst(cfg, *recv).unwrap();
                     let to = CgBlockId(block.index());
@@ -564,12 +1460,40 @@ impl TypingMachine {
                     });
                 }
 
-                RawInst::PhiRecv => todo!(),
+                RawInst::PhiRecv => {
+                    // the received value can be any of the values jumped in with.
+                    let recv_t = cfg
+                        .raw_nodes()
+                        .iter()
+                        .flat_map(|node| node.weight.iter())
+                        .filter_map(|jump| match jump.op {
+                            RawInst::PhiJump { recv, value } if recv == inst.value => {
+                                value_types.get(&value).cloned()
+                            }
+
+                            _ => None,
+                        })
+                        .reduce(|left, right| {
+                            if left == right {
+                                left
+                            } else {
+                                cx.tcx().make_union(left, right).unwrap_or(left)
+                            }
+                        })
+                        .unwrap_or(TypingConstants::Unknown);
+
+                    value_types.insert(inst.value, recv_t);
+
+                    cg_block.push(CgInst::BlockParam {
+                        type_id: recv_t,
+                        ret: inst.value,
+                    });
+                }
 
                 RawInst::Return { value } => {
                     let val_t = value_types[value];
 
-                    if val_t != *return_t {
+                    if val_t != *return_t && val_t != TypingConstants::Unknown {
                         errors.push(TypeError::BadReturnType {
                             expected: *return_t,
                             actual: val_t,
@@ -583,9 +1507,463 @@ impl TypingMachine {
             }
         }
 
-        assert!(!cg_block.is_empty());
+        // every instruction of a block may have been poisoned, but then it is never compiled anyway.
+        assert!(!cg_block.enabled || !cg_block.insts.is_empty() || errors.len() > errors_before);
+
+        Ok(cg_block.insts)
+    }
+
+    /// Resolve the reflected operator `right.__rop__(left)` for a call to `left.__op__(right)` that failed to unify.
+    fn reflected_operator(
+        cx: &SessionContext,
+        cfg: &BlockCFG,
+        callable: usize,
+        arguments: &[usize],
+        value_types: &MapT<usize, TypeId>,
+    ) -> Option<(ValueId, TypeId)> {
+        let op = match Self::find_inst(cfg, callable)?.0.op {
+            RawInst::GetDunder {
+                dunder: Dunder::Infix(op),
+                ..
+            } => op,
+            _ => return None,
+        };
+
+        let (left, right) = match arguments {
+            [left, right] => (*left, *right),
+            _ => return None,
+        };
+
+        let reflected = match op {
+            InfixOp::Lt => "__gt__".to_string(),
+            InfixOp::LtE => "__ge__".to_string(),
+            InfixOp::Gt => "__lt__".to_string(),
+            InfixOp::GtE => "__le__".to_string(),
+            InfixOp::Eq | InfixOp::NotEq => format!("__{}__", op.as_ref()),
+
+            InfixOp::In
+            | InfixOp::NotIn
+            | InfixOp::Is
+            | InfixOp::IsNot
+            | InfixOp::And
+            | InfixOp::Or
+            | InfixOp::Invert => return None,
+
+            _ => format!("__r{}__", op.as_ref()),
+        };
+
+        let (left_t, right_t) = (value_types[&left], value_types[&right]);
+
+        let property = cx.typing_context.get_property(right_t, &reflected)?;
+
+        let value = match property.value {
+            PropertyValue::Id(value) => value,
+            PropertyValue::Builtin(..) => return None,
+        };
+
+        cx.tcx()
+            .unify_func(
+                property.type_id,
+                &[right_t, left_t],
+                TypingConstants::Unknown,
+            )
+            .ok()?;
+
+        match cx.tcx().get_python_type_of(property.type_id)? {
+            PythonType::Callable { ret, .. } => Some((value, ret)),
+            _ => None,
+        }
+    }
+
+    /// The receiver, kind and remaining arguments of a call of a list, dict or set method, `len(x)` is `x.__len__()`.
+    fn container_method(
+        cx: &SessionContext,
+        cfg: &BlockCFG,
+        callable: usize,
+        arguments: &[usize],
+        nonlocals: &MapT<usize, ValueId>,
+        value_types: &MapT<usize, TypeId>,
+    ) -> Option<(usize, ContainerMethod, Vec<usize>)> {
+        let (receiver, name, rest) = match Self::find_inst(cfg, callable)?.0.op {
+            RawInst::GetAttribute { object, attr } => match Self::find_inst(cfg, attr)?.0.op {
+                RawInst::RefAsStr { r } => (
+                    object,
+                    cx.spanref_to_str(r).ok()?.to_string(),
+                    arguments.to_vec(),
+                ),
+                _ => return None,
+            },
+
+            RawInst::GetDunder { object, dunder } => {
+                (object, format!("{}", dunder), arguments.get(1..)?.to_vec())
+            }
+
+            RawInst::UseVar { .. } => {
+                let builtins = cx
+                    .value_store
+                    .get_by_assoc(cx.const_runtime.borrow().singletons.builtins)?;
+
+                let len = cx.str_to_spanref("len");
+                let len = cx
+                    .value_store
+                    .with_value(builtins, |m| m.properties.get(&len.group()).cloned())
+                    .flatten()?;
+
+                if nonlocals.get(&callable) != Some(&len) {
+                    return None;
+                }
+
+                (
+                    *arguments.first()?,
+                    String::from("__len__"),
+                    arguments[1..].to_vec(),
+                )
+            }
+
+            _ => return None,
+        };
+
+        let receiver_t = *value_types.get(&receiver)?;
+
+        let method = match (cx.tcx().get_python_type_of(receiver_t)?, name.as_str()) {
+            (
+                PythonType::List { .. } | PythonType::Dict { .. } | PythonType::Set { .. },
+                "__len__",
+            ) => ContainerMethod::Len,
+
+            (PythonType::List { .. } | PythonType::Dict { .. }, "__getitem__") => {
+                ContainerMethod::GetItem
+            }
+
+            (PythonType::List { .. }, "append") => ContainerMethod::Append,
+            (PythonType::Set { .. }, "add") => ContainerMethod::Add,
+            _ => return None,
+        };
+
+        Some((receiver, method, rest))
+    }
+
+    /// The type shared by every one of the `elements` of a display, `Never` when there are none.
+    ///
+    /// Reports the first element with a different type and returns `Unknown` if there is one.
+    fn display_element_type(
+        cfg: &BlockCFG,
+        mref: ModuleRef,
+        value_types: &MapT<usize, TypeId>,
+        elements: &[usize],
+        errors: &mut Vec<ErrorTy>,
+    ) -> TypeId {
+        let span_of = |value: usize| {
+            let span = Self::find_inst(cfg, value).and_then(|(inst, _)| inst.attrs.span.clone());
+            (mref, span.unwrap_or_default())
+        };
+
+        let first = match elements.first() {
+            Some(first) => *first,
+            None => return TypingConstants::Never,
+        };
+
+        let elem_t = value_types[&first];
+
+        for element in elements {
+            let actual = value_types[element];
+
+            if actual == TypingConstants::Unknown {
+                return TypingConstants::Unknown;
+            }
+
+            if actual != elem_t {
+                errors.push(TypeError::IncompatibleTypes {
+                    left_span: span_of(first),
+                    left: elem_t,
+                    right_span: span_of(*element),
+                    right: actual,
+                });
+
+                return TypingConstants::Unknown;
+            }
+        }
+
+        elem_t
+    }
+
+    /// Set elements and dict keys are compared by value, which compiled code can only do for ints and bools.
+    fn check_hashable(
+        key_t: TypeId,
+        span: &(ModuleRef, montyc_core::Span),
+        errors: &mut Vec<ErrorTy>,
+    ) -> bool {
+        let hashable = [
+            TypingConstants::Int,
+            TypingConstants::Bool,
+            TypingConstants::Never,
+            TypingConstants::Unknown,
+        ];
+
+        if !hashable.contains(&key_t) {
+            errors.push(TypeError::Unsupported {
+                span: span.clone(),
+                message: "only ints and bools can be set elements or dict keys in compiled code."
+                    .into(),
+            });
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Refine the type of the container `value` to `refined_t`, i.e. when an empty list is first appended to.
+    ///
+    /// When the container was read from a local variable the variable is refined as well.
+    fn refine_container(
+        cfg: &BlockCFG,
+        locals: &mut MapT<u32, Variable>,
+        value_types: &mut MapT<usize, TypeId>,
+        value: usize,
+        refined_t: TypeId,
+    ) {
+        let container_t = value_types.insert(value, refined_t);
+
+        if let Some((
+            FlatInst {
+                op: RawInst::UseVar { variable },
+                ..
+            },
+            _,
+        )) = Self::find_inst(cfg, value)
+        {
+            if let Some(Variable(bindings)) = locals.get_mut(&variable.group()) {
+                bindings
+                    .iter_mut()
+                    .filter(|binding| Some(binding.type_id) == container_t)
+                    .for_each(|binding| binding.type_id = refined_t);
+            }
+        }
+    }
+
+    /// The function value that `callable` refers to, if it's known statically.
+    /// The type of a local variable, a union of the types of its bindings.
+    fn variable_type(cx: &SessionContext, bindings: &[Binding]) -> TypeId {
+        bindings
+            .iter()
+            .map(|binding| binding.type_id)
+            .reduce(|left, right| {
+                if left == right {
+                    left
+                } else {
+                    cx.tcx().make_union(left, right).unwrap_or(left)
+                }
+            })
+            .expect("local variables always have at least one binding.")
+    }
+
+    /// The type instances of the annotation `ann` have, if it refers to a class or is `None`.
+    fn annotation_type(
+        cx: &SessionContext,
+        value_types: &MapT<usize, TypeId>,
+        ann: usize,
+    ) -> Option<TypeId> {
+        let ann_t = *value_types.get(&ann)?;
+
+        if ann_t == TypingConstants::None {
+            return Some(TypingConstants::None);
+        }
+
+        match cx.tcx().get_python_type_of(ann_t)? {
+            PythonType::Type { of } => Some(of),
+            _ => None,
+        }
+    }
+
+    /// The value of the function defined by the sequence `seq_id` of `code`, inside of another function.
+    ///
+    /// Nested functions have no runtime object, so a value is created for them the first time they're seen.
+    fn nested_function(
+        cx: &SessionContext,
+        code: &Rc<FlatCode>,
+        seq_id: usize,
+        type_id: TypeId,
+        captures: Vec<(SpanRef, TypeId)>,
+    ) -> ValueId {
+        let mref = code.mref();
+
+        if let Some(value) = cx.value_store.get_by_assoc((mref, seq_id)) {
+            return value;
+        }
+
+        let value = cx.value_store.insert(Value::default());
+        cx.value_store.assoc((mref, seq_id), value);
+
+        let seq = code.sequences()[seq_id].clone();
+
+        let function = montyc_core::Function {
+            value_id: TaggedValueId::func(value),
+            type_id,
+            mref,
+            name: seq.name().expect("nested functions are always named."),
+            is_extern: false,
+            captures: Some(captures),
+        };
+
+        cx.value_store.with_metadata_mut(value, |m| {
+            m.type_id.replace(type_id);
+            m.function.replace(function);
+            m.flatcode.replace((seq, Some(Rc::clone(code))));
+        });
+
+        value
+    }
+
+    fn callee_of(
+        cx: &SessionContext,
+        cfg: &BlockCFG,
+        callable: usize,
+        nonlocals: &MapT<usize, ValueId>,
+        value_types: &MapT<usize, TypeId>,
+    ) -> Option<ValueId> {
+        match Self::find_inst(cfg, callable)?.0.op {
+            RawInst::UseVar { .. } => nonlocals.get(&callable).cloned(),
+
+            RawInst::GetAttribute { object, attr } => {
+                let name = match Self::find_inst(cfg, attr)?.0.op {
+                    RawInst::RefAsStr { r } => cx.spanref_to_str(r).ok()?.to_string(),
+                    _ => return None,
+                };
+
+                let object_t = *value_types.get(&object)?;
+
+                match cx.typing_context.get_property(object_t, &name)?.value {
+                    PropertyValue::Id(value) => Some(value),
+                    PropertyValue::Builtin(..) => None,
+                }
+            }
+
+            _ => None,
+        }
+    }
+
+    /// The parameters of a function defined in Python, including its receiver.
+    fn signature_of(cx: &SessionContext, callee: ValueId) -> Option<Vec<CalleeParam>> {
+        let func = cx.get_function(callee).ok()?;
+        let code = cx.get_function_flatcode(func.value_id).ok()?;
+
+        let def = match code.ast? {
+            AstNode::FuncDef(def) => def,
+            _ => return None,
+        };
+
+        let mut params = vec![];
+
+        if let Some(recv) = &def.reciever {
+            params.push(CalleeParam {
+                name: cx.spanref_to_str(recv.inner.as_name()?).ok()?.to_string(),
+                kind: ParameterKind::PositionalOrKeyword,
+                default: None,
+            });
+        }
+
+        for param in def.args.unwrap_or_default() {
+            params.push(CalleeParam {
+                name: cx.spanref_to_str(param.name).ok()?.to_string(),
+                kind: param.kind,
+                default: param.default.as_ref().map(constant_of),
+            });
+        }
+
+        Some(params)
+    }
+
+    /// Bind the arguments of a call to the callee's parameters in signature order.
+    ///
+    /// A parameter that was not passed an argument is bound to its constant default
+    /// instead, as an `Err` since it's not a value of the caller yet.
+    ///
+    fn bind_arguments(
+        params: &[CalleeParam],
+        positional: &[usize],
+        keywords: &[(String, usize)],
+        callsite: &(ModuleRef, montyc_core::Span),
+    ) -> Result<Vec<Result<usize, Constant>>, TypeError> {
+        let bad_call = |message: String| TypeError::BadCallArguments {
+            callsite: callsite.clone(),
+            message,
+        };
+
+        // `*args` and `**kwargs` are not part of the compiled signature.
+        let (named, variadic): (Vec<_>, Vec<_>) =
+            params.iter().partition(|param| !param.kind.is_variadic());
+
+        let n_positional = named
+            .iter()
+            .filter(|param| param.kind.is_positional())
+            .count();
+
+        if positional.len() > n_positional {
+            return Err(match variadic.is_empty() {
+                true => bad_call(format!(
+                    "expected at most {} positional arguments but {} were supplied",
+                    n_positional,
+                    positional.len()
+                )),
+
+                false => TypeError::Unsupported {
+                    span: callsite.clone(),
+                    message: "excess arguments for `*args` can only be passed in comptime code."
+                        .into(),
+                },
+            });
+        }
+
+        let mut bound: Vec<Option<usize>> = vec![None; named.len()];
+
+        for (slot, value) in bound.iter_mut().zip(positional.iter()) {
+            slot.replace(*value);
+        }
+
+        for (name, value) in keywords {
+            let ix = named.iter().position(|param| {
+                param.kind != ParameterKind::PositionalOnly && param.name == *name
+            });
+
+            match ix {
+                Some(ix) if bound[ix].is_some() => {
+                    return Err(bad_call(format!("multiple values for argument `{}`", name)))
+                }
+
+                Some(ix) => bound[ix] = Some(*value),
+
+                None if variadic
+                    .iter()
+                    .any(|param| param.kind == ParameterKind::VarKeyword) =>
+                {
+                    return Err(TypeError::Unsupported {
+                        span: callsite.clone(),
+                        message: "excess keyword arguments for `**kwargs` can only be passed in comptime code.".into(),
+                    })
+                }
+
+                None => return Err(bad_call(format!("unexpected keyword argument `{}`", name))),
+            }
+        }
 
-        Ok(cg_block)
+        named
+            .iter()
+            .zip(bound)
+            .map(|(param, value)| match (value, &param.default) {
+                (Some(value), _) => Ok(Ok(value)),
+                (None, Some(Some(cst))) => Ok(Err(cst.clone())),
+                (None, Some(None)) => Err(TypeError::Unsupported {
+                    span: callsite.clone(),
+                    message: format!(
+                        "the default of `{}` must be a constant to be used in compiled code.",
+                        param.name
+                    ),
+                }),
+
+                (None, None) => Err(bad_call(format!("missing argument `{}`", param.name))),
+            })
+            .collect()
     }
 
     pub(crate) fn find_inst(cfg: &BlockCFG, inst_ix: usize) -> Option<(&FlatInst, NodeIndex)> {
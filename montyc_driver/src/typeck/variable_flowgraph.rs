@@ -92,7 +92,7 @@ pub struct VariableFlowGraph(RawVFG);
 impl VariableFlowGraph {
     pub(crate) fn new(cx: &SessionContext, b_cfg: &BlockCFGBuilder) -> MontyResult<Self> {
         let mut builder = VFGBuilder { inp: b_cfg.clone() };
-        let vfg = builder.visit(cx, NodeIndex::from(0))?;
+        let vfg = builder.visit(cx, NodeIndex::from(0), &mut vec![])?;
 
         Ok(VariableFlowGraph(vfg))
     }
@@ -7,14 +7,15 @@
 #![deny(warnings)]
 
 mod lower;
+#[cfg(test)]
 mod tests;
 
 pub mod raw_inst;
 
 use std::fmt::Display;
 
-use montyc_core::{ast::Constant, ModuleRef, Span, SpanRef};
-use montyc_parser::AstNode;
+use montyc_core::{ast::Constant, ModuleRef, ParseError, Span, SpanRef};
+use montyc_parser::{ast::Statement, spanned::Spanned, AstNode};
 
 use self::raw_inst::RawInst;
 
@@ -73,6 +74,33 @@ impl FlatSeq {
         self.inst.as_mut_slice()
     }
 
+    /// The name of the function or class this sequence is the body of, if it is one.
+    #[inline]
+    pub fn name(&self) -> Option<SpanRef> {
+        match self.ast.as_ref()? {
+            AstNode::FuncDef(def) => def.name.inner.as_name(),
+            AstNode::ClassDef(def) => def.name.inner.as_name(),
+            _ => None,
+        }
+    }
+
+    /// The names of the parameters of the function this sequence is the body of.
+    pub fn parameters(&self) -> Vec<SpanRef> {
+        let params = match self.ast.as_ref() {
+            Some(AstNode::FuncDef(def)) => {
+                let reciever = def.reciever.as_ref().and_then(|recv| recv.inner.as_name());
+                let params = def.args.iter().flatten().map(|param| param.name);
+
+                return reciever.into_iter().chain(params).collect();
+            }
+
+            Some(AstNode::Lambda(montyc_parser::ast::Expr::Lambda { args, .. })) => args,
+            _ => return vec![],
+        };
+
+        params.iter().map(|param| param.name).collect()
+    }
+
     /// true if the body of the sequence consists of only an ellipsis or a string, followed by an ellipsis.
     #[inline]
     pub fn is_stubbed(&self) -> bool {
@@ -88,12 +116,39 @@ impl FlatSeq {
     }
 }
 
+/// Branch bookkeeping for an enclosing loop, used when lowering `break` and `continue`.
+#[derive(Debug, Clone)]
+pub(crate) struct LoopScope {
+    /// The jump target `continue` branches to.
+    pub(crate) start: usize,
+    /// Placeholder branches emitted for `break` that get patched once the loop exit is known.
+    pub(crate) breaks: Vec<usize>,
+    /// How many `unwinds` were active when the loop was entered.
+    pub(crate) unwinds: usize,
+}
+
+/// Cleanup that has to be emitted when control leaves a `try` or `with` statement early, i.e. by `break` or `return`.
+#[derive(Debug, Clone)]
+pub(crate) enum Unwind {
+    /// A handler pushed by `setup-except` that needs a `pop-block`.
+    Handler,
+    /// An exception being handled by an `except` clause that needs a `pop-except`.
+    Except,
+    /// A `finally` handler that needs a `pop-block` followed by its body inline.
+    Finally(Vec<Spanned<Statement>>),
+    /// A `with` statement handler that needs a `pop-block` followed by a call to `exit`.
+    With { manager: usize, exit: usize },
+}
+
 /// An SSA-based, linear, sequence of code-like IR generated by flattening an AST.
 #[derive(Debug, Clone)]
 pub struct FlatCode {
     sequence_index: usize,
     pub mref: ModuleRef,
     pub(crate) sequences: Vec<FlatSeq>,
+    pub(crate) loops: Vec<LoopScope>,
+    pub(crate) unwinds: Vec<Unwind>,
+    pub(crate) errors: Vec<ParseError>,
 }
 
 impl Display for FlatCode {
@@ -127,6 +182,15 @@ impl FlatCode {
         }
     }
 
+    /// Record a syntax error found while lowering, i.e. a `break` outside of a loop.
+    fn syntax_error(&mut self, span: Option<Span>, message: &str) {
+        self.errors.push(ParseError {
+            span: span.unwrap_or(0..0),
+            message: message.to_string(),
+            expected: vec![],
+        });
+    }
+
     fn last_inst(&self) -> Option<&FlatInst> {
         self.sequences.get(self.sequence_index)?.inst.last()
     }
@@ -164,9 +228,13 @@ impl FlatCode {
         assert!(self.sequences.get(self.sequence_index).is_some());
 
         let index = self.sequence_index;
+        let loops = std::mem::take(&mut self.loops);
+        let unwinds = std::mem::take(&mut self.unwinds);
 
         f(self);
 
+        self.loops = loops;
+        self.unwinds = unwinds;
         self.sequence_index = old_index;
         index
     }
@@ -184,6 +252,9 @@ impl FlatCode {
                 ast: None,
                 span,
             }],
+            loops: vec![],
+            unwinds: vec![],
+            errors: vec![],
         }
     }
 
@@ -192,11 +263,51 @@ impl FlatCode {
         self.mref
     }
 
+    /// The syntax errors found while lowering, the code should not be used if there are any.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
     /// The sequences of the code.
     pub fn sequences(&self) -> &[FlatSeq] {
         &self.sequences
     }
 
+    /// The variables the sequence `sequence_id` reads without binding them itself, in order of first use.
+    ///
+    /// Variables read by functions and classes defined inside of the sequence are included
+    /// when they aren't bound by the sequence either, these are the variables a closure captures.
+    pub fn free_variables(&self, sequence_id: usize) -> Vec<SpanRef> {
+        let seq = &self.sequences[sequence_id];
+
+        let mut bound: Vec<u32> = seq.parameters().iter().map(SpanRef::group).collect();
+        let mut used = vec![];
+
+        for inst in seq.inst() {
+            match &inst.op {
+                RawInst::SetVar { variable, .. } => bound.push(variable.group()),
+                RawInst::UseVar { variable } => used.push(*variable),
+                RawInst::Defn { sequence_id, .. }
+                | RawInst::Lambda { sequence_id, .. }
+                | RawInst::BuildClass {
+                    sequence: sequence_id,
+                    ..
+                } => used.extend(self.free_variables(*sequence_id)),
+                _ => (),
+            }
+        }
+
+        let mut free: Vec<SpanRef> = vec![];
+
+        for var in used {
+            if !bound.contains(&var.group()) && !free.iter().any(|f| f.group() == var.group()) {
+                free.push(var);
+            }
+        }
+
+        free
+    }
+
     /// Check if the given sequence only has an ellipsis instruction inside it.
     pub fn is_sequence_ellipsis_stubbed(&self, seq: usize) -> bool {
         self.sequences
@@ -1,15 +1,38 @@
-use montyc_core::ast::Constant;
+use montyc_core::ast::{Constant, ParameterKind};
 use montyc_core::{patma, Span};
 
 use montyc_parser::ast::{
-    Annotation, Assign, Atom, ClassDef, Expr, FunctionDef, IfChain, Import, Primary, Return, While,
+    self, Annotation, Assign, Atom, ClassDef, Comprehension, ExceptHandler, Expr, For, FunctionDef,
+    IfChain, Import, InfixOp, Primary, Raise, Return, Statement, Try, UnaryOp, While, With,
+    WithItem,
 };
-use montyc_parser::{AstNode, AstObject, AstVisitor};
+use montyc_parser::{spanned::Spanned, AstNode, AstObject, AstVisitor};
 
 use crate::SequenceType;
 
-use super::raw_inst::{Dunder, RawInst};
-use super::{FlatCode, INVALID_VALUE};
+use super::raw_inst::{Argument, Dunder, Parameter, RawInst};
+use super::{FlatCode, LoopScope, Unwind, INVALID_VALUE};
+
+/// Lower the annotations and defaults of `params`, these are evaluated once, when the function is defined.
+fn lower_parameters(this: &mut FlatCode, params: &[ast::Parameter]) -> Vec<Parameter> {
+    params
+        .iter()
+        .map(|param| {
+            let annotation = param
+                .annotation
+                .as_ref()
+                .map(|ann| ann.visit_with(this, None));
+            let default = param.default.as_ref().map(|def| def.visit_with(this, None));
+
+            Parameter {
+                name: param.name,
+                annotation,
+                default,
+                kind: param.kind,
+            }
+        })
+        .collect()
+}
 
 fn visit_const(this: &mut FlatCode, node: &Atom, span: Option<Span>) -> usize {
     let const_v = match node {
@@ -31,6 +54,345 @@ fn visit_const(this: &mut FlatCode, node: &Atom, span: Option<Span>) -> usize {
     const_v
 }
 
+/// Emit the cleanup for every `try` statement entered after the first `depth` unwinds, innermost first.
+fn unwind_to(this: &mut FlatCode, depth: usize) {
+    let unwinds = this.unwinds.clone();
+
+    for (ix, unwind) in unwinds.iter().enumerate().skip(depth).rev() {
+        match unwind {
+            Unwind::Handler => {
+                this.inst(RawInst::PopBlock);
+            }
+
+            Unwind::Except => {
+                this.inst(RawInst::PopExcept);
+            }
+
+            Unwind::Finally(body) => {
+                this.inst(RawInst::PopBlock);
+
+                // the finally body only runs inside of the statements enclosing its `try`.
+                this.unwinds.truncate(ix);
+
+                for node in body {
+                    node.visit_with(this, None);
+                }
+            }
+
+            Unwind::With { manager, exit } => {
+                this.inst(RawInst::PopBlock);
+
+                let none = this.inst(RawInst::Const(Constant::None));
+
+                this.inst(RawInst::Call {
+                    callable: *exit,
+                    arguments: vec![*manager, none, none, none],
+                });
+            }
+        }
+    }
+
+    this.unwinds = unwinds;
+}
+
+/// Emit the `with` statement entering `items` in order, the first item being the outermost one.
+fn with_items(this: &mut FlatCode, items: &[Spanned<WithItem>], body: &[Spanned<Statement>]) {
+    let (item, rest) = match items.split_first() {
+        Some(split) => split,
+        None => {
+            for node in body {
+                node.visit_with(this, None);
+            }
+
+            return;
+        }
+    };
+
+    let WithItem {
+        context_expr,
+        target,
+    } = &item.inner;
+
+    let manager = context_expr.inner.visit_with(this, None);
+
+    // `__exit__` is looked up before calling `__enter__`.
+    let enter = this.inst(RawInst::GetDunder {
+        object: manager,
+        dunder: Dunder::Enter,
+    });
+
+    let exit = this.inst(RawInst::GetDunder {
+        object: manager,
+        dunder: Dunder::Exit,
+    });
+
+    let value = this.inst(RawInst::Call {
+        callable: enter,
+        arguments: vec![manager],
+    });
+
+    this.set_span_for_values([enter, exit, value], context_expr.span.clone());
+
+    let setup = this.inst(RawInst::SetupWith {
+        handler: INVALID_VALUE,
+    });
+
+    this.unwinds.push(Unwind::With { manager, exit });
+
+    if let Some(target) = target {
+        match &target.inner {
+            Primary::Atomic(Spanned {
+                inner: Atom::Name(variable),
+                ..
+            }) => {
+                let set_var = this.inst(RawInst::SetVar {
+                    variable: *variable,
+                    value,
+                });
+
+                this.set_span_for_values([set_var], target.span.clone());
+            }
+
+            _ => this.syntax_error(
+                Some(target.span.clone()),
+                "only plain names are supported as with statement targets.",
+            ),
+        }
+    }
+
+    with_items(this, rest, body);
+
+    // leaving the body normally calls `__exit__(None, None, None)`.
+    this.inst(RawInst::PopBlock);
+    this.unwinds.pop();
+
+    let none = this.inst(RawInst::Const(Constant::None));
+
+    this.inst(RawInst::Call {
+        callable: exit,
+        arguments: vec![manager, none, none, none],
+    });
+
+    let to_after = this.inst(RawInst::Br { to: INVALID_VALUE });
+
+    // an exception escaping the body is suppressed if `__exit__` returns something truthy.
+    let handler = this.inst(RawInst::JumpTarget);
+    let exc = this.inst(RawInst::GetException);
+
+    this.unwinds.push(Unwind::Except);
+
+    let kind = this.inst(RawInst::ClassOf { object: exc });
+    let none = this.inst(RawInst::Const(Constant::None));
+
+    let suppress = this.inst(RawInst::Call {
+        callable: exit,
+        arguments: vec![manager, kind, exc, none],
+    });
+
+    let test = truthy(this, suppress);
+
+    let jump = this.inst(RawInst::If {
+        test,
+        truthy: None,
+        falsey: Some(INVALID_VALUE),
+    });
+
+    this.inst(RawInst::PopExcept);
+
+    let suppressed = this.inst(RawInst::Br { to: INVALID_VALUE });
+    let reraise = this.inst(RawInst::JumpTarget);
+
+    this.inst(RawInst::Raise { exc: None });
+    this.unwinds.pop();
+
+    let after = this.inst(RawInst::JumpTarget);
+
+    let seq = this.sequences.get_mut(this.sequence_index).unwrap();
+
+    seq.inst[setup].op = RawInst::SetupWith { handler };
+    seq.inst[jump].op = RawInst::If {
+        test,
+        truthy: None,
+        falsey: Some(reraise),
+    };
+    seq.inst[to_after].op = RawInst::Br { to: after };
+    seq.inst[suppressed].op = RawInst::Br { to: after };
+}
+
+/// Bind `value` to the `target` of a `for` loop or comprehension, unpacking it element by element when the target is a tuple.
+fn store_target(this: &mut FlatCode, target: &Spanned<Primary>, value: usize, what: &str) {
+    let unsupported = |this: &mut FlatCode, span: Span| {
+        this.syntax_error(
+            Some(span),
+            &format!(
+                "only names and tuples of names are supported as {} targets.",
+                what
+            ),
+        )
+    };
+
+    match &target.inner {
+        Primary::Atomic(Spanned {
+            inner: Atom::Name(variable),
+            ..
+        }) => {
+            let set_var = this.inst(RawInst::SetVar {
+                variable: *variable,
+                value,
+            });
+
+            this.set_span_for_values([set_var], target.span.clone());
+        }
+
+        Primary::Atomic(Spanned {
+            inner: Atom::Tuple(elements),
+            ..
+        }) => {
+            let len = elements.len();
+
+            for (index, element) in elements.iter().enumerate() {
+                let unpacked = this.inst(RawInst::Unpack { value, index, len });
+
+                this.set_span_for_values([unpacked], element.span.clone());
+
+                match &element.inner {
+                    Expr::Primary(element) => store_target(this, element, unpacked, what),
+                    _ => unsupported(this, element.span.clone()),
+                }
+            }
+        }
+
+        _ => unsupported(this, target.span.clone()),
+    }
+}
+
+/// Emit the nested loops of a comprehension's `clauses`, calling `element` in the innermost loop.
+///
+/// Comprehensions are lowered inline, so their targets are plain variables of the enclosing scope.
+fn comprehension_loops(
+    this: &mut FlatCode,
+    clauses: &[Comprehension],
+    element: &mut dyn FnMut(&mut FlatCode),
+) {
+    let (clause, rest) = match clauses.split_first() {
+        Some(split) => split,
+        None => return element(this),
+    };
+
+    let object = clause.iter.inner.visit_with(this, None);
+    let iter = this.inst(RawInst::GetIter { object });
+
+    this.set_span_for_values([iter], clause.iter.span.clone());
+
+    let to_start = this.inst(RawInst::JumpTarget);
+    let start = this.inst(RawInst::JumpTarget);
+
+    this.sequences.get_mut(this.sequence_index).unwrap().inst[to_start].op =
+        RawInst::Br { to: start };
+
+    // the exhausted destination is patched once the loop body has been emitted.
+    let next = this.inst(RawInst::IterNext {
+        iter,
+        exhausted: INVALID_VALUE,
+    });
+
+    this.set_span_for_values([next], clause.target.span.clone());
+
+    store_target(this, &clause.target, next, "comprehension");
+
+    let to_body = this.inst(RawInst::Br { to: INVALID_VALUE });
+    let body = this.inst(RawInst::JumpTarget);
+
+    this.sequences.get_mut(this.sequence_index).unwrap().inst[to_body].op =
+        RawInst::Br { to: body };
+
+    // a failing condition skips straight to the next item.
+    for test in &clause.ifs {
+        let test = test.inner.visit_with(this, None);
+        let test = truthy(this, test);
+
+        let jump = this.inst(RawInst::If {
+            test,
+            truthy: None,
+            falsey: None,
+        });
+
+        let when_true = this.inst(RawInst::JumpTarget);
+
+        this.sequences.get_mut(this.sequence_index).unwrap().inst[jump].op = RawInst::If {
+            test,
+            truthy: Some(when_true),
+            falsey: Some(start),
+        };
+    }
+
+    comprehension_loops(this, rest, element);
+
+    this.inst(RawInst::Br { to: start });
+    let after = this.inst(RawInst::JumpTarget);
+
+    this.sequences.get_mut(this.sequence_index).unwrap().inst[next].op = RawInst::IterNext {
+        iter,
+        exhausted: after,
+    };
+}
+
+/// Call the `__bool__` dunder of `value`.
+fn truthy(this: &mut FlatCode, value: usize) -> usize {
+    let bool_dunder = this.inst(RawInst::GetDunder {
+        object: value,
+        dunder: Dunder::AsBool,
+    });
+
+    this.inst(RawInst::Call {
+        callable: bool_dunder,
+        arguments: vec![value],
+    })
+}
+
+/// Emit `left <op> right`, membership tests call `__contains__` on the right operand.
+fn infix(this: &mut FlatCode, left: usize, op: InfixOp, right: usize) -> usize {
+    match op {
+        InfixOp::Is => this.inst(RawInst::Is { left, right }),
+
+        InfixOp::IsNot => {
+            let value = this.inst(RawInst::Is { left, right });
+            this.inst(RawInst::Not { value })
+        }
+
+        InfixOp::In | InfixOp::NotIn => {
+            let dunder = this.inst(RawInst::GetDunder {
+                object: right,
+                dunder: Dunder::Infix(InfixOp::In),
+            });
+
+            let contains = this.inst(RawInst::Call {
+                callable: dunder,
+                arguments: vec![right, left],
+            });
+
+            if op == InfixOp::In {
+                contains
+            } else {
+                let value = truthy(this, contains);
+                this.inst(RawInst::Not { value })
+            }
+        }
+
+        _ => {
+            let dunder = this.inst(RawInst::GetDunder {
+                object: left,
+                dunder: Dunder::Infix(op),
+            });
+
+            this.inst(RawInst::Call {
+                callable: dunder,
+                arguments: vec![left, right],
+            })
+        }
+    }
+}
+
 impl AstVisitor<usize> for FlatCode {
     fn visit_any(&mut self, o: &dyn AstObject) -> usize {
         match o.into_ast_node() {
@@ -47,22 +409,25 @@ impl AstVisitor<usize> for FlatCode {
             .map(|dec| dec.visit_with(self, None))
             .collect();
 
+        let receiver = |recv: &Spanned<Atom>| Parameter {
+            name: recv.inner.as_name().unwrap(),
+            annotation: None,
+            default: None,
+            kind: ParameterKind::PositionalOrKeyword,
+        };
+
         let params = match (&fndef.reciever, &fndef.args) {
             (None, None) => vec![],
-            (Some(recv), None) => vec![(recv.inner.as_name().unwrap(), None)],
+            (Some(recv), None) => vec![receiver(recv)],
 
             (recv, Some(params)) => {
                 let mut parameters = Vec::with_capacity(params.len() + 1);
 
                 if let Some(reciever) = recv {
-                    parameters.push((reciever.inner.as_name().unwrap(), None));
-                }
-
-                for (name, annotation) in params.iter() {
-                    let ann = annotation.as_ref().map(|ann| ann.visit_with(self, None));
-                    parameters.push((name.clone(), ann));
+                    parameters.push(receiver(reciever));
                 }
 
+                parameters.extend(lower_parameters(self, params));
                 parameters
             }
         };
@@ -168,6 +533,93 @@ impl AstVisitor<usize> for FlatCode {
         value
     }
 
+    fn visit_list(&mut self, node: &Atom, span: Option<Span>) -> usize {
+        let inner = patma!(elements, Atom::List(elements) in node).unwrap();
+        let inner: Vec<_> = inner
+            .iter()
+            .map(|elem| elem.visit_with(self, None))
+            .collect();
+
+        let value = self.inst(RawInst::List(inner.into_boxed_slice()));
+
+        if let Some(span) = span {
+            self.set_span_for_values([value], span);
+        }
+
+        value
+    }
+
+    fn visit_set(&mut self, node: &Atom, span: Option<Span>) -> usize {
+        let inner = patma!(elements, Atom::Set(elements) in node).unwrap();
+        let inner: Vec<_> = inner
+            .iter()
+            .map(|elem| elem.visit_with(self, None))
+            .collect();
+
+        let value = self.inst(RawInst::Set(inner.into_boxed_slice()));
+
+        if let Some(span) = span {
+            self.set_span_for_values([value], span);
+        }
+
+        value
+    }
+
+    fn visit_dict(&mut self, node: &Atom, span: Option<Span>) -> usize {
+        let items = patma!(items, Atom::Dict(items) in node).unwrap();
+        let items: Vec<_> = items
+            .iter()
+            .map(|(key, value)| (key.visit_with(self, None), value.visit_with(self, None)))
+            .collect();
+
+        let value = self.inst(RawInst::Dict(items.into_boxed_slice()));
+
+        if let Some(span) = span {
+            self.set_span_for_values([value], span);
+        }
+
+        value
+    }
+
+    fn visit_comprehension(&mut self, node: &Atom, span: Option<Span>) -> usize {
+        let (acc, clauses) = match node {
+            Atom::ListComp(_, clauses) => (self.inst(RawInst::List(Box::new([]))), clauses),
+            Atom::SetComp(_, clauses) => (self.inst(RawInst::Set(Box::new([]))), clauses),
+            Atom::DictComp(_, clauses) => (self.inst(RawInst::Dict(Box::new([]))), clauses),
+            _ => unreachable!(),
+        };
+
+        if let Some(span) = span {
+            self.set_span_for_values([acc], span);
+        }
+
+        comprehension_loops(self, clauses, &mut |this| match node {
+            Atom::ListComp(element, _) => {
+                let value = element.visit_with(this, None);
+                this.inst(RawInst::ListAppend { list: acc, value });
+            }
+
+            Atom::SetComp(element, _) => {
+                let value = element.visit_with(this, None);
+                this.inst(RawInst::SetAdd { set: acc, value });
+            }
+
+            Atom::DictComp(item, _) => {
+                let key = item.0.visit_with(this, None);
+                let value = item.1.visit_with(this, None);
+                this.inst(RawInst::DictInsert {
+                    dict: acc,
+                    key,
+                    value,
+                });
+            }
+
+            _ => unreachable!(),
+        });
+
+        acc
+    }
+
     // -- other visitors
 
     fn visit_ellipsis(&mut self, node: &Atom, span: Option<Span>) -> usize {
@@ -191,8 +643,19 @@ impl AstVisitor<usize> for FlatCode {
                         .into_boxed_slice();
 
                     let root = path.get(0).unwrap().clone();
+                    let is_dotted = path.len() > 1;
+
+                    let import = self.inst(RawInst::Import { path, relative: 0 });
 
-                    let value = self.inst(RawInst::Import { path, relative: 0 });
+                    // `import a.b.c` imports every module along the way but only binds `a`.
+                    let value = if is_dotted {
+                        self.inst(RawInst::Import {
+                            path: vec![root].into_boxed_slice(),
+                            relative: 0,
+                        })
+                    } else {
+                        import
+                    };
 
                     let set_var = self.inst(RawInst::SetVar {
                         variable: root,
@@ -200,7 +663,7 @@ impl AstVisitor<usize> for FlatCode {
                     });
 
                     if let Some(span) = span.clone() {
-                        self.set_span_for_values([value, set_var], span);
+                        self.set_span_for_values([import, value, set_var], span);
                     }
                 }
             }
@@ -210,31 +673,39 @@ impl AstVisitor<usize> for FlatCode {
                 names,
                 level,
             } => {
-                let path = module
-                    .inner
-                    .components()
-                    .into_iter()
+                let path: Box<[_]> = module
+                    .iter()
+                    .flat_map(|module| module.inner.components())
                     .map(|at| at.as_name().unwrap())
                     .collect();
 
                 let module = self.inst(RawInst::Import {
-                    path,
+                    path: path.clone(),
                     relative: *level,
                 });
 
+                if let Some(span) = span.clone() {
+                    self.set_span_for_values([module], span);
+                }
+
                 for name in names {
                     let name = name.inner.as_name().unwrap();
-                    let attr = self.inst(RawInst::RefAsStr { r: name });
 
-                    let attr = self.inst(RawInst::GetAttribute {
-                        object: module,
-                        attr,
+                    let attr = self.inst(RawInst::ImportFrom {
+                        module,
+                        path: path.clone(),
+                        relative: *level,
+                        name,
                     });
 
-                    self.inst(RawInst::SetVar {
+                    let set_var = self.inst(RawInst::SetVar {
                         variable: name,
                         value: attr,
                     });
+
+                    if let Some(span) = span.clone() {
+                        self.set_span_for_values([attr, set_var], span);
+                    }
                 }
             }
         }
@@ -244,7 +715,14 @@ impl AstVisitor<usize> for FlatCode {
 
     fn visit_classdef(&mut self, classdef: &ClassDef, span: Option<Span>) -> usize {
         let name = classdef.name.inner.as_name().unwrap();
-        let class = self.inst(RawInst::Class { name });
+
+        let bases = classdef
+            .bases
+            .iter()
+            .map(|base| base.inner.visit_with(self, None))
+            .collect();
+
+        let class = self.inst(RawInst::Class { name, bases });
 
         let set_var = self.inst(RawInst::SetVar {
             variable: name,
@@ -324,6 +802,10 @@ impl AstVisitor<usize> for FlatCode {
             },
         );
 
+        self.sequences[class_body]
+            .ast
+            .replace(classdef.into_ast_node());
+
         self.inst(RawInst::BuildClass {
             sequence: class_body,
             class,
@@ -510,6 +992,8 @@ impl AstVisitor<usize> for FlatCode {
             Err(_) => self.inst(RawInst::Const(Constant::None)),
         };
 
+        unwind_to(self, 0);
+
         let ret = self.inst(RawInst::Return { value: rv });
 
         if let Some(span) = span {
@@ -536,10 +1020,18 @@ impl AstVisitor<usize> for FlatCode {
 
         let when_true = self.inst(RawInst::JumpTarget);
 
+        self.loops.push(LoopScope {
+            start,
+            breaks: vec![],
+            unwinds: self.unwinds.len(),
+        });
+
         for node in &while_.body {
             node.visit_with(self, None);
         }
 
+        let scope = self.loops.pop().unwrap();
+
         self.inst(RawInst::Br { to: start });
         let or_else = self.inst(RawInst::JumpTarget);
 
@@ -551,25 +1043,394 @@ impl AstVisitor<usize> for FlatCode {
             falsey: Some(or_else),
         };
 
+        for br in scope.breaks {
+            seq.inst[br].op = RawInst::Br { to: or_else };
+        }
+
         INVALID_VALUE
     }
 
-    fn visit_binop(&mut self, expr: &Expr, _: Option<Span>) -> usize {
+    fn visit_for(&mut self, for_: &For, span: Option<Span>) -> usize {
+        let object = for_.iter.inner.visit_with(self, None);
+        let iter = self.inst(RawInst::GetIter { object });
+
+        if let Some(span) = span {
+            self.set_span_for_values([iter], span);
+        }
+
+        let to_start = self.inst(RawInst::JumpTarget);
+        let start = self.inst(RawInst::JumpTarget);
+
+        self.sequences.get_mut(self.sequence_index).unwrap().inst[to_start].op =
+            RawInst::Br { to: start };
+
+        // the exhausted destination is patched once the else block has been emitted.
+        let next = self.inst(RawInst::IterNext {
+            iter,
+            exhausted: INVALID_VALUE,
+        });
+
+        self.set_span_for_values([next], for_.target.span.clone());
+
+        store_target(self, &for_.target, next, "for loop");
+
+        let to_body = self.inst(RawInst::Br { to: INVALID_VALUE });
+        let body = self.inst(RawInst::JumpTarget);
+
+        self.loops.push(LoopScope {
+            start,
+            breaks: vec![],
+            unwinds: self.unwinds.len(),
+        });
+
+        for node in &for_.body {
+            node.visit_with(self, None);
+        }
+
+        let scope = self.loops.pop().unwrap();
+
+        self.inst(RawInst::Br { to: start });
+        let or_else = self.inst(RawInst::JumpTarget);
+
+        for node in for_.orelse.iter().flatten() {
+            node.visit_with(self, None);
+        }
+
+        let or_else_tail = self.inst(RawInst::Br { to: INVALID_VALUE });
+        let after = self.inst(RawInst::JumpTarget);
+
+        let seq = self.sequences.get_mut(self.sequence_index).unwrap();
+
+        seq.inst[next].op = RawInst::IterNext {
+            iter,
+            exhausted: or_else,
+        };
+
+        seq.inst[to_body].op = RawInst::Br { to: body };
+        seq.inst[or_else_tail].op = RawInst::Br { to: after };
+
+        // `break` skips the else block entirely.
+        for br in scope.breaks {
+            seq.inst[br].op = RawInst::Br { to: after };
+        }
+
+        INVALID_VALUE
+    }
+
+    fn visit_try(&mut self, try_: &Try, span: Option<Span>) -> usize {
+        // exceptions escaping the body, the handlers, or the else block run the finally body and get re-raised.
+        let finally = try_.finalbody.as_ref().map(|body| {
+            let setup = self.inst(RawInst::SetupExcept {
+                handler: INVALID_VALUE,
+            });
+
+            self.unwinds.push(Unwind::Finally(body.clone()));
+
+            setup
+        });
+
+        let handlers = match try_.handlers.is_empty() {
+            true => None,
+            false => {
+                let setup = self.inst(RawInst::SetupExcept {
+                    handler: INVALID_VALUE,
+                });
+
+                self.unwinds.push(Unwind::Handler);
+
+                Some(setup)
+            }
+        };
+
+        if let Some(span) = span {
+            self.set_span_for_values(
+                finally
+                    .iter()
+                    .chain(handlers.iter())
+                    .copied()
+                    .collect::<Vec<_>>(),
+                span,
+            );
+        }
+
+        for node in &try_.body {
+            node.visit_with(self, None);
+        }
+
+        let mut exits = vec![];
+
+        if let Some(setup) = handlers {
+            self.inst(RawInst::PopBlock);
+            self.unwinds.pop();
+
+            for node in try_.orelse.iter().flatten() {
+                node.visit_with(self, None);
+            }
+
+            exits.push(self.inst(RawInst::Br { to: INVALID_VALUE }));
+
+            let handler = self.inst(RawInst::JumpTarget);
+            let exc = self.inst(RawInst::GetException);
+
+            self.unwinds.push(Unwind::Except);
+
+            for clause in &try_.handlers {
+                let ExceptHandler { kind, name, body } = &clause.inner;
+
+                // a bare `except:` matches any exception.
+                let jump = kind.as_ref().map(|kind_expr| {
+                    let kind = kind_expr.inner.visit_with(self, None);
+                    let test = self.inst(RawInst::ExcMatch { exc, kind });
+
+                    self.set_span_for_values([test], kind_expr.span.clone());
+
+                    let jump = self.inst(RawInst::If {
+                        test,
+                        truthy: None,
+                        falsey: Some(INVALID_VALUE),
+                    });
+
+                    (test, jump)
+                });
+
+                if let Some(name) = name {
+                    let set_var = self.inst(RawInst::SetVar {
+                        variable: name.inner.as_name().unwrap(),
+                        value: exc,
+                    });
+
+                    self.set_span_for_values([set_var], name.span.clone());
+                }
+
+                for node in body {
+                    node.visit_with(self, None);
+                }
+
+                self.inst(RawInst::PopExcept);
+                exits.push(self.inst(RawInst::Br { to: INVALID_VALUE }));
+
+                let next = self.inst(RawInst::JumpTarget);
+
+                if let Some((test, jump)) = jump {
+                    self.sequences.get_mut(self.sequence_index).unwrap().inst[jump].op =
+                        RawInst::If {
+                            test,
+                            truthy: None,
+                            falsey: Some(next),
+                        };
+                }
+            }
+
+            // none of the clauses matched, keep unwinding.
+            self.inst(RawInst::Raise { exc: None });
+            self.unwinds.pop();
+
+            self.sequences.get_mut(self.sequence_index).unwrap().inst[setup].op =
+                RawInst::SetupExcept { handler };
+        }
+
+        let after = self.inst(RawInst::JumpTarget);
+
+        {
+            let seq = self.sequences.get_mut(self.sequence_index).unwrap();
+
+            for br in exits {
+                seq.inst[br].op = RawInst::Br { to: after };
+            }
+        }
+
+        if let (Some(setup), Some(body)) = (finally, try_.finalbody.as_ref()) {
+            self.inst(RawInst::PopBlock);
+            self.unwinds.pop();
+
+            for node in body {
+                node.visit_with(self, None);
+            }
+
+            let to_end = self.inst(RawInst::Br { to: INVALID_VALUE });
+            let handler = self.inst(RawInst::JumpTarget);
+
+            self.unwinds.push(Unwind::Except);
+
+            for node in body {
+                node.visit_with(self, None);
+            }
+
+            self.inst(RawInst::Raise { exc: None });
+            self.unwinds.pop();
+
+            let end = self.inst(RawInst::JumpTarget);
+
+            let seq = self.sequences.get_mut(self.sequence_index).unwrap();
+
+            seq.inst[to_end].op = RawInst::Br { to: end };
+            seq.inst[setup].op = RawInst::SetupExcept { handler };
+        }
+
+        INVALID_VALUE
+    }
+
+    fn visit_with_stmt(&mut self, with: &With, span: Option<Span>) -> usize {
+        if with.is_async {
+            let raise = self.inst(RawInst::Unsupported {
+                message: "async with statements can not be evaluated.",
+            });
+
+            if let Some(span) = span {
+                self.set_span_for_values([raise], span);
+            }
+
+            return INVALID_VALUE;
+        }
+
+        with_items(self, &with.items, &with.body);
+
+        INVALID_VALUE
+    }
+
+    fn visit_raise(&mut self, raise: &Raise, span: Option<Span>) -> usize {
+        let exc = raise
+            .exc
+            .as_ref()
+            .map(|exc| exc.inner.visit_with(self, None));
+        let raise = self.inst(RawInst::Raise { exc });
+
+        if let Some(span) = span {
+            self.set_span_for_values([raise], span);
+        }
+
+        raise
+    }
+
+    fn visit_break(&mut self, span: Option<Span>) -> usize {
+        let depth = match self.loops.last() {
+            Some(scope) => scope.unwinds,
+            None => {
+                self.syntax_error(span, "'break' outside loop");
+                return INVALID_VALUE;
+            }
+        };
+
+        unwind_to(self, depth);
+
+        let br = self.inst(RawInst::Br { to: INVALID_VALUE });
+
+        self.loops.last_mut().unwrap().breaks.push(br);
+
+        if let Some(span) = span {
+            self.set_span_for_values([br], span);
+        }
+
+        br
+    }
+
+    fn visit_continue(&mut self, span: Option<Span>) -> usize {
+        let (to, depth) = match self.loops.last() {
+            Some(scope) => (scope.start, scope.unwinds),
+            None => {
+                self.syntax_error(span, "'continue' not properly in loop");
+                return INVALID_VALUE;
+            }
+        };
+
+        unwind_to(self, depth);
+
+        let br = self.inst(RawInst::Br { to });
+
+        if let Some(span) = span {
+            self.set_span_for_values([br], span);
+        }
+
+        br
+    }
+
+    fn visit_binop(&mut self, expr: &Expr, span: Option<Span>) -> usize {
         let (left, op, right) =
             patma!((left, op, right), Expr::BinOp { left, op, right } in expr).unwrap();
 
         let left_value = left.visit_with(self, None);
         let right_value = right.visit_with(self, None);
 
-        let dunder = self.inst(RawInst::GetDunder {
-            object: left_value,
-            dunder: Dunder::Infix(op.clone()),
-        });
+        let rv = infix(self, left_value, *op, right_value);
 
-        self.inst(RawInst::Call {
-            callable: dunder,
-            arguments: vec![left_value, right_value],
-        })
+        if let Some(span) = span {
+            self.set_span_for_values([rv], span);
+        }
+
+        rv
+    }
+
+    fn visit_compare(&mut self, expr: &Expr, span: Option<Span>) -> usize {
+        let (left, ops) = patma!((left, ops), Expr::Compare { left, ops } in expr).unwrap();
+
+        // `a < b < c` is evaluated as `a < b and b < c` except that `b` is only evaluated once.
+        //
+        // The first falsey comparison short-circuits the chain and becomes its value:
+        //
+        //   <a> <b> <a < b> -[false]-> ... -> phi-jump(a < b) --+
+        //                   \                                   |
+        //                    -[true]-> <c> <b < c> -> phi-jump -+-> PhiRecv(φ)
+        //
+        let mut left_value = left.visit_with(self, None);
+        let mut short_circuits = vec![];
+
+        let (last, init) = ops.split_last().unwrap();
+
+        for (op, right) in init {
+            let right_value = right.visit_with(self, None);
+            let result = infix(self, left_value, *op, right_value);
+            let test = truthy(self, result);
+
+            // patched into an `If` once the false branch exists.
+            let test_jump = self.inst(RawInst::Nop);
+            self.inst(RawInst::JumpTarget);
+
+            short_circuits.push((test_jump, test, result));
+            left_value = right_value;
+        }
+
+        let (op, right) = last;
+        let right_value = right.visit_with(self, None);
+        let result = infix(self, left_value, *op, right_value);
+        let last_jump = self.inst(RawInst::Br { to: INVALID_VALUE });
+
+        let short_circuits = short_circuits
+            .into_iter()
+            .map(|(test_jump, test, result)| {
+                let header = self.inst(RawInst::JumpTarget);
+                let jump = self.inst(RawInst::Br { to: INVALID_VALUE });
+
+                (test_jump, test, header, jump, result)
+            })
+            .collect::<Vec<_>>();
+
+        let phi_recv = self.inst(RawInst::PhiRecv);
+
+        let seq = self.sequences.get_mut(self.sequence_index).unwrap();
+
+        seq.inst[last_jump].op = RawInst::PhiJump {
+            recv: phi_recv,
+            value: result,
+        };
+
+        for (test_jump, test, header, jump, result) in short_circuits {
+            seq.inst[test_jump].op = RawInst::If {
+                test,
+                truthy: None,
+                falsey: Some(header),
+            };
+
+            seq.inst[jump].op = RawInst::PhiJump {
+                recv: phi_recv,
+                value: result,
+            };
+        }
+
+        if let Some(span) = span {
+            self.set_span_for_values([phi_recv], span);
+        }
+
+        phi_recv
     }
 
     fn visit_unary(&mut self, unary: &Expr, _: Option<Span>) -> usize {
@@ -577,6 +1438,11 @@ impl AstVisitor<usize> for FlatCode {
 
         let value = value.visit_with(self, None);
 
+        if let UnaryOp::Not = op {
+            let value = truthy(self, value);
+            return self.inst(RawInst::Not { value });
+        }
+
         let dunder = self.inst(RawInst::GetDunder {
             object: value,
             dunder: Dunder::Unary(op.clone()),
@@ -672,19 +1538,73 @@ impl AstVisitor<usize> for FlatCode {
         value
     }
 
+    fn visit_lambda(&mut self, lambda: &Expr, span: Option<Span>) -> usize {
+        let (args, body) = patma!((args, body), Expr::Lambda { args, body } in lambda).unwrap();
+
+        let params = lower_parameters(self, args);
+        let seq_span = span.clone().unwrap_or_else(|| body.span.clone());
+
+        let sequence_id =
+            self.with_new_sequence(1, SequenceType::Function, (self.mref(), seq_span), |this| {
+                let value = body.visit_with(this, None);
+                this.inst(RawInst::Return { value });
+            });
+
+        self.sequences[sequence_id]
+            .ast
+            .replace(lambda.into_ast_node());
+
+        let func = self.inst(RawInst::Lambda {
+            params,
+            sequence_id,
+        });
+
+        if let Some(span) = span {
+            self.set_span_for_values([func], span);
+        }
+
+        func
+    }
+
     fn visit_call(&mut self, call: &Primary, span: Option<Span>) -> usize {
         let (func, args) = patma!((func, args), Primary::Call { func, args } in call).unwrap();
 
         let callable = func.visit_with(self, None);
-        let arguments = match args {
-            Some(args) => args.iter().map(|arg| arg.visit_with(self, None)).collect(),
-            None => vec![],
-        };
+        let args = args.as_deref().unwrap_or_default();
 
-        let rv = self.inst(RawInst::Call {
-            callable,
-            arguments,
-        });
+        let rv = if args
+            .iter()
+            .all(|arg| matches!(arg.inner, ast::Argument::Positional(_)))
+        {
+            let arguments = args
+                .iter()
+                .map(|arg| arg.inner.value().visit_with(self, None))
+                .collect();
+
+            self.inst(RawInst::Call {
+                callable,
+                arguments,
+            })
+        } else {
+            let arguments = args
+                .iter()
+                .map(|arg| {
+                    let value = arg.inner.value().visit_with(self, None);
+
+                    match &arg.inner {
+                        ast::Argument::Positional(_) => Argument::Positional(value),
+                        ast::Argument::Starred(_) => Argument::Starred(value),
+                        ast::Argument::Keyword(name, _) => Argument::Keyword(*name, value),
+                        ast::Argument::DoubleStarred(_) => Argument::DoubleStarred(value),
+                    }
+                })
+                .collect();
+
+            self.inst(RawInst::CallEx {
+                callable,
+                arguments,
+            })
+        };
 
         if let Some(span) = span {
             self.set_span_for_values([rv], span);
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use montyc_core::SpanRef;
+use montyc_core::{ast::ParameterKind, SpanRef};
 use montyc_parser::ast::{InfixOp, UnaryOp};
 
 #[derive(Debug, Clone, Copy)]
@@ -18,34 +18,70 @@ pub enum Dunder {
     DocComment,
     /// __bool__
     AsBool,
+    /// __enter__
+    Enter,
+    /// __exit__
+    Exit,
 }
 
 impl Display for Dunder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Dunder::Unary(_) => todo!(),
+            Dunder::Unary(unary) => write!(f, "__{}__", unary.as_ref()),
             Dunder::Infix(infix) => write!(f, "__{}__", infix.as_ref()),
             Dunder::DocComment => write!(f, "__doc__"),
             Dunder::GetItem => write!(f, "__getitem__"),
             Dunder::SetItem => write!(f, "__setitem__"),
             Dunder::AsBool => write!(f, "__bool__"),
+            Dunder::Enter => write!(f, "__enter__"),
+            Dunder::Exit => write!(f, "__exit__"),
         }
     }
 }
 
+/// A parameter of a `defn`, `default` is evaluated when the function is defined.
+#[derive(Debug, Clone)]
+#[allow(missing_docs)]
+pub struct Parameter<V = usize, R = SpanRef> {
+    pub name: R,
+    pub annotation: Option<V>,
+    pub default: Option<V>,
+    pub kind: ParameterKind,
+}
+
+/// An argument of a `call-ex`.
+#[derive(Debug, Clone)]
+pub enum Argument<V = usize, R = SpanRef> {
+    /// `value`
+    Positional(V),
+    /// `*value`
+    Starred(V),
+    /// `name=value`
+    Keyword(R, V),
+    /// `**value`
+    DoubleStarred(V),
+}
+
 #[derive(Debug, Clone)]
 #[allow(missing_docs)]
 pub enum RawInst<V = usize, R = SpanRef> {
     /// Define a function like: `def {name}({params}) -> {returns}`
     Defn {
         name: R,
-        params: Vec<(R, Option<V>)>,
+        params: Vec<Parameter<V, R>>,
         returns: Option<V>,
         sequence_id: usize,
     },
 
+    /// Define an anonymous function like: `lambda {params}: ...`
+    Lambda {
+        params: Vec<Parameter<V, R>>,
+        sequence_id: usize,
+    },
+
     Class {
         name: R,
+        bases: Box<[V]>,
     },
 
     BuildClass {
@@ -63,6 +99,12 @@ pub enum RawInst<V = usize, R = SpanRef> {
         arguments: Vec<V>,
     },
 
+    /// Call a callable-like value with keyword and/or unpacked arguments like `callable(a, *b, c=d, **e)`.
+    CallEx {
+        callable: V,
+        arguments: Box<[Argument<V, R>]>,
+    },
+
     /// A named variable assignment like `a = ...`
     SetVar {
         variable: R,
@@ -101,10 +143,57 @@ pub enum RawInst<V = usize, R = SpanRef> {
         relative: usize,
     },
 
+    /// The attribute `name` of `module`, or the submodule `path.name` when `module` has no such attribute.
+    ImportFrom {
+        module: V,
+        path: Box<[R]>,
+        relative: usize,
+        name: R,
+    },
+
     Const(montyc_core::ast::Constant),
 
     Tuple(Box<[V]>),
 
+    /// A new list containing `elements` in order.
+    List(Box<[V]>),
+
+    /// A new set containing `elements`.
+    Set(Box<[V]>),
+
+    /// A new dict containing the `(key, value)` pairs, later keys win.
+    Dict(Box<[(V, V)]>),
+
+    /// Push `value` onto the end of `list`, used to build list comprehensions.
+    ListAppend {
+        list: V,
+        value: V,
+    },
+
+    /// Add `value` to `set`, used to build set comprehensions.
+    SetAdd {
+        set: V,
+        value: V,
+    },
+
+    /// Insert `key: value` into `dict`, used to build dict comprehensions.
+    DictInsert {
+        dict: V,
+        key: V,
+        value: V,
+    },
+
+    /// The boolean negation of `value`, which must already be a bool.
+    Not {
+        value: V,
+    },
+
+    /// Check if `left` and `right` are the same object.
+    Is {
+        left: V,
+        right: V,
+    },
+
     Nop,
 
     Undefined,
@@ -116,6 +205,24 @@ pub enum RawInst<V = usize, R = SpanRef> {
         falsey: Option<V>,
     },
 
+    /// Element `index` of `value`, which must produce exactly `len` elements when iterated, like `a, b = value`.
+    Unpack {
+        value: V,
+        index: usize,
+        len: usize,
+    },
+
+    /// Produce an iterator over `object`, like calling `iter(object)`.
+    GetIter {
+        object: V,
+    },
+
+    /// Advance `iter` producing the next value, jump to `exhausted` when there are no more values.
+    IterNext {
+        iter: V,
+        exhausted: V,
+    },
+
     /// An unconditional branch to a value ref.
     Br {
         to: V,
@@ -139,20 +246,62 @@ pub enum RawInst<V = usize, R = SpanRef> {
         name: R,
         annotation: V,
     },
+
+    /// Raise `exc` (an exception class or instance), re-raising the exception being handled when `None`.
+    Raise {
+        exc: Option<V>,
+    },
+
+    /// Raise a `NotImplementedError`, this stands in for syntax that parses but can't be evaluated yet.
+    Unsupported {
+        message: &'static str,
+    },
+
+    /// Push an exception handler, exceptions raised until the matching `pop-block` jump to `handler`.
+    SetupExcept {
+        handler: V,
+    },
+
+    /// Like `setup-except` but `handler` is the cleanup of a `with` statement, it calls `__exit__` with the exception.
+    SetupWith {
+        handler: V,
+    },
+
+    /// Pop the innermost exception handler pushed by `setup-except` or `setup-with`.
+    PopBlock,
+
+    /// The exception currently being handled.
+    GetException,
+
+    /// Check if the exception `exc` is an instance of the exception class `kind`.
+    ExcMatch {
+        exc: V,
+        kind: V,
+    },
+
+    /// Finish handling the current exception.
+    PopExcept,
+
+    /// The class of `object`, i.e. `type(object)`.
+    ClassOf {
+        object: V,
+    },
 }
 
 pub trait InstVisitor<T, V = usize, R = SpanRef> {
     fn visit_defn(
         &mut self,
         name: R,
-        params: &[(R, Option<V>)],
+        params: &[Parameter<V, R>],
         returns: Option<V>,
         seq: usize,
     ) -> T;
-    fn visit_class(&mut self, name: R) -> T;
+    fn visit_lambda(&mut self, params: &[Parameter<V, R>], seq: usize) -> T;
+    fn visit_class(&mut self, name: R, bases: &[V]) -> T;
     fn visit_build_class(&mut self, klass: V, seq: usize) -> T;
     fn visit_ref_as_str(&mut self, r: R) -> T;
     fn visit_call(&mut self, callable: V, arguments: &[V]) -> T;
+    fn visit_call_ex(&mut self, callable: V, arguments: &[Argument<V, R>]) -> T;
     fn visit_set_var(&mut self, variable: R, value: V) -> T;
     fn visit_use_var(&mut self, variable: R) -> T;
     fn visit_get_attribute(&mut self, variable: R, value: V) -> T;
@@ -162,14 +311,30 @@ pub trait InstVisitor<T, V = usize, R = SpanRef> {
     fn visit_import(&mut self, path: &[R], relative: usize) -> T;
     fn visit_const(&mut self, cst: &montyc_core::ast::Constant) -> T;
     fn visit_tuple(&mut self, tple: &[V]) -> T;
+    fn visit_list(&mut self, elements: &[V]) -> T;
+    fn visit_set(&mut self, elements: &[V]) -> T;
+    fn visit_dict(&mut self, items: &[(V, V)]) -> T;
+    fn visit_list_append(&mut self, list: V, value: V) -> T;
+    fn visit_set_add(&mut self, set: V, value: V) -> T;
+    fn visit_dict_insert(&mut self, dict: V, key: V, value: V) -> T;
+    fn visit_not(&mut self, value: V) -> T;
+    fn visit_is(&mut self, left: V, right: V) -> T;
     fn visit_nop(&mut self) -> T;
     fn visit_undef(&mut self) -> T;
     fn visit_if(&mut self, test: V, truthy: Option<V>, falsey: Option<V>) -> T;
+    fn visit_get_iter(&mut self, object: V) -> T;
+    fn visit_iter_next(&mut self, iter: V, exhausted: V) -> T;
     fn visit_br(&mut self, to: V) -> T;
     fn visit_phi_jump(&mut self, recv: V, value: V) -> T;
     fn visit_jump_target(&mut self) -> T;
     fn visit_phi_recv(&mut self) -> T;
     fn visit_return(&mut self, value: V) -> T;
+    fn visit_raise(&mut self, exc: Option<V>) -> T;
+    fn visit_setup_except(&mut self, handler: V) -> T;
+    fn visit_pop_block(&mut self) -> T;
+    fn visit_get_exception(&mut self) -> T;
+    fn visit_exc_match(&mut self, exc: V, kind: V) -> T;
+    fn visit_pop_except(&mut self) -> T;
 }
 
 fn format_vec_of_values(seq: &[usize]) -> String {
@@ -179,6 +344,28 @@ fn format_vec_of_values(seq: &[usize]) -> String {
         .join(", ")
 }
 
+fn format_params(params: &[Parameter<usize, SpanRef>]) -> String {
+    params
+        .iter()
+        .map(|param| {
+            let prefix = match param.kind {
+                ParameterKind::VarPositional => "*",
+                ParameterKind::VarKeyword => "**",
+                _ => "",
+            };
+
+            match param.default {
+                Some(default) => format!(
+                    "{}{:?}: {:?} = %{}",
+                    prefix, param.name, param.annotation, default
+                ),
+                None => format!("{}{:?}: {:?}", prefix, param.name, param.annotation),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl Display for RawInst {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -201,17 +388,18 @@ impl Display for RawInst {
                 "defn({:?}) {:?} [{}] {:?}",
                 sequence_id,
                 name,
-                {
-                    let p = params
-                        .iter()
-                        .map(|(name, ann)| format!("{:?}: {:?}", name, ann))
-                        .collect::<Vec<_>>();
-                    p.join(", ")
-                },
+                format_params(params),
                 returns
             ),
 
-            RawInst::Class { name } => write!(f, "class {:?}", name),
+            RawInst::Lambda {
+                params,
+                sequence_id,
+            } => write!(f, "lambda({:?}) [{}]", sequence_id, format_params(params)),
+
+            RawInst::Class { name, bases } => {
+                write!(f, "class {:?} [{}]", name, format_vec_of_values(bases))
+            }
             RawInst::Call {
                 callable,
                 arguments,
@@ -222,6 +410,22 @@ impl Display for RawInst {
                 format_vec_of_values(arguments)
             ),
 
+            RawInst::CallEx {
+                callable,
+                arguments,
+            } => write!(f, "call-ex %{:?} [{}]", callable, {
+                let args = arguments
+                    .iter()
+                    .map(|arg| match arg {
+                        Argument::Positional(value) => format!("%{}", value),
+                        Argument::Starred(value) => format!("*%{}", value),
+                        Argument::Keyword(name, value) => format!("{:?}=%{}", name, value),
+                        Argument::DoubleStarred(value) => format!("**%{}", value),
+                    })
+                    .collect::<Vec<_>>();
+                args.join(", ")
+            }),
+
             RawInst::SetVar { variable, value } => write!(f, "set-var {:?} %{:?}", variable, value),
 
             RawInst::UseVar { variable } => write!(f, "use-var {:?}", variable),
@@ -246,7 +450,12 @@ impl Display for RawInst {
             } => write!(f, "set-dunder %{:?} {:?} %{:?}", object, dunder, value),
 
             RawInst::Import { path, relative: _ } => write!(f, "import {:?}", path),
+            RawInst::ImportFrom { module, name, .. } => {
+                write!(f, "import-from %{:?} {:?}", module, name)
+            }
             RawInst::Const(c) => write!(f, "const {}", c),
+            RawInst::Not { value } => write!(f, "not %{:?}", value),
+            RawInst::Is { left, right } => write!(f, "is %{:?} %{:?}", left, right),
             RawInst::Nop => write!(f, "nop"),
             RawInst::Undefined => write!(f, "undef"),
             RawInst::If {
@@ -264,12 +473,45 @@ impl Display for RawInst {
                     .map(|v| format!("%{:?}", v))
                     .unwrap_or("nop".to_string())
             ),
+            RawInst::Unpack { value, index, len } => {
+                write!(f, "unpack %{:?} [{} of {}]", value, index, len)
+            }
+            RawInst::GetIter { object } => write!(f, "get-iter %{:?}", object),
+            RawInst::IterNext { iter, exhausted } => {
+                write!(f, "iter-next %{:?} else %{:?}", iter, exhausted)
+            }
             RawInst::Br { to } => write!(f, "branch %{:?}", to),
             RawInst::PhiJump { recv, value } => write!(f, "phi-jump %{:?} %{:?}", recv, value),
             RawInst::PhiRecv => write!(f, "phi-recv"),
             RawInst::Return { value } => write!(f, "return %{:?}", value),
             RawInst::Tuple(inner) => write!(f, "tuple [{}]", format_vec_of_values(inner)),
+            RawInst::List(inner) => write!(f, "list [{}]", format_vec_of_values(inner)),
+            RawInst::Set(inner) => write!(f, "set [{}]", format_vec_of_values(inner)),
+            RawInst::Dict(items) => write!(f, "dict [{}]", {
+                let items = items
+                    .iter()
+                    .map(|(key, value)| format!("%{}: %{}", key, value))
+                    .collect::<Vec<_>>();
+                items.join(", ")
+            }),
+            RawInst::ListAppend { list, value } => {
+                write!(f, "list-append %{:?} %{:?}", list, value)
+            }
+            RawInst::SetAdd { set, value } => write!(f, "set-add %{:?} %{:?}", set, value),
+            RawInst::DictInsert { dict, key, value } => {
+                write!(f, "dict-insert %{:?} %{:?} %{:?}", dict, key, value)
+            }
             RawInst::JumpTarget => write!(f, "jump-target"),
+            RawInst::Raise { exc: Some(exc) } => write!(f, "raise %{:?}", exc),
+            RawInst::Raise { exc: None } => write!(f, "raise"),
+            RawInst::Unsupported { message } => write!(f, "unsupported {:?}", message),
+            RawInst::SetupExcept { handler } => write!(f, "setup-except %{:?}", handler),
+            RawInst::SetupWith { handler } => write!(f, "setup-with %{:?}", handler),
+            RawInst::PopBlock => write!(f, "pop-block"),
+            RawInst::GetException => write!(f, "get-exception"),
+            RawInst::ExcMatch { exc, kind } => write!(f, "exc-match %{:?} %{:?}", exc, kind),
+            RawInst::PopExcept => write!(f, "pop-except"),
+            RawInst::ClassOf { object } => write!(f, "class-of %{:?}", object),
         }
     }
 }
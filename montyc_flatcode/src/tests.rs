@@ -1 +1,117 @@
+use montyc_core::{ModuleRef, ParseError};
+use montyc_parser::AstObject;
 
+use crate::{raw_inst::RawInst, FlatCode};
+
+/// Parse and lower `source`.
+fn lower(source: &str) -> FlatCode {
+    let module = montyc_parser::parse(source, montyc_parser::comb::module, None, ModuleRef(1));
+
+    let mut code = FlatCode::new((ModuleRef(1), module.span().unwrap_or(0..0)));
+    module.visit_with(&mut code, None);
+
+    code
+}
+
+/// Parse and lower `source`, returning the syntax errors found while lowering it.
+fn lowering_errors(source: &str) -> Vec<ParseError> {
+    lower(source).errors().to_vec()
+}
+
+#[test]
+fn break_and_continue_outside_loop() {
+    let source = "break\nwhile x:\n    continue\ncontinue\n";
+    let errors = lowering_errors(source);
+
+    let messages: Vec<_> = errors.iter().map(|err| err.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        ["'break' outside loop", "'continue' not properly in loop"]
+    );
+
+    assert_eq!(&source[errors[0].span.clone()], "break");
+    assert_eq!(&source[errors[1].span.clone()], "continue");
+}
+
+#[test]
+fn loops_do_not_leak_into_definitions() {
+    let errors = lowering_errors("for x in y:\n    def f():\n        break\n");
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "'break' outside loop");
+}
+
+#[test]
+fn for_loop_targets_must_be_names() {
+    let source = "for x[0] in z:\n    break\n";
+    let errors = lowering_errors(source);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "only names and tuples of names are supported as for loop targets."
+    );
+    assert_eq!(&source[errors[0].span.clone()], "x[0]");
+}
+
+#[test]
+fn tuple_targets_are_unpacked() {
+    let code = lower("for k, (a, b) in z:\n    pass\nxs = [a for a, b in z]\n");
+    assert!(code.errors().is_empty());
+
+    let unpacked = code
+        .sequences()
+        .iter()
+        .flat_map(|seq| seq.inst())
+        .filter(|inst| matches!(inst.op, RawInst::Unpack { .. }))
+        .count();
+
+    assert_eq!(unpacked, 6);
+
+    let source = "for k, x[0] in z:\n    break\n";
+    let errors = lowering_errors(source);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(&source[errors[0].span.clone()], "x[0]");
+}
+
+#[test]
+fn comprehension_targets_must_be_names() {
+    let source = "xs = [1 for x[0] in z]\n";
+    let errors = lowering_errors(source);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "only names and tuples of names are supported as comprehension targets."
+    );
+    assert_eq!(&source[errors[0].span.clone()], "x[0]");
+}
+
+#[test]
+fn with_statement_targets_must_be_names() {
+    let source = "with m() as x[0]:\n    pass\n";
+    let errors = lowering_errors(source);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "only plain names are supported as with statement targets."
+    );
+    assert_eq!(&source[errors[0].span.clone()], "x[0]");
+}
+
+#[test]
+fn async_with_raises_at_runtime() {
+    let source = "async with m() as x:\n    pass\n";
+    let code = lower(source);
+    assert!(code.errors().is_empty());
+
+    let unsupported = code
+        .sequences()
+        .iter()
+        .flat_map(|seq| seq.inst())
+        .any(|inst| matches!(inst.op, RawInst::Unsupported { .. }));
+
+    assert!(unsupported);
+}
@@ -0,0 +1,1030 @@
+//! A compact bytecode compiled from `FlatSeq`s, this is what the evaluator actually executes.
+//!
+//! FlatCode is convenient to produce but slow to interpret: names are resolved
+//! by `SpanRef` through hash maps, values live in a map keyed by instruction,
+//! and control flow goes through `jump-target`/`phi-recv` placeholders. The
+//! bytecode fixes these ahead of time:
+//!
+//! * every variable gets a slot in a dense array, and values live in dense
+//!   registers indexed by the producing FlatCode instruction.
+//! * constants (and `ref-as-str` strings) are interned into a per-code pool.
+//! * instructions with no runtime effect are dropped and every jump target
+//!   is resolved to the index of the op it lands on.
+//!
+//! Code objects are compiled lazily and cached per sequence by the `Runtime`.
+//!
+
+use std::fmt;
+
+use ahash::AHashMap;
+use montyc_core::{ast::Constant, ModuleRef, Span, SpanRef};
+use montyc_flatcode::{
+    raw_inst::{Argument, Dunder, Parameter, RawInst},
+    FlatSeq,
+};
+
+/// A register holding the result of an op, numbered after the FlatCode instruction that produced it.
+pub type Reg = u32;
+
+/// The index of a variable in a frame's slots.
+pub type Slot = u32;
+
+/// The index of an op in a code object.
+pub type Pc = u32;
+
+/// A function definition referred to by `Op::MakeFunction`.
+#[derive(Debug, Clone)]
+pub struct FunctionDef {
+    /// The name the function is bound to, `None` for lambdas.
+    pub name: Option<SpanRef>,
+    pub params: Box<[Parameter<Reg>]>,
+    pub returns: Option<Reg>,
+    pub seq_id: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum Op {
+    LoadConst {
+        dst: Reg,
+        cst: u32,
+    },
+
+    /// Read the variable in `slot`, falling back to the enclosing scopes by `name` if it is unset.
+    LoadName {
+        dst: Reg,
+        slot: Slot,
+        name: SpanRef,
+    },
+
+    StoreName {
+        slot: Slot,
+        value: Reg,
+    },
+
+    GetAttr {
+        dst: Reg,
+        object: Reg,
+        attr: Reg,
+    },
+
+    SetAttr {
+        object: Reg,
+        attr: Reg,
+        value: Reg,
+    },
+
+    GetDunder {
+        dst: Reg,
+        object: Reg,
+        dunder: Dunder,
+    },
+
+    SetDunder {
+        object: Reg,
+        dunder: Dunder,
+        value: Reg,
+    },
+
+    Call {
+        dst: Reg,
+        callable: Reg,
+        arguments: Box<[Reg]>,
+    },
+
+    CallEx {
+        dst: Reg,
+        callable: Reg,
+        arguments: Box<[Argument<Reg>]>,
+    },
+
+    MakeFunction {
+        dst: Reg,
+        def: u32,
+    },
+
+    MakeClass {
+        dst: Reg,
+        name: SpanRef,
+        bases: Box<[Reg]>,
+    },
+
+    /// Execute the class body `seq_id` in the namespace of `class`.
+    BuildClass {
+        class: Reg,
+        seq_id: usize,
+    },
+
+    Import {
+        dst: Reg,
+        path: Box<[SpanRef]>,
+        relative: usize,
+    },
+
+    /// Get `name` from the `module` imported from `path`, importing the submodule `path.name` if it is missing.
+    ImportFrom {
+        dst: Reg,
+        module: Reg,
+        path: Box<[SpanRef]>,
+        relative: usize,
+        name: SpanRef,
+    },
+
+    Tuple {
+        dst: Reg,
+        elements: Box<[Reg]>,
+    },
+
+    List {
+        dst: Reg,
+        elements: Box<[Reg]>,
+    },
+
+    Set {
+        dst: Reg,
+        elements: Box<[Reg]>,
+    },
+
+    Dict {
+        dst: Reg,
+        items: Box<[(Reg, Reg)]>,
+    },
+
+    ListAppend {
+        list: Reg,
+        value: Reg,
+    },
+
+    SetAdd {
+        set: Reg,
+        value: Reg,
+    },
+
+    DictInsert {
+        dict: Reg,
+        key: Reg,
+        value: Reg,
+    },
+
+    Not {
+        dst: Reg,
+        value: Reg,
+    },
+
+    Is {
+        dst: Reg,
+        left: Reg,
+        right: Reg,
+    },
+
+    Undefined {
+        dst: Reg,
+    },
+
+    Jump {
+        to: Pc,
+    },
+
+    JumpIf {
+        test: Reg,
+        truthy: Pc,
+        falsey: Pc,
+    },
+
+    /// Move `value` into the register `dst` of a phi node and jump to `to`.
+    PhiJump {
+        dst: Reg,
+        value: Reg,
+        to: Pc,
+    },
+
+    Unpack {
+        dst: Reg,
+        value: Reg,
+        index: usize,
+        len: usize,
+    },
+
+    GetIter {
+        dst: Reg,
+        object: Reg,
+    },
+
+    IterNext {
+        dst: Reg,
+        iter: Reg,
+        exhausted: Pc,
+    },
+
+    Return {
+        value: Reg,
+    },
+
+    Raise {
+        exc: Option<Reg>,
+    },
+
+    Unsupported {
+        message: &'static str,
+    },
+
+    SetupExcept {
+        handler: Pc,
+    },
+
+    PopBlock,
+
+    GetException {
+        dst: Reg,
+    },
+
+    ExcMatch {
+        dst: Reg,
+        exc: Reg,
+        kind: Reg,
+    },
+
+    PopExcept,
+
+    ClassOf {
+        dst: Reg,
+        object: Reg,
+    },
+}
+
+/// A key that constants are deduplicated by, strings are equal if their span groups are.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum ConstKey {
+    Int(i64),
+    Float(u64),
+    Bool(bool),
+    String(u32),
+    None,
+    Ellipsis,
+}
+
+impl From<&Constant> for ConstKey {
+    fn from(cst: &Constant) -> Self {
+        match cst {
+            Constant::Int(n) => ConstKey::Int(*n),
+            Constant::Float(f) => ConstKey::Float(f.to_bits()),
+            Constant::Bool(b) => ConstKey::Bool(*b),
+            Constant::String(st) => ConstKey::String(st.group()),
+            Constant::None => ConstKey::None,
+            Constant::Ellipsis => ConstKey::Ellipsis,
+        }
+    }
+}
+
+/// The bytecode of a single `FlatSeq`.
+#[derive(Debug)]
+pub struct CodeObject {
+    pub(crate) ops: Box<[Op]>,
+
+    /// The span of the source each op was compiled from, for tracebacks.
+    pub(crate) spans: Box<[Option<Span>]>,
+
+    pub(crate) consts: Box<[Constant]>,
+
+    /// A representative name of every slot.
+    pub(crate) names: Box<[SpanRef]>,
+
+    /// Maps span groups to the slot of the variable.
+    pub(crate) slots: AHashMap<u32, Slot>,
+
+    pub(crate) functions: Box<[FunctionDef]>,
+
+    /// Whether functions or classes are defined by the code, these refer to its variables by name.
+    pub(crate) defines_scopes: bool,
+
+    /// The amount of registers a frame executing this code needs.
+    pub(crate) n_registers: usize,
+
+    pub mref: ModuleRef,
+
+    /// The name of the function or class this is the body of, if it is one.
+    pub name: Option<SpanRef>,
+}
+
+#[derive(Default)]
+struct Compiler {
+    ops: Vec<Op>,
+    spans: Vec<Option<Span>>,
+    consts: Vec<Constant>,
+    const_ix: AHashMap<ConstKey, u32>,
+    names: Vec<SpanRef>,
+    slots: AHashMap<u32, Slot>,
+    functions: Vec<FunctionDef>,
+}
+
+impl Compiler {
+    fn function(
+        &mut self,
+        name: Option<SpanRef>,
+        params: &[Parameter],
+        returns: Option<usize>,
+        seq_id: usize,
+        dst: Reg,
+    ) -> Op {
+        self.functions.push(FunctionDef {
+            name,
+            params: params
+                .iter()
+                .map(|param| Parameter {
+                    name: param.name,
+                    annotation: param.annotation.map(|ann| ann as Reg),
+                    default: param.default.map(|def| def as Reg),
+                    kind: param.kind,
+                })
+                .collect(),
+            returns: returns.map(|r| r as Reg),
+            seq_id,
+        });
+
+        Op::MakeFunction {
+            dst,
+            def: (self.functions.len() - 1) as u32,
+        }
+    }
+
+    fn slot(&mut self, name: SpanRef) -> Slot {
+        let Self { names, slots, .. } = self;
+
+        *slots.entry(name.group()).or_insert_with(|| {
+            names.push(name);
+            (names.len() - 1) as Slot
+        })
+    }
+
+    fn constant(&mut self, cst: &Constant) -> u32 {
+        let Self {
+            consts, const_ix, ..
+        } = self;
+
+        *const_ix.entry(ConstKey::from(cst)).or_insert_with(|| {
+            consts.push(cst.clone());
+            (consts.len() - 1) as u32
+        })
+    }
+}
+
+/// Whether `op` does anything at runtime, ops that don't are not compiled.
+fn is_elided(op: &RawInst) -> bool {
+    matches!(
+        op,
+        RawInst::Nop | RawInst::JumpTarget | RawInst::PhiRecv | RawInst::SetAnnotation { .. }
+    )
+}
+
+fn regs(values: &[usize]) -> Box<[Reg]> {
+    values.iter().map(|v| *v as Reg).collect()
+}
+
+impl CodeObject {
+    /// Compile a sequence into bytecode.
+    pub fn compile(seq: &FlatSeq) -> Self {
+        let inst = seq.inst();
+
+        // the pc of the op every instruction compiles to, or of the next op if it gets elided.
+        let mut pcs = Vec::with_capacity(inst.len() + 1);
+        let mut pc = 0;
+
+        for i in inst {
+            pcs.push(pc);
+
+            if !is_elided(&i.op) {
+                pc += 1;
+            }
+        }
+
+        pcs.push(pc);
+
+        let mut cx = Compiler::default();
+        let mut span = None;
+
+        for (ix, i) in inst.iter().enumerate() {
+            if i.attrs.span.is_some() {
+                span = i.attrs.span.clone();
+            }
+
+            let dst = ix as Reg;
+
+            let op = match &i.op {
+                op if is_elided(op) => continue,
+
+                RawInst::Const(cst) => Op::LoadConst {
+                    dst,
+                    cst: cx.constant(cst),
+                },
+
+                RawInst::RefAsStr { r } => Op::LoadConst {
+                    dst,
+                    cst: cx.constant(&Constant::String(*r)),
+                },
+
+                RawInst::UseVar { variable } => Op::LoadName {
+                    dst,
+                    slot: cx.slot(*variable),
+                    name: *variable,
+                },
+
+                RawInst::SetVar { variable, value } => Op::StoreName {
+                    slot: cx.slot(*variable),
+                    value: *value as Reg,
+                },
+
+                RawInst::GetAttribute { object, attr } => Op::GetAttr {
+                    dst,
+                    object: *object as Reg,
+                    attr: *attr as Reg,
+                },
+
+                RawInst::SetAttribute {
+                    object,
+                    attr,
+                    value,
+                } => Op::SetAttr {
+                    object: *object as Reg,
+                    attr: *attr as Reg,
+                    value: *value as Reg,
+                },
+
+                RawInst::GetDunder { object, dunder } => Op::GetDunder {
+                    dst,
+                    object: *object as Reg,
+                    dunder: *dunder,
+                },
+
+                RawInst::SetDunder {
+                    object,
+                    dunder,
+                    value,
+                } => Op::SetDunder {
+                    object: *object as Reg,
+                    dunder: *dunder,
+                    value: *value as Reg,
+                },
+
+                RawInst::Call {
+                    callable,
+                    arguments,
+                } => Op::Call {
+                    dst,
+                    callable: *callable as Reg,
+                    arguments: regs(arguments),
+                },
+
+                RawInst::CallEx {
+                    callable,
+                    arguments,
+                } => Op::CallEx {
+                    dst,
+                    callable: *callable as Reg,
+                    arguments: arguments
+                        .iter()
+                        .map(|arg| match arg {
+                            Argument::Positional(v) => Argument::Positional(*v as Reg),
+                            Argument::Starred(v) => Argument::Starred(*v as Reg),
+                            Argument::Keyword(name, v) => Argument::Keyword(*name, *v as Reg),
+                            Argument::DoubleStarred(v) => Argument::DoubleStarred(*v as Reg),
+                        })
+                        .collect(),
+                },
+
+                RawInst::Defn {
+                    name,
+                    params,
+                    returns,
+                    sequence_id,
+                } => {
+                    cx.slot(*name);
+                    cx.function(Some(*name), params, *returns, *sequence_id, dst)
+                }
+
+                RawInst::Lambda {
+                    params,
+                    sequence_id,
+                } => cx.function(None, params, None, *sequence_id, dst),
+
+                RawInst::Class { name, bases } => {
+                    cx.slot(*name);
+
+                    Op::MakeClass {
+                        dst,
+                        name: *name,
+                        bases: regs(bases),
+                    }
+                }
+
+                RawInst::BuildClass { sequence, class } => Op::BuildClass {
+                    class: *class as Reg,
+                    seq_id: *sequence,
+                },
+
+                RawInst::Import { path, relative } => Op::Import {
+                    dst,
+                    path: path.clone(),
+                    relative: *relative,
+                },
+
+                RawInst::ImportFrom {
+                    module,
+                    path,
+                    relative,
+                    name,
+                } => Op::ImportFrom {
+                    dst,
+                    module: *module as Reg,
+                    path: path.clone(),
+                    relative: *relative,
+                    name: *name,
+                },
+
+                RawInst::Tuple(elements) => Op::Tuple {
+                    dst,
+                    elements: regs(elements),
+                },
+
+                RawInst::List(elements) => Op::List {
+                    dst,
+                    elements: regs(elements),
+                },
+
+                RawInst::Set(elements) => Op::Set {
+                    dst,
+                    elements: regs(elements),
+                },
+
+                RawInst::Dict(items) => Op::Dict {
+                    dst,
+                    items: items
+                        .iter()
+                        .map(|(key, value)| (*key as Reg, *value as Reg))
+                        .collect(),
+                },
+
+                RawInst::ListAppend { list, value } => Op::ListAppend {
+                    list: *list as Reg,
+                    value: *value as Reg,
+                },
+
+                RawInst::SetAdd { set, value } => Op::SetAdd {
+                    set: *set as Reg,
+                    value: *value as Reg,
+                },
+
+                RawInst::DictInsert { dict, key, value } => Op::DictInsert {
+                    dict: *dict as Reg,
+                    key: *key as Reg,
+                    value: *value as Reg,
+                },
+
+                RawInst::Not { value } => Op::Not {
+                    dst,
+                    value: *value as Reg,
+                },
+
+                RawInst::Is { left, right } => Op::Is {
+                    dst,
+                    left: *left as Reg,
+                    right: *right as Reg,
+                },
+
+                RawInst::Undefined => Op::Undefined { dst },
+
+                RawInst::If {
+                    test,
+                    truthy,
+                    falsey,
+                } => Op::JumpIf {
+                    test: *test as Reg,
+                    truthy: pcs[truthy.unwrap_or(ix + 1)],
+                    falsey: pcs[falsey.unwrap_or(ix + 1)],
+                },
+
+                RawInst::Br { to } => Op::Jump { to: pcs[*to] },
+
+                RawInst::PhiJump { recv, value } => Op::PhiJump {
+                    dst: *recv as Reg,
+                    value: *value as Reg,
+                    to: pcs[*recv],
+                },
+
+                RawInst::Unpack { value, index, len } => Op::Unpack {
+                    dst,
+                    value: *value as Reg,
+                    index: *index,
+                    len: *len,
+                },
+
+                RawInst::GetIter { object } => Op::GetIter {
+                    dst,
+                    object: *object as Reg,
+                },
+
+                RawInst::IterNext { iter, exhausted } => Op::IterNext {
+                    dst,
+                    iter: *iter as Reg,
+                    exhausted: pcs[*exhausted],
+                },
+
+                RawInst::Return { value } => Op::Return {
+                    value: *value as Reg,
+                },
+
+                RawInst::Raise { exc } => Op::Raise {
+                    exc: exc.map(|exc| exc as Reg),
+                },
+
+                RawInst::Unsupported { message } => Op::Unsupported { message },
+
+                RawInst::SetupExcept { handler } | RawInst::SetupWith { handler } => {
+                    Op::SetupExcept {
+                        handler: pcs[*handler],
+                    }
+                }
+
+                RawInst::PopBlock => Op::PopBlock,
+                RawInst::GetException => Op::GetException { dst },
+
+                RawInst::ExcMatch { exc, kind } => Op::ExcMatch {
+                    dst,
+                    exc: *exc as Reg,
+                    kind: *kind as Reg,
+                },
+
+                RawInst::PopExcept => Op::PopExcept,
+
+                RawInst::ClassOf { object } => Op::ClassOf {
+                    dst,
+                    object: *object as Reg,
+                },
+
+                RawInst::Nop
+                | RawInst::JumpTarget
+                | RawInst::PhiRecv
+                | RawInst::SetAnnotation { .. } => unreachable!(),
+            };
+
+            cx.ops.push(op);
+            cx.spans.push(span.clone());
+        }
+
+        let Compiler {
+            ops,
+            spans,
+            consts,
+            names,
+            slots,
+            functions,
+            ..
+        } = cx;
+
+        let defines_scopes =
+            !functions.is_empty() || ops.iter().any(|op| matches!(op, Op::MakeClass { .. }));
+
+        Self {
+            ops: ops.into_boxed_slice(),
+            spans: spans.into_boxed_slice(),
+            consts: consts.into_boxed_slice(),
+            names: names.into_boxed_slice(),
+            slots,
+            defines_scopes,
+            functions: functions.into_boxed_slice(),
+            n_registers: inst.len(),
+            mref: seq.span.0,
+            name: seq.name(),
+        }
+    }
+
+    #[inline]
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    #[inline]
+    pub fn consts(&self) -> &[Constant] {
+        &self.consts
+    }
+
+    /// The slot of the variable with the span group `group`, if this code uses it.
+    #[inline]
+    pub fn slot_of(&self, group: u32) -> Option<Slot> {
+        self.slots.get(&group).copied()
+    }
+}
+
+fn fmt_regs(regs: &[Reg]) -> String {
+    regs.iter()
+        .map(|reg| format!("%{}", reg))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::LoadConst { dst, cst } => write!(f, "%{} = load-const #{}", dst, cst),
+            Op::LoadName { dst, slot, .. } => write!(f, "%{} = load-name ${}", dst, slot),
+            Op::StoreName { slot, value } => write!(f, "store-name ${} %{}", slot, value),
+            Op::GetAttr { dst, object, attr } => {
+                write!(f, "%{} = get-attr %{} %{}", dst, object, attr)
+            }
+            Op::SetAttr {
+                object,
+                attr,
+                value,
+            } => write!(f, "set-attr %{} %{} %{}", object, attr, value),
+            Op::GetDunder {
+                dst,
+                object,
+                dunder,
+            } => write!(f, "%{} = get-dunder %{} {}", dst, object, dunder),
+            Op::SetDunder {
+                object,
+                dunder,
+                value,
+            } => write!(f, "set-dunder %{} {} %{}", object, dunder, value),
+            Op::Call {
+                dst,
+                callable,
+                arguments,
+            } => write!(f, "%{} = call %{} [{}]", dst, callable, fmt_regs(arguments)),
+            Op::CallEx {
+                dst,
+                callable,
+                arguments,
+            } => write!(f, "%{} = call-ex %{} [{}]", dst, callable, {
+                let args = arguments
+                    .iter()
+                    .map(|arg| match arg {
+                        Argument::Positional(v) => format!("%{}", v),
+                        Argument::Starred(v) => format!("*%{}", v),
+                        Argument::Keyword(name, v) => format!("{:?}=%{}", name, v),
+                        Argument::DoubleStarred(v) => format!("**%{}", v),
+                    })
+                    .collect::<Vec<_>>();
+                args.join(", ")
+            }),
+            Op::MakeFunction { dst, def } => write!(f, "%{} = make-function fn#{}", dst, def),
+            Op::MakeClass { dst, bases, .. } => {
+                write!(f, "%{} = make-class [{}]", dst, fmt_regs(bases))
+            }
+            Op::BuildClass { class, seq_id } => {
+                write!(f, "build-class %{} seq({})", class, seq_id)
+            }
+            Op::Import {
+                dst,
+                path,
+                relative,
+            } => write!(f, "%{} = import {:?} (relative {})", dst, path, relative),
+            Op::ImportFrom {
+                dst, module, name, ..
+            } => write!(f, "%{} = import-from %{} {:?}", dst, module, name),
+            Op::Tuple { dst, elements } => write!(f, "%{} = tuple [{}]", dst, fmt_regs(elements)),
+            Op::List { dst, elements } => write!(f, "%{} = list [{}]", dst, fmt_regs(elements)),
+            Op::Set { dst, elements } => write!(f, "%{} = set [{}]", dst, fmt_regs(elements)),
+            Op::Dict { dst, items } => write!(f, "%{} = dict [{}]", dst, {
+                let items = items
+                    .iter()
+                    .map(|(key, value)| format!("%{}: %{}", key, value))
+                    .collect::<Vec<_>>();
+                items.join(", ")
+            }),
+            Op::ListAppend { list, value } => write!(f, "list-append %{} %{}", list, value),
+            Op::SetAdd { set, value } => write!(f, "set-add %{} %{}", set, value),
+            Op::DictInsert { dict, key, value } => {
+                write!(f, "dict-insert %{} %{} %{}", dict, key, value)
+            }
+            Op::Not { dst, value } => write!(f, "%{} = not %{}", dst, value),
+            Op::Is { dst, left, right } => write!(f, "%{} = is %{} %{}", dst, left, right),
+            Op::Undefined { dst } => write!(f, "%{} = undef", dst),
+            Op::Jump { to } => write!(f, "jump @{}", to),
+            Op::JumpIf {
+                test,
+                truthy,
+                falsey,
+            } => write!(f, "jump-if %{} @{} else @{}", test, truthy, falsey),
+            Op::PhiJump { dst, value, to } => write!(f, "%{} = %{}; jump @{}", dst, value, to),
+            Op::Unpack {
+                dst,
+                value,
+                index,
+                len,
+            } => write!(f, "%{} = unpack %{} [{} of {}]", dst, value, index, len),
+            Op::GetIter { dst, object } => write!(f, "%{} = get-iter %{}", dst, object),
+            Op::IterNext {
+                dst,
+                iter,
+                exhausted,
+            } => write!(f, "%{} = iter-next %{} else @{}", dst, iter, exhausted),
+            Op::Return { value } => write!(f, "return %{}", value),
+            Op::Raise { exc: Some(exc) } => write!(f, "raise %{}", exc),
+            Op::Raise { exc: None } => write!(f, "raise"),
+            Op::Unsupported { message } => write!(f, "unsupported {:?}", message),
+            Op::SetupExcept { handler } => write!(f, "setup-except @{}", handler),
+            Op::PopBlock => write!(f, "pop-block"),
+            Op::GetException { dst } => write!(f, "%{} = get-exception", dst),
+            Op::ExcMatch { dst, exc, kind } => {
+                write!(f, "%{} = exc-match %{} %{}", dst, exc, kind)
+            }
+            Op::PopExcept => write!(f, "pop-except"),
+            Op::ClassOf { dst, object } => write!(f, "%{} = class-of %{}", dst, object),
+        }
+    }
+}
+
+impl fmt::Display for CodeObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (pc, op) in self.ops.iter().enumerate() {
+            writeln!(f, "  @{} {}", pc, op)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use montyc_core::ast::ParameterKind;
+
+    use crate::{rt::AcceptInput, test::*};
+
+    #[test]
+    pub fn compile_while_loop() {
+        let (_, mut host) = setup();
+
+        let code = host
+            .accept_input("x = 1\nwhile x < 3:\n    x = x + 1")
+            .unwrap();
+
+        let seq = &code.sequences()[0];
+        let object = CodeObject::compile(seq);
+
+        // `1` is used twice but only interned once.
+        assert_eq!(object.consts().len(), 2);
+        assert_eq!(object.names.len(), 1);
+
+        assert!(object.ops().len() < seq.inst().len());
+        assert_eq!(object.n_registers, seq.inst().len());
+
+        for op in object.ops() {
+            let targets = match op {
+                Op::Jump { to } => vec![*to],
+                Op::JumpIf { truthy, falsey, .. } => vec![*truthy, *falsey],
+                Op::PhiJump { to, .. } => vec![*to],
+                Op::IterNext { exhausted, .. } => vec![*exhausted],
+                _ => continue,
+            };
+
+            for pc in targets {
+                assert!(pc as usize <= object.ops().len(), "{}", object);
+            }
+        }
+    }
+
+    #[test]
+    pub fn compile_relative_import_from() {
+        let (_, mut host) = setup();
+
+        let code = host.accept_input("from ..a import b, c").unwrap();
+        let object = CodeObject::compile(&code.sequences()[0]);
+
+        let imports: Vec<_> = object
+            .ops()
+            .iter()
+            .filter_map(|op| match op {
+                Op::Import { path, relative, .. } => Some((path.len(), *relative)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(imports, [(1, 2)]);
+
+        let names: Vec<_> = object
+            .ops()
+            .iter()
+            .filter_map(|op| match op {
+                Op::ImportFrom { path, relative, .. } => Some((path.len(), *relative)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(names, [(1, 2), (1, 2)]);
+    }
+
+    #[test]
+    pub fn compile_keyword_call_and_defaults() {
+        let (_, mut host) = setup();
+
+        let code = host
+            .accept_input("def f(a, b=1, *args, c, **kwargs):\n    pass\nf(1, *x, c=2, **y)")
+            .unwrap();
+
+        let object = CodeObject::compile(&code.sequences()[0]);
+
+        let kinds: Vec<_> = object.functions[0]
+            .params
+            .iter()
+            .map(|param| (param.kind, param.default.is_some()))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                (ParameterKind::PositionalOrKeyword, false),
+                (ParameterKind::PositionalOrKeyword, true),
+                (ParameterKind::VarPositional, false),
+                (ParameterKind::KeywordOnly, false),
+                (ParameterKind::VarKeyword, false),
+            ]
+        );
+
+        let arguments = object
+            .ops()
+            .iter()
+            .find_map(|op| match op {
+                Op::CallEx { arguments, .. } => Some(arguments),
+                _ => None,
+            })
+            .expect("a call with keywords compiles to `call-ex`");
+
+        assert!(matches!(
+            arguments.as_ref(),
+            [
+                Argument::Positional(_),
+                Argument::Starred(_),
+                Argument::Keyword(_, _),
+                Argument::DoubleStarred(_)
+            ]
+        ));
+    }
+
+    #[test]
+    pub fn compile_list_comprehension() {
+        let (_, mut host) = setup();
+
+        let code = host.accept_input("[x for x in [1, 2] if x]").unwrap();
+
+        let object = CodeObject::compile(&code.sequences()[0]);
+
+        let lists = object
+            .ops()
+            .iter()
+            .filter(|op| matches!(op, Op::List { .. }))
+            .count();
+
+        // the comprehension starts out empty and is appended to inside the loop.
+        assert_eq!(lists, 2);
+        assert!(object
+            .ops()
+            .iter()
+            .any(|op| matches!(op, Op::ListAppend { .. })));
+    }
+
+    #[test]
+    pub fn compile_lambda() {
+        let (_, mut host) = setup();
+
+        let code = host.accept_input("f = lambda x, y=1: x + y").unwrap();
+
+        let object = CodeObject::compile(&code.sequences()[0]);
+
+        // lambdas aren't bound to a name by their definition, only by the assignment.
+        assert!(object.functions[0].name.is_none());
+        assert_eq!(object.functions[0].params.len(), 2);
+        assert!(object.functions[0].params[1].default.is_some());
+
+        let body = CodeObject::compile(&code.sequences()[object.functions[0].seq_id]);
+
+        assert!(body.name.is_none());
+        assert!(matches!(body.ops().last(), Some(Op::Return { .. })));
+    }
+
+    #[test]
+    pub fn compile_with() {
+        let (_, mut host) = setup();
+
+        let code = host.accept_input("with a as b:\n    pass").unwrap();
+
+        let object = CodeObject::compile(&code.sequences()[0]);
+
+        // the cleanup of a `with` statement is an ordinary exception handler.
+        assert!(object.ops().iter().any(|op| matches!(
+            op,
+            Op::GetDunder {
+                dunder: Dunder::Enter,
+                ..
+            }
+        )));
+        assert!(object
+            .ops()
+            .iter()
+            .any(|op| matches!(op, Op::SetupExcept { .. })));
+        assert!(object
+            .ops()
+            .iter()
+            .any(|op| matches!(op, Op::ClassOf { .. })));
+    }
+}
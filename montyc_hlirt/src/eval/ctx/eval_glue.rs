@@ -90,10 +90,10 @@ pub trait EvalGlue {
                 PyValue::Ellipsis => todo!(),
                 PyValue::Bytes(_) => todo!(),
                 PyValue::Str(_) => todo!(),
-                PyValue::List(_) => todo!("list getattr"),
                 PyValue::Callable(_) => todo!(),
 
-                PyValue::None => None,
+                // the methods of lists and sets are bound natively by `get-attribute`.
+                PyValue::None | PyValue::List(_) | PyValue::Tuple(_) | PyValue::Set(_) => None,
             };
 
             result.ok_or_else(|| PyException::attribute_error(object, hash))
@@ -109,7 +109,10 @@ pub trait EvalGlue {
                 PyValue::Module { inner, .. }
                 | PyValue::Any(inner)
                 | PyValue::Function { inner, .. }
-                | PyValue::Class { inner, .. } => inner.__dict__.insert(hash, (key, value)),
+                | PyValue::Class { inner, .. } => {
+                    inner.__dict__.insert(hash, (key, value));
+                    Ok(())
+                }
 
                 PyValue::Dynamic(_) => todo!(),
                 PyValue::Int(_) => todo!(),
@@ -122,11 +125,16 @@ pub trait EvalGlue {
 
                 PyValue::List(_) => todo!("list getattr"),
 
-                PyValue::Dict(_) => todo!(),
-                PyValue::Callable(_) => todo!(),
-            });
+                PyValue::Dict(_) | PyValue::Set(_) => PyException::type_error()
+                    .set_message("the attributes of dicts and sets can not be set")
+                    .into(),
 
-        Ok(())
+                PyValue::Tuple(_) => PyException::type_error()
+                    .set_message("the attributes of tuples can not be set")
+                    .into(),
+
+                PyValue::Callable(_) => todo!(),
+            })
     }
 
     fn getattr(&mut self, base: ObjectId, attrs: &dyn sealed::Attrs) -> PyResult<ObjectId> {
@@ -173,6 +181,10 @@ pub trait EvalGlue {
         Ok(self.runtime_mut().new_int(n))
     }
 
+    fn new_float(&mut self, f: f64) -> PyResult<ObjectId> {
+        Ok(self.runtime_mut().new_float(f))
+    }
+
     fn call_method_object(
         &mut self,
         object: ObjectId,
@@ -184,9 +196,25 @@ pub trait EvalGlue {
 
     fn repr_object(&mut self, object: ObjectId) -> PyResult<ObjectId>;
 
-    fn iter_object(&mut self, object: ObjectId) -> PyIter;
+    fn iter_object(&mut self, object: ObjectId) -> PyResult<PyIter>;
 
     fn hash_object(&mut self, object: ObjectId) -> PyResult<u64>;
 
     fn call_object(&mut self, callable: ObjectId, arguments: &[ObjectId]) -> PyResult<ObjectId>;
+
+    /// Like `call_object` but also passing `keywords` as `(name, value)` pairs where every name is a str object.
+    fn call_object_with_keywords(
+        &mut self,
+        callable: ObjectId,
+        arguments: &[ObjectId],
+        keywords: &[(ObjectId, ObjectId)],
+    ) -> PyResult<ObjectId> {
+        if keywords.is_empty() {
+            self.call_object(callable, arguments)
+        } else {
+            PyException::type_error()
+                .set_message("keyword arguments are not supported here")
+                .into()
+        }
+    }
 }
@@ -1,17 +1,20 @@
-use std::collections::hash_map::Entry;
 use std::hash::{BuildHasher, Hash, Hasher};
+use std::time::Instant;
 use std::{num::NonZeroU64, rc::Rc};
 
-use montyc_core::ast::Constant;
-use montyc_core::{patma, ModuleRef, SpanRef};
+use montyc_core::ast::{Constant, ParameterKind};
+use montyc_core::{dict::PyDictRaw, patma, ModuleRef, SpanRef};
+use montyc_flatcode::raw_inst::{Argument, Parameter};
 use montyc_flatcode::SequenceType;
 use montyc_flatcode::{raw_inst::Dunder, FlatCode};
 
+use crate::eval::bytecode::{FunctionDef, Pc, Reg, Slot};
 use crate::eval::frame::FrameState;
 use crate::eval::inst_exec::{InstExec, InstResult};
-use crate::exception::{InnerExc, PyException, PyResult, PyResultExt};
+use crate::exception::{InnerExc, PyException, PyFrame, PyResult, PyResultExt};
 use crate::object::{
-    AnyFunc, GlobalsHook, ObjectId, PyIter, PyObject, PyValue, RawObject, SharedObject,
+    native_tables, AnyFunc, GlobalsHook, ObjectId, PyIter, PyObject, PyValue, RawObject,
+    SharedObject,
 };
 use crate::rt::{ModuleKey, ModuleMetadata, Runtime, RuntimeHost, RuntimeHostExt};
 use crate::storage::ObjectSpace;
@@ -41,6 +44,8 @@ impl UnboundEvaluationContext {
             preamble: vec![],
             state: self,
             n_ticks: None,
+            ticks_elapsed: 0,
+            deadline: None,
         }
     }
 }
@@ -56,6 +61,8 @@ where
 
     preamble: Vec<(ModuleRef, Vec<Box<str>>)>,
     n_ticks: Option<NonZeroU64>,
+    ticks_elapsed: u64,
+    deadline: Option<Instant>,
     pub(crate) state: UnboundEvaluationContext,
 }
 
@@ -77,6 +84,8 @@ where
             Call(ObjectId),
             CallShared(SharedObject),
             Callable(AnyFunc),
+            Tuple(Box<[ObjectId]>),
+            Unhashable,
         }
 
         let hash = |this: &PyValue| -> Option<Defered> {
@@ -87,8 +96,11 @@ where
                 },
 
                 PyValue::Int(n) => n.hash(&mut hasher),
-                PyValue::Float(n) => (*n as u64).hash(&mut hasher),
-                PyValue::Bool(n) => n.hash(&mut hasher),
+                // integral floats must hash the same as the ints they compare equal to.
+                PyValue::Float(n) if n.fract() == 0.0 => (*n as i64).hash(&mut hasher),
+                PyValue::Float(n) => n.to_bits().hash(&mut hasher),
+                // bools must hash the same as the ints they compare equal to.
+                PyValue::Bool(n) => (*n as i64).hash(&mut hasher),
                 PyValue::Bytes(n) => n.hash(&mut hasher),
                 PyValue::Str(n) => n.hash(&mut hasher),
 
@@ -98,7 +110,11 @@ where
                 | PyValue::None
                 | PyValue::Ellipsis => object.hash(&mut hasher),
 
-                PyValue::List(_) | PyValue::Dict(_) => todo!("TypeError: unhashable type."),
+                PyValue::List(_) | PyValue::Dict(_) | PyValue::Set(_) => {
+                    return Some(Defered::Unhashable)
+                }
+
+                PyValue::Tuple(elems) => return Some(Defered::Tuple(elems.clone())),
 
                 PyValue::Dynamic(object) => return Some(Defered::CallShared(object.clone())),
                 PyValue::Callable(f) => return Some(Defered::Callable(f.clone())),
@@ -116,6 +132,21 @@ where
 
                     Defered::CallShared(shared) => shared.hash(self).trace()?,
                     Defered::Callable(any) => self.call_any_func(&any, &[]).trace()?,
+
+                    // tuples hash their elements, so equal tuples hash the same.
+                    Defered::Tuple(elems) => {
+                        for elem in elems.iter() {
+                            self.hash_object(*elem).trace()?.hash(&mut hasher);
+                        }
+
+                        return Ok(hasher.finish());
+                    }
+
+                    Defered::Unhashable => {
+                        return PyException::type_error()
+                            .set_message("unhashable type")
+                            .into()
+                    }
                 };
 
                 let n = self.rt.objects.with_object(object, |val| match val {
@@ -134,35 +165,80 @@ where
     }
 
     #[inline]
-    fn iter_object(&mut self, object: ObjectId) -> PyIter {
-        self.rt.objects.with_object(object, |this| match this {
-            PyValue::Int(_)
-            | PyValue::Float(_)
-            | PyValue::Bool(_)
-            | PyValue::None
-            | PyValue::Ellipsis
-            | PyValue::Class { .. }
-            | PyValue::Module { .. }
-            | PyValue::Function { .. } => PyIter::empty(),
+    fn iter_object(&mut self, object: ObjectId) -> PyResult<PyIter> {
+        if self.builtin_class("range") == Some(self.rt.class_of(object)) {
+            let (start, stop, step) = self.range_bounds(object).trace()?;
 
-            PyValue::List(lst) if lst.is_empty() => PyIter::empty(),
+            return Ok(PyIter::range(start, stop, step));
+        }
 
-            PyValue::Dynamic(_) => todo!(),
+        // iterating strings and bytes creates new objects, so take a copy out of the object space first.
+        let st = self
+            .rt
+            .objects
+            .with_object(object, |this| patma!(st.clone(), PyValue::Str(st) in this));
 
-            PyValue::Any(_) => todo!(),
+        if let Some(st) = st {
+            let chars = st
+                .chars()
+                .map(|ch| self.rt.new_string(ch.encode_utf8(&mut [0; 4])))
+                .collect::<Vec<_>>();
 
-            PyValue::Bytes(_) => todo!(),
+            return Ok(PyIter::from(chars.into_iter()));
+        }
 
-            PyValue::Str(_) => todo!(),
+        let bytes = self.rt.objects.with_object(
+            object,
+            |this| patma!(bytes.clone(), PyValue::Bytes(bytes) in this),
+        );
 
-            PyValue::List(lst) => PyIter::from(lst.iter().cloned()),
-            PyValue::Dict(dict) => PyIter::from(dict.values().map(|kv| kv.0)),
-            PyValue::Callable(_) => todo!(),
-        })
+        if let Some(bytes) = bytes {
+            let ints = bytes
+                .into_iter()
+                .map(|byte| self.rt.new_int(byte.into()))
+                .collect::<Vec<_>>();
+
+            return Ok(PyIter::from(ints.into_iter()));
+        }
+
+        let items = self.rt.objects.with_object(object, |this| match this {
+            PyValue::List(lst) => Some(lst.clone()),
+            PyValue::Tuple(elems) => Some(elems.to_vec()),
+            PyValue::Dict(dict) => Some(dict.values().map(|kv| kv.0).collect()),
+            PyValue::Set(set) => Some(set.values().copied().collect()),
+            _ => None,
+        });
+
+        match items {
+            Some(items) => Ok(PyIter::from(items.into_iter())),
+            None => PyException::type_error()
+                .set_message(format!(
+                    "'{}' object is not iterable",
+                    self.type_name(object)
+                ))
+                .into(),
+        }
     }
 
     #[inline]
     fn call_object(&mut self, callable: ObjectId, arguments: &[ObjectId]) -> PyResult<ObjectId> {
+        self.call_object_with_keywords(callable, arguments, &[])
+    }
+
+    fn call_object_with_keywords(
+        &mut self,
+        callable: ObjectId,
+        arguments: &[ObjectId],
+        keywords: &[(ObjectId, ObjectId)],
+    ) -> PyResult<ObjectId> {
+        let is_user_class = self.rt.objects.with_object(callable, |this| {
+            matches!(this, PyValue::Class { name: Some(_), .. })
+        });
+
+        if is_user_class {
+            return self.instantiate(callable, arguments, keywords).trace();
+        }
+
         let callable = self
             .rt
             .objects
@@ -192,21 +268,54 @@ where
         let none_v = self.rt.singletons.none_v;
 
         match callable {
+            PyValue::Dynamic(_) | PyValue::Callable(_) if !keywords.is_empty() => {
+                PyException::type_error()
+                    .set_message("native functions take no keyword arguments")
+                    .into()
+            }
+
             PyValue::Dynamic(obj) => obj.call(CallCx::new(self, arguments, none_v)),
             PyValue::Callable(func) => self.call_any_func(&func, arguments),
-            PyValue::Function { body, params, .. } => {
+            PyValue::Function {
+                body,
+                params,
+                parent,
+                inner,
+                ..
+            } => {
                 let (module, seq_id) =
                     patma!((m, s), AnyFunc::Code { module: m, seq_id: s} in body).unwrap();
 
-                let seq = &module.sequences()[seq_id];
-                let mut frame = FrameState::new(None, Some(module.mref));
+                let bound = self
+                    .bind_arguments(&params, &inner, arguments, keywords)
+                    .trace()?;
+
+                let code = self.rt.code_object(&module, seq_id);
+
+                // functions and classes defined by the code refer to its locals through a namespace object.
+                let env = if code.defines_scopes {
+                    Some(self.rt.objects.insert(PyValue::Class {
+                        name: None,
+                        parent,
+                        bases: Default::default(),
+                        inner: RawObject {
+                            alloc_id: Default::default(),
+                            __dict__: Default::default(),
+                            __class__: Default::default(),
+                        },
+                    }))
+                } else {
+                    None
+                };
+
+                let mut frame = FrameState::new(code, env, Some(module.mref));
+                frame.enclosing = parent;
 
-                for (n, ((p, _), a)) in params.iter().zip(arguments.iter()).enumerate() {
-                    self.define(&mut frame, p.group(), *a).trace()?;
-                    frame.values.insert(n, *a);
+                for (name, value) in bound {
+                    self.define(&mut frame, name.group(), value).trace()?;
                 }
 
-                self.exec_seq_with_frame(seq, frame)
+                self.exec_frame(frame)
             }
 
             _ => unreachable!(),
@@ -220,18 +329,34 @@ where
         args: &[ObjectId],
     ) -> PyResult<ObjectId> {
         let obj = self.rt.objects.with_object(object, |this| match this {
-            PyValue::Dynamic(obj) => obj.clone(),
-            _ => todo!("call_method_object"),
+            PyValue::Dynamic(obj) => Some(obj.clone()),
+            _ => None,
         });
 
-        obj.call_method(self, method, args).trace()
+        match obj {
+            Some(obj) => obj.call_method(self, method, args).trace(),
+            None => {
+                let method = self.getattr(object, &[method]).trace()?;
+                self.call_object(method, args).trace()
+            }
+        }
     }
 
     fn repr_object(&mut self, object: ObjectId) -> PyResult<ObjectId> {
+        let container = self.rt.objects.with_object(object, |this| {
+            matches!(this, PyValue::Dict(_) | PyValue::Set(_))
+        });
+
+        if container {
+            return PyException::type_error()
+                .set_message("dicts and sets can not be repr'd at compile time")
+                .into();
+        }
+
         let obj = self.rt.objects.with_object(object, |this| match this {
             PyValue::Dynamic(obj) => Err(obj.clone()),
             PyValue::Int(n) => Ok(format!("{}", n)),
-            PyValue::Float(n) => Ok(format!("{}", n)),
+            PyValue::Float(n) => Ok(format!("{:?}", n)),
 
             PyValue::Bool(true) => Ok(format!("True")),
             PyValue::Bool(false) => Ok(format!("False")),
@@ -240,8 +365,8 @@ where
             PyValue::Ellipsis => Ok(format!("...")),
             PyValue::Bytes(bytes) => Ok(format!("b'{:x?}'", bytes.as_slice())),
             PyValue::Str(st) => Ok(format!("{:?}", st)),
-            PyValue::List(_) => todo!(),
-            PyValue::Dict(_) => todo!(),
+            PyValue::List(_) | PyValue::Tuple(_) => todo!(),
+            PyValue::Dict(_) | PyValue::Set(_) => unreachable!(),
             PyValue::Module { .. } => todo!(),
             PyValue::Any(_) => todo!(),
             PyValue::Function { .. } => todo!(),
@@ -268,7 +393,9 @@ where
             PyValue::Bytes(_) => todo!(),
             PyValue::Str(_) => todo!(),
             PyValue::List(_) => todo!(),
+            PyValue::Tuple(_) => todo!(),
             PyValue::Dict(_) => todo!(),
+            PyValue::Set(_) => todo!(),
             PyValue::Callable(_) => todo!(),
             PyValue::Module { .. } => todo!(),
             PyValue::Function { .. } => todo!(),
@@ -298,19 +425,35 @@ impl<'rt, 'host, H> InstExec for EvaluationContext<'rt, 'host, H>
 where
     H: RuntimeHostExt,
 {
-    fn ref_as_str(&mut self, frame: &mut FrameState, r: SpanRef) -> InstResult {
-        let st = self.host.spanref_to_str(r).to_owned();
-        let st = self.new_string(st.as_str()).trace()?;
+    fn class(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        name: SpanRef,
+        bases: &[Reg],
+    ) -> InstResult {
+        let bases = bases
+            .iter()
+            .map(|base| frame.value(*base))
+            .collect::<Box<[_]>>();
 
-        let _ = frame.values.insert(frame.current_inst_ix, st);
+        for base in bases.iter() {
+            let is_class = self
+                .rt
+                .objects
+                .with_object(*base, |this| matches!(this, PyValue::Class { .. }));
 
-        Ok(frame.next_inst())
-    }
+            if !is_class {
+                return PyException::type_error()
+                    .set_message("bases must be classes")
+                    .into();
+            }
+        }
 
-    fn class(&mut self, frame: &mut FrameState, name: SpanRef) -> InstResult {
         let class_obj = self.rt.objects.insert(PyValue::Class {
             name: Some(name),
-            parent: frame.frame_object,
+            parent: self.defining_scope(frame),
+            bases,
             inner: RawObject {
                 alloc_id: Default::default(),
                 __dict__: Default::default(),
@@ -320,21 +463,27 @@ where
 
         self.define(frame, name.group(), class_obj)?;
 
-        frame.values.insert(frame.current_inst_ix, class_obj);
+        frame.set_value(dst, class_obj);
 
         Ok(frame.next_inst())
     }
 
-    fn call(&mut self, frame: &mut FrameState, callable: usize, arguments: &[usize]) -> InstResult {
-        let callable = frame.values[&callable];
+    fn call(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        callable: Reg,
+        arguments: &[Reg],
+    ) -> InstResult {
+        let callable = frame.value(callable);
         let arguments = arguments
             .iter()
-            .map(|val| frame.values[val])
+            .map(|val| frame.value(*val))
             .collect::<Vec<_>>();
 
         let rv = self.call_object(callable, arguments.as_ref())?;
 
-        frame.values.insert(frame.current_inst_ix, rv);
+        frame.set_value(dst, rv);
 
         Ok(frame.next_inst())
     }
@@ -342,39 +491,136 @@ where
     fn set_attribute(
         &mut self,
         _frame: &mut FrameState,
-        _object: usize,
-        _attr: usize,
-        _value: usize,
+        _object: Reg,
+        _attr: Reg,
+        _value: Reg,
     ) -> InstResult {
         todo!()
     }
 
-    fn get_attribute(&mut self, frame: &mut FrameState, object: usize, attr: usize) -> InstResult {
-        let attr = frame.values[&attr];
-        let object = frame.values[&object];
+    fn get_attribute(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        object: Reg,
+        attr: Reg,
+    ) -> InstResult {
+        let attr = frame.value(attr);
+        let object = frame.value(object);
+
+        // the methods of the builtin containers are native, bind them to their receiver.
+        let method = self.rt.objects.with_object(object, |this| match this {
+            PyValue::Bytes(_)
+            | PyValue::List(_)
+            | PyValue::Tuple(_)
+            | PyValue::Dict(_)
+            | PyValue::Set(_) => self.rt.objects.with_object(attr, |name| {
+                name.as_str()
+                    .and_then(|name| native_tables::lookup(this, name))
+            }),
+
+            _ => None,
+        });
+
+        let attr_value = match method {
+            Some(method) => {
+                let bound = move |cx: CallCx| {
+                    let args = std::iter::once(object)
+                        .chain(cx.args.iter().copied())
+                        .collect::<Vec<_>>();
+
+                    method(CallCx {
+                        ecx: cx.ecx,
+                        args: &args,
+                        globals: cx.globals,
+                    })
+                };
+
+                self.rt.objects.insert(PyValue::Callable(AnyFunc::Boxed {
+                    inner: Rc::new(bound),
+                    hook: None,
+                }))
+            }
+
+            None => self.getattr(object, &[attr])?,
+        };
+
+        frame.set_value(dst, attr_value);
+
+        Ok(frame.next_inst())
+    }
+
+    fn unpack(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        value: Reg,
+        index: usize,
+        len: usize,
+    ) -> InstResult {
+        let mut iter = self.iter_object(frame.value(value)).trace()?;
+        let mut elements = Vec::with_capacity(len);
+
+        while let Some(element) = iter.next(self) {
+            elements.push(element?);
+
+            if elements.len() > len {
+                return PyException::value_error()
+                    .set_message(format!("too many values to unpack (expected {})", len))
+                    .into();
+            }
+        }
 
-        let attr_value = self.getattr(object, &[attr])?;
+        if elements.len() < len {
+            return PyException::value_error()
+                .set_message(format!(
+                    "not enough values to unpack (expected {}, got {})",
+                    len,
+                    elements.len()
+                ))
+                .into();
+        }
 
-        frame.values.insert(frame.current_inst_ix, attr_value);
+        frame.set_value(dst, elements[index]);
 
         Ok(frame.next_inst())
     }
 
-    fn phi_jump(&mut self, frame: &mut FrameState, recv: usize, value: usize) -> InstResult {
-        let value = frame.values[&value];
-        frame.values.insert(recv, value);
-        Ok(recv)
+    fn get_iter(&mut self, frame: &mut FrameState, dst: Reg, object: Reg) -> InstResult {
+        let object = frame.value(object);
+        let iter = self.iter_object(object).trace()?;
+
+        frame.iterators.insert(dst, iter);
+
+        Ok(frame.next_inst())
     }
 
-    fn if_(
+    fn iter_next(
         &mut self,
         frame: &mut FrameState,
-        test: usize,
-        truthy: Option<usize>,
-        falsey: Option<usize>,
+        dst: Reg,
+        iter: Reg,
+        exhausted: Pc,
     ) -> InstResult {
-        let test = frame.values[&test];
-        let inst_ix = frame.current_inst_ix;
+        let mut it = frame
+            .iterators
+            .remove(&iter)
+            .expect("iter-next on a value that is not an iterator.");
+
+        match it.next(self) {
+            Some(value) => {
+                frame.set_value(dst, value?);
+                frame.iterators.insert(iter, it);
+
+                Ok(frame.next_inst())
+            }
+
+            None => Ok(exhausted as usize),
+        }
+    }
+
+    fn if_(&mut self, frame: &mut FrameState, test: Reg, truthy: Pc, falsey: Pc) -> InstResult {
+        let test = frame.value(test);
 
         let case = if test == self.rt.singletons.true_v {
             truthy
@@ -384,98 +630,228 @@ where
             falsey
         };
 
-        let inst_ix = if let Some(dst) = case {
-            dst
-        } else {
-            inst_ix + 1
-        };
-
-        Ok(inst_ix)
+        Ok(case as usize)
     }
 
-    fn define_fn(
+    fn call_ex(
         &mut self,
         frame: &mut FrameState,
-        name: SpanRef,
-        params: &[(SpanRef, Option<usize>)],
-        returns: Option<usize>,
-        seq_id: usize,
+        dst: Reg,
+        callable: Reg,
+        arguments: &[Argument<Reg>],
     ) -> InstResult {
+        let callable = frame.value(callable);
+
+        let mut positional = vec![];
+        let mut keywords = vec![];
+
+        for argument in arguments {
+            match argument {
+                Argument::Positional(value) => positional.push(frame.value(*value)),
+
+                Argument::Starred(value) => {
+                    let mut iter = self.iter_object(frame.value(*value)).trace()?;
+
+                    while let Some(value) = iter.next(self) {
+                        positional.push(value?);
+                    }
+                }
+
+                Argument::Keyword(name, value) => {
+                    let name = self.host.spanref_to_str(*name).to_string();
+                    keywords.push((self.rt.new_string(&name), frame.value(*value)));
+                }
+
+                Argument::DoubleStarred(value) => {
+                    let mapping = frame.value(*value);
+                    let items = self.rt.objects.with_object(mapping, |this| match this {
+                        PyValue::Dict(dict) => {
+                            Some(dict.iter().map(|(_, kv)| *kv).collect::<Vec<_>>())
+                        }
+                        _ => None,
+                    });
+
+                    let items = match items {
+                        Some(items) => items,
+                        None => {
+                            return PyException::type_error()
+                                .set_message("argument after ** must be a dict")
+                                .into()
+                        }
+                    };
+
+                    for (key, value) in items {
+                        if self
+                            .rt
+                            .objects
+                            .with_object(key, |this| this.as_str().is_none())
+                        {
+                            return PyException::type_error()
+                                .set_message("keywords must be strings")
+                                .into();
+                        }
+
+                        keywords.push((key, value));
+                    }
+                }
+            }
+        }
+
+        let rv = self.call_object_with_keywords(callable, &positional, &keywords)?;
+
+        frame.set_value(dst, rv);
+
+        Ok(frame.next_inst())
+    }
+
+    fn define_fn(&mut self, frame: &mut FrameState, dst: Reg, def: u32) -> InstResult {
         let module = Rc::clone(&self.state.code);
+        let code = Rc::clone(&frame.code);
 
-        let returns = returns.map(|ix| frame.values[&ix]);
+        let FunctionDef {
+            name,
+            params,
+            returns,
+            seq_id,
+        } = &code.functions[def as usize];
+
+        let (name, seq_id) = (*name, *seq_id);
+
+        let returns = returns.map(|reg| frame.value(reg));
 
         let params = params
             .iter()
-            .map(|(var, ann)| (var.clone(), ann.map(|ix| frame.values[&ix])))
+            .map(|param| Parameter {
+                name: param.name,
+                annotation: param.annotation.map(|reg| frame.value(reg)),
+                default: param.default.map(|reg| frame.value(reg)),
+                kind: param.kind,
+            })
             .collect();
 
         let func_obj = self
             .rt
             .new_function(AnyFunc::Code { module, seq_id }, params, returns);
 
-        self.define(frame, name.group(), func_obj)?;
-        frame.values.insert(frame.current_inst_ix, func_obj);
+        if let Some(name) = name {
+            self.define(frame, name.group(), func_obj)?;
+        }
+
+        frame.set_value(dst, func_obj);
 
-        let name_as_str = self.host.spanref_to_str(name);
+        let name_as_str = name.map_or("<lambda>", |name| self.host.spanref_to_str(name));
         let name_str_obj = self.rt.new_string(name_as_str);
 
         let name_str_hash = self.rt.hash("__name__");
         let name_slot = self.rt.new_string("__name__");
 
+        let scope = self.defining_scope(frame);
+
         self.rt.objects.with_object_mut(func_obj, move |v| match v {
-            PyValue::Function { inner, .. } => inner
-                .__dict__
-                .insert(name_str_hash, (name_slot, name_str_obj)),
+            PyValue::Function { inner, parent, .. } => {
+                *parent = scope;
+
+                inner
+                    .__dict__
+                    .insert(name_str_hash, (name_slot, name_str_obj))
+            }
             _ => unreachable!(),
         });
 
         Ok(frame.next_inst())
     }
 
-    fn set_var(&mut self, frame: &mut FrameState, var: SpanRef, value: usize) -> InstResult {
-        let value = frame.values[&value];
+    fn set_var(&mut self, frame: &mut FrameState, slot: Slot, value: Reg) -> InstResult {
+        let value = frame.value(value);
 
-        self.define(frame, var.group(), value)?;
+        self.define_slot(frame, slot, value)?;
 
         Ok(frame.next_inst())
     }
 
-    fn use_var(&mut self, frame: &mut FrameState, var: SpanRef) -> InstResult {
-        let val = self.lookup(frame, &var)?;
+    fn use_var(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        slot: Slot,
+        var: SpanRef,
+    ) -> InstResult {
+        let val = match frame.local(slot) {
+            Some(val) => val,
+            None => self.lookup(frame, &var)?,
+        };
 
-        frame.values.insert(frame.current_inst_ix, val);
+        frame.set_value(dst, val);
 
         Ok(frame.next_inst())
     }
 
-    fn import(&mut self, frame: &mut FrameState, path: &[SpanRef], relative: usize) -> InstResult {
+    fn import(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        path: &[SpanRef],
+        relative: usize,
+    ) -> InstResult {
         let mref = frame.mref.clone().unwrap_or(self.state.mref);
         let module = self.import_module(mref, path, relative).trace()?;
 
-        let _ = frame.values.insert(frame.current_inst_ix, module);
+        frame.set_value(dst, module);
+
+        Ok(frame.next_inst())
+    }
+
+    fn import_from(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        module: Reg,
+        path: &[SpanRef],
+        relative: usize,
+        name: SpanRef,
+    ) -> InstResult {
+        let module = frame.value(module);
+        let attr = self.host.spanref_to_str(name).to_string();
+
+        let value = match self.getattr(module, &attr.as_str()) {
+            Ok(value) => value,
+
+            // `name` may be a submodule of a package that has not been imported yet.
+            Err(exc) if exc.is_attribute_arror() => {
+                let mref = frame.mref.unwrap_or(self.state.mref);
+                let path: Vec<_> = path.iter().cloned().chain([name]).collect();
+
+                self.import_module(mref, &path, relative)
+                    .map_err(|exc| match exc.is_import_error() {
+                        true => PyException::import_error()
+                            .set_message(format!("cannot import name '{}'", attr)),
+                        false => exc,
+                    })
+                    .trace()?
+            }
+
+            Err(exc) => return Err(exc),
+        };
+
+        frame.set_value(dst, value);
 
         Ok(frame.next_inst())
     }
 
-    fn return_(&mut self, frame: &mut FrameState, value: usize) -> InstResult {
-        let object = frame.values[&value];
+    fn return_(&mut self, frame: &mut FrameState, value: Reg) -> InstResult {
+        let object = frame.value(value);
 
         Err(PyException::return_(object))
     }
 
-    fn const_(&mut self, frame: &mut FrameState, cst: &Constant) -> InstResult {
-        let slot = frame.values.entry(frame.current_inst_ix);
-
-        let value = match cst {
+    fn const_(&mut self, frame: &mut FrameState, dst: Reg, cst: u32) -> InstResult {
+        let value = match &frame.code.consts[cst as usize] {
             Constant::None => self.rt.singletons.none_v,
             Constant::Ellipsis => self.rt.singletons.ellipsis_v,
 
             Constant::Int(n) => self.rt.new_int(*n),
 
-            Constant::Float(_f) => {
-                todo!(); /*self.rt.new_float(*f)} */
-            }
+            Constant::Float(f) => self.rt.new_float(*f),
 
             Constant::Bool(b) => {
                 if *b {
@@ -491,164 +867,838 @@ where
             }
         };
 
-        match slot {
-            Entry::Occupied(mut entry) => {
-                entry.insert(value);
-            }
-
-            Entry::Vacant(entry) => {
-                entry.insert(value);
-            }
-        }
+        frame.set_value(dst, value);
 
         Ok(frame.next_inst())
     }
 
-    fn set_dunder(
+    fn get_dunder(
         &mut self,
         frame: &mut FrameState,
-        object: usize,
+        dst: Reg,
+        object: Reg,
         dunder: Dunder,
-        value: usize,
     ) -> InstResult {
-        let object = frame.values[&object];
-        let value = frame.values[&value];
+        let object = frame.value(object);
+        let name = format!("{}", dunder);
 
-        match dunder {
-            Dunder::Unary(_) => todo!(),
-            Dunder::Infix(_) => todo!(),
+        let klass = self.rt.objects.with_object(object, |this| match this {
+            PyValue::Any(raw) => Some(raw.__class__),
+            _ => None,
+        });
 
-            Dunder::DocComment => {
-                let __doc__ = self.rt.new_string("__doc__");
-                self.setattr(object, __doc__, value).trace()?
+        // methods of user defined classes live in the class dicts.
+        let method = klass.and_then(|klass| {
+            let hash = self.rt.hash(name.as_str());
+
+            self.rt
+                .mro(klass)
+                .into_iter()
+                .find_map(|base| self.getattr_direct_hash(base, hash).ok())
+                .map(|(_, method)| method)
+        });
+
+        let method = match method {
+            Some(method) => method,
+            None => {
+                let native = self
+                    .rt
+                    .objects
+                    .with_object(object, |this| native_tables::lookup(this, &name));
+
+                match native {
+                    Some(inner) => self
+                        .rt
+                        .objects
+                        .insert(PyValue::Callable(AnyFunc::Native { inner, hook: None })),
+
+                    None => {
+                        let hash = self.rt.hash(name.as_str());
+                        return Err(PyException::attribute_error(object, hash));
+                    }
+                }
             }
+        };
 
-            Dunder::GetItem => todo!(),
-            Dunder::SetItem => todo!(),
-            Dunder::AsBool => todo!(),
-        }
+        frame.set_value(dst, method);
 
         Ok(frame.next_inst())
     }
 
-    fn build_class(&mut self, frame: &mut FrameState, sequence: usize, class: usize) -> InstResult {
-        let klass = frame.values[&class];
+    fn not(&mut self, frame: &mut FrameState, dst: Reg, value: Reg) -> InstResult {
+        let value = frame.value(value);
+
+        let negated = if value == self.rt.singletons.true_v {
+            self.rt.singletons.false_v
+        } else {
+            assert_eq!(value, self.rt.singletons.false_v);
 
-        let seq = self.state.code.sequences()[sequence].clone();
-        assert_eq!(seq.kind, SequenceType::Class);
+            self.rt.singletons.true_v
+        };
 
-        let klass_frame = FrameState::new(Some(klass), frame.mref);
-        self.exec_seq_with_frame(&seq, klass_frame)?;
+        frame.set_value(dst, negated);
 
         Ok(frame.next_inst())
     }
-}
-
-impl<'rt, 'host, H> EvaluationContext<'rt, 'host, H>
-where
-    H: RuntimeHostExt,
-{
-    #[inline]
-    pub(super) fn define(
-        &mut self,
-        frame: &mut FrameState,
-        var: u32,
-        value: ObjectId,
-    ) -> Result<Option<ObjectId>, PyException> {
-        log::trace!(
-            "[EvaluationContext::define] defining variable({:?}) := {:?}",
-            self.host.spangroup_to_str(var),
-            value
-        );
 
-        if let Some(object) = frame.frame_object.as_ref() {
-            let k = self.host.spangroup_to_str(var);
-            let k = self.rt.new_string(k);
+    fn is(&mut self, frame: &mut FrameState, dst: Reg, left: Reg, right: Reg) -> InstResult {
+        let same = if frame.value(left) == frame.value(right) {
+            self.rt.singletons.true_v
+        } else {
+            self.rt.singletons.false_v
+        };
 
-            self.setattr(*object, k, value)?;
-        }
+        frame.set_value(dst, same);
 
-        Ok(frame.locals.insert(var, value))
+        Ok(frame.next_inst())
     }
 
-    #[inline]
-    pub(super) fn lookup(&mut self, frame: &mut FrameState, var: &SpanRef) -> PyResult<ObjectId> {
-        let var_group = var.group();
-
-        match frame.locals.get(&var_group) {
-            Some(obj) => return Ok(*obj),
-            None => (),
-        }
+    fn list(&mut self, frame: &mut FrameState, dst: Reg, elements: &[Reg]) -> InstResult {
+        let elements = elements.iter().map(|reg| frame.value(*reg));
+        let list = self.rt.new_list(elements);
 
-        let var = *var;
+        frame.set_value(dst, list);
 
-        let frame_object = frame
-            .frame_object
-            .clone()
-            .unwrap_or(self.rt.singletons.builtins);
+        Ok(frame.next_inst())
+    }
 
-        let mut this = frame_object;
+    fn tuple(&mut self, frame: &mut FrameState, dst: Reg, elements: &[Reg]) -> InstResult {
+        let elements = elements.iter().map(|reg| frame.value(*reg)).collect();
+        let tuple = self.rt.objects.insert(PyValue::Tuple(elements));
 
-        let attr = self.rt.hash(self.host.spanref_to_str(var));
+        frame.set_value(dst, tuple);
 
-        let f = |val: &PyValue, this: ObjectId| -> PyResult<ObjectId> {
-            match val {
-                // all scope chains eventually resolve to a module object.
-                PyValue::Class { parent: None, .. } | PyValue::Module { .. } => {
-                    return Err(PyException::name_error(var))
-                }
+        Ok(frame.next_inst())
+    }
 
-                PyValue::Class {
-                    parent: Some(parent),
-                    inner: _,
-                    ..
-                } => {
-                    if this == *parent {
-                        // The parent of `this` is itself? that's a cycle
-                        // and also probably a bug either way its being treated as a NameError.
+    fn set(&mut self, frame: &mut FrameState, dst: Reg, elements: &[Reg]) -> InstResult {
+        let set = self.rt.objects.insert(PyValue::Set(Default::default()));
 
-                        return Err(PyException::name_error(var));
-                    }
+        frame.set_value(dst, set);
 
-                    let next = Ok(if this == frame_object { *parent } else { this });
+        for element in elements {
+            self.set_add(frame, dst, *element)?;
+        }
 
-                    return next;
-                }
+        Ok(frame.next_inst())
+    }
 
-                _ => unreachable!(),
-            }
-        };
+    fn dict(&mut self, frame: &mut FrameState, dst: Reg, items: &[(Reg, Reg)]) -> InstResult {
+        let dict = self.rt.objects.insert(PyValue::Dict(Default::default()));
 
-        loop {
-            this = self.rt.objects.with_object(this, |v| f(v, this))?;
+        frame.set_value(dst, dict);
 
-            if let Ok((_, v)) = self.getattr_direct_hash(this, attr) {
-                return Ok(v);
-            }
+        for (key, value) in items {
+            self.dict_insert(frame, dst, *key, *value)?;
         }
+
+        Ok(frame.next_inst())
     }
 
-    fn call_any_func(&mut self, func: &AnyFunc, arguments: &[ObjectId]) -> PyResult<ObjectId> {
-        log::trace!("[call_any_func] {:?}", func);
+    fn list_append(&mut self, frame: &mut FrameState, list: Reg, value: Reg) -> InstResult {
+        let value = frame.value(value);
 
-        let none_v = self.rt.singletons.none_v;
+        self.rt
+            .objects
+            .with_object_mut(frame.value(list), |this| match this {
+                PyValue::List(elements) => elements.push(value),
+                _ => unreachable!("list-append on a value that is not a list."),
+            });
 
-        match func {
-            AnyFunc::Native { inner: f, hook } => {
-                let hook_call_cx = CallCx::new(self, arguments, none_v);
-                let globals = if let Some(hook) = hook {
-                    match hook {
-                        GlobalsHook::Globals(glob) => *glob,
-                        GlobalsHook::ComputeWith(hook) => hook(hook_call_cx).trace()?,
-                    }
-                } else {
-                    none_v
-                };
+        Ok(frame.next_inst())
+    }
 
-                let call_cx = CallCx::new(self, arguments, globals);
+    fn set_add(&mut self, frame: &mut FrameState, set: Reg, value: Reg) -> InstResult {
+        let value = frame.value(value);
+        let hash = self.hash_object(value)?;
 
-                f(call_cx)
-            }
+        self.rt
+            .objects
+            .with_object_mut(frame.value(set), |this| match this {
+                PyValue::Set(elements) => {
+                    elements.entry(hash).or_insert(value);
+                }
+                _ => unreachable!("set-add on a value that is not a set."),
+            });
+
+        Ok(frame.next_inst())
+    }
+
+    fn dict_insert(
+        &mut self,
+        frame: &mut FrameState,
+        dict: Reg,
+        key: Reg,
+        value: Reg,
+    ) -> InstResult {
+        let (key, value) = (frame.value(key), frame.value(value));
+        let hash = self.hash_object(key)?;
+
+        self.rt
+            .objects
+            .with_object_mut(frame.value(dict), |this| match this {
+                PyValue::Dict(items) => {
+                    items.insert(hash, (key, value));
+                }
+                _ => unreachable!("dict-insert on a value that is not a dict."),
+            });
+
+        Ok(frame.next_inst())
+    }
+
+    fn set_dunder(
+        &mut self,
+        frame: &mut FrameState,
+        object: Reg,
+        dunder: Dunder,
+        value: Reg,
+    ) -> InstResult {
+        let object = frame.value(object);
+        let value = frame.value(value);
+
+        match dunder {
+            Dunder::Unary(_) => todo!(),
+            Dunder::Infix(_) => todo!(),
+
+            Dunder::DocComment => {
+                let __doc__ = self.rt.new_string("__doc__");
+                self.setattr(object, __doc__, value).trace()?
+            }
+
+            Dunder::GetItem => todo!(),
+            Dunder::SetItem => todo!(),
+            Dunder::AsBool => todo!(),
+            Dunder::Enter => todo!(),
+            Dunder::Exit => todo!(),
+        }
+
+        Ok(frame.next_inst())
+    }
+
+    fn raise(&mut self, frame: &mut FrameState, exc: Option<Reg>) -> InstResult {
+        let exc = match exc {
+            Some(exc) => frame.value(exc),
+            None => {
+                return match frame.exceptions.last() {
+                    Some(exc) => Err(exc.clone()),
+                    None => PyException::runtime_error()
+                        .set_message("No active exception to reraise")
+                        .into(),
+                }
+            }
+        };
+
+        let is_class = self
+            .rt
+            .objects
+            .with_object(exc, |this| matches!(this, PyValue::Class { .. }));
+
+        let exc = if is_class {
+            self.call_object(exc, &[]).trace()?
+        } else {
+            exc
+        };
+
+        let klass = self.rt.class_of(exc);
+
+        if let Some(base_exception) = self.builtin_class("BaseException") {
+            if !self.rt.is_subclass(klass, base_exception) {
+                return PyException::type_error()
+                    .set_message("exceptions must derive from BaseException")
+                    .into();
+            }
+        }
+
+        let class_name = self.rt.objects.with_object(klass, |this| match this {
+            PyValue::Class {
+                name: Some(name), ..
+            } => Some(*name),
+            _ => None,
+        });
+
+        let class_name = match class_name {
+            Some(name) => self.host.spanref_to_str(name).to_owned(),
+            None => "Exception".to_owned(),
+        };
+
+        let exception = PyException::raised(exc, &class_name);
+
+        match self.exception_message(exc) {
+            Some(message) => Err(exception.set_message(message)),
+            None => Err(exception),
+        }
+    }
+
+    fn get_exception(&mut self, frame: &mut FrameState, dst: Reg) -> InstResult {
+        let exc = frame
+            .exceptions
+            .last()
+            .cloned()
+            .expect("get-exception outside of an exception handler.");
+
+        let object = match exc.object() {
+            Some(object) => object,
+            None => {
+                // Runtime raised exceptions are only turned into Python objects once they get caught.
+                let klass = match self.builtin_class(exc.kind()) {
+                    Some(klass) => klass,
+                    None => return Err(exc),
+                };
+
+                let object = self.instantiate(klass, &[], &[]).trace()?;
+                let message = self.rt.new_string(exc.message().unwrap_or_default());
+                let args = self.rt.new_list([message]);
+                let args_key = self.rt.new_string("args");
+
+                self.setattr(object, args_key, args).trace()?;
+
+                let mut caught = PyException::raised(object, exc.kind());
+                caught.message = exc.message;
+                caught.trace = exc.trace;
+
+                *frame.exceptions.last_mut().unwrap() = caught;
+
+                object
+            }
+        };
+
+        frame.set_value(dst, object);
+
+        Ok(frame.next_inst())
+    }
+
+    fn exc_match(&mut self, frame: &mut FrameState, dst: Reg, exc: Reg, kind: Reg) -> InstResult {
+        let exc = frame.value(exc);
+        let kind = frame.value(kind);
+
+        let is_class = self
+            .rt
+            .objects
+            .with_object(kind, |this| matches!(this, PyValue::Class { .. }));
+
+        if !is_class {
+            return PyException::type_error()
+                .set_message(
+                    "catching classes that do not inherit from BaseException is not allowed",
+                )
+                .into();
+        }
+
+        let matched = if self.rt.is_subclass(self.rt.class_of(exc), kind) {
+            self.rt.singletons.true_v
+        } else {
+            self.rt.singletons.false_v
+        };
+
+        frame.set_value(dst, matched);
+
+        Ok(frame.next_inst())
+    }
+
+    fn class_of(&mut self, frame: &mut FrameState, dst: Reg, object: Reg) -> InstResult {
+        let klass = self.rt.class_of(frame.value(object));
+
+        frame.set_value(dst, klass);
+
+        Ok(frame.next_inst())
+    }
+
+    fn build_class(&mut self, frame: &mut FrameState, seq_id: usize, class: Reg) -> InstResult {
+        let klass = frame.value(class);
+
+        let module = Rc::clone(&self.state.code);
+        assert_eq!(module.sequences()[seq_id].kind, SequenceType::Class);
+
+        let code = self.rt.code_object(&module, seq_id);
+        let klass_frame = FrameState::new(code, Some(klass), frame.mref);
+        self.exec_frame(klass_frame)?;
+
+        Ok(frame.next_inst())
+    }
+}
+
+impl<'rt, 'host, H> EvaluationContext<'rt, 'host, H>
+where
+    H: RuntimeHostExt,
+{
+    /// How many ticks elapse between checks of the deadline.
+    const DEADLINE_CHECK_INTERVAL: u64 = 512;
+
+    #[inline]
+    pub(super) fn define(
+        &mut self,
+        frame: &mut FrameState,
+        var: u32,
+        value: ObjectId,
+    ) -> Result<Option<ObjectId>, PyException> {
+        log::trace!(
+            "[EvaluationContext::define] defining variable({:?}) := {:?}",
+            self.host.spangroup_to_str(var),
+            value
+        );
+
+        if let Some(object) = frame.frame_object.as_ref() {
+            let k = self.host.spangroup_to_str(var);
+            let k = self.rt.new_string(k);
+
+            self.setattr(*object, k, value)?;
+        }
+
+        // variables the code never refers to don't get a slot.
+        let previous = match frame.code.slot_of(var) {
+            Some(slot) => frame.locals[slot as usize].replace(value),
+            None => None,
+        };
+
+        Ok(previous)
+    }
+
+    /// Like `define` but for a variable the code has already resolved to a slot.
+    #[inline]
+    pub(super) fn define_slot(
+        &mut self,
+        frame: &mut FrameState,
+        slot: Slot,
+        value: ObjectId,
+    ) -> Result<Option<ObjectId>, PyException> {
+        let var = frame.code.names[slot as usize].group();
+
+        self.define(frame, var, value)
+    }
+
+    /// The scope functions and classes defined in `frame` resolve their free variables in.
+    ///
+    /// Like in Python the body of a class is not a scope of the functions defined in it.
+    fn defining_scope(&self, frame: &FrameState) -> Option<ObjectId> {
+        match frame.frame_object {
+            Some(object) => self.rt.objects.with_object(object, |this| match this {
+                PyValue::Class {
+                    name: Some(_),
+                    parent,
+                    ..
+                } => *parent,
+                _ => Some(object),
+            }),
+
+            None => frame.enclosing,
+        }
+    }
+
+    #[inline]
+    pub(super) fn lookup(&mut self, frame: &mut FrameState, var: &SpanRef) -> PyResult<ObjectId> {
+        if let Some(obj) = frame
+            .code
+            .slot_of(var.group())
+            .and_then(|slot| frame.local(slot))
+        {
+            return Ok(obj);
+        }
+
+        let var = *var;
+        let attr = self.rt.hash(self.host.spanref_to_str(var));
+
+        // walk the enclosing scopes outwards, all scope chains end at a module.
+        let mut scope = frame.frame_object.or(frame.enclosing);
+
+        while let Some(this) = scope {
+            if let Ok((_, v)) = self.getattr_direct_hash(this, attr) {
+                return Ok(v);
+            }
+
+            scope = self.rt.objects.with_object(this, |v| match v {
+                // The parent of `this` is itself? that's a cycle and also probably a bug.
+                PyValue::Class {
+                    parent: Some(parent),
+                    ..
+                } if *parent != this => Some(*parent),
+
+                _ => None,
+            });
+        }
+
+        let builtins = self.rt.singletons.builtins;
+
+        if !builtins.is_uninit() {
+            if let Ok((_, v)) = self.getattr_direct_hash(builtins, attr) {
+                return Ok(v);
+            }
+        }
+
+        Err(PyException::name_error(var))
+    }
+
+    /// Look up a class defined in the builtins module by name.
+    fn builtin_class(&mut self, name: &str) -> Option<ObjectId> {
+        let builtins = self.rt.singletons.builtins;
+
+        if builtins.is_uninit() {
+            return None;
+        }
+
+        let hash = self.rt.hash(name);
+        let (_, klass) = self.getattr_direct_hash(builtins, hash).ok()?;
+
+        self.rt
+            .objects
+            .with_object(klass, |this| matches!(this, PyValue::Class { .. }))
+            .then(|| klass)
+    }
+
+    /// Bind the positional `arguments` and `keywords` of a call to the `params` of a function.
+    ///
+    /// Missing parameters take their default, excess positional arguments are collected
+    /// into a list for `*args` and excess keywords into a dict for `**kwargs`.
+    ///
+    fn bind_arguments(
+        &mut self,
+        params: &[Parameter<ObjectId>],
+        func: &RawObject,
+        arguments: &[ObjectId],
+        keywords: &[(ObjectId, ObjectId)],
+    ) -> PyResult<Vec<(SpanRef, ObjectId)>> {
+        let name = {
+            let hash = self.rt.hash("__name__");
+
+            func.__dict__
+                .get(hash)
+                .and_then(|(_, name)| {
+                    self.rt
+                        .objects
+                        .with_object(name, |this| this.as_str().map(ToString::to_string))
+                })
+                .unwrap_or_else(|| "<function>".into())
+        };
+
+        let mut bound: Vec<Option<ObjectId>> = vec![None; params.len()];
+
+        let positional = params
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, param)| param.kind.is_positional().then(|| ix))
+            .collect::<Vec<_>>();
+
+        let var_positional = params
+            .iter()
+            .position(|param| param.kind == ParameterKind::VarPositional);
+
+        let var_keyword = params
+            .iter()
+            .position(|param| param.kind == ParameterKind::VarKeyword);
+
+        if arguments.len() > positional.len() && var_positional.is_none() {
+            return PyException::type_error()
+                .set_message(format!(
+                    "{}() takes {} positional arguments but {} were given",
+                    name,
+                    positional.len(),
+                    arguments.len()
+                ))
+                .into();
+        }
+
+        for (ix, argument) in positional.iter().zip(arguments.iter()) {
+            bound[*ix] = Some(*argument);
+        }
+
+        let excess_arguments = arguments.iter().skip(positional.len()).copied();
+        let mut excess_keywords = vec![];
+
+        for (key, value) in keywords.iter().copied() {
+            let key_str = self
+                .rt
+                .objects
+                .with_object(key, |this| this.as_str().map(ToString::to_string))
+                .unwrap();
+
+            let param = params.iter().position(|param| {
+                !param.kind.is_variadic()
+                    && param.kind != ParameterKind::PositionalOnly
+                    && self.host.spanref_to_str(param.name) == key_str
+            });
+
+            match (param, var_keyword) {
+                (Some(ix), _) if bound[ix].is_some() => {
+                    return PyException::type_error()
+                        .set_message(format!(
+                            "{}() got multiple values for argument '{}'",
+                            name, key_str
+                        ))
+                        .into();
+                }
+
+                (Some(ix), _) => bound[ix] = Some(value),
+                (None, Some(_)) => excess_keywords.push((key, value)),
+                (None, None) => {
+                    return PyException::type_error()
+                        .set_message(format!(
+                            "{}() got an unexpected keyword argument '{}'",
+                            name, key_str
+                        ))
+                        .into();
+                }
+            }
+        }
+
+        if let Some(ix) = var_positional {
+            bound[ix] = Some(self.rt.new_list(excess_arguments));
+        }
+
+        if let Some(ix) = var_keyword {
+            let mut dict = PyDictRaw::default();
+
+            for (key, value) in excess_keywords {
+                let hash = self.hash_object(key)?;
+                dict.insert(hash, (key, value));
+            }
+
+            bound[ix] = Some(self.rt.objects.insert(PyValue::Dict(dict)));
+        }
+
+        let mut frame_args = Vec::with_capacity(params.len());
+
+        for (param, value) in params.iter().zip(bound) {
+            match value.or(param.default) {
+                Some(value) => frame_args.push((param.name, value)),
+                None => {
+                    let kind = match param.kind {
+                        ParameterKind::KeywordOnly => "keyword-only",
+                        _ => "positional",
+                    };
+
+                    return PyException::type_error()
+                        .set_message(format!(
+                            "{}() missing required {} argument: '{}'",
+                            name,
+                            kind,
+                            self.host.spanref_to_str(param.name)
+                        ))
+                        .into();
+                }
+            }
+        }
+
+        Ok(frame_args)
+    }
+
+    /// Create a new instance of a user defined class and call its `__init__` if it has one.
+    fn instantiate(
+        &mut self,
+        klass: ObjectId,
+        arguments: &[ObjectId],
+        keywords: &[(ObjectId, ObjectId)],
+    ) -> PyResult<ObjectId> {
+        if self.builtin_class("range") == Some(klass) {
+            return self.new_range(klass, arguments, keywords);
+        }
+
+        let object = self.rt.objects.insert(PyValue::Any(RawObject {
+            alloc_id: Default::default(),
+            __dict__: Default::default(),
+            __class__: klass,
+        }));
+
+        let __init__ = self.rt.hash("__init__");
+
+        let init = self
+            .rt
+            .mro(klass)
+            .into_iter()
+            .find_map(|base| self.getattr_direct_hash(base, __init__).ok());
+
+        match init {
+            Some((_, init)) => {
+                let arguments = std::iter::once(object)
+                    .chain(arguments.iter().copied())
+                    .collect::<Vec<_>>();
+
+                self.call_object_with_keywords(init, &arguments, keywords)
+                    .trace()?;
+            }
+
+            None if !keywords.is_empty() => {
+                return PyException::type_error()
+                    .set_message("object takes no keyword arguments")
+                    .into()
+            }
+
+            None => match self.builtin_class("BaseException") {
+                Some(base_exception) if self.rt.is_subclass(klass, base_exception) => {
+                    let args = self.rt.new_list(arguments.iter().copied());
+                    let args_key = self.rt.new_string("args");
+
+                    self.setattr(object, args_key, args).trace()?;
+                }
+
+                _ if !arguments.is_empty() => {
+                    return PyException::type_error()
+                        .set_message("object takes no arguments")
+                        .into()
+                }
+
+                _ => (),
+            },
+        }
+
+        Ok(object)
+    }
+
+    /// Construct a `range` object, called as `range(stop)` or `range(start, stop[, step])`.
+    fn new_range(
+        &mut self,
+        klass: ObjectId,
+        arguments: &[ObjectId],
+        keywords: &[(ObjectId, ObjectId)],
+    ) -> PyResult<ObjectId> {
+        if !keywords.is_empty() {
+            return PyException::type_error()
+                .set_message("range() takes no keyword arguments")
+                .into();
+        }
+
+        let mut ints = Vec::with_capacity(arguments.len());
+
+        for argument in arguments {
+            ints.push(self.index_int(*argument).trace()?);
+        }
+
+        let (start, stop, step) = match ints.as_slice() {
+            [stop] => (0, *stop, 1),
+            [start, stop] => (*start, *stop, 1),
+            [start, stop, step] => (*start, *stop, *step),
+            [] => {
+                return PyException::type_error()
+                    .set_message("range expected at least 1 argument, got 0")
+                    .into()
+            }
+            _ => {
+                return PyException::type_error()
+                    .set_message(format!(
+                        "range expected at most 3 arguments, got {}",
+                        ints.len()
+                    ))
+                    .into()
+            }
+        };
+
+        if step == 0 {
+            return PyException::value_error()
+                .set_message("range() arg 3 must not be zero")
+                .into();
+        }
+
+        let object = self.rt.objects.insert(PyValue::Any(RawObject {
+            alloc_id: Default::default(),
+            __dict__: Default::default(),
+            __class__: klass,
+        }));
+
+        for (name, n) in [("start", start), ("stop", stop), ("step", step)] {
+            let key = self.rt.new_string(name);
+            let value = self.rt.new_int(n);
+
+            self.setattr(object, key, value).trace()?;
+        }
+
+        Ok(object)
+    }
+
+    /// The `start`, `stop` and `step` of a `range` object.
+    fn range_bounds(&mut self, range: ObjectId) -> PyResult<(i64, i64, i64)> {
+        let mut bounds = [0; 3];
+
+        for (bound, name) in bounds.iter_mut().zip(["start", "stop", "step"]) {
+            let value = self.getattr(range, &name).trace()?;
+            *bound = self.index_int(value).trace()?;
+        }
+
+        match bounds {
+            [_, _, 0] => PyException::value_error()
+                .set_message("range() arg 3 must not be zero")
+                .into(),
+            [start, stop, step] => Ok((start, stop, step)),
+        }
+    }
+
+    /// The value of an int (or bool) `object`, raising a `TypeError` for anything else.
+    fn index_int(&mut self, object: ObjectId) -> PyResult<i64> {
+        let n = self.rt.objects.with_object(object, |this| match this {
+            PyValue::Int(n) => Some(*n),
+            PyValue::Bool(b) => Some(*b as i64),
+            _ => None,
+        });
+
+        match n {
+            Some(n) => Ok(n),
+            None => PyException::type_error()
+                .set_message(format!(
+                    "'{}' object cannot be interpreted as an integer",
+                    self.type_name(object)
+                ))
+                .into(),
+        }
+    }
+
+    /// The name of the type of `object` for error messages, instances are named after their class.
+    fn type_name(&mut self, object: ObjectId) -> String {
+        let class = self.rt.objects.with_object(object, |this| match this {
+            PyValue::Any(raw) => Ok(raw.__class__),
+            _ => Err(this.type_name()),
+        });
+
+        let class = match class {
+            Ok(class) => class,
+            Err(name) => return name.to_owned(),
+        };
+
+        let name = self.rt.objects.with_object(class, |this| match this {
+            PyValue::Class {
+                name: Some(name), ..
+            } => Some(*name),
+            _ => None,
+        });
+
+        name.map_or_else(
+            || "object".to_owned(),
+            |name| self.host.spanref_to_str(name).to_owned(),
+        )
+    }
+
+    /// The message of an exception object, its first argument if that is a string.
+    fn exception_message(&mut self, exc: ObjectId) -> Option<Box<str>> {
+        let args = self.getattr(exc, &"args").ok()?;
+
+        let first = self.rt.objects.with_object(args, |this| match this {
+            PyValue::List(args) => args.iter().next().copied(),
+            _ => None,
+        })?;
+
+        self.rt.objects.with_object(first, |this| match this {
+            PyValue::Str(st) => Some(st.clone()),
+            _ => None,
+        })
+    }
+
+    fn call_any_func(&mut self, func: &AnyFunc, arguments: &[ObjectId]) -> PyResult<ObjectId> {
+        log::trace!("[call_any_func] {:?}", func);
+
+        let none_v = self.rt.singletons.none_v;
+
+        match func {
+            AnyFunc::Native { inner: f, hook } => {
+                let hook_call_cx = CallCx::new(self, arguments, none_v);
+                let globals = if let Some(hook) = hook {
+                    match hook {
+                        GlobalsHook::Globals(glob) => *glob,
+                        GlobalsHook::ComputeWith(hook) => hook(hook_call_cx).trace()?,
+                    }
+                } else {
+                    none_v
+                };
+
+                let call_cx = CallCx::new(self, arguments, globals);
+
+                f(call_cx)
+            }
 
             AnyFunc::Boxed { inner: f, hook } => {
                 let hook_call_cx = CallCx::new(self, arguments, none_v);
@@ -667,37 +1717,74 @@ where
             }
 
             AnyFunc::Code { module, seq_id } => {
-                let seq = &module.sequences()[*seq_id];
-                let frame = FrameState::new(None, Some(module.mref));
+                let code = self.rt.code_object(module, *seq_id);
+                let frame = FrameState::new(code, None, Some(module.mref));
 
-                self.exec_seq_with_frame(seq, frame)
+                self.exec_frame(frame)
             }
         }
     }
 
-    pub fn exec_seq_with_frame(
-        &mut self,
-        seq: &montyc_flatcode::FlatSeq,
-        mut frame: FrameState,
-    ) -> PyResult<ObjectId> {
-        while let Some(inst) = seq.inst().get(frame.current_inst_ix) {
-            frame.current_inst_ix = match self.exec_inst(&mut frame, inst) {
-                Ok(ip) => ip,
+    /// Execute the code of `frame` until it returns or runs off the end, the dispatch loop of the evaluator.
+    pub fn exec_frame(&mut self, mut frame: FrameState) -> PyResult<ObjectId> {
+        let boundary = self.rt.objects.next_object_id();
+        let code = Rc::clone(&frame.code);
+
+        while let Some(op) = code.ops.get(frame.current_inst_ix) {
+            let result = self
+                .safepoint(&frame, boundary)
+                .and_then(|()| self.exec_inst(&mut frame, op));
+
+            frame.current_inst_ix = match result {
+                Ok(pc) => pc,
 
                 Err(PyException {
                     inner: InnerExc::Return(rv),
                     ..
                 }) => return Ok(rv),
 
-                Err(exc) => return Err(exc),
+                Err(exc) => frame.catch(exc).map_err(|exc| self.unwind(exc, &frame))?,
             };
-
-            self.tick()?;
         }
 
         Ok(self.rt.singletons.none_v)
     }
 
+    /// Record the frame `exc` is escaping from, along with the span of the op it was executing.
+    fn unwind(&self, exc: PyException, frame: &FrameState) -> PyException {
+        let code = &frame.code;
+
+        let span = code.spans.get(frame.current_inst_ix).cloned().flatten();
+
+        let name = code.name.map(|name| self.host.spanref_to_str(name).into());
+
+        exc.push_frame(PyFrame {
+            mref: code.mref,
+            name,
+            span,
+        })
+    }
+
+    /// Called between two instructions of `frame`, accounts for a tick and collects garbage under allocation pressure.
+    ///
+    /// Only the objects allocated at or after `boundary`, when the frame started executing, are collected.
+    ///
+    #[inline]
+    fn safepoint(&mut self, frame: &FrameState, boundary: ObjectId) -> PyResult<()> {
+        self.tick()?;
+
+        if self.rt.under_pressure() {
+            let mut roots = vec![];
+
+            frame.refs_with(&mut roots);
+
+            self.rt.collect_young(&roots, boundary);
+            self.rt.check_heap_limit()?;
+        }
+
+        Ok(())
+    }
+
     #[inline]
     fn tick(&mut self) -> Result<(), PyException> {
         match self.n_ticks.map(NonZeroU64::get) {
@@ -709,6 +1796,17 @@ where
             None => (),
         };
 
+        self.ticks_elapsed = self.ticks_elapsed.wrapping_add(1);
+
+        // reading the clock is comparatively expensive, only do it every so often.
+        if self.ticks_elapsed % Self::DEADLINE_CHECK_INTERVAL == 0 {
+            if let Some(deadline) = self.deadline {
+                if Instant::now() >= deadline {
+                    return Err(PyException::deadline());
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -732,28 +1830,61 @@ where
     }
 
     pub(crate) fn compile_from_spec(&mut self, spec: ObjectId) -> PyResult<ObjectId> {
-        let mut inner = RawObject::default();
-
-        let name = self.getattr(spec, &"name").trace()?;
         let loader = self.getattr(spec, &"loader").trace()?;
-        let get_data = self.new_string("get_data").trace()?;
 
-        let source = self.call_method_object(loader, get_data, &[]).trace()?;
-
-        let source = self
-            .runtime_mut()
-            .objects
-            .with_object(source, |this| match this {
-                PyValue::Str(st) => st.clone(),
-                _ => unreachable!(),
-            });
+        // namespace packages have no loader and an empty body.
+        let source = if loader == self.rt.singletons.none_v {
+            String::new()
+        } else {
+            let get_data = self.new_string("get_data").trace()?;
+            let source = self.call_method_object(loader, get_data, &[]).trace()?;
+
+            self.runtime_mut()
+                .objects
+                .with_object(source, |this| match this {
+                    PyValue::Str(st) => st.to_string(),
+                    _ => unreachable!(),
+                })
+        };
 
         let code = self
             .host
             .as_accept_input()
             .accept_input(&source)
             .map(Rc::new)
-            .unwrap();
+            .map_err(|err| PyException::import_error().set_message(format!("{:?}", err)))?;
+
+        self.module_from_spec(spec, code)
+    }
+
+    /// Create the (not yet executed) module object for `code` that was loaded from `spec`.
+    ///
+    /// The module's `__package__`, `__path__` and `__file__` come from the `parent`,
+    /// `submodule_search_locations` and `origin` of the spec when it has them.
+    ///
+    pub fn module_from_spec(&mut self, spec: ObjectId, code: Rc<FlatCode>) -> PyResult<ObjectId> {
+        fn getattr_opt<E: EvalGlue>(
+            ecx: &mut E,
+            spec: ObjectId,
+            attr: &str,
+        ) -> PyResult<Option<ObjectId>> {
+            let none_v = ecx.runtime().singletons.none_v;
+
+            match ecx.getattr(spec, &attr) {
+                Ok(value) if value == none_v => Ok(None),
+                Ok(value) => Ok(Some(value)),
+                Err(exc) if exc.is_attribute_arror() => Ok(None),
+                Err(exc) => Err(exc),
+            }
+        }
+
+        let mut inner = RawObject::default();
+
+        let name = self.getattr(spec, &"name").trace()?;
+        let loader = self.getattr(spec, &"loader").trace()?;
+        let package = getattr_opt(self, spec, "parent").trace()?;
+        let path = getattr_opt(self, spec, "submodule_search_locations").trace()?;
+        let origin = getattr_opt(self, spec, "origin").trace()?;
 
         let mref = code.mref;
 
@@ -763,7 +1894,8 @@ where
             let module_init_dict = [
                 self.rt.make_kv_pair("__name__", |_| name),
                 self.rt.make_kv_pair("__doc__", |_| none_v),
-                // self.rt.make_kv_pair("__package__", |_| none_v),
+                self.rt
+                    .make_kv_pair("__package__", |_| package.unwrap_or(none_v)),
                 self.rt.make_kv_pair("__loader__", |_| loader),
                 self.rt.make_kv_pair("__spec__", |_| spec),
             ];
@@ -772,12 +1904,18 @@ where
                 inner.__dict__.insert(h, (k, v));
             }
 
-            inner.__class__ = self.rt.singletons.module_class;
+            // only packages have a `__path__`.
+            if let Some(path) = path {
+                let (h, k, v) = self.rt.make_kv_pair("__path__", |_| path);
+                inner.__dict__.insert(h, (k, v));
+            }
 
-            // rt.objects.with_object_mut(sys_modules, |this| match this {
-            //     PyValue::Dict(map) => map.insert(name_hash, (name, none_v)),
-            //     val => todo!("{:?}", val),
-            // });
+            if let Some(origin) = origin {
+                let (h, k, v) = self.rt.make_kv_pair("__file__", |_| origin);
+                inner.__dict__.insert(h, (k, v));
+            }
+
+            inner.__class__ = self.rt.singletons.module_class;
 
             self.rt.objects.insert_with(|_| PyValue::Module {
                 mkey: ModuleKey::User(mref),
@@ -795,6 +1933,90 @@ where
 
         Ok(module)
     }
+
+    /// Execute the body of a module created by `module_from_spec` in its own namespace.
+    pub fn exec_module(&mut self, module: ObjectId) -> PyResult<ObjectId> {
+        let mref = self.rt.objects.with_object(module, |this| match this {
+            PyValue::Module {
+                mkey: ModuleKey::User(mref),
+                ..
+            } => Ok(*mref),
+            _ => PyException::type_error()
+                .set_message("can only execute user modules")
+                .into(),
+        })?;
+
+        let code = self
+            .rt
+            .module_objects
+            .get(&mref)
+            .and_then(|meta| meta.code.clone())
+            .ok_or_else(|| PyException::import_error().set_message("module has no code"))?;
+
+        if code.sequences().is_empty() {
+            return Ok(module);
+        }
+
+        let frame = self.module_frame(&code, module)?;
+
+        self.exec_frame(frame)?;
+
+        Ok(module)
+    }
+
+    /// A frame for executing the module-level sequence of `code` with the builtins already defined.
+    fn module_frame(&mut self, code: &Rc<FlatCode>, module: ObjectId) -> PyResult<FrameState> {
+        let mref = code.mref();
+        let module_code = self.rt.code_object(code, 0);
+        let mut frame = FrameState::new(module_code, Some(module), Some(mref));
+
+        if !self.rt.singletons.none_v.is_uninit() {
+            const BUILTINS: &[&str] = &["int", "bool", "str"];
+
+            if self.rt.singletons.builtins.is_uninit() {
+                log::warn!("builtins is unit, only defining int, bool, and str.");
+
+                let builtin_values = [
+                    self.rt.singletons.int_class,
+                    self.rt.singletons.bool_class,
+                    self.rt.singletons.string_class,
+                ];
+
+                for (name, value) in BUILTINS.iter().zip(builtin_values) {
+                    if value.is_uninit() {
+                        continue;
+                    }
+
+                    let hash = self.rt.hash(name);
+
+                    let name = self.host.spangroup_of_hash(hash, name);
+
+                    self.define(&mut frame, name, value)?;
+                }
+            } else if self.rt.singletons.builtins != self.rt.singletons.none_v {
+                let builtins_dict =
+                    self.rt
+                        .objects
+                        .with_object(self.rt.singletons.builtins, |builtins| match builtins {
+                            PyValue::Module { inner, .. } => inner.__dict__.clone(),
+                            _ => unreachable!(),
+                        });
+
+                for (hash, (key, value)) in builtins_dict.iter() {
+                    let name = self.rt.objects.with_object(*key, |k| match k {
+                        PyValue::Str(st) => st.clone(),
+                        _ => unreachable!(),
+                    });
+
+                    let name = self.host.spangroup_of_hash(*hash, &name);
+
+                    self.define(&mut frame, name, *value)?;
+                }
+            }
+        }
+
+        Ok(frame)
+    }
 }
 
 impl<'rt, 'host, H> EvaluationContext<'rt, 'host, H>
@@ -819,6 +2041,16 @@ where
         self
     }
 
+    /// Set the wall-clock deadline of this evaluation, or None if unbounded.
+    ///
+    /// The clock is only checked every few hundred ticks so execution may run
+    /// slightly past the deadline before an `ExceededDeadline` exception is thrown.
+    ///
+    pub fn set_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
     pub fn from_module_import<I, S>(mut self, module: ModuleRef, names: I) -> Self
     where
         I: IntoIterator<Item = S>,
@@ -847,62 +2079,16 @@ where
             .ok_or_else(|| todo!("Module does not exist..."))?
             .alloc;
 
-        let (module_seq, _) = match code.sequences() {
-            [] => return Ok(module),
-            [module_seq, rest @ ..] => (module_seq, rest),
-        };
+        if code.sequences().is_empty() {
+            return Ok(module);
+        }
 
-        let mut frame = FrameState::new(Some(module), Some(mref));
+        let mut frame = self.module_frame(&code, module)?;
 
         let mut preamble = vec![];
 
         std::mem::swap(&mut preamble, &mut self.preamble);
 
-        if !self.rt.singletons.none_v.is_uninit() {
-            const BUILTINS: &[&str] = &["int", "bool", "str"];
-
-            if self.rt.singletons.builtins.is_uninit() {
-                log::warn!("builtins is unit, only defining int, bool, and str.");
-
-                let builtin_values = [
-                    self.rt.singletons.int_class,
-                    self.rt.singletons.bool_class,
-                    self.rt.singletons.string_class,
-                ];
-
-                for (name, value) in BUILTINS.iter().zip(builtin_values) {
-                    if value.is_uninit() {
-                        continue;
-                    }
-
-                    let hash = self.rt.hash(name);
-
-                    let name = self.host.spangroup_of_hash(hash, name);
-
-                    self.define(&mut frame, name, value)?;
-                }
-            } else if self.rt.singletons.builtins != self.rt.singletons.none_v {
-                let builtins_dict =
-                    self.rt
-                        .objects
-                        .with_object(self.rt.singletons.builtins, |builtins| match builtins {
-                            PyValue::Module { inner, .. } => inner.__dict__.clone(),
-                            _ => unreachable!(),
-                        });
-
-                for (hash, (key, value)) in builtins_dict.iter() {
-                    let name = self.rt.objects.with_object(*key, |k| match k {
-                        PyValue::Str(st) => st.clone(),
-                        _ => unreachable!(),
-                    });
-
-                    let name = self.host.spangroup_of_hash(*hash, &name);
-
-                    self.define(&mut frame, name, *value)?;
-                }
-            }
-        }
-
         for (mref, names) in preamble {
             let module = self
                 .rt
@@ -922,20 +2108,7 @@ where
             }
         }
 
-        while let Some(inst) = module_seq.inst().get(frame.current_inst_ix) {
-            frame.current_inst_ix = match self.exec_inst(&mut frame, inst) {
-                Ok(ip) => ip,
-
-                Err(PyException {
-                    inner: InnerExc::Return(_obj),
-                    ..
-                }) => unreachable!("Return in module body."),
-
-                Err(exc) => return Err(exc),
-            };
-
-            self.tick()?;
-        }
+        self.exec_frame(frame)?;
 
         Ok(module)
     }
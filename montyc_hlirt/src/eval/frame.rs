@@ -1,44 +1,126 @@
+use std::rc::Rc;
+
 use ahash::AHashMap;
 use montyc_core::ModuleRef;
 
-use crate::object::ObjectId;
+use crate::exception::PyException;
+use crate::object::{ObjectId, PyIter};
 
-// -- FrameState
+use super::bytecode::{CodeObject, Reg, Slot};
 
-type SpanGroup = u32;
+// -- FrameState
 
-type FrameValues = AHashMap<usize, ObjectId>;
-type FrameLocals = AHashMap<SpanGroup /* group of SpanRef */, ObjectId>;
+type FrameIterators = AHashMap<Reg, PyIter>;
+type FrameHandlers = Vec<(usize, usize)>;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct FrameState {
+    /// The code being executed.
+    pub(super) code: Rc<CodeObject>,
+
     /// The underlying namespace object.
     pub(super) frame_object: Option<ObjectId>,
 
-    /// A value which is read-only when inside of an `InstExec` handler but gets written to in the main driver.
+    /// The scope of the function being executed, where variables that aren't locals are looked up.
+    pub(super) enclosing: Option<ObjectId>,
+
+    /// The pc of the op being executed, read-only when inside of an `InstExec` handler but gets written to in the main driver.
     pub(super) current_inst_ix: usize,
 
-    // Result values produced by the flatcode.
-    pub(super) values: FrameValues,
+    // Result values produced by the ops, indexed by register.
+    pub(super) values: Box<[Option<ObjectId>]>,
+
+    // Frame locals, indexed by slot.
+    pub(super) locals: Box<[Option<ObjectId>]>,
+
+    // Live iterators produced by `get-iter` ops, keyed by the register of the op.
+    pub(super) iterators: FrameIterators,
+
+    // Handlers pushed by `setup-except` as (handler pc, depth of `exceptions` when pushed.)
+    pub(super) handlers: FrameHandlers,
 
-    // Frame locals.
-    pub(super) locals: FrameLocals,
+    // The exceptions currently being handled, innermost last.
+    pub(super) exceptions: Vec<PyException>,
 
     /// The semantic module of the current frame, used to calculate imports.
     pub(super) mref: Option<ModuleRef>,
 }
 
 impl FrameState {
-    pub fn new(frame_object: Option<ObjectId>, mref: Option<ModuleRef>) -> Self {
+    pub fn new(
+        code: Rc<CodeObject>,
+        frame_object: Option<ObjectId>,
+        mref: Option<ModuleRef>,
+    ) -> Self {
         Self {
+            values: vec![None; code.n_registers].into_boxed_slice(),
+            locals: vec![None; code.names.len()].into_boxed_slice(),
+            code,
             frame_object,
+            enclosing: None,
+            current_inst_ix: 0,
+            iterators: Default::default(),
+            handlers: Default::default(),
+            exceptions: Default::default(),
             mref,
-            ..Default::default()
         }
     }
 
+    /// The value in register `reg`.
+    #[inline]
+    #[track_caller]
+    pub(super) fn value(&self, reg: Reg) -> ObjectId {
+        self.values[reg as usize].expect("read of a register that was never written to.")
+    }
+
+    #[inline]
+    pub(super) fn set_value(&mut self, reg: Reg, value: ObjectId) {
+        self.values[reg as usize] = Some(value);
+    }
+
+    /// The value of the local in `slot`, if it has been assigned.
+    #[inline]
+    pub(super) fn local(&self, slot: Slot) -> Option<ObjectId> {
+        self.locals[slot as usize]
+    }
+
     #[inline]
     pub fn next_inst(&self) -> usize {
         self.current_inst_ix.saturating_add(1)
     }
+
+    /// Extend `bucket` with every object this frame refers to, these are the frame's garbage collection roots.
+    pub(crate) fn refs_with(&self, bucket: &mut Vec<ObjectId>) {
+        bucket.extend(self.frame_object);
+        bucket.extend(self.enclosing);
+        bucket.extend(self.values.iter().flatten());
+        bucket.extend(self.locals.iter().flatten());
+
+        for iter in self.iterators.values() {
+            iter.refs_with(bucket);
+        }
+
+        for exc in self.exceptions.iter() {
+            exc.refs_with(bucket);
+        }
+    }
+
+    /// Jump to the innermost exception handler or hand the exception back if there is none.
+    #[inline]
+    pub(super) fn catch(&mut self, exc: PyException) -> Result<usize, PyException> {
+        if !exc.is_catchable() {
+            return Err(exc);
+        }
+
+        match self.handlers.pop() {
+            Some((handler, depth)) => {
+                self.exceptions.truncate(depth);
+                self.exceptions.push(exc);
+
+                Ok(handler)
+            }
+
+            None => Err(exc),
+        }
+    }
 }
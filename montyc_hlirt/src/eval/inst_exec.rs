@@ -1,166 +1,287 @@
-use montyc_core::ast::Constant;
 use montyc_core::SpanRef;
-use montyc_flatcode::raw_inst::{Dunder, RawInst};
-use montyc_flatcode::FlatInst;
+use montyc_flatcode::raw_inst::{Argument, Dunder};
 
-use crate::exception::PyResult;
+use crate::exception::{PyException, PyResult};
 
+use super::bytecode::{Op, Pc, Reg, Slot};
 use super::frame::FrameState;
 
 pub type InstResult = PyResult<usize>;
 
 pub trait InstExec {
-    /// Top-level dispatcher for calling the appropriate inst handler given the instruction op.
+    /// Top-level dispatcher for calling the appropriate op handler given the op.
     ///
     /// You usually don't ever need to overload this yourself unless you're doing something spooky.
     ///
     #[inline]
-    fn exec_inst(&mut self, frame: &mut FrameState, inst: &FlatInst) -> InstResult {
-        let FlatInst {
-            op,
-            value: _inst_result,
-            ..
-        } = &inst;
-
-        log::trace!("[BoundEvaluationContext::eval] {:?}", inst);
+    fn exec_inst(&mut self, frame: &mut FrameState, op: &Op) -> InstResult {
+        log::trace!("[BoundEvaluationContext::eval] {}", op);
 
         match op {
-            RawInst::SetAnnotation { .. } => {
-                // self.set_annotation(name, annotation);
-                Ok(frame.next_inst())
-            }
-            RawInst::Tuple(_) => todo!(),
-            RawInst::Undefined => todo!(),
+            Op::Tuple { dst, elements } => self.tuple(frame, *dst, elements),
+            Op::Undefined { .. } => todo!(),
+
+            Op::List { dst, elements } => self.list(frame, *dst, elements),
+            Op::Set { dst, elements } => self.set(frame, *dst, elements),
+            Op::Dict { dst, items } => self.dict(frame, *dst, items),
+            Op::ListAppend { list, value } => self.list_append(frame, *list, *value),
+            Op::SetAdd { set, value } => self.set_add(frame, *set, *value),
+            Op::DictInsert { dict, key, value } => self.dict_insert(frame, *dict, *key, *value),
 
-            RawInst::BuildClass { sequence, class } => self.build_class(frame, *sequence, *class),
+            Op::BuildClass { class, seq_id } => self.build_class(frame, *seq_id, *class),
 
-            RawInst::UseVar { variable: v } => self.use_var(frame, v.clone()),
-            RawInst::SetVar { variable: v, value } => self.set_var(frame, v.clone(), value.clone()),
+            Op::LoadConst { dst, cst } => self.const_(frame, *dst, *cst),
+            Op::LoadName { dst, slot, name } => self.use_var(frame, *dst, *slot, *name),
+            Op::StoreName { slot, value } => self.set_var(frame, *slot, *value),
 
-            RawInst::If {
+            Op::JumpIf {
                 test,
                 truthy,
                 falsey,
-            } => self.if_(frame, *test, truthy.clone(), falsey.clone()),
+            } => self.if_(frame, *test, *truthy, *falsey),
 
-            RawInst::Br { to } => self.branch(frame, *to),
-            RawInst::Const(cst) => self.const_(frame, cst),
-            RawInst::PhiJump { recv, value } => self.phi_jump(frame, *recv, *value),
-            RawInst::PhiRecv => self.phi_recv(frame),
-            RawInst::Nop => self.nop(frame),
-            RawInst::JumpTarget => self.jump_target(frame),
-            RawInst::Return { value } => self.return_(frame, *value),
-
-            RawInst::SetDunder {
+            Op::Unpack {
+                dst,
+                value,
+                index,
+                len,
+            } => self.unpack(frame, *dst, *value, *index, *len),
+            Op::GetIter { dst, object } => self.get_iter(frame, *dst, *object),
+            Op::IterNext {
+                dst,
+                iter,
+                exhausted,
+            } => self.iter_next(frame, *dst, *iter, *exhausted),
+            Op::Jump { to } => self.branch(frame, *to),
+            Op::PhiJump { dst, value, to } => self.phi_jump(frame, *dst, *value, *to),
+            Op::Return { value } => self.return_(frame, *value),
+
+            Op::SetDunder {
                 object,
                 dunder,
                 value,
             } => self.set_dunder(frame, *object, *dunder, *value),
 
-            RawInst::GetDunder {
-                object: _,
-                dunder: _,
-            } => todo!(),
-            RawInst::Defn {
-                name,
-                params,
-                returns,
-                sequence_id: seq_id,
-            } => self.define_fn(
-                frame,
-                name.clone(),
-                params.as_slice(),
-                returns.clone(),
-                *seq_id,
-            ),
-
-            RawInst::Class { name } => self.class(frame, *name),
-
-            RawInst::Call {
+            Op::GetDunder {
+                dst,
+                object,
+                dunder,
+            } => self.get_dunder(frame, *dst, *object, *dunder),
+            Op::Not { dst, value } => self.not(frame, *dst, *value),
+            Op::Is { dst, left, right } => self.is(frame, *dst, *left, *right),
+            Op::MakeFunction { dst, def } => self.define_fn(frame, *dst, *def),
+
+            Op::MakeClass { dst, name, bases } => self.class(frame, *dst, *name, bases),
+
+            Op::Call {
+                dst,
+                callable,
+                arguments,
+            } => self.call(frame, *dst, *callable, arguments),
+
+            Op::CallEx {
+                dst,
                 callable,
                 arguments,
-            } => self.call(frame, *callable, arguments.as_slice()),
+            } => self.call_ex(frame, *dst, *callable, arguments),
 
-            RawInst::GetAttribute { object, attr } => self.get_attribute(frame, *object, *attr),
-            RawInst::SetAttribute {
+            Op::GetAttr { dst, object, attr } => self.get_attribute(frame, *dst, *object, *attr),
+            Op::SetAttr {
                 object,
                 attr,
                 value,
             } => self.set_attribute(frame, *object, *attr, *value),
 
-            RawInst::Import { path, relative } => self.import(frame, path, *relative),
-            RawInst::RefAsStr { r } => self.ref_as_str(frame, *r),
+            Op::Import {
+                dst,
+                path,
+                relative,
+            } => self.import(frame, *dst, path, *relative),
+
+            Op::ImportFrom {
+                dst,
+                module,
+                path,
+                relative,
+                name,
+            } => self.import_from(frame, *dst, *module, path, *relative, *name),
+
+            Op::Raise { exc } => self.raise(frame, *exc),
+            Op::Unsupported { message } => PyException::not_implemented_error()
+                .set_message(message)
+                .into(),
+            Op::SetupExcept { handler } => self.setup_except(frame, *handler),
+            Op::PopBlock => self.pop_block(frame),
+            Op::GetException { dst } => self.get_exception(frame, *dst),
+            Op::ExcMatch { dst, exc, kind } => self.exc_match(frame, *dst, *exc, *kind),
+            Op::PopExcept => self.pop_except(frame),
+            Op::ClassOf { dst, object } => self.class_of(frame, *dst, *object),
         }
     }
 
-    fn nop(&mut self, frame: &mut FrameState) -> InstResult {
-        Ok(frame.next_inst())
+    fn branch(&mut self, _frame: &mut FrameState, to: Pc) -> InstResult {
+        Ok(to as usize)
+    }
+
+    fn phi_jump(&mut self, frame: &mut FrameState, dst: Reg, value: Reg, to: Pc) -> InstResult {
+        frame.set_value(dst, frame.value(value));
+        Ok(to as usize)
     }
 
-    fn jump_target(&mut self, frame: &mut FrameState) -> InstResult {
+    fn setup_except(&mut self, frame: &mut FrameState, handler: Pc) -> InstResult {
+        frame
+            .handlers
+            .push((handler as usize, frame.exceptions.len()));
         Ok(frame.next_inst())
     }
 
-    fn phi_recv(&mut self, frame: &mut FrameState) -> InstResult {
+    fn pop_block(&mut self, frame: &mut FrameState) -> InstResult {
+        let _ = frame.handlers.pop();
         Ok(frame.next_inst())
     }
 
-    fn branch(&mut self, _frame: &mut FrameState, to: usize) -> InstResult {
-        Ok(to)
+    fn pop_except(&mut self, frame: &mut FrameState) -> InstResult {
+        let _ = frame.exceptions.pop();
+        Ok(frame.next_inst())
     }
 
-    fn build_class(&mut self, frame: &mut FrameState, sequence: usize, class: usize) -> InstResult;
+    fn build_class(&mut self, frame: &mut FrameState, seq_id: usize, class: Reg) -> InstResult;
+
+    fn class(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        name: SpanRef,
+        bases: &[Reg],
+    ) -> InstResult;
+
+    fn raise(&mut self, frame: &mut FrameState, exc: Option<Reg>) -> InstResult;
 
-    fn ref_as_str(&mut self, _frame: &mut FrameState, r: SpanRef) -> InstResult;
+    fn get_exception(&mut self, frame: &mut FrameState, dst: Reg) -> InstResult;
 
-    fn class(&mut self, frame: &mut FrameState, name: SpanRef) -> InstResult;
+    fn exc_match(&mut self, frame: &mut FrameState, dst: Reg, exc: Reg, kind: Reg) -> InstResult;
 
-    fn call(&mut self, frame: &mut FrameState, callable: usize, arguments: &[usize]) -> InstResult;
+    fn class_of(&mut self, frame: &mut FrameState, dst: Reg, object: Reg) -> InstResult;
+
+    fn call(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        callable: Reg,
+        arguments: &[Reg],
+    ) -> InstResult;
+
+    fn call_ex(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        callable: Reg,
+        arguments: &[Argument<Reg>],
+    ) -> InstResult;
 
     fn set_attribute(
         &mut self,
         frame: &mut FrameState,
-        object: usize,
-        attr: usize,
-        value: usize,
+        object: Reg,
+        attr: Reg,
+        value: Reg,
+    ) -> InstResult;
+
+    fn get_attribute(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        object: Reg,
+        attr: Reg,
     ) -> InstResult;
 
-    fn get_attribute(&mut self, frame: &mut FrameState, object: usize, attr: usize) -> InstResult;
+    fn unpack(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        value: Reg,
+        index: usize,
+        len: usize,
+    ) -> InstResult;
+
+    fn get_iter(&mut self, frame: &mut FrameState, dst: Reg, object: Reg) -> InstResult;
+
+    fn iter_next(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        iter: Reg,
+        exhausted: Pc,
+    ) -> InstResult;
+
+    fn if_(&mut self, frame: &mut FrameState, test: Reg, truthy: Pc, falsey: Pc) -> InstResult;
+
+    fn define_fn(&mut self, frame: &mut FrameState, dst: Reg, def: u32) -> InstResult;
 
-    fn phi_jump(&mut self, frame: &mut FrameState, recv: usize, value: usize) -> InstResult;
+    fn set_var(&mut self, frame: &mut FrameState, slot: Slot, value: Reg) -> InstResult;
 
-    fn if_(
+    fn use_var(&mut self, frame: &mut FrameState, dst: Reg, slot: Slot, var: SpanRef)
+        -> InstResult;
+
+    fn import(
         &mut self,
         frame: &mut FrameState,
-        test: usize,
-        truthy: Option<usize>,
-        falsey: Option<usize>,
+        dst: Reg,
+        path: &[SpanRef],
+        relative: usize,
     ) -> InstResult;
 
-    fn define_fn(
+    fn import_from(
         &mut self,
         frame: &mut FrameState,
+        dst: Reg,
+        module: Reg,
+        path: &[SpanRef],
+        relative: usize,
         name: SpanRef,
-        params: &[(SpanRef, Option<usize>)],
-        returns: Option<usize>,
-        seq_id: usize,
     ) -> InstResult;
 
-    fn set_var(&mut self, frame: &mut FrameState, var: SpanRef, value: usize) -> InstResult;
+    fn return_(&mut self, frame: &mut FrameState, value: Reg) -> InstResult;
+
+    fn const_(&mut self, frame: &mut FrameState, dst: Reg, cst: u32) -> InstResult;
+
+    fn get_dunder(
+        &mut self,
+        frame: &mut FrameState,
+        dst: Reg,
+        object: Reg,
+        dunder: Dunder,
+    ) -> InstResult;
+
+    fn not(&mut self, frame: &mut FrameState, dst: Reg, value: Reg) -> InstResult;
 
-    fn use_var(&mut self, frame: &mut FrameState, var: SpanRef) -> InstResult;
+    fn is(&mut self, frame: &mut FrameState, dst: Reg, left: Reg, right: Reg) -> InstResult;
 
-    fn import(&mut self, frame: &mut FrameState, path: &[SpanRef], relative: usize) -> InstResult;
+    fn list(&mut self, frame: &mut FrameState, dst: Reg, elements: &[Reg]) -> InstResult;
 
-    fn return_(&mut self, frame: &mut FrameState, value: usize) -> InstResult;
+    fn tuple(&mut self, frame: &mut FrameState, dst: Reg, elements: &[Reg]) -> InstResult;
 
-    fn const_(&mut self, frame: &mut FrameState, cst: &Constant) -> InstResult;
+    fn set(&mut self, frame: &mut FrameState, dst: Reg, elements: &[Reg]) -> InstResult;
+
+    fn dict(&mut self, frame: &mut FrameState, dst: Reg, items: &[(Reg, Reg)]) -> InstResult;
+
+    fn list_append(&mut self, frame: &mut FrameState, list: Reg, value: Reg) -> InstResult;
+
+    fn set_add(&mut self, frame: &mut FrameState, set: Reg, value: Reg) -> InstResult;
+
+    fn dict_insert(
+        &mut self,
+        frame: &mut FrameState,
+        dict: Reg,
+        key: Reg,
+        value: Reg,
+    ) -> InstResult;
 
     fn set_dunder(
         &mut self,
         frame: &mut FrameState,
-        object: usize,
+        object: Reg,
         dunder: Dunder,
-        value: usize,
+        value: Reg,
     ) -> InstResult;
 }
@@ -1,3 +1,4 @@
+pub mod bytecode;
 pub mod ctx;
 pub mod frame;
 pub(crate) mod inst_exec;
@@ -1,6 +1,6 @@
 use std::{io, panic::Location, rc::Rc};
 
-use montyc_core::SpanRef;
+use montyc_core::{ModuleRef, Span, SpanRef};
 
 use crate::object::ObjectId;
 
@@ -28,15 +28,21 @@ impl<T> PyResultExt for PyResult<T> {
 
 impl Into<PyException> for io::Error {
     fn into(self) -> PyException {
-        PyException::new(InnerExc::OsError(Rc::new(self)))
+        let message = self.to_string();
+
+        PyException::new(InnerExc::OsError(Rc::new(self))).set_message(message)
     }
 }
 
 #[derive(Debug, Clone)]
 pub(crate) enum InnerExc {
-    // Object(ObjectId),
+    /// A Python exception object raised by `raise`, along with the name of its class.
+    Object(ObjectId, Box<str>),
+
     ExceededTicks,
 
+    ExceededDeadline,
+
     UnknownObject(ObjectId),
 
     Return(ObjectId),
@@ -51,6 +57,18 @@ pub(crate) enum InnerExc {
 
     TypeError,
 
+    ValueError,
+
+    RuntimeError,
+
+    ZeroDivisionError,
+
+    IndexError,
+
+    KeyError,
+
+    MemoryError,
+
     NotImplementedError,
 }
 
@@ -59,11 +77,23 @@ pub struct TracePoint {
     pub location: Option<Location<'static>>,
 }
 
+/// A Python frame an exception escaped from, and the span of the instruction it was executing.
+#[derive(Debug, Clone)]
+pub struct PyFrame {
+    pub mref: ModuleRef,
+
+    /// The name of the function or class being executed, or None for module level code.
+    pub name: Option<Box<str>>,
+
+    pub span: Option<Span>,
+}
+
 #[derive(Debug, Clone)]
 pub struct PyException {
     pub(crate) message: Option<Box<str>>,
     pub(crate) inner: InnerExc,
     pub(crate) trace: Vec<TracePoint>,
+    pub(crate) frames: Vec<PyFrame>,
 }
 
 impl PyException {
@@ -73,6 +103,7 @@ impl PyException {
             message: None,
             inner,
             trace: Vec::with_capacity(2),
+            frames: vec![],
         };
 
         this.trace()
@@ -95,6 +126,89 @@ impl<T> From<PyException> for Result<T, PyException> {
 }
 
 impl PyException {
+    /// The name of the python exception class this exception corresponds to.
+    pub fn kind(&self) -> &str {
+        match &self.inner {
+            InnerExc::Object(_, class) => class,
+            InnerExc::ExceededTicks => "RuntimeError",
+            InnerExc::ExceededDeadline => "RuntimeError",
+            InnerExc::UnknownObject(_) => "SystemError",
+            InnerExc::Return(_) => "SystemError",
+            InnerExc::NameError(_) => "NameError",
+            InnerExc::AttributeError(_, _) => "AttributeError",
+            InnerExc::OsError(_) => "OSError",
+            InnerExc::ImportError => "ImportError",
+            InnerExc::TypeError => "TypeError",
+            InnerExc::ValueError => "ValueError",
+            InnerExc::RuntimeError => "RuntimeError",
+            InnerExc::ZeroDivisionError => "ZeroDivisionError",
+            InnerExc::IndexError => "IndexError",
+            InnerExc::KeyError => "KeyError",
+            InnerExc::MemoryError => "MemoryError",
+            InnerExc::NotImplementedError => "NotImplementedError",
+        }
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The name that could not be found, if this is a `NameError`.
+    pub fn missing_name(&self) -> Option<SpanRef> {
+        match self.inner {
+            InnerExc::NameError(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The raised Python exception object, if this exception was raised by Python code.
+    pub fn object(&self) -> Option<ObjectId> {
+        match self.inner {
+            InnerExc::Object(object, _) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Extend `bucket` with the objects this exception refers to.
+    pub(crate) fn refs_with(&self, bucket: &mut Vec<ObjectId>) {
+        match self.inner {
+            InnerExc::Object(object, _)
+            | InnerExc::Return(object)
+            | InnerExc::AttributeError(object, _) => bucket.push(object),
+            _ => (),
+        }
+    }
+
+    /// Whether `try` statements are allowed to handle this exception.
+    ///
+    /// Returns and exceeding the tick bound or deadline are implemented as exceptions but are not Python exceptions.
+    pub(crate) fn is_catchable(&self) -> bool {
+        !matches!(
+            self.inner,
+            InnerExc::Return(_) | InnerExc::ExceededTicks | InnerExc::ExceededDeadline
+        )
+    }
+
+    /// Whether this exception was thrown because the evaluation ran out of ticks.
+    pub fn is_exceeded_ticks(&self) -> bool {
+        matches!(self.inner, InnerExc::ExceededTicks)
+    }
+
+    /// Whether this exception was thrown because the evaluation ran past its deadline.
+    pub fn is_exceeded_deadline(&self) -> bool {
+        matches!(self.inner, InnerExc::ExceededDeadline)
+    }
+
+    /// The frames this exception escaped from, innermost first.
+    pub fn frames(&self) -> &[PyFrame] {
+        self.frames.as_slice()
+    }
+
+    pub(crate) fn push_frame(mut self, frame: PyFrame) -> Self {
+        self.frames.push(frame);
+        self
+    }
+
     pub fn is_import_error(&self) -> bool {
         matches!(self.inner, InnerExc::ImportError)
     }
@@ -116,11 +230,21 @@ impl PyException {
         Self::new(InnerExc::ExceededTicks)
     }
 
+    #[track_caller]
+    pub fn deadline() -> Self {
+        Self::new(InnerExc::ExceededDeadline)
+    }
+
     #[track_caller]
     pub fn return_(object: ObjectId) -> Self {
         Self::new(InnerExc::Return(object))
     }
 
+    #[track_caller]
+    pub(crate) fn raised(object: ObjectId, class: &str) -> Self {
+        Self::new(InnerExc::Object(object, class.into()))
+    }
+
     #[track_caller]
     pub fn no_such_object(alloc: ObjectId) -> Self {
         Self::new(InnerExc::UnknownObject(alloc))
@@ -150,4 +274,34 @@ impl PyException {
     pub fn type_error() -> Self {
         Self::new(InnerExc::TypeError)
     }
+
+    #[track_caller]
+    pub fn value_error() -> Self {
+        Self::new(InnerExc::ValueError)
+    }
+
+    #[track_caller]
+    pub fn runtime_error() -> Self {
+        Self::new(InnerExc::RuntimeError)
+    }
+
+    #[track_caller]
+    pub fn zero_division_error() -> Self {
+        Self::new(InnerExc::ZeroDivisionError)
+    }
+
+    #[track_caller]
+    pub fn index_error() -> Self {
+        Self::new(InnerExc::IndexError)
+    }
+
+    #[track_caller]
+    pub fn key_error() -> Self {
+        Self::new(InnerExc::KeyError)
+    }
+
+    #[track_caller]
+    pub fn memory_error() -> Self {
+        Self::new(InnerExc::MemoryError)
+    }
 }
@@ -7,7 +7,8 @@
 //! and is the implementation of the `import` flatcode instruction.
 //!
 //! Only the `PathFinder`, `FileFinder` logic is implemented for source (.py)
-//! files only. No namespace capabilities, and probably full of bugs.
+//! files only. Namespace packages (PEP 420) are supported but their `__path__`
+//! is not recomputed when `sys.path` changes, and it is probably full of bugs.
 //!
 
 use std::io;
@@ -22,7 +23,7 @@ use crate::argparse::Parser;
 use crate::eval::ctx::{CallCx, EvalGlue, EvaluationContext};
 use crate::exception::{PyException, PyResult, PyResultExt};
 use crate::object::{AnyFunc, GlobalsHook, IntoPyValue, PyObject, PyValue, SharedObject};
-use crate::rt::{ModuleKey, Runtime, RuntimeHostExt};
+use crate::rt::{ModuleKey, Runtime, RuntimeHost, RuntimeHostExt};
 use crate::storage::ObjectSpace;
 use crate::{argparse, ObjectId};
 
@@ -55,13 +56,13 @@ impl FileFinder {
         Ok(finder)
     }
 
-    fn fill_cache(&self) -> io::Result<()> {
+    fn fill_cache(&self, host: &dyn RuntimeHost) -> io::Result<()> {
         log::trace!(
             "[FileFinder::fill_cache] Sourcing cache from base_path={:?}",
             self.base_path
         );
 
-        for entry in self.base_path.read_dir()? {
+        for entry in host.try_read_directory(&self.base_path)?.into_vec() {
             if let Ok(entry) = entry {
                 let path = entry.path();
                 let name = path
@@ -93,10 +94,9 @@ impl FileFinder {
         _target: Option<ObjectId>,
     ) -> PyResult<ObjectId> {
         let none_v = ecx.runtime_mut().singletons.none_v;
-        let mut _is_namespace = false;
 
         if self.cache_dirty_flag.load(Ordering::SeqCst) {
-            if let Err(err) = self.fill_cache() {
+            if let Err(err) = self.fill_cache(ecx.runtime_host()) {
                 log::trace!("[FileFinder::find_spec] error during cache fill {:?}", err);
 
                 self.cache_dirty_flag.store(true, Ordering::SeqCst);
@@ -121,30 +121,39 @@ impl FileFinder {
             tail
         );
 
-        if let Some(path) = self.cache.get(tail) {
-            log::trace!("[FileFinder::find_spec] found tail={:?} in cache", tail);
+        let mut namespace_path = None;
 
-            // check if the module is the name of a directory and thus a package.
-            let base_path = path.join(tail);
+        let package_path = self
+            .cache
+            .get(tail)
+            .map(|path| path.value().clone())
+            .filter(|path| path.is_dir());
 
-            log::trace!(
-                "[FileFinder::find_spec] base_path ++ tail = {:?}",
-                base_path
-            );
+        if let Some(path) = package_path {
+            log::trace!("[FileFinder::find_spec] found tail={:?} in cache", tail);
 
-            for (suffix, _loader) in self.loaders.iter() {
+            // check if the module is the name of a directory and thus a package.
+            for (suffix, loader) in self.loaders.iter() {
                 let init_filename = format!("__init__{}", suffix);
-                let full_path = base_path.join(init_filename);
+                let full_path = path.join(init_filename);
 
                 if full_path.is_file() {
-                    todo!(
-                        "return self._get_spec(loader, fullname, {:?}, [base_path], target)",
-                        full_path
-                    );
+                    log::trace!("[FileFinder::find_spec]  found package at {:?}", full_path);
+
+                    let package_path = ecx.new_string(path.to_string_lossy().as_ref()).trace()?;
+
+                    let search_locations = ecx
+                        .runtime_mut()
+                        .objects
+                        .insert(PyValue::List(vec![package_path]));
+
+                    return self
+                        .get_spec(ecx, *loader, fullname, &full_path, search_locations)
+                        .trace();
                 }
             }
 
-            _is_namespace = self.base_path.is_dir();
+            namespace_path = Some(path);
         }
 
         for (suffix, loader) in self.loaders.iter() {
@@ -163,34 +172,47 @@ impl FileFinder {
                 );
 
                 let full_path = self.base_path.join(partial);
-                let path = ecx
-                    .new_string(full_path.to_string_lossy().as_ref())
-                    .trace()?;
-
-                let loader = ecx.call_object(*loader, &[fullname, path]).trace()?;
-                let spec = ModuleSpec::from_file_location(fullname, loader);
 
-                return Ok(ecx.runtime_mut().objects.insert(spec.into_py_val()));
+                return self
+                    .get_spec(ecx, *loader, fullname, &full_path, none_v)
+                    .trace();
             }
         }
 
-        todo!("is_namespace");
+        match namespace_path {
+            Some(path) => {
+                log::trace!(
+                    "[FileFinder::find_spec] Potential namespace package {:?}",
+                    path
+                );
 
-        // if is_namespace {
-        //     log::trace!(
-        //         "[FileFinder::find_space] Potential namespace package {}",
-        //         fullname
-        //     );
+                let path = ecx.new_string(path.to_string_lossy().as_ref()).trace()?;
+                let search_locations = ecx.runtime_mut().objects.insert(PyValue::List(vec![path]));
 
-        //     let spec = ModuleSpec {
-        //         paths: vec![self.base_path.clone()],
-        //         loader: todo!(),
-        //     };
+                let spec = ModuleSpec::namespace(fullname, none_v, search_locations);
 
-        //     Some(spec)
-        // } else {
-        //     None
-        // }
+                Ok(ecx.runtime_mut().objects.insert(spec.into_py_val()))
+            }
+
+            None => Ok(none_v),
+        }
+    }
+
+    fn get_spec(
+        &self,
+        ecx: &mut dyn EvalGlue,
+        loader: ObjectId,
+        fullname: ObjectId,
+        path: &Path,
+        submodule_search_locations: ObjectId,
+    ) -> PyResult<ObjectId> {
+        let path = ecx.new_string(path.to_string_lossy().as_ref()).trace()?;
+        let loader = ecx.call_object(loader, &[fullname, path]).trace()?;
+
+        let spec =
+            ModuleSpec::from_file_location(fullname, loader, path, submodule_search_locations);
+
+        Ok(ecx.runtime_mut().objects.insert(spec.into_py_val()))
     }
 }
 
@@ -199,6 +221,10 @@ impl PyObject for FileFinder {
         std::any::TypeId::of::<Self>()
     }
 
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {
+        bucket.extend(self.loaders.values());
+    }
+
     fn call(&self, _cx: crate::eval::ctx::CallCx) -> PyResult<ObjectId> {
         todo!()
     }
@@ -247,11 +273,61 @@ impl PyObject for FileFinder {
 pub(crate) struct ModuleSpec {
     name: ObjectId,
     loader: ObjectId,
+    origin: ObjectId,
+    submodule_search_locations: ObjectId,
 }
 
 impl ModuleSpec {
-    pub fn from_file_location(name: ObjectId, loader: ObjectId) -> Self {
-        Self { name, loader }
+    pub fn from_file_location(
+        name: ObjectId,
+        loader: ObjectId,
+        origin: ObjectId,
+        submodule_search_locations: ObjectId,
+    ) -> Self {
+        Self {
+            name,
+            loader,
+            origin,
+            submodule_search_locations,
+        }
+    }
+
+    /// The spec of a namespace package (PEP 420) whose portions are the directories in `submodule_search_locations`.
+    ///
+    /// Namespace packages have neither a loader nor an origin, there is no file to execute.
+    ///
+    pub fn namespace(
+        name: ObjectId,
+        none_v: ObjectId,
+        submodule_search_locations: ObjectId,
+    ) -> Self {
+        Self {
+            name,
+            loader: none_v,
+            origin: none_v,
+            submodule_search_locations,
+        }
+    }
+
+    /// The name of the package this module belongs to, packages are their own parent.
+    fn parent(&self, ecx: &mut dyn EvalGlue) -> PyResult<ObjectId> {
+        let none_v = ecx.runtime().singletons.none_v;
+
+        let name = ecx
+            .runtime()
+            .objects
+            .with_object(self.name, |this| match this {
+                PyValue::Str(st) => Ok(st.to_string()),
+                _ => PyException::type_error().into(),
+            })?;
+
+        if self.submodule_search_locations != none_v {
+            Ok(self.name)
+        } else {
+            let parent = name.rsplit_once('.').map_or("", |(parent, _)| parent);
+
+            ecx.new_string(parent)
+        }
     }
 }
 
@@ -266,6 +342,15 @@ impl PyObject for ModuleSpec {
         todo!()
     }
 
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {
+        bucket.extend([
+            self.name,
+            self.loader,
+            self.origin,
+            self.submodule_search_locations,
+        ]);
+    }
+
     fn call(&self, _cx: crate::eval::ctx::CallCx) -> PyResult<ObjectId> {
         todo!()
     }
@@ -277,11 +362,20 @@ impl PyObject for ModuleSpec {
     fn get_attribute(&self, ecx: &mut dyn EvalGlue, attr: ObjectId) -> PyResult<ObjectId> {
         let loader = ecx.new_string("loader").trace()?;
         let name = ecx.new_string("name").trace()?;
+        let origin = ecx.new_string("origin").trace()?;
+        let parent = ecx.new_string("parent").trace()?;
+        let submodule_search_locations = ecx.new_string("submodule_search_locations").trace()?;
 
         if attr == loader {
             Ok(self.loader)
         } else if attr == name {
             Ok(self.name)
+        } else if attr == origin {
+            Ok(self.origin)
+        } else if attr == parent {
+            self.parent(ecx).trace()
+        } else if attr == submodule_search_locations {
+            Ok(self.submodule_search_locations)
         } else {
             PyException::attribute_error(attr, 0).into()
         }
@@ -300,6 +394,10 @@ impl PyObject for SourceFileLoader {
         todo!()
     }
 
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {
+        bucket.push(self.path);
+    }
+
     fn call_method(
         &self,
         ecx: &mut dyn EvalGlue,
@@ -317,7 +415,10 @@ impl PyObject for SourceFileLoader {
                     val => unreachable!("{:?}", val),
                 });
 
-            let source_bytes = std::fs::read_to_string(path).unwrap();
+            let source_bytes = ecx
+                .runtime_host()
+                .try_read_file(&path)
+                .map_err(|err| -> PyException { err.into() })?;
 
             return ecx.new_string(&*source_bytes).trace();
         }
@@ -369,18 +470,20 @@ impl PathFinder {
         let loader = cx.ecx.getattr(spec, &"loader").trace()?;
 
         if loader == none_v {
-            todo!("spec.loader is None, go do namespace logic")
-            // namespace_path = spec.submodule_search_locations
-            // if namespace_path:
-            //     # We found at least one namespace path.  Return a spec which
-            //     # can create the namespace package.
-            //     spec.origin = None
-            //     spec.submodule_search_locations = _NamespacePath(fullname, namespace_path, cls._get_spec)
-            //     return spec
-            // else:
-            //     return None
+            // only portions of a namespace package were found, unlike `_NamespacePath` the
+            // search locations are computed once and not recomputed when `sys.path` changes.
+            let namespace_path = cx
+                .ecx
+                .getattr(spec, &"submodule_search_locations")
+                .trace()?;
+
+            if namespace_path == none_v {
+                Ok(none_v)
+            } else {
+                Ok(spec)
+            }
         } else {
-            return Ok(spec);
+            Ok(spec)
         }
     }
 
@@ -401,7 +504,8 @@ impl PathFinder {
         let none_v = ecx.runtime_mut().singletons.none_v;
         let find_spec = ecx.new_string("find_spec").trace()?;
 
-        let mut path_iter = ecx.iter_object(path);
+        let mut namespace_path = vec![];
+        let mut path_iter = ecx.iter_object(path).trace()?;
 
         while let Some(entry) = path_iter.next(ecx) {
             log::trace!("[PathFinder::get_spec]   path_iter -> {:?}", entry);
@@ -448,20 +552,43 @@ impl PathFinder {
                 return Ok(spec);
             }
 
-            // todo!();
+            // a namespace portion, keep looking for a regular package or module.
+            let portions = ecx.getattr(spec, &"submodule_search_locations").trace()?;
 
-            // let spec_portions = ecx.getattr(spec, &"submodule_search_locations").trace()?;
+            if portions == none_v {
+                return PyException::import_error()
+                    .set_message("spec missing loader")
+                    .into();
+            }
 
-            // if spec_portions == none_v {
-            //     return PyException::import_error()
-            //         .set_message("spec missing loader")
-            //         .into();
-            // }
+            let portions = ecx
+                .runtime()
+                .objects
+                .with_object(portions, |this| match this {
+                    PyValue::List(lst) => lst.clone(),
+                    val => unreachable!("{:?}", val),
+                });
+
+            log::trace!(
+                "[PathFinder::get_spec]     spec is a namespace portion, portions={:?}",
+                portions
+            );
 
-            todo!("namespace_path.extend(portions)");
+            namespace_path.extend(portions);
         }
 
-        todo!("spec = _bootstrap.ModuleSpec(fullname, None)")
+        if namespace_path.is_empty() {
+            return Ok(none_v);
+        }
+
+        let namespace_path = ecx
+            .runtime_mut()
+            .objects
+            .insert(PyValue::List(namespace_path));
+
+        let spec = ModuleSpec::namespace(fullname, none_v, namespace_path);
+
+        Ok(ecx.runtime_mut().objects.insert(spec.into_py_val()))
     }
 
     /// Get the finder for the path entry from sys.path_importer_cache
@@ -531,7 +658,7 @@ impl PathFinder {
         log::trace!("[PathFinder::path_hooks] finding path hook for {:?}", path);
 
         let sys_path_hooks = ecx.getattr(sys, &"path_hooks").trace()?;
-        let mut sys_path_hooks_iter = ecx.iter_object(sys_path_hooks);
+        let mut sys_path_hooks_iter = ecx.iter_object(sys_path_hooks).trace()?;
 
         while let Some(hook) = sys_path_hooks_iter.next(ecx) {
             let hook = hook.trace()?;
@@ -560,6 +687,12 @@ impl PyObject for PathFinder {
         std::any::TypeId::of::<Self>()
     }
 
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {
+        for entry in self.path_importer_cache.iter() {
+            bucket.extend([*entry.key(), *entry.value()]);
+        }
+    }
+
     fn repr(&self, ecx: &mut dyn EvalGlue) -> PyResult<ObjectId> {
         ecx.new_string("PathFinder")
     }
@@ -630,7 +763,7 @@ where
     //     }
     // };
 
-    let mut meta_path_iter = ecx.iter_object(meta_path);
+    let mut meta_path_iter = ecx.iter_object(meta_path).trace()?;
     let find_spec_str = ecx.new_string("find_spec")?;
 
     let none_v = ecx.runtime_mut().singletons.none_v;
@@ -665,92 +798,156 @@ where
     Ok(ecx.rt.singletons.none_v)
 }
 
-/// like `__import__` but returns a `ModuleSpec`
-pub(crate) fn import_module<H>(
-    ecx: &mut EvaluationContext<'_, '_, H>,
-    path: &[SpanRef],
-    relative: usize,
-) -> PyResult<ObjectId>
+fn sys_modules(ecx: &mut dyn EvalGlue) -> PyResult<ObjectId> {
+    let sys = ecx.runtime().singletons.sys;
+
+    ecx.getattr(sys, &"modules").trace()
+}
+
+/// `sys.modules.get(name)`
+fn sys_modules_get(ecx: &mut dyn EvalGlue, name: &str) -> PyResult<Option<ObjectId>> {
+    let sys_modules = sys_modules(ecx).trace()?;
+    let rt = ecx.runtime();
+    let hash = rt.hash(name);
+
+    Ok(rt.objects.with_object(sys_modules, |this| match this {
+        PyValue::Dict(dict) => dict.get(hash).map(|(_, module)| module),
+        _ => unreachable!(),
+    }))
+}
+
+/// `sys.modules[name] = module`
+fn sys_modules_insert(ecx: &mut dyn EvalGlue, name: &str, module: ObjectId) -> PyResult<()> {
+    let sys_modules = sys_modules(ecx).trace()?;
+    let rt = ecx.runtime_mut();
+    let (hash, key, value) = rt.make_kv_pair(name, |_| module);
+
+    rt.objects.with_object_mut(sys_modules, |this| match this {
+        PyValue::Dict(dict) => dict.insert(hash, (key, value)),
+        _ => unreachable!(),
+    });
+
+    Ok(())
+}
+
+/// `del sys.modules[name]`
+fn sys_modules_remove(ecx: &mut dyn EvalGlue, name: &str) -> PyResult<()> {
+    let sys_modules = sys_modules(ecx).trace()?;
+    let rt = ecx.runtime_mut();
+    let hash = rt.hash(name);
+
+    rt.objects.with_object_mut(sys_modules, |this| match this {
+        PyValue::Dict(dict) => dict.remove(&hash),
+        _ => unreachable!(),
+    });
+
+    Ok(())
+}
+
+/// Import the absolute module `name`, and every package above it, unless it is already in `sys.modules`.
+///
+/// Submodules are searched for in the `__path__` of their parent package, which for a namespace
+/// package spans every search root that has a portion of it.
+///
+fn find_and_load<H>(ecx: &mut EvaluationContext<'_, '_, H>, name: &str) -> PyResult<ObjectId>
 where
     H: RuntimeHostExt,
 {
-    let sys = if ecx.rt.singletons.sys == ObjectId::default() {
-        todo!("sys module has not been initialized yet, sys.path is unavailable.");
-    } else {
-        ecx.rt.singletons.sys
-    };
-
-    let (_, sys_modules) = ecx
-        .getattr_direct_hash(sys, ecx.rt.hash("modules"))
-        .unwrap();
+    if let Some(module) = sys_modules_get(ecx, name).trace()? {
+        return Ok(module);
+    }
 
-    match path {
-        [] => unreachable!(),
+    let none_v = ecx.rt.singletons.none_v;
 
-        [name] => {
-            let name = ecx.host.spanref_to_str(*name).to_string();
+    let (parent, path) = match name.rsplit_once('.') {
+        Some((parent, child)) => {
+            let parent_module = find_and_load(ecx, parent).trace()?;
 
-            log::trace!(
-                "[EvaluationContext::import_module] only one segment in import path {:?}",
-                name
-            );
+            // importing the parent may have imported this module as well.
+            if let Some(module) = sys_modules_get(ecx, name).trace()? {
+                return Ok(module);
+            }
 
-            let name_obj = ecx.new_string(&name)?;
-            let spec = find_spec(ecx, name_obj, ecx.rt.singletons.none_v).trace()?;
+            let path = match ecx.getattr(parent_module, &"__path__") {
+                Ok(path) => path,
+                Err(exc) if exc.is_attribute_arror() => {
+                    return PyException::import_error()
+                        .set_message(format!(
+                            "No module named '{}'; '{}' is not a package",
+                            name, parent
+                        ))
+                        .into()
+                }
+                Err(exc) => return Err(exc),
+            };
 
-            if spec == ecx.rt.singletons.none_v {
-                PyException::import_error()
-                    .set_message(format!("No module named '{}'", name))
-                    .into()
-            } else {
-                return Ok(spec);
-            }
+            (Some((parent_module, child)), path)
         }
 
-        [parent, ..] => {
-            let (_, contains) = ecx
-                .getattr_direct_hash(sys_modules, ecx.rt.hash("__contains__"))
-                .unwrap();
-
-            let parent_as_str =
-                ecx.new_string(ecx.host.spanref_to_str(*parent).to_string().as_str())?;
+        None => (None, none_v),
+    };
 
-            let parent_module =
-                if ecx.call_object(contains, &[parent_as_str])? == ecx.rt.singletons.false_v {
-                    import_module(ecx, &[parent.clone()], relative).trace()?
-                } else {
-                    let name_as_str = path
-                        .iter()
-                        .map(|part| ecx.host.spanref_to_str(*part))
-                        .collect::<Vec<_>>()
-                        .join(".");
+    let name_obj = ecx.new_string(name)?;
+    let spec = find_spec(ecx, name_obj, path).trace()?;
 
-                    let name_as_str = ecx.new_string(&name_as_str)?;
+    if spec == none_v {
+        return PyException::import_error()
+            .set_message(format!("No module named '{}'", name))
+            .into();
+    }
 
-                    if ecx.call_object(contains, &[name_as_str])? == ecx.rt.singletons.true_v {
-                        // importing the parent module may have caused this import to be fulfilled...
-                        // "crazy side-effects!"
+    let module = ecx.compile_from_spec(spec).trace()?;
 
-                        let (_, get_item) =
-                            ecx.getattr_direct_hash(sys_modules, ecx.rt.hash("__getitem__"))?;
+    // the module is visible before it is executed so that circular imports find it.
+    sys_modules_insert(ecx, name, module).trace()?;
 
-                        return ecx.call_object(get_item, &[name_as_str]);
-                    }
+    if let Err(exc) = ecx.exec_module(module) {
+        sys_modules_remove(ecx, name).trace()?;
+        return Err(exc);
+    }
 
-                    let (_, get_item) =
-                        ecx.getattr_direct_hash(sys_modules, ecx.rt.hash("__getitem__"))?;
+    if let Some((parent_module, child)) = parent {
+        let (hash, key, value) = ecx.rt.make_kv_pair(child, |_| module);
 
-                    ecx.call_object(get_item, &[parent_as_str])?
-                };
+        ecx.rt
+            .objects
+            .with_object_mut(parent_module, |this| match this {
+                PyValue::Module { inner, .. } => inner.__dict__.insert(hash, (key, value)),
+                _ => unreachable!(),
+            });
+    }
 
-            let (_, _parent_module_path) =
-                ecx.getattr_direct_hash(parent_module, ecx.rt.hash("__path__"))?;
+    Ok(module)
+}
 
-            // let spec = import_module(ecx, parent_module__path__, todo!())?;
+/// like `__import__`, imports the module at `path` and every package above it.
+pub(crate) fn import_module<H>(
+    ecx: &mut EvaluationContext<'_, '_, H>,
+    path: &[SpanRef],
+    relative: usize,
+) -> PyResult<ObjectId>
+where
+    H: RuntimeHostExt,
+{
+    if ecx.rt.singletons.sys == ObjectId::default() {
+        todo!("sys module has not been initialized yet, sys.path is unavailable.");
+    }
 
-            todo!("spec")
-        }
+    if relative != 0 {
+        return PyException::import_error()
+            .set_message("relative imports are not supported by the bootstrap importer")
+            .into();
     }
+
+    let name = path
+        .iter()
+        .map(|part| ecx.host.spanref_to_str(*part))
+        .collect::<Vec<_>>()
+        .join(".");
+
+    log::trace!("[EvaluationContext::import_module] importing {:?}", name);
+
+    find_and_load(ecx, &name)
 }
 
 /// Top-level initialization for import bootstrapping.
@@ -4,7 +4,7 @@
 #[cfg(test)]
 pub(crate) mod test {
     use ahash::AHashMap;
-    use montyc_core::ModuleRef;
+    use montyc_core::{ModuleRef, MontyError};
     use montyc_flatcode::FlatCode;
     use montyc_parser::{AstObject, SpanInterner};
 
@@ -25,7 +25,7 @@ pub(crate) mod test {
     }
 
     impl RuntimeHostExt for TestHost {
-        type Error = ();
+        type Error = MontyError;
 
         fn as_accept_input<'a, 'b, 'c>(
             &'a mut self,
@@ -81,6 +81,8 @@ pub(crate) mod test {
 
                     let sref = sint.insert(0..st.len());
 
+                    self.sources.insert(mref, st.to_string());
+
                     sref.group()
                 }
             }
@@ -105,7 +107,7 @@ pub(crate) mod test {
     impl AcceptInput<&str, FlatCode> for TestHost {
         fn accept_input(&mut self, input: &str) -> Result<montyc_flatcode::FlatCode, Self::Error> {
             let mref = self.mrefs.into();
-            let module = montyc_parser::parse(
+            let module = montyc_parser::try_parse(
                 input,
                 montyc_parser::comb::module,
                 Some(self.sint.clone()),
@@ -115,11 +117,22 @@ pub(crate) mod test {
             self.mrefs += 1;
             self.sources.insert(mref, input.to_string());
 
+            let module = module.map_err(|errors| MontyError::Parse {
+                module: mref,
+                errors,
+            })?;
+
             let mut code = FlatCode::new((mref, module.span().unwrap_or(0..0)));
 
             module.visit_with(&mut code, None);
 
-            Ok(code)
+            match code.errors() {
+                [] => Ok(code),
+                errors => Err(MontyError::Parse {
+                    module: mref,
+                    errors: errors.to_vec(),
+                }),
+            }
         }
     }
 
@@ -144,7 +157,7 @@ pub(crate) mod test {
             unimplemented!()
         }
 
-        fn iter_object(&mut self, _object: ObjectId) -> PyIter {
+        fn iter_object(&mut self, _object: ObjectId) -> PyResult<PyIter> {
             unimplemented!()
         }
 
@@ -199,7 +212,7 @@ pub mod rt;
 
 pub use {
     eval::ctx,
-    exception::{PyException, PyResult, PyResultExt},
+    exception::{PyException, PyFrame, PyResult, PyResultExt},
     object::ObjectId,
     storage::ObjectSpace,
 };
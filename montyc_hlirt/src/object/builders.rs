@@ -130,6 +130,7 @@ impl ObjectBuilder<{ CLASS }> {
         let class = rt.objects.insert_with(|alloc_id| PyValue::Class {
             name: None,
             parent: None,
+            bases: Default::default(),
             inner: RawObject {
                 alloc_id,
                 __class__,
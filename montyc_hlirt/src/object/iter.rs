@@ -2,11 +2,19 @@ use crate::eval::ctx::EvalGlue;
 use crate::exception::PyResult;
 use crate::ObjectId;
 
+#[derive(Debug)]
 enum IterKind {
     Empty,
     Direct(std::vec::IntoIter<ObjectId>),
+    /// The integers of a `range` object, produced lazily.
+    Range {
+        next: i64,
+        stop: i64,
+        step: i64,
+    },
 }
 
+#[derive(Debug)]
 pub struct PyIter {
     kind: IterKind,
 }
@@ -29,10 +37,43 @@ impl PyIter {
         }
     }
 
-    pub fn next(&mut self, _ecx: &mut dyn EvalGlue) -> Option<PyResult<ObjectId>> {
+    /// Iterate the integers from `start` (inclusive) to `stop` (exclusive) by a non-zero `step`.
+    pub(crate) fn range(start: i64, stop: i64, step: i64) -> Self {
+        debug_assert_ne!(step, 0);
+
+        Self {
+            kind: IterKind::Range {
+                next: start,
+                stop,
+                step,
+            },
+        }
+    }
+
+    /// Extend `bucket` with the objects this iterator has yet to produce.
+    pub(crate) fn refs_with(&self, bucket: &mut Vec<ObjectId>) {
+        match &self.kind {
+            IterKind::Empty | IterKind::Range { .. } => (),
+            IterKind::Direct(it) => bucket.extend_from_slice(it.as_slice()),
+        }
+    }
+
+    pub fn next(&mut self, ecx: &mut dyn EvalGlue) -> Option<PyResult<ObjectId>> {
         match &mut self.kind {
             IterKind::Empty => None,
             IterKind::Direct(it) => it.next().map(Ok),
+            IterKind::Range { next, stop, step } => {
+                let n = *next;
+
+                if (*step > 0 && n >= *stop) || (*step < 0 && n <= *stop) {
+                    return None;
+                }
+
+                // stepping past `i64::MAX` also steps past `stop`.
+                *next = n.checked_add(*step).unwrap_or(*stop);
+
+                Some(ecx.new_int(n))
+            }
         }
     }
 }
@@ -44,10 +44,10 @@ impl ObjectId {
 
 pub mod builders;
 pub mod iter;
+pub mod native_tables;
 pub mod pyobject;
 pub mod raw_object;
 pub mod shared_object;
 pub mod value;
-pub mod native_tables {}
 
 pub use self::{builders::*, iter::*, pyobject::*, raw_object::*, shared_object::*, value::*};
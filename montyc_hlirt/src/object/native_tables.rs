@@ -0,0 +1,437 @@
+//! Native implementations of the dunder methods, and other methods, of the primitive types.
+//!
+//! Primitives (ints, strings, lists, etc.) don't have a class `__dict__` to
+//! look their methods up in, so instead they are resolved here by name.
+//!
+
+use crate::{
+    eval::ctx::CallCx,
+    exception::{PyException, PyResult},
+    storage::ObjectSpace,
+    ObjectId,
+};
+
+use super::{NativeFn, PyValue};
+
+/// Look up the native implementation of the dunder `name` for a primitive `value`.
+pub fn lookup(value: &PyValue, name: &str) -> Option<NativeFn> {
+    let func: NativeFn = match (value, name) {
+        (_, "__eq__") => |cx| {
+            let eq = values_eq(&cx, cx.args[0], cx.args[1]);
+            Ok(bool_v(cx, eq))
+        },
+
+        (_, "__ne__") => |cx| {
+            let eq = values_eq(&cx, cx.args[0], cx.args[1]);
+            Ok(bool_v(cx, !eq))
+        },
+
+        (_, "__bool__") => |cx| {
+            let truthy = cx
+                .ecx
+                .runtime()
+                .objects
+                .with_object(cx.args[0], |this| match this {
+                    PyValue::Int(n) => *n != 0,
+                    PyValue::Bool(b) => *b,
+                    PyValue::Float(f) => *f != 0.0,
+                    PyValue::None => false,
+                    PyValue::Str(st) => !st.is_empty(),
+                    PyValue::Bytes(bytes) => !bytes.is_empty(),
+                    PyValue::List(lst) => !lst.is_empty(),
+                    PyValue::Tuple(elems) => !elems.is_empty(),
+                    PyValue::Dict(dict) => !dict.is_empty(),
+                    PyValue::Set(set) => !set.is_empty(),
+                    _ => true,
+                });
+
+            Ok(bool_v(cx, truthy))
+        },
+
+        (PyValue::Int(_) | PyValue::Bool(_) | PyValue::Float(_), _) => match name {
+            "__lt__" => |cx| num_compare(cx, "<", |a, b| a < b, |a, b| a < b),
+            "__le__" => |cx| num_compare(cx, "<=", |a, b| a <= b, |a, b| a <= b),
+            "__gt__" => |cx| num_compare(cx, ">", |a, b| a > b, |a, b| a > b),
+            "__ge__" => |cx| num_compare(cx, ">=", |a, b| a >= b, |a, b| a >= b),
+            "__add__" => |cx| num_arith(cx, "+", i64::checked_add, |a, b| a + b),
+            "__sub__" => |cx| num_arith(cx, "-", i64::checked_sub, |a, b| a - b),
+            "__mul__" => |cx| num_arith(cx, "*", i64::checked_mul, |a, b| a * b),
+
+            "__truediv__" => |cx| match num_operands(&cx, "/")? {
+                (_, Number::Int(0)) => zero_division("division by zero"),
+                (_, Number::Float(b)) if b == 0.0 => zero_division("float division by zero"),
+                (a, b) => cx.ecx.new_float(a.as_f64() / b.as_f64()),
+            },
+
+            "__floordiv__" => |cx| match num_operands(&cx, "//")? {
+                (_, Number::Int(0)) => zero_division("integer division or modulo by zero"),
+                (_, Number::Float(b)) if b == 0.0 => zero_division("float floor division by zero"),
+                (Number::Int(a), Number::Int(b)) => match a.checked_div(b) {
+                    Some(q) if (a % b != 0) && ((a < 0) != (b < 0)) => cx.ecx.new_int(q - 1),
+                    Some(q) => cx.ecx.new_int(q),
+                    None => overflow(),
+                },
+                (a, b) => cx.ecx.new_float((a.as_f64() / b.as_f64()).floor()),
+            },
+
+            "__mod__" => |cx| match num_operands(&cx, "%")? {
+                (_, Number::Int(0)) => zero_division("integer division or modulo by zero"),
+                (_, Number::Float(b)) if b == 0.0 => zero_division("float modulo"),
+                (Number::Int(a), Number::Int(b)) => match a.checked_rem(b) {
+                    Some(r) if r != 0 && ((r < 0) != (b < 0)) => cx.ecx.new_int(r + b),
+                    Some(r) => cx.ecx.new_int(r),
+                    None => overflow(),
+                },
+                (a, b) => {
+                    let (a, b) = (a.as_f64(), b.as_f64());
+                    cx.ecx.new_float(a - b * (a / b).floor())
+                }
+            },
+
+            "__neg__" => |cx| match num_operand(&cx, cx.args[0], "unary -")? {
+                Number::Int(n) => n.checked_neg().map_or_else(overflow, |n| cx.ecx.new_int(n)),
+                Number::Float(f) => cx.ecx.new_float(-f),
+            },
+
+            "__pos__" => |cx| match num_operand(&cx, cx.args[0], "unary +")? {
+                Number::Int(n) => cx.ecx.new_int(n),
+                Number::Float(f) => cx.ecx.new_float(f),
+            },
+
+            _ => return None,
+        },
+
+        (PyValue::Str(_), "__contains__") => |cx| {
+            let (haystack, needle) = (cx.args[0], cx.args[1]);
+            let objects = &cx.ecx.runtime().objects;
+
+            let needle = objects.with_object(needle, |this| match this {
+                PyValue::Str(st) => Ok(st.clone()),
+                _ => PyException::type_error()
+                    .set_message("'in <string>' requires string as left operand")
+                    .into(),
+            })?;
+
+            let found = objects.with_object(haystack, |this| match this {
+                PyValue::Str(st) => st.contains(&*needle),
+                _ => unreachable!(),
+            });
+
+            Ok(bool_v(cx, found))
+        },
+
+        (PyValue::List(_) | PyValue::Tuple(_), "__contains__") => |cx| {
+            let (lst, needle) = (cx.args[0], cx.args[1]);
+
+            let elems = cx
+                .ecx
+                .runtime()
+                .objects
+                .with_object(lst, |this| match this {
+                    PyValue::List(lst) => lst.clone(),
+                    PyValue::Tuple(elems) => elems.to_vec(),
+                    _ => unreachable!(),
+                });
+
+            let found = elems.into_iter().any(|elem| values_eq(&cx, elem, needle));
+
+            Ok(bool_v(cx, found))
+        },
+
+        (PyValue::Dict(_), "__contains__") => |cx| {
+            let (dict, key) = (cx.args[0], cx.args[1]);
+            let hash = cx.ecx.hash_object(key)?;
+
+            let found = cx
+                .ecx
+                .runtime()
+                .objects
+                .with_object(dict, |this| match this {
+                    PyValue::Dict(dict) => dict.get(hash).is_some(),
+                    _ => unreachable!(),
+                });
+
+            Ok(bool_v(cx, found))
+        },
+
+        (PyValue::Set(_), "__contains__") => |cx| {
+            let (set, element) = (cx.args[0], cx.args[1]);
+            let hash = cx.ecx.hash_object(element)?;
+
+            let found = cx
+                .ecx
+                .runtime()
+                .objects
+                .with_object(set, |this| match this {
+                    PyValue::Set(set) => set.get(hash).is_some(),
+                    _ => unreachable!(),
+                });
+
+            Ok(bool_v(cx, found))
+        },
+
+        (
+            PyValue::Bytes(_)
+            | PyValue::List(_)
+            | PyValue::Tuple(_)
+            | PyValue::Dict(_)
+            | PyValue::Set(_),
+            "__len__",
+        ) => |cx| {
+            let len = cx
+                .ecx
+                .runtime()
+                .objects
+                .with_object(cx.args[0], |this| match this {
+                    PyValue::Bytes(bytes) => bytes.len(),
+                    PyValue::List(lst) => lst.len(),
+                    PyValue::Tuple(elems) => elems.len(),
+                    PyValue::Dict(dict) => dict.len(),
+                    PyValue::Set(set) => set.len(),
+                    _ => unreachable!(),
+                });
+
+            cx.ecx.new_int(len as i64)
+        },
+
+        (PyValue::List(_) | PyValue::Tuple(_), "__getitem__") => |cx| {
+            let (lst, index) = (cx.args[0], cx.args[1]);
+
+            let kind = cx
+                .ecx
+                .runtime()
+                .objects
+                .with_object(lst, |this| this.type_name());
+
+            let index = match num_operand(&cx, index, "[]") {
+                Ok(Number::Int(index)) => index,
+                _ => {
+                    return PyException::type_error()
+                        .set_message(format!("{} indices must be integers", kind))
+                        .into()
+                }
+            };
+
+            let item = cx.ecx.runtime().objects.with_object(lst, |this| {
+                let elems = match this {
+                    PyValue::List(lst) => lst.as_slice(),
+                    PyValue::Tuple(elems) => elems,
+                    _ => unreachable!(),
+                };
+
+                // negative indices count from the end of the list.
+                let index = if index < 0 {
+                    index + elems.len() as i64
+                } else {
+                    index
+                };
+
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| elems.get(index).copied())
+            });
+
+            match item {
+                Some(item) => Ok(item),
+                None => PyException::index_error()
+                    .set_message(format!("{} index out of range", kind))
+                    .into(),
+            }
+        },
+
+        (PyValue::Dict(_), "__getitem__") => |cx| {
+            let (dict, key) = (cx.args[0], cx.args[1]);
+            let hash = cx.ecx.hash_object(key)?;
+
+            let value = cx
+                .ecx
+                .runtime()
+                .objects
+                .with_object(dict, |this| match this {
+                    PyValue::Dict(dict) => dict.get(hash).map(|(_, value)| value),
+                    _ => unreachable!(),
+                });
+
+            match value {
+                Some(value) => Ok(value),
+                None => PyException::key_error().into(),
+            }
+        },
+
+        (PyValue::List(_), "append") => |cx| {
+            let (lst, value) = (cx.args[0], cx.args[1]);
+
+            cx.ecx
+                .runtime()
+                .objects
+                .with_object_mut(lst, |this| match this {
+                    PyValue::List(lst) => lst.push(value),
+                    _ => unreachable!(),
+                });
+
+            Ok(cx.ecx.runtime().singletons.none_v)
+        },
+
+        (PyValue::Set(_), "add") => |cx| {
+            let (set, element) = (cx.args[0], cx.args[1]);
+            let hash = cx.ecx.hash_object(element)?;
+
+            // like CPython the element already in the set is kept.
+            cx.ecx
+                .runtime()
+                .objects
+                .with_object_mut(set, |this| match this {
+                    PyValue::Set(set) => {
+                        set.entry(hash).or_insert(element);
+                    }
+                    _ => unreachable!(),
+                });
+
+            Ok(cx.ecx.runtime().singletons.none_v)
+        },
+
+        _ => return None,
+    };
+
+    Some(func)
+}
+
+#[inline]
+fn bool_v(cx: CallCx, b: bool) -> ObjectId {
+    let singletons = &cx.ecx.runtime().singletons;
+
+    if b {
+        singletons.true_v
+    } else {
+        singletons.false_v
+    }
+}
+
+/// Primitives are compared by value, everything else by identity.
+fn values_eq(cx: &CallCx, left: ObjectId, right: ObjectId) -> bool {
+    if left == right {
+        return true;
+    }
+
+    let objects = &cx.ecx.runtime().objects;
+
+    let left = objects.with_object(left, |this| match this {
+        PyValue::Int(n) => Some(PyValue::Int(*n)),
+        PyValue::Bool(b) => Some(PyValue::Int(*b as i64)),
+        PyValue::Float(f) => Some(PyValue::Float(*f)),
+        PyValue::Str(st) => Some(PyValue::Str(st.clone())),
+        PyValue::Bytes(bytes) => Some(PyValue::Bytes(bytes.clone())),
+        _ => None,
+    });
+
+    match left {
+        Some(left) => objects.with_object(right, |this| match (&left, this) {
+            (PyValue::Int(l), PyValue::Int(r)) => l == r,
+            (PyValue::Int(l), PyValue::Bool(r)) => *l == (*r as i64),
+            (PyValue::Int(l), PyValue::Float(r)) => (*l as f64) == *r,
+            (PyValue::Float(l), PyValue::Float(r)) => l == r,
+            (PyValue::Float(l), PyValue::Int(r)) => *l == (*r as f64),
+            (PyValue::Str(l), PyValue::Str(r)) => l == r,
+            (PyValue::Bytes(l), PyValue::Bytes(r)) => l == r,
+            _ => false,
+        }),
+
+        None => false,
+    }
+}
+
+/// The value of an int, bool or float operand of an arithmetic operator.
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    /// Ints are promoted to floats whenever the other operand is a float.
+    #[inline]
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(n) => n as f64,
+            Number::Float(f) => f,
+        }
+    }
+}
+
+fn num_operand(cx: &CallCx, object: ObjectId, sigil: &str) -> PyResult<Number> {
+    let number = cx
+        .ecx
+        .runtime()
+        .objects
+        .with_object(object, |this| match this {
+            PyValue::Int(n) => Some(Number::Int(*n)),
+            PyValue::Bool(b) => Some(Number::Int(*b as i64)),
+            PyValue::Float(f) => Some(Number::Float(*f)),
+            _ => None,
+        });
+
+    match number {
+        Some(number) => Ok(number),
+        None => PyException::type_error()
+            .set_message(format!("bad operand type for '{}'", sigil))
+            .into(),
+    }
+}
+
+fn num_operands(cx: &CallCx, sigil: &str) -> PyResult<(Number, Number)> {
+    let operands = (
+        num_operand(cx, cx.args[0], sigil),
+        num_operand(cx, cx.args[1], sigil),
+    );
+
+    match operands {
+        (Ok(left), Ok(right)) => Ok((left, right)),
+        _ => PyException::type_error()
+            .set_message(format!("'{}' not supported between these operands", sigil))
+            .into(),
+    }
+}
+
+fn num_compare(
+    cx: CallCx,
+    sigil: &str,
+    int_op: fn(i64, i64) -> bool,
+    float_op: fn(f64, f64) -> bool,
+) -> PyResult<ObjectId> {
+    let result = match num_operands(&cx, sigil)? {
+        (Number::Int(left), Number::Int(right)) => int_op(left, right),
+        (left, right) => float_op(left.as_f64(), right.as_f64()),
+    };
+
+    Ok(bool_v(cx, result))
+}
+
+fn num_arith(
+    cx: CallCx,
+    sigil: &str,
+    int_op: fn(i64, i64) -> Option<i64>,
+    float_op: fn(f64, f64) -> f64,
+) -> PyResult<ObjectId> {
+    match num_operands(&cx, sigil)? {
+        (Number::Int(left), Number::Int(right)) => match int_op(left, right) {
+            Some(n) => cx.ecx.new_int(n),
+            None => overflow(),
+        },
+
+        (left, right) => cx.ecx.new_float(float_op(left.as_f64(), right.as_f64())),
+    }
+}
+
+#[inline]
+fn overflow<T>() -> PyResult<T> {
+    PyException::runtime_error()
+        .set_message("integer overflow")
+        .into()
+}
+
+#[inline]
+fn zero_division<T>(message: &str) -> PyResult<T> {
+    PyException::zero_division_error()
+        .set_message(message)
+        .into()
+}
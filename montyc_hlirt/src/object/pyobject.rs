@@ -62,4 +62,11 @@ pub trait PyObject: std::fmt::Debug {
 
         ecx.new_string(&repr)
     }
+
+    /// Extend `bucket` with every object this object refers to.
+    ///
+    /// Objects that are not reported here may be freed by the garbage collector
+    /// while this object is still alive.
+    ///
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {}
 }
@@ -50,6 +50,10 @@ impl PyObject for SharedObject {
         self.with(|o| o.repr(ecx))
     }
 
+    fn refs(&self, bucket: &mut Vec<ObjectId>) {
+        self.with(|o| o.refs(bucket))
+    }
+
     fn call(&self, cx: crate::eval::ctx::CallCx) -> PyResult<ObjectId> {
         self.with(|o| o.call(cx))
     }
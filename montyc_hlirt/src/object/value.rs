@@ -1,11 +1,11 @@
 use std::{fmt::Display, rc::Rc};
 
 use montyc_core::{dict::PyDictRaw, patma, SpanRef};
-use montyc_flatcode::FlatCode;
+use montyc_flatcode::{raw_inst::Parameter, FlatCode};
 
 use crate::{eval::ctx::CallCx, exception::PyResult, rt::ModuleKey, ObjectId};
 
-use super::{raw_object::RawObject, shared_object::SharedObject};
+use super::{pyobject::PyObject, raw_object::RawObject, shared_object::SharedObject};
 
 pub(crate) mod sealed {
     use std::rc::Rc;
@@ -175,7 +175,7 @@ impl std::fmt::Debug for AnyFunc {
 pub enum FuncLike<'a> {
     Def {
         body: &'a AnyFunc,
-        params: &'a [(SpanRef, Option<ObjectId>)],
+        params: &'a [Parameter<ObjectId>],
         returns: Option<ObjectId>,
     },
 }
@@ -197,7 +197,11 @@ pub enum PyValue {
     Bytes(Vec<u8>),
     Str(Box<str>),
     List(Vec<ObjectId>),
+    #[from(ignore)]
+    Tuple(Box<[ObjectId]>),
     Dict(PyDictRaw<(ObjectId, ObjectId)>),
+    #[from(ignore)]
+    Set(PyDictRaw<ObjectId>),
     Callable(AnyFunc),
 
     // Complex types
@@ -208,7 +212,7 @@ pub enum PyValue {
 
     Function {
         body: AnyFunc,
-        params: Box<[(SpanRef, Option<ObjectId>)]>,
+        params: Box<[Parameter<ObjectId>]>,
         parent: Option<ObjectId>,
         returns: Option<ObjectId>,
         inner: RawObject,
@@ -217,6 +221,8 @@ pub enum PyValue {
     Class {
         name: Option<SpanRef>,
         parent: Option<ObjectId>,
+        /// The base classes, in the order they were listed in the class definition.
+        bases: Box<[ObjectId]>,
         inner: RawObject,
     },
 
@@ -259,6 +265,30 @@ impl PyValue {
         patma!(elem.as_slice(), PyValue::List(ref elem) in self)
     }
 
+    /// The name of the type of this value as it appears in error messages.
+    ///
+    /// Instances of classes are named after their class, which takes a runtime to look up,
+    /// so they are only named `object` here.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PyValue::Any(_) | PyValue::Dynamic(_) => "object",
+            PyValue::Int(_) => "int",
+            PyValue::Float(_) => "float",
+            PyValue::Bool(_) => "bool",
+            PyValue::None => "NoneType",
+            PyValue::Ellipsis => "ellipsis",
+            PyValue::Bytes(_) => "bytes",
+            PyValue::Str(_) => "str",
+            PyValue::List(_) => "list",
+            PyValue::Tuple(_) => "tuple",
+            PyValue::Dict(_) => "dict",
+            PyValue::Set(_) => "set",
+            PyValue::Callable(_) | PyValue::Function { .. } => "function",
+            PyValue::Module { .. } => "module",
+            PyValue::Class { .. } => "type",
+        }
+    }
+
     #[inline]
     pub fn as_func<'a>(&'a self) -> Option<FuncLike<'a>> {
         match self {
@@ -293,16 +323,47 @@ impl PyValue {
     /// Like `refs` but allows the caller to provide a vec to extend.
     #[inline]
     pub fn refs_with(&self, bucket: &mut Vec<ObjectId>) {
-        fn func_refs(func: &AnyFunc, _bucket: &mut Vec<ObjectId>) {
+        fn func_refs(func: &AnyFunc, bucket: &mut Vec<ObjectId>) {
             match func {
+                AnyFunc::Native {
+                    hook: Some(GlobalsHook::Globals(globals)),
+                    ..
+                }
+                | AnyFunc::Boxed {
+                    hook: Some(GlobalsHook::Globals(globals)),
+                    ..
+                } => bucket.push(*globals),
+
                 AnyFunc::Native { .. } | AnyFunc::Boxed { .. } | AnyFunc::Code { .. } => (),
             }
         }
 
         match self {
-            PyValue::Class { inner: raw, .. }
-            | PyValue::Module { inner: raw, .. }
-            | PyValue::Any(raw) => bucket.extend(
+            PyValue::Class {
+                inner: raw,
+                bases,
+                parent,
+                ..
+            } => bucket.extend(
+                raw.__dict__
+                    .iter()
+                    .flat_map(|(_, (k, v))| [*k, *v].into_iter())
+                    .chain(Some(raw.__class__.clone()).into_iter())
+                    .chain(bases.iter().cloned())
+                    .chain(*parent),
+            ),
+
+            PyValue::Module {
+                inner: raw,
+                mkey: ModuleKey::Object(object),
+            } => bucket.extend(
+                raw.__dict__
+                    .iter()
+                    .flat_map(|(_, (k, v))| [*k, *v].into_iter())
+                    .chain([raw.__class__, *object]),
+            ),
+
+            PyValue::Module { inner: raw, .. } | PyValue::Any(raw) => bucket.extend(
                 raw.__dict__
                     .iter()
                     .flat_map(|(_, (k, v))| [*k, *v].into_iter())
@@ -310,10 +371,13 @@ impl PyValue {
             ),
 
             PyValue::List(elems) => bucket.extend_from_slice(elems.as_slice()),
+            PyValue::Tuple(elems) => bucket.extend_from_slice(elems),
             PyValue::Dict(elems) => {
                 bucket.extend(elems.iter().flat_map(|(_, (k, v))| [*k, *v].into_iter()))
             }
 
+            PyValue::Set(elems) => bucket.extend(elems.values().copied()),
+
             PyValue::Callable(func) => {
                 func_refs(func, bucket);
             }
@@ -337,7 +401,7 @@ impl PyValue {
                 bucket.extend(
                     params
                         .iter()
-                        .map(|(_, ann)| ann.clone())
+                        .flat_map(|param| [param.annotation, param.default])
                         .chain(Some(returns.clone()))
                         .filter_map(|ann| ann.clone()),
                 );
@@ -345,7 +409,7 @@ impl PyValue {
                 func_refs(body, bucket);
             }
 
-            PyValue::Dynamic(_) => todo!(),
+            PyValue::Dynamic(object) => object.refs(bucket),
 
             PyValue::Int(_)
             | PyValue::Float(_)
@@ -0,0 +1,142 @@
+//! A tracing mark-and-sweep garbage collector over the runtime's `ObjectSpace`.
+//!
+//! Objects are never freed while Rust code may still be holding on to their
+//! `ObjectId`s, so collections only happen at "safe points" between two
+//! instructions of a frame. At a safe point the frame's own references are all
+//! in its `FrameState`, but its callers (suspended frames and native functions)
+//! may be holding references the collector can not see. To stay sound every
+//! object allocated before the frame started executing is treated as a root,
+//! only the objects the frame itself allocated are candidates for collection.
+//!
+
+use ahash::{AHashMap, AHashSet};
+
+use crate::exception::{PyException, PyResult};
+use crate::storage::ObjectSpace;
+use crate::ObjectId;
+
+use super::Runtime;
+
+/// The fewest live objects there have to be before a collection is attempted.
+const MIN_COLLECTION_THRESHOLD: usize = 16 * 1024;
+
+#[derive(Debug)]
+pub struct GcState {
+    /// Objects the host has pinned, and how many times they have been pinned.
+    pins: AHashMap<ObjectId, usize>,
+
+    /// The amount of objects at which the next collection happens.
+    next_collection: usize,
+
+    /// The most objects that may be alive at once.
+    heap_limit: Option<usize>,
+}
+
+impl Default for GcState {
+    fn default() -> Self {
+        Self {
+            pins: AHashMap::new(),
+            next_collection: MIN_COLLECTION_THRESHOLD,
+            heap_limit: None,
+        }
+    }
+}
+
+impl<Space: ObjectSpace> Runtime<Space> {
+    /// Keep `object`, and everything it refers to, alive until it is unpinned as many times as it was pinned.
+    pub fn pin(&mut self, object: ObjectId) {
+        *self.gc.pins.entry(object).or_default() += 1;
+    }
+
+    pub fn unpin(&mut self, object: ObjectId) {
+        if let Some(count) = self.gc.pins.get_mut(&object) {
+            *count -= 1;
+
+            if *count == 0 {
+                self.gc.pins.remove(&object);
+            }
+        }
+    }
+
+    /// Set the maximum amount of live objects, exceeding it raises a `MemoryError`.
+    pub fn set_heap_limit(&mut self, limit: Option<usize>) {
+        self.gc.heap_limit = limit;
+    }
+
+    /// Whether enough objects have been allocated since the last collection to warrant another.
+    #[inline]
+    pub(crate) fn under_pressure(&self) -> bool {
+        let size = self.objects.size_hint().unwrap_or(0);
+
+        size >= self.gc.next_collection || self.gc.heap_limit.map_or(false, |limit| size > limit)
+    }
+
+    /// Raise a `MemoryError` if there are more live objects than the heap limit allows.
+    pub(crate) fn check_heap_limit(&self) -> PyResult<()> {
+        let size = self.objects.size_hint().unwrap_or(0);
+
+        match self.gc.heap_limit {
+            Some(limit) if size > limit => PyException::memory_error()
+                .set_message(format!(
+                    "{} live objects exceeds the heap limit of {}",
+                    size, limit
+                ))
+                .into(),
+
+            _ => Ok(()),
+        }
+    }
+
+    /// Free every object that is unreachable from the runtime's roots.
+    ///
+    /// Only call this when no evaluation is in progress, the runtime can not know about
+    /// the objects being held by executing frames.
+    ///
+    pub fn collect_garbage(&mut self) -> usize {
+        self.collect_young(&[], ObjectId::from(1))
+    }
+
+    /// Free the objects allocated at or after `boundary` that are unreachable from `roots`,
+    /// the runtime's roots, and the objects allocated before `boundary`.
+    pub(crate) fn collect_young(&mut self, roots: &[ObjectId], boundary: ObjectId) -> usize {
+        let mut worklist = roots.to_vec();
+
+        worklist.extend(self.singletons.objects());
+        worklist.extend(self.module_objects.values().map(|module| module.alloc));
+        worklist.extend(self.gc.pins.keys());
+
+        self.objects.for_each(|object, _| {
+            if object < boundary {
+                worklist.push(object);
+            }
+        });
+
+        let mut marked = AHashSet::with_capacity(worklist.len());
+
+        while let Some(object) = worklist.pop() {
+            if object.is_uninit() || !self.objects.contains(object) || !marked.insert(object) {
+                continue;
+            }
+
+            self.objects
+                .with_object(object, |value| value.refs_with(&mut worklist));
+        }
+
+        let freed = self.objects.retain(|object, _| marked.contains(&object));
+
+        // interned values are only weakly held, forget the ones that were just freed.
+        self.singletons
+            .dynamic
+            .retain(|_, object| marked.contains(object));
+
+        self.gc.next_collection = MIN_COLLECTION_THRESHOLD.max(marked.len() * 2);
+
+        log::debug!(
+            "[Runtime::collect_young] freed {} objects, {} are still alive.",
+            freed,
+            marked.len()
+        );
+
+        freed
+    }
+}
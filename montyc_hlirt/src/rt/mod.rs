@@ -7,8 +7,9 @@ use std::rc::Rc;
 
 use montyc_core::{dict::PyDictRaw, ModuleRef, SpanRef};
 use montyc_core::{MapT, MODULE};
-use montyc_flatcode::FlatCode;
+use montyc_flatcode::{raw_inst::Parameter, FlatCode};
 
+use crate::eval::bytecode::CodeObject;
 use crate::eval::ctx::{EvaluationContext, UnboundEvaluationContext};
 use crate::exception::{PyException, PyResult, PyResultExt};
 use crate::object::{AnyFunc, ObjectBuilder, ObjectId, PyValue};
@@ -96,6 +97,7 @@ pub enum ModuleKey {
     Object(ObjectId),
 }
 
+pub mod gc;
 pub mod singletons;
 
 /// An interpreter runtime capable of executing Python.
@@ -112,6 +114,12 @@ pub struct Runtime<Space: ObjectSpace = DefaultObjectSpace> {
 
     /// A map of module refs to their module objects.
     pub module_objects: MapT<ModuleRef, ModuleMetadata>,
+
+    /// Bookkeeping of the garbage collector.
+    pub(crate) gc: gc::GcState,
+
+    /// Bytecode compiled from the sequences of modules, keyed by the module and sequence index.
+    pub(crate) code_cache: MapT<(ModuleRef, usize), (Rc<FlatCode>, Rc<CodeObject>)>,
 }
 
 impl<Space: ObjectSpace> Runtime<Space> {
@@ -153,6 +161,8 @@ impl<Space: ObjectSpace> Runtime<Space> {
             bytes_class,
             list_class,
             dict_class,
+            set_class,
+            tuple_class,
             ..
         } = &self.singletons;
 
@@ -170,16 +180,70 @@ impl<Space: ObjectSpace> Runtime<Space> {
             PyValue::Any(raw) => raw.__class__,
             PyValue::Bytes(_) => *bytes_class,
             PyValue::List(_) => *list_class,
+            PyValue::Tuple(_) => *tuple_class,
             PyValue::Dict(_) => *dict_class,
+            PyValue::Set(_) => *set_class,
             PyValue::Callable(func) => match func {
                 AnyFunc::Native { .. } | AnyFunc::Boxed { .. } => *function_class,
                 AnyFunc::Code { .. } => *function_class,
             },
         })
     }
+
+    /// The method resolution order of a class, the class itself followed by its bases depth-first.
+    pub fn mro(&self, klass: ObjectId) -> Vec<ObjectId> {
+        let mut mro = vec![];
+        let mut stack = vec![klass];
+
+        while let Some(klass) = stack.pop() {
+            if mro.contains(&klass) {
+                continue;
+            }
+
+            mro.push(klass);
+
+            self.objects.with_object(klass, |this| match this {
+                PyValue::Class { bases, .. } => stack.extend(bases.iter().rev()),
+                _ => (),
+            });
+        }
+
+        mro
+    }
+
+    /// Check if `klass` is `base` or derives from it.
+    pub fn is_subclass(&self, klass: ObjectId, base: ObjectId) -> bool {
+        self.mro(klass).contains(&base)
+    }
 }
 
 impl<Space: ObjectSpace> Runtime<Space> {
+    /// Get the bytecode of the sequence `seq_id` of `module`, compiling it the first time it is asked for.
+    pub fn code_object(&mut self, module: &Rc<FlatCode>, seq_id: usize) -> Rc<CodeObject> {
+        match self.code_cache.get(&(module.mref, seq_id)) {
+            // the same module ref may be lowered more than once, only reuse code compiled from this lowering.
+            Some((code, object)) if Rc::ptr_eq(code, module) => Rc::clone(object),
+
+            _ => {
+                let object = Rc::new(CodeObject::compile(&module.sequences()[seq_id]));
+
+                log::trace!(
+                    "[Runtime::code_object] compiled sequence {} of {:?}:\n{}",
+                    seq_id,
+                    module.mref,
+                    object
+                );
+
+                self.code_cache.insert(
+                    (module.mref, seq_id),
+                    (Rc::clone(module), Rc::clone(&object)),
+                );
+
+                object
+            }
+        }
+    }
+
     pub fn new_string(&mut self, string: &str) -> ObjectId {
         let hash = self.hash(string);
         let key = DynamicSingleton::Str(hash);
@@ -222,6 +286,10 @@ impl<Space: ObjectSpace> Runtime<Space> {
         alloc
     }
 
+    pub fn new_float(&mut self, f: f64) -> ObjectId {
+        self.objects.insert(PyValue::Float(f))
+    }
+
     pub fn new_callable<C>(&self, callable: C) -> ObjectId
     where
         C: Into<ReadyCallable>,
@@ -281,7 +349,7 @@ impl<Space: ObjectSpace> Runtime<Space> {
     pub fn new_function(
         &self,
         body: AnyFunc,
-        params: Box<[(SpanRef, Option<ObjectId>)]>,
+        params: Box<[Parameter<ObjectId>]>,
         returns: Option<ObjectId>,
     ) -> ObjectId {
         let func = PyValue::Function {
@@ -303,6 +371,8 @@ impl Runtime {
             singletons: Default::default(),
             objects: DefaultObjectSpace::new(),
             module_objects: Default::default(),
+            gc: Default::default(),
+            code_cache: Default::default(),
         }
     }
 
@@ -319,6 +389,7 @@ impl Runtime {
                     self.objects.insert(PyValue::Class {
                         name: None,
                         parent: None,
+                        bases: Default::default(),
                         inner: Default::default(),
                     });
 
@@ -31,7 +31,9 @@ pub struct Singletons {
     pub bytes_class: ObjectId,
     pub list_class: ObjectId,
     pub dict_class: ObjectId,
+    pub set_class: ObjectId,
     pub tuple_class: ObjectId,
+    pub range_class: ObjectId,
 
     // Constants
     pub none_v: ObjectId,
@@ -46,6 +48,40 @@ pub struct Singletons {
     pub(crate) dynamic: AHashMap<DynamicSingleton, ObjectId>,
 }
 
+impl Singletons {
+    /// Every singleton object, these are always considered alive by the garbage collector.
+    ///
+    /// The interned ints and strings in `dynamic` are not included, the collector treats them as weak references.
+    pub(crate) fn objects(&self) -> impl Iterator<Item = ObjectId> + '_ {
+        [
+            self.builtins,
+            self.monty,
+            self.sys,
+            self.function_class,
+            self.module_class,
+            self.string_class,
+            self.bool_class,
+            self.int_class,
+            self.type_class,
+            self.object_class,
+            self.float_class,
+            self.none_class,
+            self.ellipsis_class,
+            self.bytes_class,
+            self.list_class,
+            self.dict_class,
+            self.set_class,
+            self.tuple_class,
+            self.range_class,
+            self.none_v,
+            self.false_v,
+            self.true_v,
+            self.ellipsis_v,
+        ]
+        .into_iter()
+    }
+}
+
 impl fmt::Debug for Singletons {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Singletons")
@@ -65,6 +101,9 @@ impl fmt::Debug for Singletons {
             .field("bytes_class", &self.bytes_class)
             .field("list_class", &self.list_class)
             .field("dict_class", &self.dict_class)
+            .field("set_class", &self.set_class)
+            .field("tuple_class", &self.tuple_class)
+            .field("range_class", &self.range_class)
             .field("none_v", &self.none_v)
             .field("false_v", &self.false_v)
             .field("true_v", &self.true_v)
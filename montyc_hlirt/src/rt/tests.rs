@@ -1,13 +1,127 @@
+use std::num::NonZeroU64;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
 use super::*;
 use crate::{
-    eval::ctx::EvalGlue,
+    eval::ctx::{CallCx, EvalGlue},
     exception::InnerExc,
-    object::{PyObject, SharedObject},
-    test::setup,
+    object::{PyObject, ReadyCallable, SharedObject},
+    test::{setup, TestHost},
 };
 
+/// The builtins the evaluation tests rely on, `setup` leaves the runtime without any.
+const BUILTINS_PRELUDE: &str = "
+class BaseException:
+    pass
+class Exception(BaseException):
+    pass
+class ImportError(Exception):
+    pass
+class LookupError(Exception):
+    pass
+class KeyError(LookupError):
+    pass
+class NameError(Exception):
+    pass
+class TypeError(Exception):
+    pass
+class ValueError(Exception):
+    pass
+def len(obj):
+    return obj.__len__()
+class range:
+    pass
+";
+
+/// A native function counting how often it has been called.
+#[derive(Debug)]
+struct Counter(Rc<AtomicUsize>);
+
+impl PyObject for Counter {
+    fn call(&self, cx: crate::eval::ctx::CallCx) -> PyResult<ObjectId> {
+        let n = self.0.fetch_add(1, Ordering::Relaxed);
+
+        cx.ecx.new_int(i64::try_from(n).unwrap())
+    }
+
+    unsafe fn std_type_id(&self) -> std::any::TypeId {
+        std::any::TypeId::of::<Counter>()
+    }
+}
+
+/// An initialized runtime with `BUILTINS_PRELUDE` as its builtins module.
+fn setup_with_builtins() -> (Runtime, TestHost) {
+    let (mut rt, mut host) = setup();
+
+    rt.try_init(&mut host);
+
+    let builtins = rt
+        .eval(&mut host, BUILTINS_PRELUDE)
+        .unwrap()
+        .run_until_complete()
+        .unwrap();
+
+    rt.singletons.builtins = builtins;
+
+    (rt, host)
+}
+
+/// Evaluate every `(source, expected)` case with a `Counter` imported as `n`,
+/// asserting that the case called it `expected` times.
+fn eval_cases<S: AsRef<str>>(rt: &mut Runtime, host: &mut TestHost, cases: &[(S, usize)]) {
+    eval_cases_in(rt, host, ObjectBuilder::module(), cases)
+}
+
+/// Like `eval_cases` but every attribute of `module` is imported alongside `n`.
+fn eval_cases_in<S: AsRef<str>>(
+    rt: &mut Runtime,
+    host: &mut TestHost,
+    module: ObjectBuilder<{ MODULE }>,
+    cases: &[(S, usize)],
+) {
+    let counter = Rc::new(AtomicUsize::new(0));
+    let module = module.setattr("n", SharedObject::new(Counter(counter.clone())));
+    let names: Vec<_> = module.properties.keys().cloned().collect();
+
+    let mref = host.mrefs.into();
+
+    host.mrefs += 1;
+
+    rt.synthesise_module(mref, module).unwrap();
+
+    for (source, expected) in cases {
+        let source = source.as_ref();
+        let count = counter.load(Ordering::Relaxed);
+
+        rt.eval(host, source)
+            .unwrap()
+            .from_module_import(mref, names.iter())
+            .run_until_complete()
+            .unwrap();
+
+        assert_eq!(
+            counter.load(Ordering::Relaxed) - count,
+            *expected,
+            "{:?}",
+            source
+        );
+    }
+}
+
+/// Evaluate `source` asserting that it raises an exception of the given kind.
+fn assert_raises(rt: &mut Runtime, host: &mut TestHost, source: &str, kind: &str) -> PyException {
+    match rt.eval(host, source).unwrap().run_until_complete() {
+        Err(exc) => {
+            assert_eq!(exc.kind(), kind, "{:?}", source);
+            exc
+        }
+
+        val => panic!("Expected a {} for {:?} instead got {:?}", kind, source, val),
+    }
+}
+
 #[test]
 pub fn basic() {
     let (mut rt, mut host) = setup();
@@ -46,6 +160,25 @@ pub fn name_error() {
     }
 }
 
+#[test]
+pub fn syntax_errors_are_returned() {
+    let (mut rt, mut host) = setup();
+
+    for (source, culprit) in [("x = = 1", "="), ("break", "break")] {
+        match rt.eval(&mut host, source).map(|_| ()) {
+            Err(RuntimeError::Host(montyc_core::MontyError::Parse { errors, .. })) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(&source[errors[0].span.clone()], culprit);
+            }
+
+            val => panic!(
+                "Expected a syntax error for {:?} instead got {:?}",
+                source, val
+            ),
+        }
+    }
+}
+
 #[test]
 #[should_panic(
     expected = "not yet implemented: sys module has not been initialized yet, sys.path is unavailable."
@@ -152,3 +285,595 @@ pub fn import_example_module_with_init_sys() {
         .run_until_complete()
         .unwrap();
 }
+
+#[test]
+pub fn import_namespace_package_across_search_roots() {
+    let (mut rt, mut host) = setup();
+
+    rt.try_init(&mut host);
+
+    let base = std::env::temp_dir().join(format!("montyc-hlirt-ns-{}", std::process::id()));
+    let roots = [base.join("a"), base.join("b")];
+
+    let files = [
+        (&roots[0], "ns/one.py"),
+        (&roots[1], "ns/two.py"),
+        (&roots[1], "ns/nested/three.py"),
+    ];
+
+    for (root, file) in files {
+        let file = root.join(file);
+
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, "x = 1\n").unwrap();
+    }
+
+    let sys = rt.singletons.sys;
+    let (_, sys_path) = rt.getattr_direct_hash(sys, rt.hash("path")).unwrap();
+
+    for root in roots.iter() {
+        let root = rt.new_string(root.to_string_lossy().as_ref());
+
+        rt.objects.with_object_mut(sys_path, |val| match val {
+            PyValue::List(lst) => lst.push(root),
+            _ => unreachable!(),
+        });
+    }
+
+    let result = rt
+        .eval(
+            &mut host,
+            "import ns.one\nimport ns.two\nimport ns.nested.three\nns.nested.three.x",
+        )
+        .unwrap()
+        .run_until_complete();
+
+    let _ = std::fs::remove_dir_all(&base);
+
+    result.unwrap();
+
+    let (_, sys_modules) = rt.getattr_direct_hash(sys, rt.hash("modules")).unwrap();
+    let ns = rt.objects.with_object(sys_modules, |val| match val {
+        PyValue::Dict(dict) => dict.get(rt.hash("ns")).unwrap().1,
+        _ => unreachable!(),
+    });
+
+    // both roots contribute a portion of `ns`.
+    let (_, ns_path) = rt.getattr_direct_hash(ns, rt.hash("__path__")).unwrap();
+
+    rt.objects.with_object(ns_path, |val| match val {
+        PyValue::List(lst) => assert_eq!(lst.len(), 2),
+        _ => unreachable!(),
+    });
+}
+
+#[test]
+pub fn for_loop_over_native_list() {
+    let (mut rt, mut host) = setup();
+
+    let elements = (1..=3).map(|n| rt.new_int(n)).collect();
+    let module = ObjectBuilder::module().setattr("xs", PyValue::List(elements));
+
+    let cases = [
+        ("for x in xs:\n    n()", 3),
+        ("for x in xs:\n    n()\nelse:\n    n()", 4),
+        ("for x in xs:\n    break\nelse:\n    n()", 0),
+        ("for x in xs:\n    continue\n    n()\nelse:\n    n()", 1),
+        (
+            "for x in xs:\n    for y in xs:\n        n()\n        break",
+            3,
+        ),
+        (
+            "for x in xs:\n    try:\n        break\n    finally:\n        n()",
+            1,
+        ),
+    ];
+
+    eval_cases_in(&mut rt, &mut host, module, &cases);
+}
+
+#[test]
+pub fn ranges_and_iteration() {
+    let (mut rt, mut host) = setup_with_builtins();
+
+    let cases = [
+        ("for i in range(3):\n    n()", 3),
+        ("for i in range(0):\n    n()", 0),
+        ("for i in range(1, 10, 3):\n    n()", 3),
+        ("for i in range(5, 0, -2):\n    n()", 3),
+        ("for i in range(0, 5, -1):\n    n()", 0),
+        (
+            "t = 0\nfor i in range(1, 5):\n    t = t + i\nif t == 10:\n    n()",
+            1,
+        ),
+        ("r = range(2, 4)\nif r.stop - r.start == 2:\n    n()", 1),
+        ("for c in word:\n    n()", 3),
+        ("for b in data:\n    n()\n    if b == 2:\n        n()", 4),
+        (
+            "for k, v in [(1, 2), (3, 4)]:\n    if v - k == 1:\n        n()",
+            2,
+        ),
+        (
+            "for i, (a, b) in [(0, (1, 2))]:\n    if a + b == 3:\n        n()",
+            1,
+        ),
+        (
+            "d = {k: v for k, v in [(1, 2), (3, 4)]}\nfor k in d:\n    n()",
+            2,
+        ),
+        (
+            "for _ in [a for a, b in [(1, 2), (3, 4)] if b == 4]:\n    n()",
+            1,
+        ),
+        (
+            "t = (1, 2)\nif len(t) == 2:\n    if t[-1] == 2:\n        n()",
+            1,
+        ),
+        ("d = {(1, 2): 1}\nif d[(1, 2)] == 1:\n    n()", 1),
+    ];
+
+    let module = ObjectBuilder::module()
+        .setattr("word", PyValue::Str("abc".into()))
+        .setattr("data", PyValue::Bytes(vec![1, 2, 3]));
+
+    eval_cases_in(&mut rt, &mut host, module, &cases);
+
+    for (source, kind, message) in [
+        (
+            "range(0, 1, 0)",
+            "ValueError",
+            "range() arg 3 must not be zero",
+        ),
+        (
+            "range('a')",
+            "TypeError",
+            "'str' object cannot be interpreted as an integer",
+        ),
+        (
+            "range()",
+            "TypeError",
+            "range expected at least 1 argument, got 0",
+        ),
+        (
+            "for x in 1:\n    pass",
+            "TypeError",
+            "'int' object is not iterable",
+        ),
+        (
+            "for x in None:\n    pass",
+            "TypeError",
+            "'NoneType' object is not iterable",
+        ),
+        (
+            "for x in len:\n    pass",
+            "TypeError",
+            "'function' object is not iterable",
+        ),
+        (
+            "for x in range:\n    pass",
+            "TypeError",
+            "'type' object is not iterable",
+        ),
+        (
+            "class C:\n    pass\nfor x in C():\n    pass",
+            "TypeError",
+            "'C' object is not iterable",
+        ),
+        ("len(*1)", "TypeError", "'int' object is not iterable"),
+        (
+            "for a, b in [(1, 2, 3)]:\n    pass",
+            "ValueError",
+            "too many values to unpack (expected 2)",
+        ),
+        (
+            "for a, b in [(1,)]:\n    pass",
+            "ValueError",
+            "not enough values to unpack (expected 2, got 1)",
+        ),
+        (
+            "for a, b in [1]:\n    pass",
+            "TypeError",
+            "'int' object is not iterable",
+        ),
+    ] {
+        let exc = assert_raises(&mut rt, &mut host, source, kind);
+        assert_eq!(exc.message(), Some(message), "{:?}", source);
+    }
+}
+
+#[test]
+pub fn try_except_finally() {
+    let (mut rt, mut host) = setup_with_builtins();
+
+    let cases = [
+        ("try:\n    n()\nexcept Exception:\n    n()", 1),
+        ("try:\n    raise ValueError('x')\nexcept TypeError:\n    pass\nexcept ValueError:\n    n()", 1),
+        ("try:\n    raise ValueError\nexcept Exception as e:\n    n()\nelse:\n    n()", 1),
+        ("try:\n    n()\nexcept Exception:\n    pass\nelse:\n    n()\nfinally:\n    n()", 3),
+        ("try:\n    try:\n        raise KeyError\n    finally:\n        n()\nexcept LookupError:\n    n()", 2),
+        ("try:\n    try:\n        raise KeyError\n    except KeyError:\n        n()\n        raise\nexcept Exception:\n    n()", 2),
+        ("try:\n    undefined\nexcept NameError:\n    n()", 1),
+        ("try:\n    import missing\nexcept ImportError:\n    n()", 1),
+        ("class Error(Exception):\n    pass\ntry:\n    raise Error\nexcept Exception:\n    n()", 1),
+    ];
+
+    eval_cases(&mut rt, &mut host, &cases);
+
+    let exc = assert_raises(&mut rt, &mut host, "raise ValueError('bad')", "ValueError");
+
+    assert!(exc.message().unwrap().contains("bad"));
+}
+
+#[test]
+pub fn comparisons() {
+    let (mut rt, mut host) = setup_with_builtins();
+
+    let cases = [
+        ("i = 0\nwhile i < 3:\n    n()\n    i = i + 1", 3),
+        ("i = 5\nwhile i >= 1:\n    n()\n    i = i - 2", 3),
+        ("if 1 <= 1 < 2:\n    n()", 1),
+        ("if 1 < 3 < 2:\n    n()", 0),
+        ("if 3 > 2 > 1 > 0:\n    n()", 1),
+        ("if 1 == 1 != 2:\n    n()", 1),
+        ("if not 1 > 2:\n    n()", 1),
+        ("if None is None:\n    n()", 1),
+        ("if 1 is not None:\n    n()", 1),
+    ];
+
+    eval_cases(&mut rt, &mut host, &cases);
+}
+
+#[test]
+pub fn keyword_and_default_arguments() {
+    let (mut rt, mut host) = setup_with_builtins();
+
+    let cases = [
+        ("def f(a, b=2):\n    return a + b\nif f(1) == 3:\n    n()", 1),
+        ("def f(a, b=2):\n    return a - b\nif f(b=1, a=5) == 4:\n    n()", 1),
+        ("def f(a, *, b):\n    return a - b\nif f(5, b=1) == 4:\n    n()", 1),
+        ("def f(a, /, b=1):\n    return a + b\nif f(1, 2) == 3:\n    n()", 1),
+        ("def f(*args):\n    return args\nfor _ in f(1, 2, 3):\n    n()", 3),
+        (
+            "def f(a, b):\n    return a - b\ndef g(h, *args, **kwargs):\n    return h(*args, **kwargs)\nif g(f, 5, b=1) == 4:\n    n()",
+            1,
+        ),
+        // defaults are evaluated once, when the function is defined.
+        ("x = 1\ndef f(a=x):\n    return a\nx = 2\nif f() == 1:\n    n()", 1),
+    ];
+
+    eval_cases(&mut rt, &mut host, &cases);
+
+    let bad_calls = [
+        "def f(a):\n    pass\nf()",
+        "def f(a):\n    pass\nf(1, 2)",
+        "def f(a):\n    pass\nf(b=1)",
+        "def f(a, b):\n    pass\nf(1, a=1)",
+        "def f(a, /):\n    pass\nf(a=1)",
+        "def f(*, a):\n    pass\nf(1)",
+    ];
+
+    for source in bad_calls {
+        assert_raises(&mut rt, &mut host, source, "TypeError");
+    }
+}
+
+#[test]
+pub fn floats() {
+    let (mut rt, mut host) = setup_with_builtins();
+
+    let cases = [
+        ("if 1.5e3 == 1500:\n    n()", 1),
+        ("if .25 + .25 == 0.5:\n    n()", 1),
+        ("if 2E-2 > 0.02:\n    n()", 0),
+        ("if 3 * 0.5 == 1.5:\n    n()", 1),
+        ("if 1 / 2 == 0.5:\n    n()", 1),
+        ("if 7.5 // 2 == 3.0:\n    n()", 1),
+        ("if -7.5 % 2 == 0.5:\n    n()", 1),
+        ("if 1 < 1.5 <= 2.0:\n    n()", 1),
+        ("x = 2.5\nif -x < 0:\n    n()", 1),
+        ("if 0.0:\n    n()", 0),
+    ];
+
+    eval_cases(&mut rt, &mut host, &cases);
+}
+
+#[test]
+pub fn tick_bound() {
+    let (mut rt, mut host) = setup();
+
+    let sources = [
+        "while True:\n    pass",
+        // running out of ticks can not be caught.
+        "try:\n    while True:\n        pass\nexcept:\n    pass",
+    ];
+
+    for source in sources {
+        let result = rt
+            .eval(&mut host, source)
+            .unwrap()
+            .set_upper_tick_bound(NonZeroU64::new(100))
+            .run_until_complete();
+
+        match result {
+            Err(exc) if exc.is_exceeded_ticks() => {
+                let frame = exc.frames().first().expect("no frame was recorded");
+                assert!(frame.span.is_some(), "{:?}", source);
+            }
+
+            val => panic!(
+                "Expected the tick bound to be exceeded instead got {:?}",
+                val
+            ),
+        }
+    }
+
+    rt.eval(&mut host, "x = 1")
+        .unwrap()
+        .set_upper_tick_bound(NonZeroU64::new(100))
+        .run_until_complete()
+        .unwrap();
+}
+
+#[test]
+pub fn deadline() {
+    let (mut rt, mut host) = setup();
+
+    let result = rt
+        .eval(&mut host, "while True:\n    pass")
+        .unwrap()
+        .set_deadline(Some(Instant::now()))
+        .run_until_complete();
+
+    match result {
+        Err(exc) if exc.is_exceeded_deadline() => (),
+        val => panic!("Expected the deadline to be exceeded instead got {:?}", val),
+    }
+}
+
+#[test]
+pub fn traceback() {
+    let (mut rt, mut host) = setup();
+
+    let source = "def f():\n    return 1 // 0\n\ndef g(h):\n    return h()\n\ng(f)";
+
+    let exc = rt
+        .eval(&mut host, source)
+        .unwrap()
+        .run_until_complete()
+        .unwrap_err();
+
+    assert_eq!(exc.kind(), "ZeroDivisionError");
+
+    let frames: Vec<_> = exc
+        .frames()
+        .iter()
+        .map(|frame| {
+            let span = frame.span.clone().expect("frame has no span");
+            (frame.name.as_deref(), &source[span])
+        })
+        .collect();
+
+    assert_eq!(
+        frames,
+        [(Some("f"), "1 // 0"), (Some("g"), "h()"), (None, "g(f)")]
+    );
+}
+
+#[test]
+pub fn garbage_collection() {
+    let (mut rt, mut host) = setup();
+
+    rt.try_init(&mut host);
+
+    // every iteration allocates fresh ints that are garbage by the next one.
+    let source = "x = 0\nwhile x < 50000:\n    y = x * 1000\n    x = x + 1";
+
+    rt.eval(&mut host, source)
+        .unwrap()
+        .run_until_complete()
+        .unwrap();
+
+    let live = rt.objects.size_hint().unwrap();
+
+    assert!(live < 50000, "{} objects are still alive", live);
+
+    // without an evaluation in progress only the module and what it refers to survive.
+    let before = rt.objects.size_hint().unwrap();
+    let freed = rt.collect_garbage();
+
+    assert_eq!(rt.objects.size_hint().unwrap(), before - freed);
+}
+
+#[test]
+pub fn heap_limit() {
+    let (mut rt, mut host) = setup();
+
+    rt.set_heap_limit(Some(1));
+
+    let result = rt.eval(&mut host, "x = 1000").unwrap().run_until_complete();
+
+    match result {
+        Err(exc) => assert_eq!(exc.kind(), "MemoryError"),
+        val => panic!("Expected a MemoryError instead got {:?}", val),
+    }
+}
+
+#[test]
+pub fn lists_dicts_and_sets() {
+    let (mut rt, mut host) = setup_with_builtins();
+
+    let cases = [
+        ("xs = [1, 2, 3]\nif len(xs) == 3:\n    n()", 1),
+        ("xs = [1, 2, 3]\nif xs[0] + xs[-1] == 4:\n    n()", 1),
+        ("xs = []\nxs.append(4)\nif xs[0] == 4:\n    n()", 1),
+        ("for _ in [x * 2 for x in [1, 2, 3] if x != 2]:\n    n()", 2),
+        (
+            "for _ in [x * y for x in [1, 2] for y in [3, 4, 5]]:\n    n()",
+            6,
+        ),
+        ("d = {1: 2, 3: 4}\nif d[3] == 4:\n    n()", 1),
+        ("for _ in {x: x for x in [1, 2, 2]}:\n    n()", 2),
+        ("for _ in {1, 2, 2, 3}:\n    n()", 3),
+        (
+            "s = {x // 2 for x in [1, 2, 3]}\nif len(s) == 2:\n    n()",
+            1,
+        ),
+        ("if len({}) == 0:\n    n()", 1),
+    ];
+
+    eval_cases(&mut rt, &mut host, &cases);
+
+    let errors = [
+        ("[1, 2][2]", "IndexError"),
+        ("{1: 2}[3]", "KeyError"),
+        ("{[1]: 2}", "TypeError"),
+        ("{{1}: 2}", "TypeError"),
+    ];
+
+    for (source, kind) in errors {
+        assert_raises(&mut rt, &mut host, source, kind);
+    }
+
+    // native code handed a dict or a set gets an exception instead of a panic.
+    let module = ObjectBuilder::module()
+        .setattr(
+            "repr",
+            ReadyCallable::from(|cx: CallCx| cx.ecx.repr_object(cx.args[0])),
+        )
+        .setattr(
+            "setattr",
+            ReadyCallable::from(|cx: CallCx| {
+                cx.ecx.setattr(cx.args[0], cx.args[1], cx.args[2])?;
+                Ok(cx.ecx.runtime().singletons.none_v)
+            }),
+        );
+
+    let mref = host.mrefs.into();
+
+    host.mrefs += 1;
+
+    rt.synthesise_module(mref, module).unwrap();
+
+    for source in [
+        "repr({1: 2})",
+        "repr({1})",
+        "setattr({}, \"x\", 1)",
+        "setattr({1}, \"x\", 1)",
+    ] {
+        let result = rt
+            .eval(&mut host, source)
+            .unwrap()
+            .from_module_import(mref, ["repr", "setattr"])
+            .run_until_complete();
+
+        match result {
+            Err(exc) => assert_eq!(exc.kind(), "TypeError", "{:?}", source),
+            val => panic!(
+                "Expected a TypeError for {:?} instead got {:?}",
+                source, val
+            ),
+        }
+    }
+}
+
+#[test]
+pub fn closures_and_lambdas() {
+    let (mut rt, mut host) = setup_with_builtins();
+
+    let cases = [
+        ("def g():\n    n()\ng()\ng()", 2),
+        (
+            "def make(k):\n    def add(x):\n        return x + k\n    return add\nif make(1)(2) == 3:\n    n()",
+            1,
+        ),
+        (
+            "def f():\n    x = 1\n    def g():\n        return x\n    x = 2\n    return g()\nif f() == 2:\n    n()",
+            1,
+        ),
+        (
+            "def a(x):\n    def b():\n        def c():\n            return x\n        return c()\n    return b()\nif a(5) == 5:\n    n()",
+            1,
+        ),
+        ("f = lambda x, y=2: x * y\nif f(3) == 6:\n    n()", 1),
+        (
+            "apply = lambda f, x: f(x)\nfor x in [1, 2, 3]:\n    if apply(lambda y: y * x, 2) == x * 2:\n        n()",
+            3,
+        ),
+        (
+            "def counter():\n    count = [0]\n    def bump():\n        count.append(1)\n        return len(count)\n    return bump\nc = counter()\nc()\nif c() == 3:\n    n()",
+            1,
+        ),
+    ];
+
+    eval_cases(&mut rt, &mut host, &cases);
+
+    assert_raises(
+        &mut rt,
+        &mut host,
+        "f = lambda: undefined\nf()",
+        "NameError",
+    );
+}
+
+#[test]
+pub fn with_statements() {
+    let (mut rt, mut host) = setup_with_builtins();
+
+    // `Ctx` counts entering and exiting, `Suppress` counts and swallows `ValueError`s.
+    const MANAGERS: &str = "
+class Ctx:
+    def __enter__(self):
+        n()
+        return self
+    def __exit__(self, kind, exc, tb):
+        n()
+        return False
+class Suppress:
+    def __enter__(self):
+        return 1
+    def __exit__(self, kind, exc, tb):
+        if kind is ValueError:
+            n()
+            return True
+        return False
+";
+
+    let cases = [
+        ("with Ctx():\n    n()", 3),
+        ("with Ctx() as a, Ctx() as b:\n    pass", 4),
+        ("with Suppress() as x:\n    if x == 1:\n        n()", 1),
+        ("with Suppress():\n    raise ValueError\nn()", 2),
+        (
+            "try:\n    with Suppress():\n        raise KeyError\nexcept KeyError:\n    n()",
+            1,
+        ),
+        (
+            "try:\n    with Ctx():\n        raise KeyError\nexcept KeyError:\n    n()",
+            3,
+        ),
+        ("def f():\n    with Ctx():\n        return 1\nf()", 2),
+        ("for _ in [1, 2]:\n    with Ctx():\n        break", 2),
+    ];
+
+    let cases: Vec<_> = cases
+        .iter()
+        .map(|(source, expected)| (format!("{}{}", MANAGERS, source), *expected))
+        .collect();
+
+    eval_cases(&mut rt, &mut host, &cases);
+
+    assert_raises(&mut rt, &mut host, "with 1:\n    pass", "AttributeError");
+
+    // `async with` only fails once it is reached.
+    eval_cases(
+        &mut rt,
+        &mut host,
+        &[("def f():\n    async with m():\n        n()\nn()", 1)],
+    );
+    assert_raises(
+        &mut rt,
+        &mut host,
+        "async with m() as x:\n    pass",
+        "NotImplementedError",
+    );
+}
@@ -24,6 +24,9 @@ pub trait ObjectSpace {
     /// Generate a new, unique, unassociated ObjectId.
     fn new_object_id(&self) -> ObjectId;
 
+    /// The ObjectId that will be generated next, every ObjectId generated before it compares less than it.
+    fn next_object_id(&self) -> ObjectId;
+
     /// Insert a PyValue and get back its ObjectId.
     fn insert(&self, value: PyValue) -> ObjectId;
 
@@ -36,6 +39,12 @@ pub trait ObjectSpace {
     /// Invoke the provided FnMut with a **mutable** reference to the PyValue associated with the given ObjectId.
     fn with_object_mut<T>(&self, object: ObjectId, f: impl FnOnce(&mut PyValue) -> T) -> T;
 
+    /// Whether there is a PyValue associated with the given ObjectId.
+    fn contains(&self, object: ObjectId) -> bool;
+
+    /// Remove every object for which `f` returns false, returning how many were removed.
+    fn retain(&self, f: impl FnMut(ObjectId, &PyValue) -> bool) -> usize;
+
     /// The amount of objects in this space.
     fn size_hint(&self) -> Option<usize>;
 
@@ -53,6 +62,10 @@ impl ObjectSpace for DefaultObjectSpace {
         ObjectId::from(id)
     }
 
+    fn next_object_id(&self) -> ObjectId {
+        ObjectId::from(self.last_object_id.load(Ordering::SeqCst))
+    }
+
     fn insert(&self, value: PyValue) -> ObjectId {
         let alloc = self.new_object_id();
         self.inner.insert(alloc, value);
@@ -82,6 +95,18 @@ impl ObjectSpace for DefaultObjectSpace {
         f(value)
     }
 
+    fn contains(&self, object: ObjectId) -> bool {
+        self.inner.contains_key(&object)
+    }
+
+    fn retain(&self, mut f: impl FnMut(ObjectId, &PyValue) -> bool) -> usize {
+        let before = self.inner.len();
+
+        self.inner.retain(|id, value| f(*id, value));
+
+        before - self.inner.len()
+    }
+
     fn size_hint(&self) -> Option<usize> {
         Some(self.inner.len())
     }
@@ -3,7 +3,15 @@ use montyc_core::SpanRef;
 
 use crate::{spanned::Spanned, token::PyToken, AstNode, AstObject, AstVisitor};
 
-use super::Expr;
+use super::{Expr, Primary};
+
+/// A single `for target in iter if cond...` clause of a comprehension.
+#[derive(Debug, Clone)]
+pub struct Comprehension {
+    pub target: Spanned<Primary>,
+    pub iter: Spanned<Expr>,
+    pub ifs: Vec<Spanned<Expr>>,
+}
 
 #[derive(Debug, Clone, derive_more::Unwrap)]
 pub enum Atom {
@@ -14,6 +22,12 @@ pub enum Atom {
     Bool(bool),
     Float(f64),
     Tuple(Vec<Spanned<Expr>>),
+    List(Vec<Spanned<Expr>>),
+    Set(Vec<Spanned<Expr>>),
+    Dict(Vec<(Spanned<Expr>, Spanned<Expr>)>),
+    ListComp(Box<Spanned<Expr>>, Vec<Comprehension>),
+    SetComp(Box<Spanned<Expr>>, Vec<Comprehension>),
+    DictComp(Box<(Spanned<Expr>, Spanned<Expr>)>, Vec<Comprehension>),
     Comment(SpanRef),
     Name(SpanRef),
 }
@@ -28,6 +42,12 @@ impl AstObject for Atom {
             Atom::Bool(_) => AstNode::Bool(self.clone()),
             Atom::Float(_) => AstNode::Float(self.clone()),
             Atom::Tuple(_) => AstNode::Tuple(self.clone()),
+            Atom::List(_) => AstNode::List(self.clone()),
+            Atom::Set(_) => AstNode::Set(self.clone()),
+            Atom::Dict(_) => AstNode::Dict(self.clone()),
+            Atom::ListComp(..) => AstNode::ListComp(self.clone()),
+            Atom::SetComp(..) => AstNode::SetComp(self.clone()),
+            Atom::DictComp(..) => AstNode::DictComp(self.clone()),
             Atom::Comment(_) => AstNode::Comment(self.clone()),
             Atom::Name(_) => AstNode::Name(self.clone()),
         }
@@ -53,6 +73,12 @@ impl AstObject for Atom {
             Atom::Bool(_) => visitor.visit_bool(self, span.or(self.span())),
             Atom::Float(_) => visitor.visit_float(self, span.or(self.span())),
             Atom::Tuple(_) => visitor.visit_tuple(self, span.or(self.span())),
+            Atom::List(_) => visitor.visit_list(self, span.or(self.span())),
+            Atom::Set(_) => visitor.visit_set(self, span.or(self.span())),
+            Atom::Dict(_) => visitor.visit_dict(self, span.or(self.span())),
+            Atom::ListComp(..) | Atom::SetComp(..) | Atom::DictComp(..) => {
+                visitor.visit_comprehension(self, span.or(self.span()))
+            }
             Atom::Comment(_) => visitor.visit_any(self),
             Atom::Name(_) => visitor.visit_name(self, span.or(self.span())),
         }
@@ -91,6 +117,7 @@ impl From<PyToken> for Atom {
             PyToken::True => Self::Bool(true),
             PyToken::False => Self::Bool(false),
             PyToken::Digits(n) => Self::Int(n),
+            PyToken::Float(n) => Self::Float(n),
             PyToken::CommentRef(n) => Self::Comment(n),
             PyToken::StringRef(n) => Self::Str(n),
             PyToken::Ident(n) => Self::Name(n),
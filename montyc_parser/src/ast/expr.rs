@@ -2,7 +2,7 @@ use logos::Span;
 
 use crate::{spanned::Spanned, AstNode, AstObject, AstVisitor};
 
-use super::{Atom, Primary};
+use super::{Atom, Parameter, Primary};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum UnaryOp {
@@ -41,14 +41,22 @@ pub enum InfixOp {
     And,
     Or,
     Xor,
+    Lt,
+    LtE,
+    Gt,
+    GtE,
+    In,
+    NotIn,
+    Is,
+    IsNot,
 }
 
 impl InfixOp {
-    pub fn sigil(&self) -> &str {
+    pub fn sigil(&self) -> &'static str {
         match self {
             InfixOp::Add => "+",
             InfixOp::Sub => "-",
-            InfixOp::Power => "^",
+            InfixOp::Power => "**",
             InfixOp::Invert => "~",
             InfixOp::FloorDiv => "//",
             InfixOp::MatMult => "@",
@@ -62,6 +70,14 @@ impl InfixOp {
             InfixOp::And => "and",
             InfixOp::Or => "or",
             InfixOp::Xor => "^",
+            InfixOp::Lt => "<",
+            InfixOp::LtE => "<=",
+            InfixOp::Gt => ">",
+            InfixOp::GtE => ">=",
+            InfixOp::In => "in",
+            InfixOp::NotIn => "not in",
+            InfixOp::Is => "is",
+            InfixOp::IsNot => "is not",
         }
     }
 }
@@ -76,7 +92,7 @@ impl AsRef<str> for InfixOp {
             InfixOp::FloorDiv => "floordiv",
             InfixOp::MatMult => "matmul",
             InfixOp::Mod => "mod",
-            InfixOp::Div => "div",
+            InfixOp::Div => "truediv",
             InfixOp::Mult => "mul",
             InfixOp::LeftShift => "lshift",
             InfixOp::RightShift => "rshift",
@@ -85,6 +101,13 @@ impl AsRef<str> for InfixOp {
             InfixOp::And => "and",
             InfixOp::Or => "or",
             InfixOp::Xor => "xor",
+            InfixOp::Lt => "lt",
+            InfixOp::LtE => "le",
+            InfixOp::Gt => "gt",
+            InfixOp::GtE => "ge",
+            InfixOp::In | InfixOp::NotIn => "contains",
+            InfixOp::Is => "is",
+            InfixOp::IsNot => "is not",
         }
     }
 }
@@ -103,6 +126,12 @@ pub enum Expr {
         right: Box<Spanned<Expr>>,
     },
 
+    /// A chain of two or more comparisons i.e. `a < b <= c`.
+    Compare {
+        left: Box<Spanned<Expr>>,
+        ops: Vec<(InfixOp, Spanned<Expr>)>,
+    },
+
     Unary {
         op: UnaryOp,
         value: Box<Spanned<Expr>>,
@@ -113,6 +142,12 @@ pub enum Expr {
         value: Box<Spanned<Expr>>,
     },
 
+    /// An anonymous function i.e. `lambda x, y=1: x + y`.
+    Lambda {
+        args: Vec<Parameter>,
+        body: Box<Spanned<Expr>>,
+    },
+
     Primary(Spanned<Primary>),
 }
 
@@ -121,8 +156,10 @@ impl AstObject for Expr {
         match self {
             Expr::If { .. } => AstNode::IfExpr(self.clone()),
             Expr::BinOp { .. } => AstNode::BinOp(self.clone()),
+            Expr::Compare { .. } => AstNode::Compare(self.clone()),
             Expr::Unary { .. } => AstNode::Unary(self.clone()),
             Expr::Named { .. } => AstNode::NamedExpr(self.clone()),
+            Expr::Lambda { .. } => AstNode::Lambda(self.clone()),
             Expr::Primary(primary) => primary.into_ast_node(),
         }
     }
@@ -136,12 +173,22 @@ impl AstObject for Expr {
                 ..
             } => left.span.start..right.span.end,
 
+            Expr::Compare { left, ops } => {
+                left.span.start
+                    ..ops
+                        .last()
+                        .map_or(left.span.end, |(_, right)| right.span.end)
+            }
+
             Expr::Named {
                 target: left,
                 value: right,
             } => left.span.start..right.span.start,
 
-            Expr::Unary { value: inner, .. } => inner.span.clone(),
+            Expr::Unary { value: inner, .. } | Expr::Lambda { body: inner, .. } => {
+                inner.span.clone()
+            }
+
             Expr::Primary(inner) => inner.span.clone(),
         })
     }
@@ -157,8 +204,10 @@ impl AstObject for Expr {
         match self {
             Expr::If { .. } => visitor.visit_ternary(self, span.or(self.span())),
             Expr::BinOp { .. } => visitor.visit_binop(self, span.or(self.span())),
+            Expr::Compare { .. } => visitor.visit_compare(self, span.or(self.span())),
             Expr::Unary { .. } => visitor.visit_unary(self, span.or(self.span())),
             Expr::Named { .. } => visitor.visit_named_expr(self, span.or(self.span())),
+            Expr::Lambda { .. } => visitor.visit_lambda(self, span.or(self.span())),
             Expr::Primary(primary) => primary.visit_with(visitor, span.or(primary.span())),
         }
     }
@@ -1,12 +1,21 @@
-use montyc_core::SpanRef;
+use montyc_core::{ast::ParameterKind, SpanRef};
 
 use super::*;
 
+/// A parameter in a function signature like `name: annotation = default`.
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: SpanRef,
+    pub annotation: Option<Spanned<Expr>>,
+    pub default: Option<Spanned<Expr>>,
+    pub kind: ParameterKind,
+}
+
 #[derive(Debug, Clone)]
 pub struct FunctionDef {
     pub reciever: Option<Spanned<Atom>>,
     pub name: Spanned<Atom>,
-    pub args: Option<Vec<(SpanRef, Option<Spanned<Expr>>)>>,
+    pub args: Option<Vec<Parameter>>,
     pub body: Vec<Spanned<Statement>>,
     pub decorator_list: Vec<Spanned<Primary>>,
     pub returns: Option<Spanned<Expr>>,
@@ -38,7 +47,7 @@ impl FunctionDef {
     pub fn is_dynamically_typed(&self) -> bool {
         self.args
             .as_ref()
-            .map(|args| args.iter().any(|arg| arg.1.is_none()))
+            .map(|args| args.iter().any(|arg| arg.annotation.is_none()))
             .unwrap_or(false)
     }
 
@@ -16,7 +16,7 @@ pub struct ImportDecl {
 pub enum Import {
     Names(Vec<Spanned<Primary>>),
     From {
-        module: Spanned<Primary>,
+        module: Option<Spanned<Primary>>,
         names: Vec<Spanned<Primary>>,
         level: usize,
     },
@@ -35,7 +35,7 @@ impl AstObject for Import {
             }
 
             Import::From { module, names, .. } => {
-                let head = &module.span;
+                let head = &module.as_ref().or(names.first())?.span;
                 let tail = &names.last()?.span;
 
                 Some(head.start..tail.end)
@@ -26,11 +26,19 @@ pub enum AstNode {
     Bool(models::Atom),
     Float(models::Atom),
     Tuple(models::Atom),
+    List(models::Atom),
+    Set(models::Atom),
+    Dict(models::Atom),
+    ListComp(models::Atom),
+    SetComp(models::Atom),
+    DictComp(models::Atom),
     Name(models::Atom),
     BinOp(models::Expr),
+    Compare(models::Expr),
     IfExpr(models::Expr),
     Unary(models::Expr),
     NamedExpr(models::Expr),
+    Lambda(models::Expr),
     None(models::Atom),
     Ellipsis(models::Atom),
     Subscript(models::Primary),
@@ -38,8 +46,14 @@ pub enum AstNode {
     Attr(models::Primary),
     Ret(models::Return),
     While(models::While),
+    For(models::For),
+    Try(models::Try),
+    With(models::With),
+    Raise(models::Raise),
     Annotation(models::Annotation),
     Pass,
+    Break,
+    Continue,
 }
 
 impl AstNode {
@@ -50,7 +64,12 @@ impl AstNode {
             AstNode::FuncDef(fndef) => fndef,
             AstNode::If(ifch) => ifch,
             AstNode::Assign(asn) => asn,
-            AstNode::Pass => self,
+            AstNode::While(while_) => while_,
+            AstNode::For(for_) => for_,
+            AstNode::Try(try_) => try_,
+            AstNode::With(with) => with,
+            AstNode::Raise(raise) => raise,
+            AstNode::Pass | AstNode::Break | AstNode::Continue => self,
             _ => todo!(),
         }
     }
@@ -84,6 +103,13 @@ impl AstObject for AstNode {
                 AstNode::Assign(asn) => asn.visit_with(visitor, span),
                 AstNode::Ret(ret) => ret.visit_with(visitor, span),
                 AstNode::Str(st) => st.visit_with(visitor, span),
+                AstNode::While(while_) => while_.visit_with(visitor, span),
+                AstNode::For(for_) => for_.visit_with(visitor, span),
+                AstNode::Try(try_) => try_.visit_with(visitor, span),
+                AstNode::With(with) => with.visit_with(visitor, span),
+                AstNode::Raise(raise) => raise.visit_with(visitor, span),
+                AstNode::Break => visitor.visit_break(span),
+                AstNode::Continue => visitor.visit_continue(span),
                 _ => todo!("{:?}", self),
             }
         }
@@ -99,6 +125,13 @@ impl From<AstNode> for Box<dyn AstObject> {
             AstNode::If(ifstmt) => Box::new(ifstmt),
             AstNode::Pass => Box::new(Statement::Pass),
             AstNode::Assign(asn) => Box::new(asn),
+            AstNode::While(while_) => Box::new(while_),
+            AstNode::For(for_) => Box::new(for_),
+            AstNode::Try(try_) => Box::new(try_),
+            AstNode::With(with) => Box::new(with),
+            AstNode::Raise(raise) => Box::new(raise),
+            AstNode::Break => Box::new(Statement::Break),
+            AstNode::Continue => Box::new(Statement::Continue),
             _ => todo!(),
         }
     }
@@ -181,6 +214,23 @@ pub trait AstVisitor<T = ()> {
         self.visit_any(node)
     }
 
+    fn visit_list(&mut self, node: &Atom, _span: Option<Span>) -> T {
+        self.visit_any(node)
+    }
+
+    fn visit_set(&mut self, node: &Atom, _span: Option<Span>) -> T {
+        self.visit_any(node)
+    }
+
+    fn visit_dict(&mut self, node: &Atom, _span: Option<Span>) -> T {
+        self.visit_any(node)
+    }
+
+    /// Visit a list, set or dict comprehension.
+    fn visit_comprehension(&mut self, node: &Atom, _span: Option<Span>) -> T {
+        self.visit_any(node)
+    }
+
     fn visit_ellipsis(&mut self, node: &Atom, _span: Option<Span>) -> T {
         self.visit_any(node)
     }
@@ -217,10 +267,38 @@ pub trait AstVisitor<T = ()> {
         self.visit_any(while_)
     }
 
+    fn visit_for(&mut self, for_: &For, _span: Option<Span>) -> T {
+        self.visit_any(for_)
+    }
+
+    fn visit_try(&mut self, try_: &Try, _span: Option<Span>) -> T {
+        self.visit_any(try_)
+    }
+
+    fn visit_raise(&mut self, raise: &Raise, _span: Option<Span>) -> T {
+        self.visit_any(raise)
+    }
+
+    fn visit_with_stmt(&mut self, with: &With, _span: Option<Span>) -> T {
+        self.visit_any(with)
+    }
+
+    fn visit_break(&mut self, _span: Option<Span>) -> T {
+        self.visit_any(&Statement::Break)
+    }
+
+    fn visit_continue(&mut self, _span: Option<Span>) -> T {
+        self.visit_any(&Statement::Continue)
+    }
+
     fn visit_binop(&mut self, expr: &Expr, _span: Option<Span>) -> T {
         self.visit_any(expr)
     }
 
+    fn visit_compare(&mut self, expr: &Expr, _span: Option<Span>) -> T {
+        self.visit_any(expr)
+    }
+
     fn visit_unary(&mut self, unary: &Expr, _span: Option<Span>) -> T {
         self.visit_any(unary)
     }
@@ -233,6 +311,10 @@ pub trait AstVisitor<T = ()> {
         self.visit_any(expr)
     }
 
+    fn visit_lambda(&mut self, lambda: &Expr, _span: Option<Span>) -> T {
+        self.visit_any(lambda)
+    }
+
     fn visit_call(&mut self, call: &Primary, _span: Option<Span>) -> T {
         self.visit_any(call)
     }
@@ -39,6 +39,160 @@ impl AstObject for While {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct For {
+    pub target: Spanned<Primary>,
+    pub iter: Spanned<Expr>,
+    pub body: Vec<Spanned<Statement>>,
+    pub orelse: Option<Vec<Spanned<Statement>>>,
+}
+
+impl AstObject for For {
+    fn into_ast_node(&self) -> AstNode {
+        AstNode::For(self.clone())
+    }
+
+    fn span(&self) -> Option<Span> {
+        let end = self
+            .orelse
+            .as_ref()
+            .and_then(|orelse| orelse.last())
+            .or_else(|| self.body.last())
+            .map(|node| node.span.end)
+            .unwrap_or(self.iter.span.end);
+
+        Some(self.target.span.start..end)
+    }
+
+    fn unspanned<'a>(&'a self) -> &'a dyn AstObject {
+        self
+    }
+
+    fn visit_with<U>(&self, visitor: &mut dyn AstVisitor<U>, span: Option<Span>) -> U
+    where
+        Self: Sized,
+    {
+        visitor.visit_for(self, span)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExceptHandler {
+    pub kind: Option<Spanned<Expr>>,
+    pub name: Option<Spanned<Atom>>,
+    pub body: Vec<Spanned<Statement>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Try {
+    pub body: Vec<Spanned<Statement>>,
+    pub handlers: Vec<Spanned<ExceptHandler>>,
+    pub orelse: Option<Vec<Spanned<Statement>>>,
+    pub finalbody: Option<Vec<Spanned<Statement>>>,
+}
+
+impl AstObject for Try {
+    fn into_ast_node(&self) -> AstNode {
+        AstNode::Try(self.clone())
+    }
+
+    fn span(&self) -> Option<Span> {
+        let start = self.body.first().map(|node| node.span.start)?;
+        let end = self
+            .finalbody
+            .as_ref()
+            .and_then(|body| body.last())
+            .or_else(|| self.orelse.as_ref().and_then(|body| body.last()))
+            .or_else(|| self.handlers.last().and_then(|h| h.inner.body.last()))
+            .or_else(|| self.body.last())
+            .map(|node| node.span.end)
+            .unwrap_or(start);
+
+        Some(start..end)
+    }
+
+    fn unspanned<'a>(&'a self) -> &'a dyn AstObject {
+        self
+    }
+
+    fn visit_with<U>(&self, visitor: &mut dyn AstVisitor<U>, span: Option<Span>) -> U
+    where
+        Self: Sized,
+    {
+        visitor.visit_try(self, span)
+    }
+}
+
+/// A single `context_expr as target` item of a `with` statement.
+#[derive(Debug, Clone)]
+pub struct WithItem {
+    pub context_expr: Spanned<Expr>,
+    pub target: Option<Spanned<Primary>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct With {
+    pub items: Vec<Spanned<WithItem>>,
+    pub body: Vec<Spanned<Statement>>,
+    /// `async with`, only parsed since coroutines can not be evaluated.
+    pub is_async: bool,
+}
+
+impl AstObject for With {
+    fn into_ast_node(&self) -> AstNode {
+        AstNode::With(self.clone())
+    }
+
+    fn span(&self) -> Option<Span> {
+        let start = self.items.first().map(|item| item.span.start)?;
+        let end = self
+            .body
+            .last()
+            .map(|node| node.span.end)
+            .or_else(|| self.items.last().map(|item| item.span.end))
+            .unwrap_or(start);
+
+        Some(start..end)
+    }
+
+    fn unspanned<'a>(&'a self) -> &'a dyn AstObject {
+        self
+    }
+
+    fn visit_with<U>(&self, visitor: &mut dyn AstVisitor<U>, span: Option<Span>) -> U
+    where
+        Self: Sized,
+    {
+        visitor.visit_with_stmt(self, span)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Raise {
+    pub exc: Option<Spanned<Expr>>,
+}
+
+impl AstObject for Raise {
+    fn into_ast_node(&self) -> AstNode {
+        AstNode::Raise(self.clone())
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.exc.as_ref().map(|exc| exc.span.clone())
+    }
+
+    fn unspanned<'a>(&'a self) -> &'a dyn AstObject {
+        self
+    }
+
+    fn visit_with<U>(&self, visitor: &mut dyn AstVisitor<U>, span: Option<Span>) -> U
+    where
+        Self: Sized,
+    {
+        visitor.visit_raise(self, span)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Return {
     pub value: Result<Spanned<Expr>, Spanned<()>>,
@@ -71,6 +225,7 @@ impl AstObject for Return {
 #[derive(Debug, Clone)]
 pub struct ClassDef {
     pub name: Spanned<Atom>,
+    pub bases: Vec<Spanned<Expr>>,
     pub decorator_list: Vec<Spanned<Primary>>,
     pub body: Vec<Spanned<Statement>>,
 }
@@ -5,6 +5,34 @@ use crate::{spanned::Spanned, AstNode, AstObject, AstVisitor};
 
 use super::{Atom, Expr};
 
+/// An argument at a call site.
+#[derive(Debug, Clone)]
+pub enum Argument {
+    /// `<expr>`
+    Positional(Spanned<Expr>),
+
+    /// `*<expr>`
+    Starred(Spanned<Expr>),
+
+    /// `<name>=<expr>`
+    Keyword(SpanRef, Spanned<Expr>),
+
+    /// `**<expr>`
+    DoubleStarred(Spanned<Expr>),
+}
+
+impl Argument {
+    /// The expression that is passed, or unpacked, as the argument.
+    pub fn value(&self) -> &Spanned<Expr> {
+        match self {
+            Self::Positional(value)
+            | Self::Starred(value)
+            | Self::Keyword(_, value)
+            | Self::DoubleStarred(value) => value,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Primary {
     Atomic(Spanned<Atom>),
@@ -18,7 +46,7 @@ pub enum Primary {
     /// `<func:primary>(<args?>)`
     Call {
         func: Box<Spanned<Primary>>,
-        args: Option<Vec<Spanned<Expr>>>,
+        args: Option<Vec<Spanned<Argument>>>,
     },
 
     /// `<primary> DOT(.) <atom>`
@@ -48,7 +76,7 @@ impl AstObject for Primary {
             Primary::Subscript { value, index } => Some(value.span()?.start..index.span()?.end),
             Primary::Call { func, args } => args
                 .as_ref()
-                .and_then(|args| Some(func.span()?.start..args.last()?.span()?.end)),
+                .and_then(|args| Some(func.span()?.start..args.last()?.span.end)),
 
             Primary::Attribute { left, attr } => Some(left.span()?.start..attr.span()?.end),
             Primary::Await(_) => todo!(),
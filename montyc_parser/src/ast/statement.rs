@@ -11,13 +11,21 @@ pub enum Statement {
     Class(ClassDef),
     If(IfChain),
     While(While),
+    For(For),
+    Try(Try),
+    With(With),
+    Raise(Raise),
     Pass,
+    Break,
+    Continue,
 }
 
 impl AstObject for Statement {
     fn into_ast_node(&self) -> AstNode {
         match self {
             Self::Pass => AstNode::Pass,
+            Self::Break => AstNode::Break,
+            Self::Continue => AstNode::Continue,
             Self::Expr(node) => node.into_ast_node(),
             Self::FnDef(node) => node.into_ast_node(),
             Self::Ret(node) => node.into_ast_node(),
@@ -27,6 +35,10 @@ impl AstObject for Statement {
             Self::Class(node) => node.into_ast_node(),
             Self::If(node) => node.into_ast_node(),
             Self::While(node) => node.into_ast_node(),
+            Self::For(node) => node.into_ast_node(),
+            Self::Try(node) => node.into_ast_node(),
+            Self::With(node) => node.into_ast_node(),
+            Self::Raise(node) => node.into_ast_node(),
         }
     }
 
@@ -45,7 +57,11 @@ impl AstObject for Statement {
             Statement::Class(ref c) => c,
             Statement::If(ref i) => i,
             Statement::While(ref w) => w,
-            Statement::Pass => self,
+            Statement::For(ref f) => f,
+            Statement::Try(ref t) => t,
+            Statement::With(ref w) => w,
+            Statement::Raise(ref r) => r,
+            Statement::Pass | Statement::Break | Statement::Continue => self,
         }
     }
 
@@ -63,7 +79,13 @@ impl AstObject for Statement {
             Statement::Class(inner) => inner.visit_with(visitor, span.or(self.span())),
             Statement::If(inner) => inner.visit_with(visitor, span.or(self.span())),
             Statement::While(inner) => inner.visit_with(visitor, span.or(self.span())),
+            Statement::For(inner) => inner.visit_with(visitor, span.or(self.span())),
+            Statement::Try(inner) => inner.visit_with(visitor, span.or(self.span())),
+            Statement::With(inner) => inner.visit_with(visitor, span.or(self.span())),
+            Statement::Raise(inner) => inner.visit_with(visitor, span.or(self.span())),
             Statement::Pass => visitor.visit_pass(),
+            Statement::Break => visitor.visit_break(span),
+            Statement::Continue => visitor.visit_continue(span),
         }
     }
 }
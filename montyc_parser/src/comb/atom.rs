@@ -1,7 +1,10 @@
-use nom::sequence::tuple;
-use nom::{branch::alt, IResult};
+use nom::{
+    branch::alt,
+    error::{Error, ErrorKind},
+    IResult,
+};
 
-use crate::ast::models::{Atom, Expr};
+use crate::ast::models::{Atom, Comprehension, Expr};
 use crate::comb::{expect, expect_any_of, expect_many_n, expect_with, whitespace};
 use crate::spanned::Spanned;
 use crate::token::PyToken;
@@ -18,7 +21,13 @@ fn expect_digits<'this, 'source, 'data>(
 pub fn expect_ident<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<PyToken>> {
-    expect_with(stream, |(tok, _)| matches!(tok, PyToken::Ident(_)))
+    let result = expect_with(stream, |(tok, _)| matches!(tok, PyToken::Ident(_)));
+
+    if result.is_err() {
+        stream.expected("an identifier");
+    }
+
+    result
 }
 
 #[inline]
@@ -61,22 +70,18 @@ fn integer<'this, 'source, 'data>(
 fn float<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Atom>> {
-    let (stream, (left, _, right)) =
-        tuple((expect_digits, expect(PyToken::Dot), expect_digits))(stream)?;
-
-    if let (PyToken::Digits(int), PyToken::Digits(real)) = (left.inner, right.inner) {
-        // TODO(mental): figure out a way to parse floats more sensibly.
-        let value = format!("{}.{}", int, real)
-            .parse()
-            .expect("Unable to parse float literal.");
-
-        let float = Atom::Float(value);
-        let span = left.span.start..right.span.end;
+    let (stream, float) = expect_with(stream, |(tok, _)| matches!(tok, PyToken::Float(_)))?;
 
-        Ok((stream, Spanned { span, inner: float }))
+    let float = if let PyToken::Float(inner) = float.inner {
+        Spanned {
+            span: float.span,
+            inner: Atom::Float(inner),
+        }
     } else {
-        unreachable!()
-    }
+        unreachable!();
+    };
+
+    Ok((stream, float))
 }
 
 #[inline]
@@ -152,6 +157,185 @@ fn tuple_literal<'this, 'source, 'data>(
     Ok((stream, tple))
 }
 
+/// Skip whitespace and newlines, both are insignificant inside brackets.
+#[inline]
+fn bracketed_whitespace<'this, 'source, 'data>(
+    mut stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, ()> {
+    while let Ok((s, _)) = alt((expect(PyToken::Whitespace), expect(PyToken::Newline)))(stream) {
+        stream = s;
+    }
+
+    Ok((stream, ()))
+}
+
+/// The remaining `, item` elements of a display whose `first` item has been parsed, allowing a trailing comma.
+#[inline]
+fn display_items<'this, 'source, 'data, T, F>(
+    mut stream: TokenStreamRef<'this, 'source, 'data>,
+    first: T,
+    item: F,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Vec<T>>
+where
+    F: Fn(
+        TokenStreamRef<'this, 'source, 'data>,
+    ) -> IResult<TokenStreamRef<'this, 'source, 'data>, T>,
+{
+    let mut items = vec![first];
+
+    loop {
+        let (s, _) = bracketed_whitespace(stream)?;
+
+        let s = match expect(PyToken::Comma)(s) {
+            Ok((s, _)) => s,
+            Err(_) => break,
+        };
+
+        let (s, _) = bracketed_whitespace(s)?;
+
+        stream = s;
+
+        match item(s) {
+            Ok((s, value)) => {
+                items.push(value);
+                stream = s;
+            }
+            Err(_) => break,
+        }
+    }
+
+    Ok((stream, items))
+}
+
+#[inline]
+fn dict_item<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, (Spanned<Expr>, Spanned<Expr>)> {
+    let (stream, key) = super::expr::expression(stream)?;
+    let (stream, _) = bracketed_whitespace(stream)?;
+    let (stream, _) = expect(PyToken::Colon)(stream)?;
+    let (stream, _) = bracketed_whitespace(stream)?;
+    let (stream, value) = super::expr::expression(stream)?;
+
+    Ok((stream, (key, value)))
+}
+
+/// One or more `for target in iter (if cond)*` clauses.
+#[inline]
+fn comprehension_clauses<'this, 'source, 'data>(
+    mut stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Vec<Comprehension>> {
+    let mut clauses = vec![];
+
+    while let Ok((s, _)) = expect(PyToken::For)(stream) {
+        let (s, _) = expect_many_n::<1>(PyToken::Whitespace)(s)?;
+        let (s, target) = super::for_::target_list(s)?;
+        let (s, _) = bracketed_whitespace(s)?;
+        let (s, _) = expect(PyToken::In)(s)?;
+        let (s, _) = bracketed_whitespace(s)?;
+        let (s, iter) = super::expr::disjunction(s)?;
+        let (mut s, _) = bracketed_whitespace(s)?;
+
+        let mut ifs = vec![];
+
+        while let Ok((s_, _)) = expect(PyToken::If)(s) {
+            let (s_, _) = bracketed_whitespace(s_)?;
+            let (s_, test) = super::expr::disjunction(s_)?;
+            let (s_, _) = bracketed_whitespace(s_)?;
+
+            ifs.push(test);
+            s = s_;
+        }
+
+        clauses.push(Comprehension { target, iter, ifs });
+        stream = s;
+    }
+
+    if clauses.is_empty() {
+        return Err(nom::Err::Error(Error::new(stream, ErrorKind::Verify)));
+    }
+
+    Ok((stream, clauses))
+}
+
+#[inline]
+fn list_literal<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Atom>> {
+    let (stream, lbracket) = expect(PyToken::LBracket)(stream)?;
+    let (stream, _) = bracketed_whitespace(stream)?;
+
+    let (stream, inner) = match super::expr::expression(stream) {
+        Ok((stream, first)) => {
+            let (stream, _) = bracketed_whitespace(stream)?;
+
+            match comprehension_clauses(stream) {
+                Ok((stream, clauses)) => (stream, Atom::ListComp(Box::new(first), clauses)),
+                Err(_) => {
+                    let (stream, values) = display_items(stream, first, super::expr::expression)?;
+
+                    (stream, Atom::List(values))
+                }
+            }
+        }
+
+        Err(_) => (stream, Atom::List(vec![])),
+    };
+
+    let (stream, _) = bracketed_whitespace(stream)?;
+    let (stream, rbracket) = expect(PyToken::RBracket)(stream)?;
+
+    let list = Spanned {
+        inner,
+        span: lbracket.span.start..rbracket.span.end,
+    };
+
+    Ok((stream, list))
+}
+
+/// A dict or set display, or comprehension, `{}` is an empty dict.
+#[inline]
+fn brace_literal<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Atom>> {
+    let (stream, lbrace) = expect(PyToken::LBrace)(stream)?;
+    let (stream, _) = bracketed_whitespace(stream)?;
+
+    let (stream, inner) = if let Ok((stream, first)) = dict_item(stream) {
+        let (stream, _) = bracketed_whitespace(stream)?;
+
+        match comprehension_clauses(stream) {
+            Ok((stream, clauses)) => (stream, Atom::DictComp(Box::new(first), clauses)),
+            Err(_) => {
+                let (stream, items) = display_items(stream, first, dict_item)?;
+                (stream, Atom::Dict(items))
+            }
+        }
+    } else if let Ok((stream, first)) = super::expr::expression(stream) {
+        let (stream, _) = bracketed_whitespace(stream)?;
+
+        match comprehension_clauses(stream) {
+            Ok((stream, clauses)) => (stream, Atom::SetComp(Box::new(first), clauses)),
+            Err(_) => {
+                let (stream, values) = display_items(stream, first, super::expr::expression)?;
+                (stream, Atom::Set(values))
+            }
+        }
+    } else {
+        (stream, Atom::Dict(vec![]))
+    };
+
+    let (stream, _) = bracketed_whitespace(stream)?;
+    let (stream, rbrace) = expect(PyToken::RBrace)(stream)?;
+
+    let display = Spanned {
+        inner,
+        span: lbrace.span.start..rbrace.span.end,
+    };
+
+    Ok((stream, display))
+}
+
 #[inline]
 pub fn atom_unspanned<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
@@ -178,7 +362,16 @@ pub fn atom<'this, 'source, 'data>(
         Ok((stream, atom))
     };
 
-    let (stream, atom) = alt((name, float, string_ref, integer, tuple_literal, fallback))(stream)?;
+    let (stream, atom) = alt((
+        name,
+        float,
+        string_ref,
+        integer,
+        tuple_literal,
+        list_literal,
+        brace_literal,
+        fallback,
+    ))(stream)?;
 
     Ok((stream, atom))
 }
@@ -1,14 +1,14 @@
 use nom::IResult;
 
 use crate::{
-    ast::models::{Atom, ClassDef, Primary},
+    ast::models::{Atom, ClassDef, Expr, Primary},
     comb::whitespace,
     spanned::Spanned,
     token::PyToken,
     TokenStreamRef,
 };
 
-use super::{atom, chomp, expect, expect_many_n, stmt::statement};
+use super::{atom, chomp, expect, expression, for_::block};
 
 #[inline]
 pub fn class_def<'this, 'source, 'data>(
@@ -18,7 +18,7 @@ pub fn class_def<'this, 'source, 'data>(
 
     let (stream, decorators) = decorator_list(stream)?;
 
-    // head of ClassDef "class <name>:"
+    // head of ClassDef "class <name>(<bases>):"
 
     let (stream, tok) = expect(PyToken::ClassDef)(stream)?;
     let (stream, _) = whitespace(stream)?;
@@ -26,6 +26,11 @@ pub fn class_def<'this, 'source, 'data>(
 
     let name = name;
 
+    let (stream, bases) = match expect(PyToken::LParen)(stream) {
+        Ok((stream, _)) => bases(stream)?,
+        Err(_) => (stream, vec![]),
+    };
+
     let start = decorators
         .get(0)
         .map(|t| t.span.start.clone())
@@ -33,28 +38,11 @@ pub fn class_def<'this, 'source, 'data>(
 
     let (stream, _) = whitespace(stream)?;
     let (stream, _) = expect(PyToken::Colon)(stream)?;
-    let (mut stream, _) = whitespace(stream)?;
+    let (stream, _) = whitespace(stream)?;
 
     // body of ClassDef
 
-    let mut body = vec![];
-
-    if let Ok((s, _)) = expect(PyToken::Newline)(stream) {
-        stream = s;
-        loop {
-            if let Ok((s, _)) = expect_many_n::<4>(PyToken::Whitespace)(stream) {
-                let (s, stmt) = statement(s)?;
-                body.push(stmt);
-                let (s, _) = expect_many_n::<0>(PyToken::Newline)(s)?;
-                stream = s;
-            } else {
-                break;
-            }
-        }
-    } else {
-        let (_s, stmt) = statement(stream)?;
-        body.push(stmt);
-    }
+    let (stream, body) = block(stream)?;
 
     let end = body
         .last()
@@ -64,6 +52,7 @@ pub fn class_def<'this, 'source, 'data>(
     let def = Spanned {
         inner: ClassDef {
             name,
+            bases,
             decorator_list: decorators,
             body,
         },
@@ -73,6 +62,35 @@ pub fn class_def<'this, 'source, 'data>(
     Ok((stream, def))
 }
 
+#[inline]
+fn bases<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Vec<Spanned<Expr>>> {
+    let mut bases = vec![];
+    let mut stream = stream;
+
+    loop {
+        let (s, _) = whitespace(stream)?;
+
+        if let Ok((s, _)) = expect(PyToken::RParen)(s) {
+            break Ok((s, bases));
+        }
+
+        let (s, base) = expression(s)?;
+        let (s, _) = whitespace(s)?;
+
+        bases.push(base);
+
+        stream = match expect(PyToken::Comma)(s) {
+            Ok((s, _)) => s,
+            Err(_) => {
+                let (s, _) = expect(PyToken::RParen)(s)?;
+                break Ok((s, bases));
+            }
+        };
+    }
+}
+
 pub(super) fn decorator<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Primary>> {
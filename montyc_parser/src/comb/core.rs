@@ -54,7 +54,16 @@ pub fn expect(
     TokenStreamRef<'this, 'source, 'data>,
     Spanned<PyToken>,
 > {
-    move |stream: TokenStreamRef<'_, '_, '_>| expect_with(stream, |(t, _)| *t == value)
+    move |stream: TokenStreamRef<'_, '_, '_>| {
+        let result = expect_with(stream, |(t, _)| *t == value);
+
+        // layout tokens are optional almost everywhere, mentioning them would only be noise.
+        if result.is_err() && !matches!(value, PyToken::Whitespace | PyToken::Newline) {
+            stream.expected(value);
+        }
+
+        result
+    }
 }
 
 #[inline]
@@ -1,21 +1,45 @@
-use nom::{branch::alt, IResult};
+use nom::{branch::alt, sequence::tuple, IResult};
 
 use crate::ast::models::{Expr, InfixOp, UnaryOp};
 use crate::spanned::Spanned;
 use crate::token::PyToken;
 use crate::TokenStreamRef;
 
-use super::core::{expect, expect_any_of, expect_any_token, expect_many_n, expect_wrapped_values};
+use super::core::{expect, expect_any_token, expect_many_n, expect_with, expect_wrapped_values};
 
+use super::funcdef::lambda_def;
 use super::primary::await_primary;
 use super::whitespace;
 
+/// A parenthesized expression i.e. `(a + b)`, tuples are left to the primary parser.
+#[inline]
+fn group<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Expr>> {
+    let (stream, lparen) = expect(PyToken::LParen)(stream)?;
+    let (stream, _) = whitespace(stream)?;
+    let (stream, Spanned { inner, .. }) = expression(stream)?;
+    let (stream, _) = whitespace(stream)?;
+    let (stream, rparen) = expect(PyToken::RParen)(stream)?;
+
+    let group = Spanned {
+        inner,
+        span: lparen.span.start..rparen.span.end,
+    };
+
+    Ok((stream, group))
+}
+
 #[inline]
 fn power<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Expr>> {
-    let (stream, left) = await_primary(stream)?;
-    let left = left.replace_with(Expr::Primary);
+    let primary = |stream| {
+        let (stream, primary) = await_primary(stream)?;
+        Ok((stream, primary.replace_with(Expr::Primary)))
+    };
+
+    let (stream, left) = alt((group, primary))(stream)?;
 
     if let Ok((stream, _)) = expect_wrapped_values([PyToken::Star; 2], PyToken::Whitespace)(stream)
     {
@@ -86,7 +110,12 @@ fn term<'this, 'source, 'data>(
         let (stream, _) = whitespace(stream)?;
         let (stream, tok) = expect_any_token([Star, Div, Modulo, At])(stream)?;
 
-        if let Ok((stream, _)) = expect(Div)(stream) {
+        let floor_div = match tok.inner {
+            Div => expect_with(stream, |(t, _)| *t == Div).ok(),
+            _ => None,
+        };
+
+        if let Some((stream, _)) = floor_div {
             let (stream, _) = whitespace(stream)?;
 
             let (stream, right) = factor(stream)?;
@@ -130,9 +159,14 @@ fn term<'this, 'source, 'data>(
 
     let (stream, _) = whitespace(stream)?;
 
-    let (stream, base) = factor(stream)?;
+    let (mut stream, mut obj) = factor(stream)?;
 
-    term_(stream, &base).or(Ok((stream, base)))
+    while let Ok((s, o)) = term_(stream, &obj) {
+        stream = s;
+        obj = o;
+    }
+
+    Ok((stream, obj))
 }
 
 #[inline]
@@ -186,12 +220,21 @@ fn shift_expr<'this, 'source, 'data>(
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Expr>> {
     use PyToken::{GreaterThan, LessThan};
 
-    let (stream, _) = whitespace(stream)?;
-    let (stream, base) = sum(stream)?;
-
-    if let Ok((stream, tok)) = expect_any_token([LessThan, GreaterThan])(stream) {
+    #[inline]
+    fn shift_<'this, 'source, 'data>(
+        stream: TokenStreamRef<'this, 'source, 'data>,
+        left: &Spanned<Expr>,
+    ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Expr>> {
         let (stream, _) = whitespace(stream)?;
-        let (stream, _) = expect(LessThan)(stream)?;
+        let (stream, tok) = expect_any_token([LessThan, GreaterThan])(stream)?;
+        let (stream, _) = expect_with(stream, |(t, _)| *t == tok.inner)?;
+        let (stream, _) = whitespace(stream)?;
+        let (stream, value) = sum(stream)?;
+
+        let span = left.span.start..value.span.end;
+
+        let left = Box::new(left.clone());
+        let right = Box::new(value);
 
         let op = if matches!(tok.inner, LessThan) {
             InfixOp::LeftShift
@@ -199,21 +242,22 @@ fn shift_expr<'this, 'source, 'data>(
             InfixOp::RightShift
         };
 
-        let (stream, value) = expression(stream)?;
-
-        let span = base.span.start..value.span.end;
-
-        let left = Box::new(base);
-        let right = Box::new(value);
-
         let inner = Expr::BinOp { left, right, op };
 
         let obj = Spanned { span, inner };
 
         Ok((stream, obj))
-    } else {
-        Ok((stream, base))
     }
+
+    let (stream, _) = whitespace(stream)?;
+    let (mut stream, mut obj) = sum(stream)?;
+
+    while let Ok((s, o)) = shift_(stream, &obj) {
+        stream = s;
+        obj = o;
+    }
+
+    Ok((stream, obj))
 }
 
 #[inline]
@@ -312,44 +356,141 @@ fn bitwise_or<'this, 'source, 'data>(
     }
 }
 #[inline]
-fn equality<'this, 'source, 'data>(
+fn compare_op<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
-) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Expr>> {
-    use PyToken::{Bang, Equal, Whitespace};
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<InfixOp>> {
+    use PyToken::{Bang, Equal, GreaterThan, In, Is, LessThan, Not, Whitespace};
+
+    let first = expect_with(stream, |(t, _)| {
+        matches!(t, Equal | Bang | LessThan | GreaterThan | In | Not | Is)
+    });
+
+    let (stream, token) = match first {
+        Ok(first) => first,
+        Err(err) => {
+            // most operators span several tokens, so note how they are spelled instead.
+            for op in [
+                "==", "!=", "<", "<=", ">", ">=", "in", "not in", "is", "is not",
+            ] {
+                stream.expected(format!("`{}`", op));
+            }
+
+            return Err(err);
+        }
+    };
 
-    let (stream, _) = expect_many_n::<0>(Whitespace)(stream)?;
-    let (stream, left) = bitwise_or(stream)?;
+    let (stream, op, end) = match token.inner {
+        Equal | Bang => {
+            let (stream, eq) = expect(Equal)(stream)?;
+            let op = if token.inner == Equal {
+                InfixOp::Eq
+            } else {
+                InfixOp::NotEq
+            };
 
-    let (stream, _) = expect_many_n::<0>(Whitespace)(stream)?;
-    let (stream, token) = expect_any_of([Equal, Bang])(stream)?;
-    let (stream, _) = expect(Equal)(stream)?;
+            (stream, op, eq.span.end)
+        }
 
-    let op = match token.inner {
-        Bang => InfixOp::NotEq,
-        Equal => InfixOp::Eq,
-        _ => unreachable!(),
-    };
+        LessThan | GreaterThan => match expect_with(stream, |(t, _)| *t == Equal) {
+            Ok((stream, eq)) => {
+                let op = if token.inner == LessThan {
+                    InfixOp::LtE
+                } else {
+                    InfixOp::GtE
+                };
+
+                (stream, op, eq.span.end)
+            }
+
+            Err(_) => {
+                let op = if token.inner == LessThan {
+                    InfixOp::Lt
+                } else {
+                    InfixOp::Gt
+                };
+
+                (stream, op, token.span.end)
+            }
+        },
 
-    let (stream, _) = expect_many_n::<0>(Whitespace)(stream)?;
-    let (stream, right) = comparison(stream)?;
+        In => (stream, InfixOp::In, token.span.end),
 
-    let cmp = Spanned {
-        span: left.span.start..right.span.end,
-        inner: Expr::BinOp {
-            left: Box::new(left),
-            right: Box::new(right),
-            op,
+        Not => {
+            let (stream, _) = expect_many_n::<1>(Whitespace)(stream)?;
+            let (stream, in_) = expect(In)(stream)?;
+
+            (stream, InfixOp::NotIn, in_.span.end)
+        }
+
+        Is => match tuple((expect_many_n::<1>(Whitespace), expect(Not)))(stream) {
+            Ok((stream, (_, not))) => (stream, InfixOp::IsNot, not.span.end),
+            Err(_) => (stream, InfixOp::Is, token.span.end),
         },
+
+        _ => unreachable!(),
     };
 
-    Ok((stream, cmp))
+    let op = Spanned {
+        inner: op,
+        span: token.span.start..end,
+    };
+
+    Ok((stream, op))
 }
 
 #[inline]
 fn comparison<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Expr>> {
-    alt((equality, bitwise_or))(stream)
+    #[inline]
+    fn compare_<'this, 'source, 'data>(
+        stream: TokenStreamRef<'this, 'source, 'data>,
+    ) -> IResult<TokenStreamRef<'this, 'source, 'data>, (InfixOp, Spanned<Expr>)> {
+        let (stream, _) = whitespace(stream)?;
+        let (stream, op) = compare_op(stream)?;
+        let (stream, _) = whitespace(stream)?;
+        let (stream, right) = bitwise_or(stream)?;
+
+        Ok((stream, (op.inner, right)))
+    }
+
+    let (stream, _) = whitespace(stream)?;
+    let (mut stream, left) = bitwise_or(stream)?;
+
+    let mut ops = vec![];
+
+    while let Ok((s, op)) = compare_(stream) {
+        stream = s;
+        ops.push(op);
+    }
+
+    let obj = match ops.len() {
+        0 => left,
+
+        // a lone comparison is just another binary operator.
+        1 => {
+            let (op, right) = ops.pop().unwrap();
+
+            Spanned {
+                span: left.span.start..right.span.end,
+                inner: Expr::BinOp {
+                    left: Box::new(left),
+                    op,
+                    right: Box::new(right),
+                },
+            }
+        }
+
+        _ => Spanned {
+            span: left.span.start..ops.last().unwrap().1.span.end,
+            inner: Expr::Compare {
+                left: Box::new(left),
+                ops,
+            },
+        },
+    };
+
+    Ok((stream, obj))
 }
 
 #[inline]
@@ -405,7 +546,7 @@ fn conjunction<'this, 'source, 'data>(
 }
 
 #[inline]
-fn disjunction<'this, 'source, 'data>(
+pub(super) fn disjunction<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Expr>> {
     let or_conjunction = |stream| {
@@ -468,7 +609,7 @@ pub fn expression<'this, 'source, 'data>(
         Ok((stream, obj))
     };
 
-    let (stream, expr) = alt((ternary_disjunction, disjunction))(stream)?;
+    let (stream, expr) = alt((lambda_def, ternary_disjunction, disjunction))(stream)?;
 
     let (stream, _) = whitespace(stream)?;
 
@@ -0,0 +1,199 @@
+use nom::{
+    sequence::{preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::{
+    ast::models::{Atom, Expr, For, Primary, Statement},
+    spanned::Spanned,
+    token::PyToken,
+    TokenStreamRef,
+};
+
+use super::{
+    blank_lines, end_of_statement, expect, expect_many_n, expect_many_n_var, expect_with,
+    expression, primary, stmt::statement_unstripped,
+};
+
+/// The body of a compound statement, either a statement on the same line or an indented block.
+///
+/// Statements of an indented block that fail to parse are recorded as syntax errors and skipped.
+#[inline]
+pub(super) fn block<'this, 'source, 'data>(
+    mut stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Vec<Spanned<Statement>>> {
+    let mut body = vec![];
+
+    // a comment right after the `:` belongs to the header, the body is the indented block below it.
+    if let Ok((s, _)) = expect_with(stream, |(t, _)| matches!(t, PyToken::CommentRef(_))) {
+        stream = s;
+    } else if let Ok((s, stmt)) = statement_unstripped(stream) {
+        body.push(stmt);
+        return Ok((s, body));
+    }
+
+    let outer_indent = stream.current_indent();
+    let mut indent_level = None;
+
+    loop {
+        let (remaining, _) = blank_lines(stream)?;
+
+        if indent_level.is_none() {
+            let (first, indent) = expect_many_n::<0>(PyToken::Whitespace)(remaining)?;
+
+            if indent.len() <= outer_indent {
+                first.expected("an indented block");
+
+                return Err(nom::Err::Error(nom::error::Error::new(
+                    first,
+                    nom::error::ErrorKind::Verify,
+                )));
+            }
+
+            indent_level.replace(indent.len());
+        }
+
+        let indent = indent_level.unwrap();
+
+        match expect_many_n_var(indent, PyToken::Whitespace)(remaining) {
+            Ok((remaining, _)) if !remaining.at_end() => {
+                remaining.clear_failures();
+
+                match terminated(statement_unstripped, end_of_statement)(remaining) {
+                    Ok((remaining, part)) => {
+                        log::trace!("parse:block ++ {:?}", part);
+
+                        body.push(part);
+                        stream = remaining;
+                    }
+
+                    Err(_) => {
+                        remaining.syntax_error();
+                        stream = remaining.resync(indent);
+                    }
+                }
+            }
+
+            _ => break,
+        }
+    }
+
+    Ok((stream, body))
+}
+
+#[inline]
+fn orelse<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+    outer_indent: usize,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Vec<Spanned<Statement>>> {
+    let (stream, _) = expect_many_n::<1>(PyToken::Newline)(stream)?;
+    let (stream, indent) = expect_many_n::<0>(PyToken::Whitespace)(stream)?;
+
+    if indent.len() != outer_indent {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            stream,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+
+    let (stream, _) = tuple((
+        expect(PyToken::Else),
+        expect_many_n::<0>(PyToken::Whitespace),
+        expect(PyToken::Colon),
+        expect_many_n::<0>(PyToken::Whitespace),
+    ))(stream)?;
+
+    block(stream)
+}
+
+/// The target of a `for` loop or comprehension clause, `k, v` is a tuple without the parentheses.
+#[inline]
+pub(super) fn target_list<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Primary>> {
+    let (mut stream, first) = primary(stream)?;
+
+    let mut rest = vec![];
+    let mut is_tuple = false;
+
+    let mut comma = preceded(
+        expect_many_n::<0>(PyToken::Whitespace),
+        expect(PyToken::Comma),
+    );
+
+    while let Ok((s, _)) = comma(stream) {
+        is_tuple = true;
+        stream = s;
+
+        // a trailing comma, like `for x, in xs`, ends the tuple.
+        match preceded(expect_many_n::<0>(PyToken::Whitespace), primary)(s) {
+            Ok((s, value)) => {
+                rest.push(value);
+                stream = s;
+            }
+
+            Err(_) => break,
+        }
+    }
+
+    if !is_tuple {
+        return Ok((stream, first));
+    }
+
+    let values: Vec<_> = std::iter::once(first)
+        .chain(rest)
+        .map(|value| value.replace_with(Expr::Primary))
+        .collect();
+
+    let span = values[0].span.start..values[values.len() - 1].span.end;
+
+    let tple = Spanned {
+        inner: Atom::Tuple(values),
+        span,
+    }
+    .replace_with(Primary::Atomic);
+
+    Ok((stream, tple))
+}
+
+#[inline]
+pub fn for_stmt<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<For>> {
+    let outer_indent = stream.current_indent();
+
+    let (stream, tok) = expect(PyToken::For)(stream)?;
+
+    let (stream, (_, target, _, _, _, iter, _, _, _)) = tuple((
+        expect_many_n::<1>(PyToken::Whitespace),
+        target_list,
+        expect_many_n::<1>(PyToken::Whitespace),
+        expect(PyToken::In),
+        expect_many_n::<1>(PyToken::Whitespace),
+        expression,
+        expect_many_n::<0>(PyToken::Whitespace),
+        expect(PyToken::Colon),
+        expect_many_n::<0>(PyToken::Whitespace),
+    ))(stream)?;
+
+    let (stream, body) = block(stream)?;
+
+    let (stream, orelse) = match orelse(stream, outer_indent) {
+        Ok((stream, orelse)) => (stream, Some(orelse)),
+        Err(_) => (stream, None),
+    };
+
+    let for_ = For {
+        target,
+        iter,
+        body,
+        orelse,
+    };
+
+    let for_ = Spanned {
+        inner: for_,
+        span: tok.span,
+    };
+
+    Ok((stream, for_))
+}
@@ -1,16 +1,15 @@
-use nom::{branch::alt, error, multi::many0, sequence::terminated, IResult};
+use montyc_core::ast::ParameterKind;
+use nom::{error, sequence::terminated, IResult};
 
 use crate::{
-    ast::{Atom, Expr, FunctionDef},
-    comb::{expect_many_n_var, whitespace},
+    ast::{Atom, Expr, FunctionDef, Parameter},
+    comb::whitespace,
     spanned::Spanned,
     token::PyToken,
     TokenStreamRef,
 };
 
-use super::{
-    class::decorator_list, expect, expect_ident, expect_many_n, expr::expression, stmt::statement,
-};
+use super::{class::decorator_list, expect, expect_ident, expr::expression, for_::block};
 
 #[inline]
 fn argument<'this, 'source, 'data>(
@@ -51,27 +50,120 @@ fn argument_annotated<'this, 'source, 'data>(
 }
 
 #[inline]
-fn arguments<'this, 'source, 'data>(
+fn parameter<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<
     TokenStreamRef<'this, 'source, 'data>,
     (
-        Option<Spanned<PyToken>>,
-        Vec<(Spanned<PyToken>, Option<Spanned<Expr>>)>,
+        Spanned<PyToken>,
+        Option<Spanned<Expr>>,
+        Option<Spanned<Expr>>,
     ),
 > {
+    let (stream, (name, annotation)) = argument_annotated(stream)?;
+
+    let (stream, default) = match expect(PyToken::Equal)(stream) {
+        Ok((stream, _)) => {
+            let (stream, _) = whitespace(stream)?;
+            let (stream, default) = expression(stream)?;
+            let (stream, _) = whitespace(stream)?;
+
+            (stream, Some(default))
+        }
+
+        Err(_) => (stream, None),
+    };
+
+    Ok((stream, (name, annotation, default)))
+}
+
+#[inline]
+fn arguments<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, (Option<Spanned<PyToken>>, Vec<Parameter>)> {
     let (stream, _) = whitespace(stream)?;
 
-    let (stream, recv) = terminated(argument, expect(PyToken::Comma))(stream)
+    let (mut stream, mut recv) = terminated(argument, expect(PyToken::Comma))(stream)
         .map(|(s, r)| (s, Some(r)))
         .unwrap_or((stream, None));
 
-    let (stream, args) = many0(alt((
-        terminated(argument_annotated, expect(PyToken::Comma)),
-        argument_annotated,
-    )))(stream)?;
+    let mut params: Vec<Parameter> = vec![];
+    let mut kind = ParameterKind::PositionalOrKeyword;
+
+    let into_parameter = |name: Spanned<PyToken>, annotation, default, kind| match name.inner {
+        PyToken::Ident(name) => Parameter {
+            name,
+            annotation,
+            default,
+            kind,
+        },
+        _ => unreachable!(),
+    };
+
+    loop {
+        let (s, _) = whitespace(stream)?;
 
-    Ok((stream, (recv, args)))
+        let s = if let Ok((s, _)) = expect(PyToken::Div)(s) {
+            // everything before a `/` is positional-only, including a receiver.
+            if let Some(recv) = recv.take() {
+                params.insert(0, into_parameter(recv, None, None, kind));
+            }
+
+            for param in params.iter_mut() {
+                param.kind = ParameterKind::PositionalOnly;
+            }
+
+            s
+        } else if let Ok((s, _)) = expect(PyToken::Star)(s) {
+            if let Ok((s, _)) = expect(PyToken::Star)(s) {
+                let (s, (name, annotation)) = argument_annotated(s)?;
+                params.push(into_parameter(
+                    name,
+                    annotation,
+                    None,
+                    ParameterKind::VarKeyword,
+                ));
+
+                s
+            } else {
+                // everything after a `*` or `*args` is keyword-only.
+                kind = ParameterKind::KeywordOnly;
+
+                match argument_annotated(s) {
+                    Ok((s, (name, annotation))) => {
+                        params.push(into_parameter(
+                            name,
+                            annotation,
+                            None,
+                            ParameterKind::VarPositional,
+                        ));
+
+                        s
+                    }
+
+                    Err(_) => s,
+                }
+            }
+        } else if let Ok((s, (name, annotation, default))) = parameter(s) {
+            params.push(into_parameter(name, annotation, default, kind));
+            s
+        } else {
+            stream = s;
+            break;
+        };
+
+        let (s, _) = whitespace(s)?;
+
+        match expect(PyToken::Comma)(s) {
+            Ok((s, _)) => stream = s,
+            Err(_) => {
+                stream = s;
+                break;
+            }
+        }
+    }
+
+    Ok((stream, (recv, params)))
 }
 
 #[inline]
@@ -88,7 +180,7 @@ pub fn function_def<'this, 'source, 'data>(
     let (stream, ident) = expect_ident(stream)?;
     let (stream, _) = whitespace(stream)?;
     let (stream, _) = expect(PyToken::LParen)(stream)?;
-    let (stream, (reciever, mut arguments)) = arguments(stream)?;
+    let (stream, (reciever, arguments)) = arguments(stream)?;
     let (stream, _) = expect(PyToken::RParen)(stream)?;
     let (stream, _) = whitespace(stream)?;
 
@@ -108,56 +200,16 @@ pub fn function_def<'this, 'source, 'data>(
 
     let (stream, _) = whitespace(stream)?;
     let (stream, _) = expect(PyToken::Colon)(stream)?;
-    let (mut stream, _) = whitespace(stream)?;
+    let (stream, _) = whitespace(stream)?;
 
     // body of the function
 
-    let mut body = vec![];
-
-    if let Ok((s, stmt)) = statement(stream) {
-        body.push(stmt);
-        stream = s;
-    } else {
-        let mut indent_level = None;
-
-        loop {
-            let (remaining, _) = expect_many_n::<0>(PyToken::Newline)(stream)?;
-
-            let remaining = if indent_level.is_none() {
-                let (_, indent) = expect_many_n::<0>(PyToken::Whitespace)(remaining)?;
-
-                indent_level.replace(indent.len());
-
-                remaining
-            } else {
-                remaining
-            };
-
-            if let Ok((remaining, _)) =
-                expect_many_n_var(indent_level.unwrap(), PyToken::Whitespace)(remaining)
-            {
-                let (remaining, part) = statement(remaining)?;
+    let (stream, body) = block(stream)?;
 
-                body.push(part);
-                stream = remaining;
-            } else {
-                break;
-            }
-        }
-    }
-
-    let args: Option<Vec<(_, _)>> = if arguments.is_empty() {
+    let args = if arguments.is_empty() {
         None
     } else {
-        let args = arguments
-            .drain(..)
-            .map(|(l, r)| match (l.inner, r) {
-                (PyToken::Ident(l), r) => (l, r),
-                _ => unreachable!(),
-            })
-            .collect();
-
-        Some(args)
+        Some(arguments)
     };
 
     let span = ident.span;
@@ -184,9 +236,114 @@ pub fn function_def<'this, 'source, 'data>(
     };
 
     let funcdef = Spanned {
-        span: funcdef.name.span.start..funcdef.body.last().unwrap().span.end,
+        span: funcdef.name.span.start
+            ..funcdef
+                .body
+                .last()
+                .map_or(funcdef.name.span.end, |stmt| stmt.span.end),
         inner: funcdef,
     };
 
     Ok((stream, funcdef))
 }
+
+/// The parameters of a lambda, these can't be annotated since a `:` ends them.
+#[inline]
+fn lambda_parameters<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Vec<Parameter>> {
+    let mut stream = stream;
+    let mut params: Vec<Parameter> = vec![];
+    let mut kind = ParameterKind::PositionalOrKeyword;
+
+    let into_parameter = |name: Spanned<PyToken>, default, kind| match name.inner {
+        PyToken::Ident(name) => Parameter {
+            name,
+            annotation: None,
+            default,
+            kind,
+        },
+        _ => unreachable!(),
+    };
+
+    loop {
+        let (s, _) = whitespace(stream)?;
+
+        let s = if let Ok((s, _)) = expect(PyToken::Div)(s) {
+            for param in params.iter_mut() {
+                param.kind = ParameterKind::PositionalOnly;
+            }
+
+            s
+        } else if let Ok((s, _)) = expect(PyToken::Star)(s) {
+            if let Ok((s, _)) = expect(PyToken::Star)(s) {
+                let (s, name) = argument(s)?;
+                params.push(into_parameter(name, None, ParameterKind::VarKeyword));
+
+                s
+            } else {
+                kind = ParameterKind::KeywordOnly;
+
+                match argument(s) {
+                    Ok((s, name)) => {
+                        params.push(into_parameter(name, None, ParameterKind::VarPositional));
+                        s
+                    }
+
+                    Err(_) => s,
+                }
+            }
+        } else if let Ok((s, name)) = argument(s) {
+            let (s, default) = match expect(PyToken::Equal)(s) {
+                Ok((s, _)) => {
+                    let (s, _) = whitespace(s)?;
+                    let (s, default) = expression(s)?;
+
+                    (s, Some(default))
+                }
+
+                Err(_) => (s, None),
+            };
+
+            params.push(into_parameter(name, default, kind));
+            s
+        } else {
+            stream = s;
+            break;
+        };
+
+        let (s, _) = whitespace(s)?;
+
+        match expect(PyToken::Comma)(s) {
+            Ok((s, _)) => stream = s,
+            Err(_) => {
+                stream = s;
+                break;
+            }
+        }
+    }
+
+    Ok((stream, params))
+}
+
+/// A lambda expression i.e. `lambda x, y=1: x + y`.
+#[inline]
+pub fn lambda_def<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Expr>> {
+    let (stream, lambda) = expect(PyToken::Lambda)(stream)?;
+    let (stream, args) = lambda_parameters(stream)?;
+    let (stream, _) = expect(PyToken::Colon)(stream)?;
+    let (stream, _) = whitespace(stream)?;
+    let (stream, body) = expression(stream)?;
+
+    let lambda = Spanned {
+        span: lambda.span.start..body.span.end,
+        inner: Expr::Lambda {
+            args,
+            body: Box::new(body),
+        },
+    };
+
+    Ok((stream, lambda))
+}
@@ -1,14 +1,53 @@
 use nom::{branch::alt, IResult};
 
 use crate::{
-    ast::{Atom, Import, Primary},
+    ast::{Import, Primary},
     comb::whitespace,
     spanned::Spanned,
     token::PyToken,
     TokenStreamRef,
 };
 
-use super::{expect, expect_many_n, primary};
+use super::{atom::name, expect, expect_many_n};
+
+/// A module name, either a plain name like `a` or a dotted one like `a.b.c`.
+#[inline]
+fn dotted_name<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Primary>> {
+    let (mut stream, first) = name(stream)?;
+
+    let mut dotted = Spanned {
+        span: first.span.clone(),
+        inner: Primary::Atomic(first),
+    };
+
+    loop {
+        let (r, _) = whitespace(stream)?;
+
+        let r = match expect(PyToken::Dot)(r) {
+            Ok((r, _)) => r,
+            Err(_) => break,
+        };
+
+        let (r, _) = whitespace(r)?;
+        let (r, attr) = name(r)?;
+
+        dotted = Spanned {
+            span: dotted.span.start..attr.span.end,
+            inner: Primary::Attribute {
+                left: Box::new(dotted),
+                attr,
+            },
+        };
+
+        stream = r;
+    }
+
+    let (stream, _) = whitespace(stream)?;
+
+    Ok((stream, dotted))
+}
 
 #[inline]
 fn import_from<'this, 'source, 'data>(
@@ -23,15 +62,9 @@ fn import_from<'this, 'source, 'data>(
 
     let stream = loop {
         let (r, _) = expect_many_n::<0>(PyToken::Whitespace)(remaining)?;
-        let (r, thing) = primary(r)?;
+        let (r, thing) = dotted_name(r)?;
         remaining = r;
 
-        match &thing.inner {
-            Primary::Atomic(atom) if matches!(atom.inner, Atom::Name(_)) => {}
-            Primary::Attribute { .. } => {}
-            _ => unreachable!(),
-        }
-
         names.push(thing);
 
         remaining = match expect(PyToken::Comma)(remaining) {
@@ -55,17 +88,29 @@ fn from_import<'this, 'source, 'data>(
     let (stream, tok) = expect(PyToken::From)(stream)?;
     let (stream, _) = whitespace(stream)?;
 
-    let (stream, dots) = expect_many_n::<0>(PyToken::Dot)(stream)?;
-    let (stream, module) = primary(stream)?;
-
-    let level = dots.len();
-
-    match &module.inner {
-        Primary::Atomic(atom) if matches!(atom.inner, Atom::Name(_)) => {}
-        Primary::Attribute { .. } => {}
-        _ => unreachable!(),
+    // `...` is lexed as a single ellipsis token so it counts for three levels.
+    let mut level = 0;
+    let mut stream = stream;
+
+    loop {
+        if let Ok((r, _)) = expect(PyToken::Dot)(stream) {
+            level += 1;
+            stream = r;
+        } else if let Ok((r, _)) = expect(PyToken::Ellipsis)(stream) {
+            level += 3;
+            stream = r;
+        } else {
+            break;
+        }
     }
 
+    // the module may only be left out of relative imports, as in `from . import x`
+    let (stream, module) = match dotted_name(stream) {
+        Ok((stream, module)) => (stream, Some(module)),
+        Err(nom::Err::Error(_)) if level > 0 => (stream, None),
+        Err(err) => return Err(err),
+    };
+
     let (stream, _) = whitespace(stream)?;
     let (stream, _) = expect(PyToken::Import)(stream)?;
     let (stream, _) = whitespace(stream)?;
@@ -76,15 +121,9 @@ fn from_import<'this, 'source, 'data>(
 
     let stream = loop {
         let (r, _) = expect_many_n::<0>(PyToken::Whitespace)(remaining)?;
-        let (r, thing) = primary(r)?;
+        let (r, thing) = dotted_name(r)?;
         remaining = r;
 
-        match &thing.inner {
-            Primary::Atomic(atom) if matches!(atom.inner, Atom::Name(_)) => {}
-            Primary::Attribute { .. } => {}
-            _ => unreachable!(),
-        }
-
         names.push(thing);
 
         remaining = match expect(PyToken::Comma)(remaining) {
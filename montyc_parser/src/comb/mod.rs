@@ -1,4 +1,8 @@
-use nom::IResult;
+use nom::{
+    multi::many0,
+    sequence::{terminated, tuple},
+    IResult,
+};
 
 use crate::{
     ast::{Atom, Expr, Module, Primary, Return, Statement},
@@ -13,12 +17,15 @@ pub mod atom;
 pub mod class;
 pub mod core;
 pub mod expr;
+pub mod for_;
 pub mod funcdef;
 pub mod ifelse;
 pub mod import;
 pub mod primary;
 pub mod stmt;
+pub mod try_;
 pub mod while_;
+pub mod with_;
 
 pub(self) use {self::core::*, assign::*, atom::*, expr::*, primary::*};
 
@@ -66,6 +73,42 @@ pub fn return_stmt<'this, 'source, 'data>(
     Ok((stream, ret))
 }
 
+/// Skip the newline ending the current line along with any blank lines following it.
+#[inline]
+pub fn blank_lines<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, ()> {
+    let (stream, _) = expect_many_n::<0>(PyToken::Newline)(stream)?;
+    let (stream, _) = many0(tuple((
+        expect_many_n::<0>(PyToken::Whitespace),
+        expect(PyToken::Newline),
+    )))(stream)?;
+
+    Ok((stream, ()))
+}
+
+/// The end of a statement, only whitespace and a comment may follow it on the same line.
+#[inline]
+pub fn end_of_statement<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, ()> {
+    let (stream, _) = whitespace(stream)?;
+    let stream = expect_with(stream, |(t, _)| matches!(t, PyToken::CommentRef(_)))
+        .map(|(stream, _)| stream)
+        .unwrap_or(stream);
+
+    if stream.at_end() || expect(PyToken::Newline)(stream).is_ok() {
+        Ok((stream, ()))
+    } else {
+        stream.expected(PyToken::Newline);
+
+        Err(nom::Err::Error(nom::error::Error::new(
+            stream,
+            nom::error::ErrorKind::Verify,
+        )))
+    }
+}
+
 fn chomp<'this, 'source, 'data>(
     mut stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Vec<Spanned<PyToken>>> {
@@ -110,14 +153,27 @@ pub fn module<'this, 'source, 'data>(
         })
         .collect();
 
+    // statements that fail to parse are recorded as syntax errors and skipped.
     loop {
-        if let Ok((s, stmt)) = stmt::statement(stream) {
-            body.push(stmt);
-            let (s, _) = expect_many_n::<0>(PyToken::Newline)(s)?;
-            stream = s;
-        } else {
+        let (s, _) = blank_lines(stream)?;
+
+        if chomp(s)?.0.at_end() {
             break;
         }
+
+        s.clear_failures();
+
+        match terminated(stmt::statement, end_of_statement)(s) {
+            Ok((s, stmt)) => {
+                body.push(stmt);
+                stream = s;
+            }
+
+            Err(_) => {
+                s.syntax_error();
+                stream = s.resync(0);
+            }
+        }
     }
 
     let (stream, mut tail) = chomp(stream)?;
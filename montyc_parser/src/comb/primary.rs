@@ -5,7 +5,7 @@ use nom::{
 };
 
 use crate::{
-    ast::{models::Primary, Atom, Expr},
+    ast::{models::Primary, Argument, Atom, Expr},
     spanned::Spanned,
     token::PyToken,
     TokenStreamRef,
@@ -59,6 +59,64 @@ fn primary_subscript<'this, 'source, 'data>(
     Ok((stream, obj))
 }
 
+/// A single call argument: `*<expr>`, `**<expr>`, `<name>=<expr>` or `<expr>`.
+#[inline]
+fn call_argument<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Argument>> {
+    if let Ok((s, star)) = expect(PyToken::Star)(stream) {
+        let (s, double) = match expect(PyToken::Star)(s) {
+            Ok((s, _)) => (s, true),
+            Err(_) => (s, false),
+        };
+
+        let (s, _) = whitespace(s)?;
+        let (s, value) = expression(s)?;
+
+        let span = star.span.start..value.span.end;
+        let arg = if double {
+            Argument::DoubleStarred(value)
+        } else {
+            Argument::Starred(value)
+        };
+
+        return Ok((s, Spanned { span, inner: arg }));
+    }
+
+    // `name=value` but not `name == value`
+    let keyword = |stream| {
+        let (s, name) = super::atom::name(stream)?;
+        let (s, _) = whitespace(s)?;
+        let (s, _) = expect(PyToken::Equal)(s)?;
+
+        if expect(PyToken::Equal)(s).is_ok() {
+            return Err(nom::Err::Error(Error::new(stream, ErrorKind::IsNot)));
+        }
+
+        let (s, _) = whitespace(s)?;
+        let (s, value) = expression(s)?;
+
+        Ok((s, (name, value)))
+    };
+
+    if let Ok((s, (name, value))) = keyword(stream) {
+        let span = name.span.start..value.span.end;
+        let name = name.inner.as_name().unwrap();
+
+        return Ok((
+            s,
+            Spanned {
+                span,
+                inner: Argument::Keyword(name, value),
+            },
+        ));
+    }
+
+    let (s, value) = expression(stream)?;
+
+    Ok((s, value.replace_with(Argument::Positional)))
+}
+
 #[inline]
 fn primary_call<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
@@ -73,7 +131,7 @@ fn primary_call<'this, 'source, 'data>(
     loop {
         let (s, _) = whitespace(stream)?;
 
-        if let Ok((s, arg)) = super::expr::expression(s) {
+        if let Ok((s, arg)) = call_argument(s) {
             args.push(arg);
             let (s, _) = expect_many_n::<0>(PyToken::Whitespace)(s).unwrap();
 
@@ -85,21 +143,19 @@ fn primary_call<'this, 'source, 'data>(
                 stream = s;
                 break;
             } else {
-                unreachable!("{:?}", s);
+                return Err(nom::Err::Error(Error::new(s, ErrorKind::Alt)));
             }
         } else if let Ok((s, r)) = expect(PyToken::RParen)(s) {
             rparen = r;
             stream = s;
             break;
         } else {
-            unreachable!("{:?}", stream);
+            return Err(nom::Err::Error(Error::new(s, ErrorKind::Alt)));
         }
     }
 
-    let _ = rparen; // TODO: figure out how to span the func name and whole call expr appropriately.
-
     let obj = Spanned {
-        span: base.span.start..base.span.end,
+        span: base.span.start..rparen.span.end,
         inner: Primary::Call {
             func: Box::new(base.clone()),
             args: if args.is_empty() { None } else { Some(args) },
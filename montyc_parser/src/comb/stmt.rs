@@ -117,6 +117,42 @@ fn dyn_while<'this, 'source, 'data>(
     Ok((stream, klass.map(Statement::While)))
 }
 
+#[inline]
+fn dyn_for<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Statement>> {
+    let (stream, for_) = super::for_::for_stmt(stream)?;
+
+    Ok((stream, for_.map(Statement::For)))
+}
+
+#[inline]
+fn dyn_try<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Statement>> {
+    let (stream, try_) = super::try_::try_stmt(stream)?;
+
+    Ok((stream, try_.map(Statement::Try)))
+}
+
+#[inline]
+fn dyn_with<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Statement>> {
+    let (stream, with) = super::with_::with_stmt(stream)?;
+
+    Ok((stream, with.map(Statement::With)))
+}
+
+#[inline]
+fn dyn_raise<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Statement>> {
+    let (stream, raise) = super::try_::raise_stmt(stream)?;
+
+    Ok((stream, raise.map(Statement::Raise)))
+}
+
 #[inline]
 fn dyn_span_ref<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
@@ -144,6 +180,22 @@ fn dyn_pass<'this, 'source, 'data>(
     Ok((stream, span.map(|_| Statement::Pass)))
 }
 
+#[inline]
+fn dyn_break<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Statement>> {
+    let (stream, span) = expect(PyToken::Break)(stream)?;
+    Ok((stream, span.map(|_| Statement::Break)))
+}
+
+#[inline]
+fn dyn_continue<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Statement>> {
+    let (stream, span) = expect(PyToken::Continue)(stream)?;
+    Ok((stream, span.map(|_| Statement::Continue)))
+}
+
 #[inline]
 fn dyn_expr<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
@@ -160,7 +212,10 @@ fn small_stmt<'this, 'source, 'data>(
         dyn_assign,
         dyn_annotation,
         dyn_return,
+        dyn_raise,
         dyn_pass,
+        dyn_break,
+        dyn_continue,
         dyn_expr,
         dyn_span_ref,
     ))(stream)
@@ -170,7 +225,16 @@ fn small_stmt<'this, 'source, 'data>(
 fn compound_stmt<'this, 'source, 'data>(
     stream: TokenStreamRef<'this, 'source, 'data>,
 ) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Statement>> {
-    alt((dyn_funcdef, dyn_import, dyn_classdef, dyn_ifstmt, dyn_while))(stream)
+    alt((
+        dyn_funcdef,
+        dyn_import,
+        dyn_classdef,
+        dyn_ifstmt,
+        dyn_while,
+        dyn_for,
+        dyn_try,
+        dyn_with,
+    ))(stream)
 }
 
 #[inline]
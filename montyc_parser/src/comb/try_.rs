@@ -0,0 +1,165 @@
+use nom::{sequence::tuple, IResult};
+
+use crate::{
+    ast::models::{ExceptHandler, Raise, Statement, Try},
+    spanned::Spanned,
+    token::PyToken,
+    TokenStreamRef,
+};
+
+use super::{blank_lines, expect, expect_many_n, expression, for_::block, name};
+
+/// The head of a clause continuing a compound statement, i.e. `except` or `finally`, at `outer_indent`.
+#[inline]
+pub(super) fn clause<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+    outer_indent: usize,
+    keyword: PyToken,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<PyToken>> {
+    let (stream, _) = blank_lines(stream)?;
+    let (stream, indent) = expect_many_n::<0>(PyToken::Whitespace)(stream)?;
+
+    if indent.len() != outer_indent {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            stream,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+
+    expect(keyword)(stream)
+}
+
+#[inline]
+fn colon<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Vec<Spanned<Statement>>> {
+    let (stream, _) = tuple((
+        expect_many_n::<0>(PyToken::Whitespace),
+        expect(PyToken::Colon),
+        expect_many_n::<0>(PyToken::Whitespace),
+    ))(stream)?;
+
+    block(stream)
+}
+
+#[inline]
+fn except_clause<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+    outer_indent: usize,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<ExceptHandler>> {
+    let (stream, tok) = clause(stream, outer_indent, PyToken::Except)?;
+
+    // a bare `except:` catches everything.
+    let (stream, kind) = match tuple((expect_many_n::<1>(PyToken::Whitespace), expression))(stream)
+    {
+        Ok((stream, (_, kind))) => (stream, Some(kind)),
+        Err(_) => (stream, None),
+    };
+
+    let (stream, name) = match kind {
+        Some(_) => match tuple((
+            expect_many_n::<0>(PyToken::Whitespace),
+            expect(PyToken::As),
+            expect_many_n::<1>(PyToken::Whitespace),
+            name,
+        ))(stream)
+        {
+            Ok((stream, (_, _, _, name))) => (stream, Some(name)),
+            Err(_) => (stream, None),
+        },
+
+        None => (stream, None),
+    };
+
+    let (stream, body) = colon(stream)?;
+
+    let end = body.last().map(|s| s.span.end).unwrap_or(tok.span.end);
+
+    let handler = Spanned {
+        inner: ExceptHandler { kind, name, body },
+        span: tok.span.start..end,
+    };
+
+    Ok((stream, handler))
+}
+
+#[inline]
+pub fn try_stmt<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Try>> {
+    let outer_indent = stream.current_indent();
+
+    let (stream, tok) = expect(PyToken::Try)(stream)?;
+    let (mut stream, body) = colon(stream)?;
+
+    let mut handlers = vec![];
+
+    while let Ok((remaining, handler)) = except_clause(stream, outer_indent) {
+        handlers.push(handler);
+        stream = remaining;
+    }
+
+    // `else` is only allowed after at least one `except` clause.
+    let (stream, orelse) = match handlers.is_empty() {
+        true => (stream, None),
+        false => match clause(stream, outer_indent, PyToken::Else) {
+            Ok((stream, _)) => {
+                let (stream, orelse) = colon(stream)?;
+                (stream, Some(orelse))
+            }
+
+            Err(_) => (stream, None),
+        },
+    };
+
+    let (stream, finalbody) = match clause(stream, outer_indent, PyToken::Finally) {
+        Ok((stream, _)) => {
+            let (stream, finalbody) = colon(stream)?;
+            (stream, Some(finalbody))
+        }
+
+        Err(_) => (stream, None),
+    };
+
+    if handlers.is_empty() && finalbody.is_none() {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            stream,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+
+    let try_ = Try {
+        body,
+        handlers,
+        orelse,
+        finalbody,
+    };
+
+    let try_ = Spanned {
+        inner: try_,
+        span: tok.span,
+    };
+
+    Ok((stream, try_))
+}
+
+#[inline]
+pub fn raise_stmt<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<Raise>> {
+    let (stream, tok) = expect(PyToken::Raise)(stream)?;
+
+    let (stream, exc) = match tuple((expect_many_n::<1>(PyToken::Whitespace), expression))(stream) {
+        Ok((stream, (_, exc))) => (stream, Some(exc)),
+        Err(_) => (stream, None),
+    };
+
+    let end = exc.as_ref().map(|e| e.span.end).unwrap_or(tok.span.end);
+
+    let raise = Spanned {
+        inner: Raise { exc },
+        span: tok.span.start..end,
+    };
+
+    Ok((stream, raise))
+}
@@ -0,0 +1,100 @@
+use nom::{sequence::tuple, IResult};
+
+use crate::{
+    ast::models::{With, WithItem},
+    spanned::Spanned,
+    token::PyToken,
+    TokenStreamRef,
+};
+
+use super::{expect, expect_many_n, expression, for_::block, primary};
+
+#[inline]
+fn with_item<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<WithItem>> {
+    let (stream, context_expr) = expression(stream)?;
+
+    let (stream, target) = match tuple((
+        expect_many_n::<0>(PyToken::Whitespace),
+        expect(PyToken::As),
+        expect_many_n::<1>(PyToken::Whitespace),
+        primary,
+    ))(stream)
+    {
+        Ok((stream, (_, _, _, target))) => (stream, Some(target)),
+        Err(_) => (stream, None),
+    };
+
+    let end = target
+        .as_ref()
+        .map(|t| t.span.end)
+        .unwrap_or(context_expr.span.end);
+
+    let item = Spanned {
+        span: context_expr.span.start..end,
+        inner: WithItem {
+            context_expr,
+            target,
+        },
+    };
+
+    Ok((stream, item))
+}
+
+#[inline]
+pub fn with_stmt<'this, 'source, 'data>(
+    stream: TokenStreamRef<'this, 'source, 'data>,
+) -> IResult<TokenStreamRef<'this, 'source, 'data>, Spanned<With>> {
+    let (stream, is_async) = match tuple((
+        expect(PyToken::Async),
+        expect_many_n::<1>(PyToken::Whitespace),
+    ))(stream)
+    {
+        Ok((stream, (tok, _))) => (stream, Some(tok)),
+        Err(_) => (stream, None),
+    };
+
+    let (stream, tok) = expect(PyToken::With)(stream)?;
+    let (mut stream, (_, first)) =
+        tuple((expect_many_n::<1>(PyToken::Whitespace), with_item))(stream)?;
+
+    let mut items = vec![first];
+
+    while let Ok((remaining, (_, _, _, item))) = tuple((
+        expect_many_n::<0>(PyToken::Whitespace),
+        expect(PyToken::Comma),
+        expect_many_n::<0>(PyToken::Whitespace),
+        with_item,
+    ))(stream)
+    {
+        items.push(item);
+        stream = remaining;
+    }
+
+    let (stream, _) = tuple((
+        expect_many_n::<0>(PyToken::Whitespace),
+        expect(PyToken::Colon),
+        expect_many_n::<0>(PyToken::Whitespace),
+    ))(stream)?;
+
+    let (stream, body) = block(stream)?;
+
+    let start = is_async
+        .as_ref()
+        .map(|tok| tok.span.start)
+        .unwrap_or(tok.span.start);
+
+    let with = With {
+        items,
+        body,
+        is_async: is_async.is_some(),
+    };
+
+    let with = Spanned {
+        inner: with,
+        span: start..tok.span.end,
+    };
+
+    Ok((stream, with))
+}
@@ -13,8 +13,10 @@ pub mod span_interner;
 pub mod spanned;
 pub mod token;
 pub mod token_iter;
+pub mod unparse;
 
 pub use ast::{AstNode, AstObject, AstVisitor};
+pub use montyc_core::ParseError;
 pub use span_interner::SpanInterner;
 use spanned::Spanned;
 use token::PyToken;
@@ -27,6 +29,15 @@ pub(crate) struct TokenStream<'source, 'data> {
     pub(crate) stream_iter: TokenStreamIter<'source, 'data>,
     pub(crate) tokens: Vec<Token>,
     stream_iter_complete: bool,
+
+    /// The span of an invalid token, lexing stops when one is found.
+    invalid_token: Option<logos::Span>,
+
+    /// The furthest token any parser failed at and descriptions of what was expected there.
+    furthest_failure: Option<(usize, Vec<String>)>,
+
+    /// Syntax errors of statements that were skipped to recover from them.
+    errors: Vec<ParseError>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -39,8 +50,17 @@ impl<'this, 'source, 'data> TokenStreamRef<'this, 'source, 'data> {
     pub(crate) fn grow(&self) -> Option<Token> {
         let mut this = self.stream.borrow_mut();
 
+        if this.stream_iter_complete {
+            return None;
+        }
+
         let token = match this.stream_iter.next() {
-            Some(t) => t.unwrap(),
+            Some(Ok(t)) => t,
+            Some(Err(span)) => {
+                this.invalid_token = Some(span);
+                this.stream_iter_complete = true;
+                return None;
+            }
             None => {
                 this.stream_iter_complete = true;
                 return None;
@@ -55,6 +75,162 @@ impl<'this, 'source, 'data> TokenStreamRef<'this, 'source, 'data> {
     pub(crate) fn is_eof(&self) -> bool {
         self.stream.borrow().stream_iter_complete
     }
+
+    /// The token at index `ix`, lexing more of the source if needed.
+    fn token_at(&self, ix: usize) -> Option<Token> {
+        if ix >= self.stream.borrow().tokens.len() && self.grow().is_none() {
+            return None;
+        }
+
+        Some(self.stream.borrow().tokens[ix].clone())
+    }
+
+    /// Check if every token has been consumed.
+    pub(crate) fn at_end(&self) -> bool {
+        self.token_at(self.tokens_slice_start).is_none()
+    }
+
+    /// Note that `what` was expected at the next token, only the furthest failures are kept.
+    pub(crate) fn expected(&self, what: impl ToString) {
+        let ix = self.tokens_slice_start;
+        let mut this = self.stream.borrow_mut();
+
+        match &mut this.furthest_failure {
+            Some((furthest, _)) if *furthest > ix => (),
+            Some((furthest, expected)) if *furthest == ix => {
+                let what = what.to_string();
+
+                if !expected.contains(&what) {
+                    expected.push(what);
+                }
+            }
+
+            furthest => *furthest = Some((ix, vec![what.to_string()])),
+        }
+    }
+
+    /// Forget the failures noted so far, this is done before parsing every statement.
+    pub(crate) fn clear_failures(&self) {
+        self.stream.borrow_mut().furthest_failure = None;
+    }
+
+    /// Record a syntax error for the statement starting at `self` which failed to parse.
+    ///
+    /// The error points at the furthest token any parser got to, since that is usually the culprit.
+    pub(crate) fn syntax_error(&self) {
+        let furthest = self.stream.borrow_mut().furthest_failure.take();
+
+        let mut error = match furthest {
+            Some((ix, expected)) if ix >= self.tokens_slice_start => self.error_at(ix, expected),
+            _ => self.error_at(self.tokens_slice_start, vec![]),
+        };
+
+        // the token after the `:` is not the mistake, the block missing before it is.
+        if error.expected == ["an indented block"] {
+            error.message = String::from("expected an indented block.");
+            error.expected.clear();
+        }
+
+        self.stream.borrow_mut().errors.push(error);
+    }
+
+    /// A syntax error at the token `ix`, skipping over any whitespace or comments.
+    fn error_at(&self, mut ix: usize, expected: Vec<String>) -> ParseError {
+        loop {
+            let (token, span) = match self.token_at(ix) {
+                Some(token) => token,
+                None => {
+                    let eof = self.stream.borrow().stream_iter.lexer.source().len();
+
+                    return ParseError {
+                        span: eof..eof,
+                        message: String::from("unexpected end of input."),
+                        expected,
+                    };
+                }
+            };
+
+            let message = match token {
+                PyToken::Whitespace | PyToken::CommentRef(_) => {
+                    ix += 1;
+                    continue;
+                }
+
+                PyToken::Newline => String::from("unexpected end of line."),
+
+                _ => format!(
+                    "unexpected `{}`.",
+                    self.stream
+                        .borrow()
+                        .stream_iter
+                        .lexer
+                        .source()
+                        .get(span.clone())
+                        .unwrap_or("")
+                ),
+            };
+
+            return ParseError {
+                span,
+                message,
+                expected,
+            };
+        }
+    }
+
+    /// Skip the rest of a statement that failed to parse.
+    ///
+    /// This stops before the newline of the next line indented by at most `indent`, lines indented
+    /// any further are assumed to be part of the statement that failed.
+    ///
+    pub(crate) fn resync(self, indent: usize) -> Self {
+        let mut ix = self.tokens_slice_start;
+
+        loop {
+            match self.token_at(ix) {
+                None => break,
+                Some((PyToken::Newline, _)) => {
+                    let mut line = ix + 1;
+
+                    while let Some((PyToken::Whitespace, _)) = self.token_at(line) {
+                        line += 1;
+                    }
+
+                    match self.token_at(line) {
+                        // blank lines and comments don't belong to any statement.
+                        Some((PyToken::Newline | PyToken::CommentRef(_), _)) => ix = line,
+                        Some(_) if line - (ix + 1) <= indent => break,
+                        Some(_) => ix = line,
+                        None => break,
+                    }
+                }
+
+                Some(_) => ix += 1,
+            }
+        }
+
+        Self {
+            stream: self.stream,
+            tokens_slice_start: ix,
+        }
+    }
+
+    /// The indentation (in whitespace tokens) of the line the next token is on.
+    pub(crate) fn current_indent(&self) -> usize {
+        let this = self.stream.borrow();
+        let consumed = &this.tokens[..self.tokens_slice_start];
+
+        let line_start = consumed
+            .iter()
+            .rposition(|(t, _)| *t == PyToken::Newline)
+            .map(|ix| ix + 1)
+            .unwrap_or(0);
+
+        consumed[line_start..]
+            .iter()
+            .take_while(|(t, _)| *t == PyToken::Whitespace)
+            .count()
+    }
 }
 
 impl<'t, 's, 'd> PartialEq for TokenStreamRef<'t, 's, 'd> {
@@ -70,6 +246,28 @@ pub fn parse<P, R>(
     span_ref: Option<SpanInterner>,
     mref: ModuleRef,
 ) -> R
+where
+    P: for<'this, 'source, 'data> Fn(
+        TokenStreamRef<'this, 'source, 'data>,
+    ) -> IResult<TokenStreamRef<'this, 'source, 'data>, R>,
+    R: Debug,
+{
+    match try_parse(source, func, span_ref, mref) {
+        Ok(result) => result,
+        Err(errors) => panic!("{:?}", errors),
+    }
+}
+
+/// Like `parse` but failures are returned instead of panicking.
+///
+/// Parsers that recover from syntax errors, like `comb::module`, may report more than one.
+///
+pub fn try_parse<P, R>(
+    source: impl AsRef<str>,
+    func: P,
+    span_ref: Option<SpanInterner>,
+    mref: ModuleRef,
+) -> Result<R, Vec<ParseError>>
 where
     P: for<'this, 'source, 'data> Fn(
         TokenStreamRef<'this, 'source, 'data>,
@@ -91,6 +289,9 @@ where
             stream_iter: token_stream,
             tokens: Vec::with_capacity(1024),
             stream_iter_complete: false,
+            invalid_token: None,
+            furthest_failure: None,
+            errors: vec![],
         };
 
         RefCell::new(stream)
@@ -102,20 +303,57 @@ where
             tokens_slice_start: 0,
         };
 
-        let (_, result) = func(stream_ref).unwrap();
+        match func(stream_ref) {
+            Ok((rest, result)) => match rest.at_end() {
+                true => Ok(result),
+                false => {
+                    rest.syntax_error();
+                    Err(())
+                }
+            },
+
+            Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+                err.input.syntax_error();
+                Err(())
+            }
+
+            Err(nom::Err::Incomplete(_)) => {
+                stream.borrow_mut().errors.push(ParseError {
+                    span: source.len()..source.len(),
+                    message: String::from("unexpected end of input."),
+                    expected: vec![],
+                });
 
-        result
+                Err(())
+            }
+        }
     };
 
-    let stream = stream.into_inner();
+    let TokenStream {
+        invalid_token,
+        mut errors,
+        ..
+    } = stream.into_inner();
+
+    if let Some(span) = invalid_token {
+        // lexing stops at an invalid token, so any errors past it are only due to the input ending early.
+        errors.retain(|err| err.span.start < span.start);
+        errors.push(ParseError {
+            message: format!("invalid token {:?}.", source.get(span.clone()).unwrap_or("")),
+            span,
+            expected: vec![],
+        });
+    }
 
-    assert!(
-        stream.stream_iter_complete,
-        "{:?}\n{:#?}",
-        stream.tokens, result
-    );
+    match result {
+        Ok(result) if errors.is_empty() => Ok(result),
+        _ => {
+            errors.sort_by_key(|err| (err.span.start, err.span.end));
+            errors.dedup_by(|a, b| a.span == b.span && a.message == b.message);
 
-    result
+            Err(errors)
+        }
+    }
 }
 
 pub type ParserT<R> =
@@ -140,3 +378,94 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use montyc_core::{ModuleRef, ParseError};
+
+    /// Parse `source` as a module, expecting it to fail.
+    fn errors(source: &str) -> Vec<ParseError> {
+        match crate::try_parse(source, crate::comb::module, None, ModuleRef(1)) {
+            Ok(module) => panic!("expected {:?} to fail, got {:?}", source, module),
+            Err(errors) => errors,
+        }
+    }
+
+    /// The source text each error points at.
+    fn spans<'a>(source: &'a str, errors: &[ParseError]) -> Vec<&'a str> {
+        errors.iter().map(|err| &source[err.span.clone()]).collect()
+    }
+
+    #[test]
+    fn every_statement_is_checked() {
+        let source = "def f(:\n    pass\nx = 1\ny = = 2\nclass\n";
+        let errors = errors(source);
+
+        let messages: Vec<_> = errors.iter().map(|err| err.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["unexpected `:`.", "unexpected `=`.", "unexpected end of line."]
+        );
+
+        assert_eq!(spans(source, &errors), [":", "=", "\n"]);
+    }
+
+    #[test]
+    fn parsing_resumes_after_a_bad_statement() {
+        // the statements after each error parse fine, so they add no errors of their own.
+        let source = "x = = 1\ny = 2\nz = (\n";
+        assert_eq!(spans(source, &errors(source)), ["=", "\n"]);
+
+        // a bad statement in a block only skips that statement, the rest of the block is parsed.
+        let source = "if x:\n    y = = 1\n    z = f(1 2)\n    w = 3\nv = )\n";
+        assert_eq!(spans(source, &errors(source)), ["=", "2", ")"]);
+    }
+
+    #[test]
+    fn missing_block() {
+        let source = "if x:\npass\n";
+        let errors = errors(source);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "expected an indented block.");
+        assert_eq!(spans(source, &errors), ["pass"]);
+
+        let source = "def f():\n    return 1\nclass C:\n";
+        let errors = self::errors(source);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "expected an indented block.");
+        assert_eq!(errors[0].span, source.len()..source.len());
+    }
+
+    #[test]
+    fn expected_tokens() {
+        let cases: &[(&str, &[&str])] = &[
+            ("import\n", &["an identifier"]),
+            ("from . import 1\n", &["an identifier"]),
+            ("def f(:\n    pass\n", &["an identifier", "`/`", "`*`", "`)`"]),
+            (
+                "x = a <\n",
+                &[
+                    "`+`", "`-`", "`~`", "`(`", "`await`", "an identifier", "`[`", "`{`", "`...`",
+                    "`True`", "`False`", "`None`",
+                ],
+            ),
+            (
+                "while x\n    pass\n",
+                &[
+                    "`.`", "`(`", "`[`", "`*`", "`/`", "`%`", "`@`", "`+`", "`-`", "`<`", "`>`",
+                    "`&`", "`^`", "`|`", "`==`", "`!=`", "`<=`", "`>=`", "`in`", "`not in`",
+                    "`is`", "`is not`", "`or`", "`if`", "`:`",
+                ],
+            ),
+        ];
+
+        for (source, expected) in cases {
+            let errors = errors(source);
+
+            assert_eq!(errors.len(), 1, "{:?}", source);
+            assert_eq!(&errors[0].expected, expected, "{:?}", source);
+        }
+    }
+}
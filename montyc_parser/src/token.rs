@@ -49,12 +49,18 @@ pub enum PyToken {
     #[token("def")]
     FnDef,
 
+    #[token("lambda")]
+    Lambda,
+
     #[token("return")]
     Return,
 
     #[token("while")]
     While,
 
+    #[token("for")]
+    For,
+
     #[token("pass")]
     Pass,
 
@@ -76,6 +82,21 @@ pub enum PyToken {
     #[token("raise")]
     Raise,
 
+    #[token("try")]
+    Try,
+
+    #[token("except")]
+    Except,
+
+    #[token("finally")]
+    Finally,
+
+    #[token("with")]
+    With,
+
+    #[token("as")]
+    As,
+
     #[token("assert")]
     Assert,
 
@@ -179,10 +200,15 @@ pub enum PyToken {
     #[regex(r"\t| ")]
     Whitespace,
 
-    #[regex(r"-?\d+", |lex| lex.slice().parse())]
-    #[regex(r"-?0[xX][0-9a-fA-F]+", |lex| lex.slice().parse())]
+    #[regex(r"\d+", |lex| lex.slice().parse())]
+    #[regex(r"0[xX][0-9a-fA-F]+", |lex| lex.slice().parse())]
     Digits(i64),
 
+    #[regex(r"\d+\.\d*([eE][+-]?\d+)?", |lex| lex.slice().parse())]
+    #[regex(r"\.\d+([eE][+-]?\d+)?", |lex| lex.slice().parse())]
+    #[regex(r"\d+[eE][+-]?\d+", |lex| lex.slice().parse())]
+    Float(f64),
+
     #[regex(r"#[^\n]*")]
     Comment,
 
@@ -218,3 +244,81 @@ impl From<PyToken> for Option<SpanRef> {
         }
     }
 }
+
+/// Describes a token as it would be written in source code, used when reporting what was expected.
+impl std::fmt::Display for PyToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PyToken::True => write!(f, "`True`"),
+            PyToken::False => write!(f, "`False`"),
+            PyToken::None => write!(f, "`None`"),
+            PyToken::Is => write!(f, "`is`"),
+            PyToken::Or => write!(f, "`or`"),
+            PyToken::Not => write!(f, "`not`"),
+            PyToken::Await => write!(f, "`await`"),
+            PyToken::Async => write!(f, "`async`"),
+            PyToken::If => write!(f, "`if`"),
+            PyToken::Elif => write!(f, "`elif`"),
+            PyToken::Else => write!(f, "`else`"),
+            PyToken::ClassDef => write!(f, "`class`"),
+            PyToken::FnDef => write!(f, "`def`"),
+            PyToken::Lambda => write!(f, "`lambda`"),
+            PyToken::Return => write!(f, "`return`"),
+            PyToken::While => write!(f, "`while`"),
+            PyToken::For => write!(f, "`for`"),
+            PyToken::Pass => write!(f, "`pass`"),
+            PyToken::Continue => write!(f, "`continue`"),
+            PyToken::In => write!(f, "`in`"),
+            PyToken::Break => write!(f, "`break`"),
+            PyToken::From => write!(f, "`from`"),
+            PyToken::Import => write!(f, "`import`"),
+            PyToken::Raise => write!(f, "`raise`"),
+            PyToken::Try => write!(f, "`try`"),
+            PyToken::Except => write!(f, "`except`"),
+            PyToken::Finally => write!(f, "`finally`"),
+            PyToken::With => write!(f, "`with`"),
+            PyToken::As => write!(f, "`as`"),
+            PyToken::Assert => write!(f, "`assert`"),
+            PyToken::Del => write!(f, "`del`"),
+            PyToken::Global => write!(f, "`global`"),
+            PyToken::Yield => write!(f, "`yield`"),
+            PyToken::Nonlocal => write!(f, "`nonlocal`"),
+            PyToken::Ellipsis => write!(f, "`...`"),
+            PyToken::LParen => write!(f, "`(`"),
+            PyToken::RParen => write!(f, "`)`"),
+            PyToken::LBracket => write!(f, "`[`"),
+            PyToken::RBracket => write!(f, "`]`"),
+            PyToken::LBrace => write!(f, "`{{`"),
+            PyToken::RBrace => write!(f, "`}}`"),
+            PyToken::At => write!(f, "`@`"),
+            PyToken::Caret => write!(f, "`^`"),
+            PyToken::Comma => write!(f, "`,`"),
+            PyToken::Equal => write!(f, "`=`"),
+            PyToken::Dot => write!(f, "`.`"),
+            PyToken::And => write!(f, "`&`"),
+            PyToken::Colon => write!(f, "`:`"),
+            PyToken::Plus => write!(f, "`+`"),
+            PyToken::Minus => write!(f, "`-`"),
+            PyToken::Star => write!(f, "`*`"),
+            PyToken::Tilde => write!(f, "`~`"),
+            PyToken::Modulo => write!(f, "`%`"),
+            PyToken::LessThan => write!(f, "`<`"),
+            PyToken::Pipe => write!(f, "`|`"),
+            PyToken::GreaterThan => write!(f, "`>`"),
+            PyToken::Bang => write!(f, "`!`"),
+            PyToken::Disappointment => write!(f, "`;`"),
+            PyToken::Escape => write!(f, "`\\`"),
+            PyToken::Div => write!(f, "`/`"),
+            PyToken::Newline => write!(f, "a newline"),
+            PyToken::FormFeed => write!(f, "a form feed"),
+            PyToken::Whitespace => write!(f, "whitespace"),
+            PyToken::Digits(_) => write!(f, "an integer"),
+            PyToken::Float(_) => write!(f, "a float"),
+            PyToken::Comment | PyToken::CommentRef(_) => write!(f, "a comment"),
+            PyToken::StringLiteral | PyToken::StringRef(_) => write!(f, "a string"),
+            PyToken::ByteLiteral => write!(f, "a bytes literal"),
+            PyToken::RawIdent | PyToken::Ident(_) => write!(f, "an identifier"),
+            PyToken::Invalid => write!(f, "an invalid token"),
+        }
+    }
+}
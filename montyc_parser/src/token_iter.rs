@@ -18,13 +18,12 @@ impl Debug for TokenStreamIter<'_, '_> {
 }
 
 impl<'source, 'data> Iterator for TokenStreamIter<'source, 'data> {
-    type Item = Result<super::Token, &'static str>;
+    type Item = Result<super::Token, logos::Span>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let token = self.lexer.next()?;
 
-        let span = (self.lexer.span(), self.lexer.slice());
-        let span_range = span.0.clone();
+        let span_range = self.lexer.span();
 
         let (token, span_range) = match token {
             PyToken::RawIdent => {
@@ -47,13 +46,11 @@ impl<'source, 'data> Iterator for TokenStreamIter<'source, 'data> {
                 (PyToken::CommentRef(n), span_range)
             }
 
-            PyToken::Invalid => unreachable!(),
+            PyToken::Invalid => return Some(Err(span_range)),
 
             _ => (token, span_range),
         };
 
-        assert_ne!(token, PyToken::Invalid, "{:?}", span);
-
         Some(Ok((token, span_range)))
     }
 }
@@ -0,0 +1,1315 @@
+//! Printing AST nodes back out as source code.
+//!
+//! The output has a single fixed style: four spaces of indentation, every
+//! statement on its own line, one space around binary operators and after
+//! commas, and parentheses only where the grammar needs them. That makes
+//! printing idempotent, printing what was parsed from some output gives back
+//! that same output.
+//!
+//! Comments are only statements in the AST when they're on a line of their
+//! own. When the parsed source is available the rest of them are kept too, at
+//! the end of the line they were on.
+
+use std::{collections::VecDeque, ops::Range};
+
+use logos::Logos;
+use montyc_core::{ast::ParameterKind, ModuleRef, SpanRef};
+
+use crate::{ast::*, spanned::Spanned, token::PyToken, ParseError, SpanInterner};
+
+const INDENT: &str = "    ";
+
+/// Parse `source` as a module and print it back out in the style of `Unparser`.
+pub fn format(source: &str) -> Result<String, Vec<ParseError>> {
+    let interner = SpanInterner::new();
+    let module = crate::try_parse(
+        source,
+        crate::comb::module,
+        Some(interner.clone()),
+        ModuleRef(1),
+    )?;
+
+    let resolve = |sref| {
+        interner
+            .span_data(sref)
+            .and_then(|data| source.get(data.range))
+    };
+
+    Ok(Unparser::new(&resolve).with_source(source).module(&module))
+}
+
+/// How tightly an expression binds, named after the grammar rules in `comb::expr`.
+///
+/// An expression is parenthesized when it binds less tightly than where it is placed requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Expression,
+    Disjunction,
+    Conjunction,
+    Inversion,
+    Comparison,
+    BitwiseOr,
+    BitwiseXor,
+    BitwiseAnd,
+    Shift,
+    Sum,
+    Term,
+    Factor,
+    Power,
+    Primary,
+}
+
+/// Prints AST nodes as source code.
+pub struct Unparser<'a> {
+    /// Resolves the names, strings and comments that the AST refers to.
+    resolve: &'a dyn Fn(SpanRef) -> Option<&'a str>,
+
+    /// The source the AST was parsed from, if any.
+    source: Option<&'a str>,
+
+    /// The spans of the comments in `source` that have not been printed yet.
+    comments: VecDeque<Range<usize>>,
+
+    output: String,
+    depth: usize,
+}
+
+impl<'a> Unparser<'a> {
+    /// An unparser that resolves span references with `resolve`.
+    pub fn new(resolve: &'a dyn Fn(SpanRef) -> Option<&'a str>) -> Self {
+        Self {
+            resolve,
+            source: None,
+            comments: VecDeque::new(),
+            output: String::new(),
+            depth: 0,
+        }
+    }
+
+    /// Use the `source` that was parsed to keep comments and blank lines when printing a module.
+    pub fn with_source(mut self, source: &'a str) -> Self {
+        self.comments = PyToken::lexer(source)
+            .spanned()
+            .filter(|(token, _)| matches!(token, PyToken::Comment))
+            .map(|(_, span)| span)
+            .collect();
+
+        self.source = Some(source);
+        self
+    }
+
+    /// Print a whole module.
+    pub fn module(mut self, module: &Module) -> String {
+        self.statements(&module.body);
+        self.leading_comments(usize::MAX);
+        self.output
+    }
+
+    /// Print a single node, statements end with a newline and expressions don't.
+    pub fn node(mut self, node: &AstNode) -> String {
+        // the spans of a lone node say nothing about where the comments of a source go.
+        self.source = None;
+        self.comments.clear();
+
+        let mut out = String::new();
+
+        let statement = match node {
+            AstNode::Import(import) => Statement::Import(import.clone()),
+            AstNode::ClassDef(klass) => Statement::Class(klass.clone()),
+            AstNode::FuncDef(funcdef) => Statement::FnDef(funcdef.clone()),
+            AstNode::If(ifch) => Statement::If(ifch.clone()),
+            AstNode::Assign(asn) => Statement::Asn(asn.clone()),
+            AstNode::Ret(ret) => Statement::Ret(ret.clone()),
+            AstNode::While(while_) => Statement::While(while_.clone()),
+            AstNode::For(for_) => Statement::For(for_.clone()),
+            AstNode::Try(try_) => Statement::Try(try_.clone()),
+            AstNode::With(with) => Statement::With(with.clone()),
+            AstNode::Raise(raise) => Statement::Raise(raise.clone()),
+            AstNode::Annotation(ann) => Statement::Ann(ann.clone()),
+            AstNode::Pass => Statement::Pass,
+            AstNode::Break => Statement::Break,
+            AstNode::Continue => Statement::Continue,
+
+            AstNode::Int(atom)
+            | AstNode::Str(atom)
+            | AstNode::Comment(atom)
+            | AstNode::Bool(atom)
+            | AstNode::Float(atom)
+            | AstNode::Tuple(atom)
+            | AstNode::List(atom)
+            | AstNode::Set(atom)
+            | AstNode::Dict(atom)
+            | AstNode::ListComp(atom)
+            | AstNode::SetComp(atom)
+            | AstNode::DictComp(atom)
+            | AstNode::Name(atom)
+            | AstNode::None(atom)
+            | AstNode::Ellipsis(atom) => {
+                self.atom(&mut out, atom);
+                return out;
+            }
+
+            AstNode::BinOp(expr)
+            | AstNode::Compare(expr)
+            | AstNode::IfExpr(expr)
+            | AstNode::Unary(expr)
+            | AstNode::NamedExpr(expr)
+            | AstNode::Lambda(expr) => {
+                self.expr(&mut out, expr, Precedence::Expression);
+                return out;
+            }
+
+            AstNode::Subscript(primary) | AstNode::Call(primary) | AstNode::Attr(primary) => {
+                self.primary(&mut out, primary);
+                return out;
+            }
+        };
+
+        self.statement(&Spanned {
+            span: 0..0,
+            inner: statement,
+        });
+
+        self.output
+    }
+
+    fn name(&self, sref: SpanRef) -> &'a str {
+        (self.resolve)(sref).expect("span reference could not be resolved.")
+    }
+
+    // -- Lines and comments
+
+    /// Write a line of `text`, `anchor` is the source it was printed from.
+    ///
+    /// Comments before the line that `anchor` starts on are written on their own beforehand,
+    /// comments between there and the end of `anchor` are kept at the end of the line.
+    fn line(&mut self, text: &str, anchor: Option<Range<usize>>) {
+        let mut trailing = vec![];
+
+        if let (Some(source), Some(anchor)) = (self.source, anchor) {
+            self.leading_comments(line_start(source, anchor.start));
+
+            while let Some(comment) = self.comments.front() {
+                if comment.start >= anchor.end {
+                    break;
+                }
+
+                trailing.extend(source.get(comment.clone()));
+                self.comments.pop_front();
+            }
+        }
+
+        for _ in 0..self.depth {
+            self.output.push_str(INDENT);
+        }
+
+        self.output.push_str(text);
+
+        for comment in trailing {
+            self.output.push_str("  ");
+            self.output.push_str(comment.trim_end());
+        }
+
+        self.output.push('\n');
+    }
+
+    /// Write a line for a simple statement spanning `span`.
+    fn simple(&mut self, text: &str, span: &Range<usize>) {
+        let anchor = self.source.map(|source| {
+            let last = span.end.saturating_sub(1).max(span.start);
+            span.start..line_end(source, last)
+        });
+
+        self.line(text, anchor);
+    }
+
+    /// Write the header line of a compound statement starting at `start`, and then its `body`.
+    fn compound(&mut self, text: &str, start: Option<usize>, body: &[Spanned<Statement>]) {
+        let anchor = start.zip(self.source).map(|(start, source)| {
+            let end = body
+                .first()
+                .map(first_offset)
+                .unwrap_or_else(|| line_end(source, start));
+
+            start..end
+        });
+
+        self.line(text, anchor);
+
+        self.depth += 1;
+
+        match body {
+            [] => self.line("pass", None),
+            body => self.statements(body),
+        }
+
+        self.depth -= 1;
+    }
+
+    /// Write the comments that start before `offset` on lines of their own.
+    fn leading_comments(&mut self, offset: usize) {
+        let source = match self.source {
+            Some(source) => source,
+            None => return,
+        };
+
+        while let Some(comment) = self.comments.front() {
+            if comment.start >= offset {
+                break;
+            }
+
+            let comment = self.comments.pop_front().unwrap();
+
+            if let Some(text) = source.get(comment) {
+                self.line(text.trim_end(), None);
+            }
+        }
+    }
+
+    /// Whether the line before the one `offset` is on is blank in the source.
+    fn follows_blank_line(&self, offset: usize) -> bool {
+        let source = match self.source {
+            Some(source) => source,
+            None => return false,
+        };
+
+        match line_start(source, offset) {
+            0 => false,
+            start => {
+                let previous = line_start(source, start - 1);
+                source[previous..start - 1].trim().is_empty()
+            }
+        }
+    }
+
+    // -- Statements
+
+    /// Write the statements of a block.
+    ///
+    /// Definitions, along with the comments right above them, are surrounded by two blank lines
+    /// at the top level of a module and by one anywhere else. Other statements keep a single blank
+    /// line if there was one before them in the source.
+    fn statements(&mut self, body: &[Spanned<Statement>]) {
+        let mut definitions = vec![false; body.len()];
+
+        for ix in (0..body.len()).rev() {
+            definitions[ix] = match &body[ix].inner {
+                Statement::FnDef(_) | Statement::Class(_) => true,
+                stmt => {
+                    as_comment(stmt).is_some()
+                        && definitions.get(ix + 1).copied().unwrap_or(false)
+                        && !self.follows_blank_line(first_offset(&body[ix + 1]))
+                }
+            };
+        }
+
+        let around_definition = if self.depth == 0 { 2 } else { 1 };
+
+        for (ix, stmt) in body.iter().enumerate() {
+            if let Some(previous) = ix.checked_sub(1) {
+                let blank_lines = match (definitions[previous], definitions[ix]) {
+                    (true, true) if as_comment(&body[previous].inner).is_some() => 0,
+                    (true, _) | (_, true) => around_definition,
+                    _ => self.follows_blank_line(first_offset(stmt)) as usize,
+                };
+
+                for _ in 0..blank_lines {
+                    self.output.push('\n');
+                }
+            }
+
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Spanned<Statement>) {
+        let span = &stmt.span;
+        let mut line = String::new();
+
+        match &stmt.inner {
+            Statement::Expr(expr) => match as_comment(&stmt.inner) {
+                Some(comment) => {
+                    self.leading_comments(span.start);
+
+                    if self.comments.front().map(|comment| comment.start) == Some(span.start) {
+                        self.comments.pop_front();
+                    }
+
+                    let comment = self.name(comment).trim_end();
+                    self.line(comment, None);
+                }
+
+                None => {
+                    self.expr(&mut line, expr, Precedence::Expression);
+                    self.simple(&line, span);
+                }
+            },
+
+            Statement::Ret(Return { value }) => {
+                line.push_str("return");
+
+                if let Ok(value) = value {
+                    line.push(' ');
+                    self.expr(&mut line, &value.inner, Precedence::Expression);
+                }
+
+                self.simple(&line, span);
+            }
+
+            Statement::Raise(Raise { exc }) => {
+                line.push_str("raise");
+
+                if let Some(exc) = exc {
+                    line.push(' ');
+                    self.expr(&mut line, &exc.inner, Precedence::Expression);
+                }
+
+                self.simple(&line, span);
+            }
+
+            Statement::Asn(Assign { name, value, kind }) => {
+                self.primary(&mut line, &name.inner);
+
+                if let Some(kind) = kind {
+                    line.push_str(": ");
+                    self.expr(&mut line, &kind.inner, Precedence::Expression);
+                }
+
+                line.push_str(" = ");
+                self.expr(&mut line, &value.inner, Precedence::Expression);
+                self.simple(&line, span);
+            }
+
+            Statement::Ann(Annotation { name, kind }) => {
+                self.atom(&mut line, &name.inner);
+                line.push_str(": ");
+                self.expr(&mut line, &kind.inner, Precedence::Expression);
+                self.simple(&line, span);
+            }
+
+            Statement::Import(import) => {
+                self.import(&mut line, import);
+                self.simple(&line, span);
+            }
+
+            Statement::Pass => self.simple("pass", span),
+            Statement::Break => self.simple("break", span),
+            Statement::Continue => self.simple("continue", span),
+
+            Statement::FnDef(funcdef) => self.function_def(funcdef),
+            Statement::Class(klass) => self.class_def(klass),
+
+            Statement::If(IfChain { branches, orelse }) => {
+                for (ix, branch) in branches.iter().enumerate() {
+                    let (keyword, start) = match ix {
+                        0 => ("if ", span.start),
+                        _ => ("elif ", branch.inner.test.span.start),
+                    };
+
+                    let mut line = String::from(keyword);
+                    self.expr(&mut line, &branch.inner.test.inner, Precedence::Expression);
+                    line.push(':');
+
+                    self.compound(&line, Some(start), &branch.inner.body);
+                }
+
+                if let Some(orelse) = orelse {
+                    self.compound("else:", None, orelse);
+                }
+            }
+
+            Statement::While(While { test, body }) => {
+                line.push_str("while ");
+                self.expr(&mut line, &test.inner, Precedence::Expression);
+                line.push(':');
+
+                self.compound(&line, Some(span.start), body);
+            }
+
+            Statement::For(For {
+                target,
+                iter,
+                body,
+                orelse,
+            }) => {
+                line.push_str("for ");
+                self.target(&mut line, &target.inner);
+                line.push_str(" in ");
+                self.expr(&mut line, &iter.inner, Precedence::Expression);
+                line.push(':');
+
+                self.compound(&line, Some(span.start), body);
+
+                if let Some(orelse) = orelse {
+                    self.compound("else:", None, orelse);
+                }
+            }
+
+            Statement::Try(Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            }) => {
+                self.compound("try:", Some(span.start), body);
+
+                for handler in handlers {
+                    let ExceptHandler { kind, name, body } = &handler.inner;
+                    let mut line = String::from("except");
+
+                    if let Some(kind) = kind {
+                        line.push(' ');
+                        self.expr(&mut line, &kind.inner, Precedence::Expression);
+                    }
+
+                    if let Some(name) = name {
+                        line.push_str(" as ");
+                        self.atom(&mut line, &name.inner);
+                    }
+
+                    line.push(':');
+
+                    self.compound(&line, Some(handler.span.start), body);
+                }
+
+                if let Some(orelse) = orelse {
+                    self.compound("else:", None, orelse);
+                }
+
+                if let Some(finalbody) = finalbody {
+                    self.compound("finally:", None, finalbody);
+                }
+            }
+
+            Statement::With(With {
+                items,
+                body,
+                is_async,
+            }) => {
+                if *is_async {
+                    line.push_str("async ");
+                }
+
+                line.push_str("with ");
+
+                for (ix, item) in items.iter().enumerate() {
+                    if ix > 0 {
+                        line.push_str(", ");
+                    }
+
+                    self.expr(
+                        &mut line,
+                        &item.inner.context_expr.inner,
+                        Precedence::Expression,
+                    );
+
+                    if let Some(target) = &item.inner.target {
+                        line.push_str(" as ");
+                        self.primary(&mut line, &target.inner);
+                    }
+                }
+
+                line.push(':');
+
+                self.compound(&line, Some(span.start), body);
+            }
+        }
+    }
+
+    fn decorators(&mut self, decorators: &[Spanned<Primary>]) {
+        for decorator in decorators {
+            let mut line = String::from("@");
+            self.primary(&mut line, &decorator.inner);
+            self.simple(&line, &decorator.span);
+        }
+    }
+
+    fn function_def(&mut self, funcdef: &FunctionDef) {
+        self.decorators(&funcdef.decorator_list);
+
+        let mut line = String::from("def ");
+        self.atom(&mut line, &funcdef.name.inner);
+        line.push('(');
+
+        let params = funcdef.args.as_deref().unwrap_or_default();
+        self.parameters(&mut line, funcdef.reciever.as_ref(), params);
+        line.push(')');
+
+        if let Some(returns) = &funcdef.returns {
+            line.push_str(" -> ");
+            self.expr(&mut line, &returns.inner, Precedence::Expression);
+        }
+
+        line.push(':');
+
+        self.compound(&line, Some(funcdef.name.span.start), &funcdef.body);
+    }
+
+    fn class_def(&mut self, klass: &ClassDef) {
+        self.decorators(&klass.decorator_list);
+
+        let mut line = String::from("class ");
+        self.atom(&mut line, &klass.name.inner);
+
+        if !klass.bases.is_empty() {
+            line.push('(');
+            self.separated(&mut line, &klass.bases, |this, out, base| {
+                this.expr(out, &base.inner, Precedence::Expression)
+            });
+            line.push(')');
+        }
+
+        line.push(':');
+
+        self.compound(&line, Some(klass.name.span.start), &klass.body);
+    }
+
+    fn import(&self, out: &mut String, import: &Import) {
+        let names = match import {
+            Import::Names(names) => {
+                out.push_str("import ");
+                names
+            }
+
+            Import::From {
+                module,
+                names,
+                level,
+            } => {
+                out.push_str("from ");
+
+                for _ in 0..*level {
+                    out.push('.');
+                }
+
+                if let Some(module) = module {
+                    self.primary(out, &module.inner);
+                }
+
+                out.push_str(" import ");
+                names
+            }
+        };
+
+        self.separated(out, names, |this, out, name| this.primary(out, &name.inner));
+    }
+
+    /// The parameters of a function or lambda, without the surrounding parentheses.
+    fn parameters(&self, out: &mut String, reciever: Option<&Spanned<Atom>>, params: &[Parameter]) {
+        let mut items = vec![];
+
+        if let Some(reciever) = reciever {
+            let mut item = String::new();
+            self.atom(&mut item, &reciever.inner);
+            items.push(item);
+        }
+
+        for (ix, param) in params.iter().enumerate() {
+            let bare_star = matches!(param.kind, ParameterKind::KeywordOnly)
+                && !params[..ix].iter().any(|param| {
+                    matches!(
+                        param.kind,
+                        ParameterKind::VarPositional | ParameterKind::KeywordOnly
+                    )
+                });
+
+            if bare_star {
+                items.push(String::from("*"));
+            }
+
+            let mut item = String::from(match param.kind {
+                ParameterKind::VarPositional => "*",
+                ParameterKind::VarKeyword => "**",
+                _ => "",
+            });
+
+            item.push_str(self.name(param.name));
+
+            if let Some(annotation) = &param.annotation {
+                item.push_str(": ");
+                self.expr(&mut item, &annotation.inner, Precedence::Expression);
+            }
+
+            if let Some(default) = &param.default {
+                item.push_str(match param.annotation {
+                    Some(_) => " = ",
+                    None => "=",
+                });
+
+                self.expr(&mut item, &default.inner, Precedence::Expression);
+            }
+
+            items.push(item);
+
+            let last_positional_only = matches!(param.kind, ParameterKind::PositionalOnly)
+                && !matches!(
+                    params.get(ix + 1).map(|param| param.kind),
+                    Some(ParameterKind::PositionalOnly)
+                );
+
+            if last_positional_only {
+                items.push(String::from("/"));
+            }
+        }
+
+        out.push_str(&items.join(", "));
+
+        // without a trailing comma a lone reciever is parsed as an ordinary parameter.
+        if reciever.is_some() && params.is_empty() {
+            out.push(',');
+        }
+    }
+
+    // -- Expressions
+
+    fn separated<T>(&self, out: &mut String, items: &[T], f: impl Fn(&Self, &mut String, &T)) {
+        for (ix, item) in items.iter().enumerate() {
+            if ix > 0 {
+                out.push_str(", ");
+            }
+
+            f(self, out, item);
+        }
+    }
+
+    /// Whether `sigil` is the operator written between `left` and `right` in the source.
+    fn spelled(&self, left: &Spanned<Expr>, right: &Spanned<Expr>, sigil: &str) -> bool {
+        self.source
+            .and_then(|source| source.get(left.span.end..right.span.start))
+            .map_or(false, |between| between.trim() == sigil)
+    }
+
+    /// The spelling and precedence of a binary operator, and the precedence its operands need.
+    fn infix(
+        &self,
+        left: &Spanned<Expr>,
+        op: InfixOp,
+        right: &Spanned<Expr>,
+    ) -> (&'static str, Precedence, Precedence, Precedence) {
+        use Precedence::*;
+
+        match op {
+            // `or` and `|` are the same operator to the parser, as are `&` and `and`.
+            InfixOp::Or if self.spelled(left, right, "|") => {
+                ("|", BitwiseOr, BitwiseXor, BitwiseOr)
+            }
+            InfixOp::Or => ("or", Disjunction, Conjunction, Disjunction),
+            InfixOp::And => ("&", BitwiseAnd, Shift, BitwiseAnd),
+            InfixOp::Xor => ("^", BitwiseXor, BitwiseAnd, BitwiseXor),
+            InfixOp::LeftShift => ("<<", Shift, Shift, Sum),
+            InfixOp::RightShift => (">>", Shift, Shift, Sum),
+            InfixOp::Add => ("+", Sum, Sum, Term),
+            InfixOp::Sub => ("-", Sum, Sum, Term),
+            InfixOp::Mult => ("*", Term, Term, Factor),
+            InfixOp::MatMult => ("@", Term, Term, Factor),
+            InfixOp::Div => ("/", Term, Term, Factor),
+            InfixOp::FloorDiv => ("//", Term, Term, Factor),
+            InfixOp::Mod => ("%", Term, Term, Factor),
+            InfixOp::Power => ("**", Power, Primary, Factor),
+            InfixOp::Invert => unreachable!("`~` is not a binary operator."),
+
+            InfixOp::Eq
+            | InfixOp::NotEq
+            | InfixOp::Lt
+            | InfixOp::LtE
+            | InfixOp::Gt
+            | InfixOp::GtE
+            | InfixOp::In
+            | InfixOp::NotIn
+            | InfixOp::Is
+            | InfixOp::IsNot => (op.sigil(), Comparison, BitwiseOr, BitwiseOr),
+        }
+    }
+
+    fn precedence(&self, expr: &Expr) -> Precedence {
+        match expr {
+            Expr::If { .. } | Expr::Lambda { .. } => Precedence::Expression,
+            Expr::BinOp { left, op, right } => self.infix(left, *op, right).1,
+            Expr::Compare { .. } => Precedence::Comparison,
+            Expr::Unary {
+                op: UnaryOp::Not, ..
+            } => Precedence::Inversion,
+            Expr::Unary { .. } => Precedence::Factor,
+
+            // these are always parenthesized.
+            Expr::Named { .. } => Precedence::Primary,
+
+            // a negative literal is a literal but it reads as a negation.
+            Expr::Primary(Spanned {
+                inner: Primary::Atomic(atom),
+                ..
+            }) => match atom.inner {
+                Atom::Int(n) if n < 0 => Precedence::Factor,
+                Atom::Float(n) if n.is_sign_negative() => Precedence::Factor,
+                _ => Precedence::Primary,
+            },
+
+            Expr::Primary(_) => Precedence::Primary,
+        }
+    }
+
+    /// Write `expr`, parenthesized if it binds less tightly than `min`.
+    fn expr(&self, out: &mut String, expr: &Expr, min: Precedence) {
+        let parenthesized = self.precedence(expr) < min;
+
+        if parenthesized {
+            out.push('(');
+        }
+
+        match expr {
+            Expr::If { test, body, orelse } => {
+                self.expr(out, &body.inner, Precedence::Disjunction);
+                out.push_str(" if ");
+                self.expr(out, &test.inner, Precedence::Disjunction);
+                out.push_str(" else ");
+                self.expr(out, &orelse.inner, Precedence::Expression);
+            }
+
+            Expr::BinOp { left, op, right } => {
+                let (sigil, _, left_min, right_min) = self.infix(left, *op, right);
+
+                self.expr(out, &left.inner, left_min);
+                out.push(' ');
+                out.push_str(sigil);
+                out.push(' ');
+                self.expr(out, &right.inner, right_min);
+            }
+
+            Expr::Compare { left, ops } => {
+                self.expr(out, &left.inner, Precedence::BitwiseOr);
+
+                for (op, right) in ops {
+                    out.push(' ');
+                    out.push_str(op.sigil());
+                    out.push(' ');
+                    self.expr(out, &right.inner, Precedence::BitwiseOr);
+                }
+            }
+
+            Expr::Unary {
+                op: UnaryOp::Not,
+                value,
+            } => {
+                out.push_str("not ");
+                self.expr(out, &value.inner, Precedence::Inversion);
+            }
+
+            Expr::Unary { op, value } => {
+                out.push(match op {
+                    UnaryOp::Invert => '~',
+                    UnaryOp::Add => '+',
+                    _ => '-',
+                });
+
+                self.expr(out, &value.inner, Precedence::Factor);
+            }
+
+            Expr::Named { target, value } => {
+                out.push('(');
+                self.atom(out, &target.inner);
+                out.push_str(" := ");
+                self.expr(out, &value.inner, Precedence::Expression);
+                out.push(')');
+            }
+
+            Expr::Lambda { args, body } => {
+                out.push_str("lambda");
+
+                if !args.is_empty() {
+                    out.push(' ');
+                    self.parameters(out, None, args);
+                }
+
+                out.push_str(": ");
+                self.expr(out, &body.inner, Precedence::Expression);
+            }
+
+            Expr::Primary(primary) => self.primary(out, &primary.inner),
+        }
+
+        if parenthesized {
+            out.push(')');
+        }
+    }
+
+    fn primary(&self, out: &mut String, primary: &Primary) {
+        match primary {
+            // floats are kept as they were spelled, `1e10` reads better than `10000000000.0`.
+            Primary::Atomic(Spanned {
+                inner: Atom::Float(n),
+                span,
+            }) => match self
+                .source
+                .and_then(|source| source.get(span.clone()))
+                .filter(|spelled| spelled.parse::<f64>().ok() == Some(*n))
+            {
+                Some(spelled) => out.push_str(spelled),
+                None => self.atom(out, &Atom::Float(*n)),
+            },
+
+            Primary::Atomic(atom) => self.atom(out, &atom.inner),
+
+            Primary::Subscript { value, index } => {
+                self.primary(out, &value.inner);
+                out.push('[');
+
+                match &index.inner {
+                    Expr::Primary(Spanned {
+                        inner:
+                            Primary::Atomic(Spanned {
+                                inner: Atom::Tuple(values),
+                                ..
+                            }),
+                        ..
+                    }) if !values.is_empty() => self.tuple_items(out, values),
+
+                    index => self.expr(out, index, Precedence::Expression),
+                }
+
+                out.push(']');
+            }
+
+            Primary::Call { func, args } => {
+                self.primary(out, &func.inner);
+                out.push('(');
+
+                let args = args.as_deref().unwrap_or_default();
+
+                self.separated(out, args, |this, out, arg| {
+                    match &arg.inner {
+                        Argument::Positional(_) => (),
+                        Argument::Starred(_) => out.push('*'),
+                        Argument::DoubleStarred(_) => out.push_str("**"),
+                        Argument::Keyword(name, _) => {
+                            out.push_str(this.name(*name));
+                            out.push('=');
+                        }
+                    }
+
+                    this.expr(out, &arg.inner.value().inner, Precedence::Expression);
+                });
+
+                out.push(')');
+            }
+
+            Primary::Attribute { left, attr } => {
+                self.primary(out, &left.inner);
+                out.push('.');
+                self.atom(out, &attr.inner);
+            }
+
+            Primary::Await(value) => {
+                out.push_str("await ");
+                self.primary(out, &value.inner);
+            }
+        }
+    }
+
+    /// The target of a `for` loop or comprehension, tuples are written without parentheses.
+    fn target(&self, out: &mut String, target: &Primary) {
+        match target {
+            Primary::Atomic(Spanned {
+                inner: Atom::Tuple(values),
+                ..
+            }) if !values.is_empty() => self.tuple_items(out, values),
+
+            target => self.primary(out, target),
+        }
+    }
+
+    /// The items of a tuple, a lone item needs a trailing comma.
+    fn tuple_items(&self, out: &mut String, values: &[Spanned<Expr>]) {
+        self.separated(out, values, |this, out, value| {
+            this.expr(out, &value.inner, Precedence::Expression)
+        });
+
+        if values.len() == 1 {
+            out.push(',');
+        }
+    }
+
+    fn comprehension(&self, out: &mut String, clauses: &[Comprehension]) {
+        for Comprehension { target, iter, ifs } in clauses {
+            out.push_str(" for ");
+            self.target(out, &target.inner);
+            out.push_str(" in ");
+            self.expr(out, &iter.inner, Precedence::Disjunction);
+
+            for test in ifs {
+                out.push_str(" if ");
+                self.expr(out, &test.inner, Precedence::Disjunction);
+            }
+        }
+    }
+
+    fn dict_item(&self, out: &mut String, (key, value): &(Spanned<Expr>, Spanned<Expr>)) {
+        self.expr(out, &key.inner, Precedence::Expression);
+        out.push_str(": ");
+        self.expr(out, &value.inner, Precedence::Expression);
+    }
+
+    fn atom(&self, out: &mut String, atom: &Atom) {
+        let expressions = |out: &mut String, values: &[Spanned<Expr>]| {
+            self.separated(out, values, |this, out, value| {
+                this.expr(out, &value.inner, Precedence::Expression)
+            })
+        };
+
+        match atom {
+            Atom::None => out.push_str("None"),
+            Atom::Ellipsis => out.push_str("..."),
+            Atom::Bool(true) => out.push_str("True"),
+            Atom::Bool(false) => out.push_str("False"),
+            Atom::Int(n) => out.push_str(&n.to_string()),
+            Atom::Float(n) => out.push_str(&format!("{:?}", n)),
+            Atom::Str(sref) | Atom::Name(sref) => out.push_str(self.name(*sref)),
+            Atom::Comment(sref) => out.push_str(self.name(*sref).trim_end()),
+
+            Atom::Tuple(values) => {
+                out.push('(');
+                self.tuple_items(out, values);
+                out.push(')');
+            }
+
+            Atom::List(values) => {
+                out.push('[');
+                expressions(out, values);
+                out.push(']');
+            }
+
+            // `{}` is an empty dict.
+            Atom::Set(values) if values.is_empty() => out.push_str("set()"),
+
+            Atom::Set(values) => {
+                out.push('{');
+                expressions(out, values);
+                out.push('}');
+            }
+
+            Atom::Dict(items) => {
+                out.push('{');
+                self.separated(out, items, |this, out, item| this.dict_item(out, item));
+                out.push('}');
+            }
+
+            Atom::ListComp(elt, clauses) => {
+                out.push('[');
+                self.expr(out, &elt.inner, Precedence::Expression);
+                self.comprehension(out, clauses);
+                out.push(']');
+            }
+
+            Atom::SetComp(elt, clauses) => {
+                out.push('{');
+                self.expr(out, &elt.inner, Precedence::Expression);
+                self.comprehension(out, clauses);
+                out.push('}');
+            }
+
+            Atom::DictComp(item, clauses) => {
+                out.push('{');
+                self.dict_item(out, item);
+                self.comprehension(out, clauses);
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// The comment that `stmt` is, if it is one.
+fn as_comment(stmt: &Statement) -> Option<SpanRef> {
+    match stmt {
+        Statement::Expr(Expr::Primary(Spanned {
+            inner:
+                Primary::Atomic(Spanned {
+                    inner: Atom::Comment(comment),
+                    ..
+                }),
+            ..
+        })) => Some(*comment),
+
+        _ => None,
+    }
+}
+
+/// Where `stmt` starts in the source, function spans start at their name after any decorators.
+fn first_offset(stmt: &Spanned<Statement>) -> usize {
+    let decorators = match &stmt.inner {
+        Statement::FnDef(funcdef) => &funcdef.decorator_list,
+        Statement::Class(klass) => &klass.decorator_list,
+        _ => return stmt.span.start,
+    };
+
+    match decorators.first() {
+        // the span of a decorator starts after its `@`.
+        Some(decorator) => decorator.span.start.saturating_sub(1),
+        None => stmt.span.start,
+    }
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    source[..offset].rfind('\n').map_or(0, |ix| ix + 1)
+}
+
+fn line_end(source: &str, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |ix| offset + ix)
+}
+
+#[cfg(test)]
+mod test {
+    use montyc_core::ModuleRef;
+
+    use super::{format, Unparser};
+    use crate::{ast::Module, SpanInterner};
+
+    /// Format `source` and check that formatting the output again changes nothing.
+    fn formatted(source: &str) -> String {
+        let output = format(source).expect("source should parse.");
+        let again = format(&output).expect("formatted output should parse.");
+
+        assert_eq!(output, again, "formatting is not idempotent.");
+
+        output
+    }
+
+    /// Parse `source` as a module, with the interner its span references point into.
+    fn parse(source: &str) -> (Module, SpanInterner) {
+        let interner = SpanInterner::new();
+        let module = crate::try_parse(
+            source,
+            crate::comb::module,
+            Some(interner.clone()),
+            ModuleRef(1),
+        )
+        .expect("source should parse.");
+
+        (module, interner)
+    }
+
+    /// Debug-print `module` with every span reference replaced by the text it points at and every span erased.
+    ///
+    /// Printing moves nodes around, so only the shape of the tree and the text of its names can be compared.
+    ///
+    fn normalized(module: &Module, interner: &SpanInterner, source: &str) -> String {
+        let debug = format!("{:?}", module);
+        let mut output = String::with_capacity(debug.len());
+        let mut rest = debug.as_str();
+
+        while let Some(start) = rest.find("SpanRef(") {
+            output.push_str(&rest[..start]);
+            rest = &rest[start + "SpanRef(".len()..];
+
+            let end = rest.find(')').unwrap();
+            let (group, distinct) = rest[..end].split_once(", ").unwrap();
+            let sref = montyc_core::SpanRef::from((
+                group.parse::<u32>().unwrap(),
+                distinct.parse::<u32>().unwrap(),
+            ));
+
+            let text = interner
+                .span_data(sref)
+                .and_then(|data| source.get(data.range))
+                .unwrap();
+
+            output.push_str(&format!("{:?}", text));
+            rest = &rest[end + 1..];
+        }
+
+        output.push_str(rest);
+
+        let mut erased = String::with_capacity(output.len());
+        let mut rest = output.as_str();
+
+        while let Some(start) = rest.find("span: ") {
+            erased.push_str(&rest[..start + "span: ".len()]);
+            rest = &rest[start + "span: ".len()..];
+
+            let end = rest
+                .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+                .unwrap_or(rest.len());
+
+            erased.push('_');
+            rest = &rest[end..];
+        }
+
+        erased.push_str(rest);
+        erased
+    }
+
+    #[test]
+    fn round_trips_every_node_kind() {
+        let source = "\
+# comment
+import os
+import os.path
+from . import sibling
+from ..parent import a, b
+from .x import y, z
+
+
+@decorator
+@decorator.attr(1, key=2)
+def f(a: int, b: str = \"s\", *args, c=None, **kwargs) -> int:
+    return a
+
+
+class A(Base):
+    x: int
+    y: int = 1
+
+    def g(self):
+        pass
+
+
+x = None
+x = ...
+x = True
+x = 1.5
+x = 'single'
+x = (1, 2)
+x = [1, 2]
+x = {1, 2}
+x = {1: 2, 3: 4}
+x = [a for a in b if a]
+x = {a for a, b in c}
+x = {a: b for a in c for b in a}
+x = a if b else c
+x = not a
+x = -a + ~b
+x = a ** -b
+x = (a + b) * c - d / e // f % g @ h
+x = a << 1 >> 2 & 3 | 4 ^ 5
+x = a < b <= c > d >= e == f != g
+x = a in b
+x = a not in b
+x = a is b
+x = a is not b
+x = a or b
+x = lambda: 1
+x = lambda a, b: a + b
+x = f(*args, **kwargs)
+x = f(a, b=1)
+x = a[1].b[c]
+if a:
+    pass
+elif b:
+    pass
+else:
+    pass
+while a:
+    break
+for i in range(10):
+    continue
+for k, (a, b) in items:
+    pass
+try:
+    pass
+except ValueError:
+    raise
+except Exception as e:
+    raise ValueError(e)
+finally:
+    pass
+with open(path) as f, lock:
+    pass
+";
+
+        let (module, interner) = parse(source);
+        let expected = normalized(&module, &interner, source);
+
+        let resolve = |sref| {
+            interner
+                .span_data(sref)
+                .and_then(|data| source.get(data.range))
+        };
+
+        let output = Unparser::new(&resolve).with_source(source).module(&module);
+        let (reparsed, reinterner) = parse(&output);
+
+        assert_eq!(
+            expected,
+            normalized(&reparsed, &reinterner, &output),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn normalizes_spacing() {
+        assert_eq!(formatted("x=1+2*3\n"), "x = 1 + 2 * 3\n");
+        assert_eq!(formatted("f(a,b ,c)\n"), "f(a, b, c)\n");
+        assert_eq!(formatted("t = (1,)\n"), "t = (1,)\n");
+    }
+
+    #[test]
+    fn minus_is_an_operator() {
+        assert_eq!(formatted("x=a-1.5\n"), "x = a - 1.5\n");
+        assert_eq!(formatted("x=a-1\n"), "x = a - 1\n");
+        assert_eq!(formatted("x = -1\n"), "x = -1\n");
+        assert_eq!(formatted("x = 2**-1\n"), "x = 2 ** -1\n");
+    }
+
+    #[test]
+    fn unpacking_targets() {
+        assert_eq!(
+            formatted("for k,v in items:\n    pass\n"),
+            "for k, v in items:\n    pass\n"
+        );
+        assert_eq!(
+            formatted("for (k, (a, b)) in items:\n    pass\n"),
+            "for k, (a, b) in items:\n    pass\n"
+        );
+        assert_eq!(
+            formatted("x = [a for a,b in xs]\n"),
+            "x = [a for a, b in xs]\n"
+        );
+        assert_eq!(
+            formatted("x = {k: v for k, v in items}\n"),
+            "x = {k: v for k, v in items}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_needed_parentheses() {
+        assert_eq!(formatted("x = (a + b) * c\n"), "x = (a + b) * c\n");
+        assert_eq!(formatted("x = a - (b - c)\n"), "x = a - (b - c)\n");
+        assert_eq!(formatted("x = (a * b) + c\n"), "x = a * b + c\n");
+        assert_eq!(formatted("x = 2 ** 3 ** 2\n"), "x = 2 ** 3 ** 2\n");
+    }
+
+    #[test]
+    fn keeps_float_spelling() {
+        assert_eq!(formatted("x = 1e10\n"), "x = 1e10\n");
+    }
+
+    #[test]
+    fn preserves_comments() {
+        let source = "\
+# leading
+import os  # trailing
+
+
+def f(a: int, b=1) -> int:
+    # inside
+    if a:  # header
+        return a
+    return b
+";
+
+        assert_eq!(formatted(source), source);
+    }
+
+    #[test]
+    fn separates_definitions() {
+        let source = "class A:\n    def f(self, x):\n        pass\n    def g(self, x):\n        pass\nx = A()\n";
+
+        assert_eq!(
+            formatted(source),
+            "class A:\n    def f(self, x):\n        pass\n\n    def g(self, x):\n        pass\n\n\nx = A()\n"
+        );
+    }
+}
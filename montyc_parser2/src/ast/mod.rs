@@ -98,16 +98,31 @@ impl_ast_node!(
     (Bool => models::Atom),
     (Float => models::Atom),
     (Tuple => models::Atom),
+    (List => models::Atom),
+    (Set => models::Atom),
+    (Dict => models::Atom),
+    (ListComp => models::Atom),
+    (SetComp => models::Atom),
+    (DictComp => models::Atom),
     (Name => models::Atom),
     (BinOp => models::Expr),
+    (Compare => models::Expr),
     (IfExpr => models::Expr),
     (Unary => models::Expr),
     (NamedExpr => models::Expr),
+    (Lambda => models::Expr),
     (None => models::Atom),
     (Ellipsis => models::Atom),
     (Subscript => models::Primary),
     (Call => models::Primary),
     (Attr => models::Primary),
     (Ret => models::Return),
-    (While => models::While)
+    (While => models::While),
+    (For => models::For),
+    (Try => models::Try),
+    (With => models::With),
+    (Raise => models::Raise),
+    (Pass => models::Statement),
+    (Break => models::Statement),
+    (Continue => models::Statement)
 );
@@ -1,5 +1,13 @@
 use super::*;
 
+/// A single `for target in iter if cond...` clause of a comprehension.
+#[derive(Debug, Clone)]
+pub struct Comprehension {
+    pub target: Spanned<Primary>,
+    pub iter: Spanned<Expr>,
+    pub ifs: Vec<Spanned<Expr>>,
+}
+
 #[derive(Debug, Clone, derive_more::Unwrap)]
 pub enum Atom {
     None,
@@ -9,13 +17,35 @@ pub enum Atom {
     Bool(bool),
     Float(f64),
     Tuple(Vec<Spanned<Expr>>),
+    List(Vec<Spanned<Expr>>),
+    Set(Vec<Spanned<Expr>>),
+    Dict(Vec<(Spanned<Expr>, Spanned<Expr>)>),
+    ListComp(Box<Spanned<Expr>>, Vec<Comprehension>),
+    SetComp(Box<Spanned<Expr>>, Vec<Comprehension>),
+    DictComp(Box<(Spanned<Expr>, Spanned<Expr>)>, Vec<Comprehension>),
     Comment(SpanRef),
     Name(SpanRef),
 }
 
 impl AstObject for Atom {
     fn into_ast_node(&self) -> AstNode {
-        todo!()
+        match self {
+            Atom::None => AstNode::None(self.clone()),
+            Atom::Ellipsis => AstNode::Ellipsis(self.clone()),
+            Atom::Int(_) => AstNode::Int(self.clone()),
+            Atom::Str(_) => AstNode::Str(self.clone()),
+            Atom::Bool(_) => AstNode::Bool(self.clone()),
+            Atom::Float(_) => AstNode::Float(self.clone()),
+            Atom::Tuple(_) => AstNode::Tuple(self.clone()),
+            Atom::List(_) => AstNode::List(self.clone()),
+            Atom::Set(_) => AstNode::Set(self.clone()),
+            Atom::Dict(_) => AstNode::Dict(self.clone()),
+            Atom::ListComp(..) => AstNode::ListComp(self.clone()),
+            Atom::SetComp(..) => AstNode::SetComp(self.clone()),
+            Atom::DictComp(..) => AstNode::DictComp(self.clone()),
+            Atom::Comment(_) => AstNode::Comment(self.clone()),
+            Atom::Name(_) => AstNode::Name(self.clone()),
+        }
     }
 
     fn type_name(&self) -> &str {
@@ -64,6 +94,7 @@ impl From<PyToken> for Atom {
             PyToken::True => Self::Bool(true),
             PyToken::False => Self::Bool(false),
             PyToken::Digits(n) => Self::Int(n),
+            PyToken::Float(n) => Self::Float(n),
             PyToken::CommentRef(n) => Self::Comment(n),
             PyToken::StringRef(n) => Self::Str(n),
             PyToken::Ident(n) => Self::Name(n),
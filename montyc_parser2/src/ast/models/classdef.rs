@@ -4,6 +4,7 @@ use crate::ast::{AstNode, AstObject, AstVisitor};
 #[derive(Debug, Clone)]
 pub struct ClassDef {
     pub name: Spanned<Atom>,
+    pub bases: Vec<Spanned<Expr>>,
     pub decorator_list: Vec<Spanned<Primary>>,
     pub body: Vec<Spanned<Statement>>,
 }
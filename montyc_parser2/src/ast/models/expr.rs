@@ -37,6 +37,14 @@ pub enum InfixOp {
     And,
     Or,
     Xor,
+    Lt,
+    LtE,
+    Gt,
+    GtE,
+    In,
+    NotIn,
+    Is,
+    IsNot,
 }
 
 impl InfixOp {
@@ -58,6 +66,14 @@ impl InfixOp {
             InfixOp::And => "and",
             InfixOp::Or => "or",
             InfixOp::Xor => "^",
+            InfixOp::Lt => "<",
+            InfixOp::LtE => "<=",
+            InfixOp::Gt => ">",
+            InfixOp::GtE => ">=",
+            InfixOp::In => "in",
+            InfixOp::NotIn => "not in",
+            InfixOp::Is => "is",
+            InfixOp::IsNot => "is not",
         }
     }
 }
@@ -72,7 +88,7 @@ impl AsRef<str> for InfixOp {
             InfixOp::FloorDiv => "floordiv",
             InfixOp::MatMult => "matmul",
             InfixOp::Mod => "mod",
-            InfixOp::Div => "div",
+            InfixOp::Div => "truediv",
             InfixOp::Mult => "mul",
             InfixOp::LeftShift => "lshift",
             InfixOp::RightShift => "rshift",
@@ -81,6 +97,13 @@ impl AsRef<str> for InfixOp {
             InfixOp::And => "and",
             InfixOp::Or => "or",
             InfixOp::Xor => "xor",
+            InfixOp::Lt => "lt",
+            InfixOp::LtE => "le",
+            InfixOp::Gt => "gt",
+            InfixOp::GtE => "ge",
+            InfixOp::In | InfixOp::NotIn => "contains",
+            InfixOp::Is => "is",
+            InfixOp::IsNot => "is not",
         }
     }
 }
@@ -99,6 +122,12 @@ pub enum Expr {
         right: Box<Spanned<Expr>>,
     },
 
+    /// A chain of two or more comparisons i.e. `a < b <= c`.
+    Compare {
+        left: Box<Spanned<Expr>>,
+        ops: Vec<(InfixOp, Spanned<Expr>)>,
+    },
+
     Unary {
         op: UnaryOp,
         value: Box<Spanned<Expr>>,
@@ -109,6 +138,12 @@ pub enum Expr {
         value: Box<Spanned<Expr>>,
     },
 
+    /// An anonymous function i.e. `lambda x, y=1: x + y`.
+    Lambda {
+        args: Vec<Parameter>,
+        body: Box<Spanned<Expr>>,
+    },
+
     Primary(Spanned<Primary>),
 }
 
@@ -140,7 +175,15 @@ impl PartialEq for Expr {
 
 impl AstObject for Expr {
     fn into_ast_node(&self) -> AstNode {
-        todo!()
+        match self {
+            Expr::If { .. } => AstNode::IfExpr(self.clone()),
+            Expr::BinOp { .. } => AstNode::BinOp(self.clone()),
+            Expr::Compare { .. } => AstNode::Compare(self.clone()),
+            Expr::Unary { .. } => AstNode::Unary(self.clone()),
+            Expr::Named { .. } => AstNode::NamedExpr(self.clone()),
+            Expr::Lambda { .. } => AstNode::Lambda(self.clone()),
+            Expr::Primary(primary) => primary.into_ast_node(),
+        }
     }
 
     fn type_name(&self) -> &str {
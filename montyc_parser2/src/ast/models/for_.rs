@@ -0,0 +1,30 @@
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct For {
+    pub target: Spanned<Primary>,
+    pub iter: Spanned<Expr>,
+    pub body: Vec<Spanned<Statement>>,
+    pub orelse: Option<Vec<Spanned<Statement>>>,
+}
+
+impl AstObject for For {
+    fn into_ast_node(&self) -> AstNode {
+        AstNode::For(self.clone())
+    }
+
+    fn type_name(&self) -> &str {
+        "For"
+    }
+
+    fn call_visitor_handler<T>(
+        &self,
+        visitor: &dyn AstVisitor<T>,
+        span: Option<montyc_core::Span>,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        visitor.visit_For(self, span)
+    }
+}
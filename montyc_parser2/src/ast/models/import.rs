@@ -14,7 +14,7 @@ pub struct ImportDecl {
 pub enum Import {
     Names(Vec<Spanned<Primary>>),
     From {
-        module: Spanned<Primary>,
+        module: Option<Spanned<Primary>>,
         names: Vec<Spanned<Primary>>,
         level: usize,
     },
@@ -7,13 +7,13 @@ pub mod string_ref;
 pub use string_ref::StringRef;
 
 pub mod atom;
-pub use atom::Atom;
+pub use atom::{Atom, Comprehension};
 
 pub mod expr;
 pub use expr::{Expr, InfixOp, UnaryOp};
 
 pub mod funcdef;
-pub use funcdef::FunctionDef;
+pub use funcdef::{FunctionDef, Parameter};
 
 pub mod ifstmt;
 pub use ifstmt::{BranchTail, If, IfChain};
@@ -22,7 +22,7 @@ pub mod import;
 pub use import::{Import, ImportDecl};
 
 pub mod primary;
-pub use primary::Primary;
+pub use primary::{Argument, Primary};
 
 pub mod statement;
 pub use statement::Statement;
@@ -30,6 +30,18 @@ pub use statement::Statement;
 pub mod while_;
 pub use while_::While;
 
+pub mod for_;
+pub use for_::For;
+
+pub mod try_;
+pub use try_::{ExceptHandler, Try};
+
+pub mod with_;
+pub use with_::{With, WithItem};
+
+pub mod raise;
+pub use raise::Raise;
+
 pub mod return_;
 pub use return_::Return;
 
@@ -1,6 +1,34 @@
 use super::*;
 use crate::ast::{AstNode, AstObject, AstVisitor};
 
+/// An argument at a call site.
+#[derive(Debug, Clone)]
+pub enum Argument {
+    /// `<expr>`
+    Positional(Spanned<Expr>),
+
+    /// `*<expr>`
+    Starred(Spanned<Expr>),
+
+    /// `<name>=<expr>`
+    Keyword(SpanRef, Spanned<Expr>),
+
+    /// `**<expr>`
+    DoubleStarred(Spanned<Expr>),
+}
+
+impl Argument {
+    /// The expression that is passed, or unpacked, as the argument.
+    pub fn value(&self) -> &Spanned<Expr> {
+        match self {
+            Self::Positional(value)
+            | Self::Starred(value)
+            | Self::Keyword(_, value)
+            | Self::DoubleStarred(value) => value,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Primary {
     Atomic(Spanned<Atom>),
@@ -14,7 +42,7 @@ pub enum Primary {
     /// `<func:primary>(<args?>)`
     Call {
         func: Box<Spanned<Primary>>,
-        args: Option<Vec<Spanned<Expr>>>,
+        args: Option<Vec<Spanned<Argument>>>,
     },
 
     /// `<primary> DOT(.) <atom>`
@@ -29,7 +57,13 @@ pub enum Primary {
 
 impl AstObject for Primary {
     fn into_ast_node(&self) -> AstNode {
-        todo!()
+        match self {
+            Primary::Atomic(atom) => atom.into_ast_node(),
+            Primary::Subscript { .. } => AstNode::Subscript(self.clone()),
+            Primary::Call { .. } => AstNode::Call(self.clone()),
+            Primary::Attribute { .. } => AstNode::Attr(self.clone()),
+            Primary::Await(inner) => inner.into_ast_node(),
+        }
     }
 
     fn type_name(&self) -> &str {
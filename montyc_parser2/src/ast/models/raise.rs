@@ -0,0 +1,27 @@
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct Raise {
+    pub exc: Option<Spanned<Expr>>,
+}
+
+impl AstObject for Raise {
+    fn into_ast_node(&self) -> AstNode {
+        AstNode::Raise(self.clone())
+    }
+
+    fn type_name(&self) -> &str {
+        "Raise"
+    }
+
+    fn call_visitor_handler<T>(
+        &self,
+        visitor: &dyn AstVisitor<T>,
+        span: Option<montyc_core::Span>,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        visitor.visit_Raise(self, span)
+    }
+}
@@ -12,13 +12,21 @@ pub enum Statement {
     Class(ClassDef),
     If(IfChain),
     While(While),
+    For(For),
+    Try(Try),
+    With(With),
+    Raise(Raise),
     Pass,
+    Break,
+    Continue,
 }
 
 impl AstObject for Statement {
     fn into_ast_node(&self) -> AstNode {
         match self {
-            Self::Pass => todo!(),
+            Self::Pass => AstNode::Pass(self.clone()),
+            Self::Break => AstNode::Break(self.clone()),
+            Self::Continue => AstNode::Continue(self.clone()),
             Self::Expr(node) => node.into_ast_node(),
             Self::FnDef(node) => node.into_ast_node(),
             Self::Ret(node) => node.into_ast_node(),
@@ -28,6 +36,10 @@ impl AstObject for Statement {
             Self::Class(node) => node.into_ast_node(),
             Self::If(node) => node.into_ast_node(),
             Self::While(node) => node.into_ast_node(),
+            Self::For(node) => node.into_ast_node(),
+            Self::Try(node) => node.into_ast_node(),
+            Self::With(node) => node.into_ast_node(),
+            Self::Raise(node) => node.into_ast_node(),
         }
     }
 
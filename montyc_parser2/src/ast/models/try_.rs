@@ -0,0 +1,37 @@
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct ExceptHandler {
+    pub kind: Option<Spanned<Expr>>,
+    pub name: Option<Spanned<Atom>>,
+    pub body: Vec<Spanned<Statement>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Try {
+    pub body: Vec<Spanned<Statement>>,
+    pub handlers: Vec<Spanned<ExceptHandler>>,
+    pub orelse: Option<Vec<Spanned<Statement>>>,
+    pub finalbody: Option<Vec<Spanned<Statement>>>,
+}
+
+impl AstObject for Try {
+    fn into_ast_node(&self) -> AstNode {
+        AstNode::Try(self.clone())
+    }
+
+    fn type_name(&self) -> &str {
+        "Try"
+    }
+
+    fn call_visitor_handler<T>(
+        &self,
+        visitor: &dyn AstVisitor<T>,
+        span: Option<montyc_core::Span>,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        visitor.visit_Try(self, span)
+    }
+}
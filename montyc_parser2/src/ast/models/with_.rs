@@ -0,0 +1,37 @@
+use super::*;
+
+/// A single `context_expr as target` item of a `with` statement.
+#[derive(Debug, Clone)]
+pub struct WithItem {
+    pub context_expr: Spanned<Expr>,
+    pub target: Option<Spanned<Primary>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct With {
+    pub items: Vec<Spanned<WithItem>>,
+    pub body: Vec<Spanned<Statement>>,
+    /// `async with`, only parsed since coroutines can not be evaluated.
+    pub is_async: bool,
+}
+
+impl AstObject for With {
+    fn into_ast_node(&self) -> AstNode {
+        AstNode::With(self.clone())
+    }
+
+    fn type_name(&self) -> &str {
+        "With"
+    }
+
+    fn call_visitor_handler<T>(
+        &self,
+        visitor: &dyn AstVisitor<T>,
+        span: Option<montyc_core::Span>,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        visitor.visit_With(self, span)
+    }
+}
@@ -1,50 +1,661 @@
+//! Expressions, from atoms up to lambdas.
+//!
+//! The precedence climbing mirrors `montyc_parser`, so both parsers agree on
+//! the shape of the trees they produce.
+
+use std::iter;
+
 use chumsky::prelude::*;
+use montyc_core::{ast::ParameterKind, SpanRef};
 
 use crate::{
-    prelude::{Atom, Expr, Primary, Spanned},
+    ast::{Argument, Atom, Comprehension, Expr, InfixOp, Parameter, Primary, Spanned, UnaryOp},
     token::PyToken,
 };
 
-fn atom() -> impl Parser<
-    (PyToken, montyc_core::Span),
-    Spanned<crate::ast::Atom>,
-    Error = Simple<(PyToken, montyc_core::Span)>,
-> {
-    recursive(|atom| {
-        let val = select! {
-            (PyToken::Ident(sr), span) => Spanned::new(Atom::Name(sr), span),
-            (PyToken::True, span) => Spanned::new(Atom::Bool(true), span),
-            (PyToken::False, span) => Spanned::new(Atom::Bool(false), span),
-            (PyToken::None, span) => Spanned::new(Atom::None, span),
-            (PyToken::Digits(n), span) => Spanned::new(Atom::Int(n), span),
-            (PyToken::Ellipsis, span) => Spanned::new(Atom::Ellipsis, span),
-        };
+use super::ParsingError;
 
-        let lparen = select! {
-            (PyToken::LParen, _) => PyToken::LParen,
-        };
+type Span = logos::Span;
 
-        let rparen = select! {
-            (PyToken::RParen, _) => PyToken::RParen,
-        };
+/// Every parser of the grammar runs over laid out tokens and fails with a `ParsingError`.
+pub(super) trait PyParser<T>:
+    Parser<PyToken, T, Error = ParsingError> + Clone + 'static
+{
+}
 
-        let comma = select! {
-            (PyToken::Comma, _) => PyToken::Comma,
-        };
+impl<T, P> PyParser<T> for P where P: Parser<PyToken, T, Error = ParsingError> + Clone + 'static {}
+
+/// A single `tok`, producing its span.
+pub(super) fn token(tok: PyToken) -> impl PyParser<Span> {
+    just(tok).map_with_span(|_, span| span)
+}
+
+/// `first` immediately followed by `second`, operators like `**` and `==` are lexed as two tokens.
+pub(super) fn adjacent(first: PyToken, second: PyToken) -> impl PyParser<Span> {
+    token(first)
+        .then(token(second))
+        .try_map(move |(left, right), span| {
+            if left.end == right.start {
+                Ok(span)
+            } else {
+                // i.e. the second `=` of `x = = 1` is what's unexpected.
+                Err(ParsingError::expected_input_found(
+                    right,
+                    None,
+                    Some(second),
+                ))
+            }
+        })
+}
+
+/// A binary operation of `op` between `left` and `right`.
+fn binop(left: Spanned<Expr>, op: InfixOp, right: Spanned<Expr>) -> Spanned<Expr> {
+    let span = left.span.start..right.span.end;
+
+    let inner = Expr::BinOp {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    };
+
+    Spanned::new(inner, span)
+}
+
+/// `tok` as the operator `op`.
+fn op<T: Clone + 'static>(tok: PyToken, op: T) -> impl PyParser<T> {
+    just(tok).to(op)
+}
+
+/// `item (sep item)*` folded to the right, i.e. `a | b | c` is `a | (b | c)`.
+fn right_assoc(
+    item: impl PyParser<Spanned<Expr>>,
+    sep: PyToken,
+    op: InfixOp,
+) -> impl PyParser<Spanned<Expr>> {
+    item.separated_by(token(sep)).at_least(1).map(move |items| {
+        items
+            .into_iter()
+            .rev()
+            .reduce(|right, left| binop(left, op, right))
+            .expect("at least one item is parsed.")
+    })
+}
+
+pub(super) fn ident() -> impl PyParser<Spanned<SpanRef>> {
+    filter_map(|span, tok| match tok {
+        PyToken::Ident(name) => Ok(Spanned::new(name, span)),
+        _ => Err(ParsingError::expected_input_found(
+            span,
+            Some(Some(PyToken::RawIdent)),
+            Some(tok),
+        )),
+    })
+}
+
+pub(super) fn name() -> impl PyParser<Spanned<Atom>> {
+    ident().map(|name| name.map(Atom::Name))
+}
+
+/// Names, strings, numbers and the constants.
+fn literal() -> impl PyParser<Spanned<Atom>> {
+    filter_map(|span, tok| match tok {
+        PyToken::Ident(_)
+        | PyToken::StringRef(_)
+        | PyToken::Digits(_)
+        | PyToken::Float(_)
+        | PyToken::True
+        | PyToken::False
+        | PyToken::None
+        | PyToken::Ellipsis => Ok(Spanned::new(Atom::from(tok), span)),
+
+        _ => Err(ParsingError::expected_input_found(
+            span,
+            [
+                PyToken::RawIdent,
+                PyToken::StringLiteral,
+                PyToken::Digits(0),
+                PyToken::Float(0.0),
+                PyToken::True,
+                PyToken::False,
+                PyToken::None,
+                PyToken::Ellipsis,
+            ]
+            .iter()
+            .copied()
+            .map(Some),
+            Some(tok),
+        )),
+    })
+}
+
+/// `expr, expr, ...` with at least one comma.
+fn tuple_items(expr: impl PyParser<Spanned<Expr>>) -> impl PyParser<Vec<Spanned<Expr>>> {
+    expr.clone()
+        .then_ignore(token(PyToken::Comma))
+        .then(expr.separated_by(token(PyToken::Comma)).allow_trailing())
+        .map(|(first, rest)| iter::once(first).chain(rest).collect())
+}
+
+/// The rest of a display after its first item.
+enum DisplayTail<T> {
+    Comprehension(Vec<Comprehension>),
+    Items(Vec<T>),
+}
+
+fn display_tail<T: 'static>(
+    item: impl PyParser<T>,
+    clauses: impl PyParser<Vec<Comprehension>>,
+) -> impl PyParser<DisplayTail<T>> {
+    let items = token(PyToken::Comma)
+        .ignore_then(item.separated_by(token(PyToken::Comma)).allow_trailing())
+        .or_not()
+        .map(|rest| DisplayTail::Items(rest.unwrap_or_default()));
+
+    clauses.map(DisplayTail::Comprehension).or(items)
+}
+
+fn atom(
+    expr: impl PyParser<Spanned<Expr>>,
+    primary: impl PyParser<Spanned<Primary>>,
+) -> impl PyParser<Spanned<Atom>> {
+    let tuple = token(PyToken::LParen)
+        .then(tuple_items(expr.clone()))
+        .then(token(PyToken::RParen))
+        .map(|((lparen, values), rparen)| {
+            Spanned::new(Atom::Tuple(values), lparen.start..rparen.end)
+        });
 
-        let items = atom.separated_by(comma).allow_trailing();
-        let tuple = items
-            .delimited_by(lparen, rparen)
-            .map(|i: Vec<Spanned<Atom>>| {
-                let tuple = Atom::Tuple(
-                    i.into_iter()
-                        .map(|i| i.replace_with(Primary::Atomic).replace_with(Expr::Primary))
-                        .collect::<Vec<Spanned<Expr>>>(),
-                );
-                Spanned::new(tuple, Default::default())
+    // one or more `for target in iter (if cond)*` clauses.
+    let clauses = token(PyToken::For)
+        .ignore_then(target_list(primary))
+        .then_ignore(token(PyToken::In))
+        .then(expr.clone())
+        .then(token(PyToken::If).ignore_then(expr.clone()).repeated())
+        .map(|((target, iter), ifs)| Comprehension { target, iter, ifs })
+        .repeated()
+        .at_least(1);
+
+    let list = token(PyToken::LBracket)
+        .then(
+            expr.clone()
+                .then(display_tail(expr.clone(), clauses.clone()))
+                .or_not(),
+        )
+        .then(token(PyToken::RBracket))
+        .map(|((lbracket, inner), rbracket)| {
+            let inner = match inner {
+                None => Atom::List(vec![]),
+                Some((first, DisplayTail::Comprehension(clauses))) => {
+                    Atom::ListComp(Box::new(first), clauses)
+                }
+                Some((first, DisplayTail::Items(rest))) => {
+                    Atom::List(iter::once(first).chain(rest).collect())
+                }
+            };
+
+            Spanned::new(inner, lbracket.start..rbracket.end)
+        });
+
+    let dict_item = expr
+        .clone()
+        .then_ignore(token(PyToken::Colon))
+        .then(expr.clone());
+
+    let dict = dict_item
+        .clone()
+        .then(display_tail(dict_item, clauses.clone()))
+        .map(|(first, tail)| match tail {
+            DisplayTail::Comprehension(clauses) => Atom::DictComp(Box::new(first), clauses),
+            DisplayTail::Items(rest) => Atom::Dict(iter::once(first).chain(rest).collect()),
+        });
+
+    let set = expr
+        .clone()
+        .then(display_tail(expr, clauses))
+        .map(|(first, tail)| match tail {
+            DisplayTail::Comprehension(clauses) => Atom::SetComp(Box::new(first), clauses),
+            DisplayTail::Items(rest) => Atom::Set(iter::once(first).chain(rest).collect()),
+        });
+
+    // `{}` is an empty dict.
+    let brace = token(PyToken::LBrace)
+        .then(dict.or(set).or_not())
+        .then(token(PyToken::RBrace))
+        .map(|((lbrace, inner), rbrace)| {
+            Spanned::new(
+                inner.unwrap_or_else(|| Atom::Dict(vec![])),
+                lbrace.start..rbrace.end,
+            )
+        });
+
+    literal().or(tuple).or(list).or(brace)
+}
+
+/// A single call argument: `*<expr>`, `**<expr>`, `<name>=<expr>` or `<expr>`.
+fn call_argument(expr: impl PyParser<Spanned<Expr>>) -> impl PyParser<Spanned<Argument>> {
+    let double_starred = adjacent(PyToken::Star, PyToken::Star)
+        .then(expr.clone())
+        .map(|(star, value)| {
+            let span = star.start..value.span.end;
+            Spanned::new(Argument::DoubleStarred(value), span)
+        });
+
+    let starred = token(PyToken::Star)
+        .then(expr.clone())
+        .map(|(star, value)| {
+            let span = star.start..value.span.end;
+            Spanned::new(Argument::Starred(value), span)
+        });
+
+    // `name=value`, a `name == value` fails on the second `=` and is positional.
+    let keyword = ident()
+        .then_ignore(token(PyToken::Equal))
+        .then(expr.clone())
+        .map(|(name, value)| {
+            let span = name.span.start..value.span.end;
+            Spanned::new(Argument::Keyword(name.inner, value), span)
+        });
+
+    let positional = expr.map(|value| value.replace_with(Argument::Positional));
+
+    double_starred.or(starred).or(keyword).or(positional)
+}
+
+/// What follows a primary: `.name`, `(args)` or `[index]`.
+enum Trailer {
+    Attribute(Spanned<Atom>),
+    Call(Vec<Spanned<Argument>>),
+    Subscript(Spanned<Expr>),
+}
+
+/// The target of a `for` loop or comprehension clause, `k, v` is a tuple without the parentheses.
+pub(super) fn target_list(
+    primary: impl PyParser<Spanned<Primary>>,
+) -> impl PyParser<Spanned<Primary>> {
+    primary
+        .clone()
+        .then(
+            token(PyToken::Comma)
+                .ignore_then(primary.separated_by(token(PyToken::Comma)).allow_trailing())
+                .or_not(),
+        )
+        .map(|(first, rest)| match rest {
+            None => first,
+            Some(rest) => {
+                let values: Vec<_> = iter::once(first)
+                    .chain(rest)
+                    .map(|value| value.replace_with(Expr::Primary))
+                    .collect();
+
+                let span = values[0].span.start..values[values.len() - 1].span.end;
+
+                Spanned::new(Atom::Tuple(values), span).replace_with(Primary::Atomic)
+            }
+        })
+}
+
+pub(super) fn primary(expr: impl PyParser<Spanned<Expr>>) -> impl PyParser<Spanned<Primary>> {
+    recursive(move |primary| {
+        let attribute = token(PyToken::Dot)
+            .ignore_then(name())
+            .map(Trailer::Attribute);
+
+        let call = call_argument(expr.clone())
+            .separated_by(token(PyToken::Comma))
+            .allow_trailing()
+            .delimited_by(token(PyToken::LParen), token(PyToken::RParen))
+            .map(Trailer::Call);
+
+        // `x[a, b]` is indexed by a tuple that isn't parenthesized.
+        let index = expr
+            .clone()
+            .then(
+                token(PyToken::Comma)
+                    .ignore_then(
+                        expr.clone()
+                            .separated_by(token(PyToken::Comma))
+                            .allow_trailing(),
+                    )
+                    .or_not(),
+            )
+            .map(|(first, rest)| match rest {
+                None => first,
+                Some(rest) => {
+                    let values: Vec<_> = iter::once(first).chain(rest).collect();
+                    let span = values[0].span.start..values[values.len() - 1].span.end;
+
+                    Spanned::new(Atom::Tuple(values), span)
+                        .replace_with(Primary::Atomic)
+                        .replace_with(Expr::Primary)
+                }
             });
 
-        val.or(tuple)
+        let subscript = index
+            .delimited_by(token(PyToken::LBracket), token(PyToken::RBracket))
+            .map(Trailer::Subscript);
+
+        let trailer = attribute
+            .or(call)
+            .or(subscript)
+            .map_with_span(|trailer, span: Span| (trailer, span));
+
+        atom(expr.clone(), primary)
+            .map(|atom| atom.replace_with(Primary::Atomic))
+            .then(trailer.repeated())
+            .foldl(|base, (trailer, trailer_span)| {
+                let span = base.span.start..trailer_span.end;
+                let base = Box::new(base);
+
+                let inner = match trailer {
+                    Trailer::Attribute(attr) => Primary::Attribute { left: base, attr },
+                    Trailer::Call(args) => Primary::Call {
+                        func: base,
+                        args: if args.is_empty() { None } else { Some(args) },
+                    },
+                    Trailer::Subscript(index) => Primary::Subscript {
+                        value: base,
+                        index: Box::new(index),
+                    },
+                };
+
+                Spanned::new(inner, span)
+            })
+    })
+}
+
+/// The parameters of a `def` or `lambda` as written, before they're given their kinds.
+#[derive(Debug, Clone)]
+pub(super) enum ParameterItem {
+    /// `/`
+    Slash,
+
+    /// `*` or `*name: annotation`
+    Star(Option<(Spanned<SpanRef>, Option<Spanned<Expr>>)>),
+
+    /// `**name: annotation`
+    DoubleStar(Spanned<SpanRef>, Option<Spanned<Expr>>),
+
+    /// `name: annotation = default`
+    Named(
+        Spanned<SpanRef>,
+        Option<Spanned<Expr>>,
+        Option<Spanned<Expr>>,
+    ),
+}
+
+/// A comma separated list of parameters, and whether it had a trailing comma.
+pub(super) fn parameter_items(
+    expr: impl PyParser<Spanned<Expr>>,
+    annotation: impl PyParser<Option<Spanned<Expr>>>,
+) -> impl PyParser<(Vec<ParameterItem>, bool)> {
+    let slash = token(PyToken::Div).to(ParameterItem::Slash);
+
+    let double_star = adjacent(PyToken::Star, PyToken::Star)
+        .ignore_then(ident())
+        .then(annotation.clone())
+        .map(|(name, annotation)| ParameterItem::DoubleStar(name, annotation));
+
+    let star = token(PyToken::Star)
+        .ignore_then(ident().then(annotation.clone()).or_not())
+        .map(ParameterItem::Star);
+
+    let named = ident()
+        .then(annotation)
+        .then(token(PyToken::Equal).ignore_then(expr).or_not())
+        .map(|((name, annotation), default)| ParameterItem::Named(name, annotation, default));
+
+    slash
+        .or(double_star)
+        .or(star)
+        .or(named)
+        .separated_by(token(PyToken::Comma))
+        .then(token(PyToken::Comma).or_not())
+        .map(|(items, trailing)| (items, trailing.is_some()))
+}
+
+/// Give each parameter its kind, a `/` makes everything before it positional-only
+/// (including the `receiver`) and a `*` makes everything after it keyword-only.
+pub(super) fn parameter_list(
+    items: Vec<ParameterItem>,
+    mut receiver: Option<Spanned<SpanRef>>,
+) -> (Option<Spanned<SpanRef>>, Vec<Parameter>) {
+    let mut params: Vec<Parameter> = vec![];
+    let mut kind = ParameterKind::PositionalOrKeyword;
+
+    for item in items {
+        match item {
+            ParameterItem::Slash => {
+                if let Some(recv) = receiver.take() {
+                    params.insert(
+                        0,
+                        Parameter {
+                            name: recv.inner,
+                            annotation: None,
+                            default: None,
+                            kind,
+                        },
+                    );
+                }
+
+                for param in params.iter_mut() {
+                    param.kind = ParameterKind::PositionalOnly;
+                }
+            }
+
+            ParameterItem::Star(named) => {
+                kind = ParameterKind::KeywordOnly;
+
+                if let Some((name, annotation)) = named {
+                    params.push(Parameter {
+                        name: name.inner,
+                        annotation,
+                        default: None,
+                        kind: ParameterKind::VarPositional,
+                    });
+                }
+            }
+
+            ParameterItem::DoubleStar(name, annotation) => params.push(Parameter {
+                name: name.inner,
+                annotation,
+                default: None,
+                kind: ParameterKind::VarKeyword,
+            }),
+
+            ParameterItem::Named(name, annotation, default) => params.push(Parameter {
+                name: name.inner,
+                annotation,
+                default,
+                kind,
+            }),
+        }
+    }
+
+    (receiver, params)
+}
+
+/// A lambda expression i.e. `lambda x, y=1: x + y`, its parameters can't be annotated since a `:` ends them.
+fn lambda(expr: impl PyParser<Spanned<Expr>>) -> impl PyParser<Spanned<Expr>> {
+    token(PyToken::Lambda)
+        .then(parameter_items(expr.clone(), empty().to(None)))
+        .then_ignore(token(PyToken::Colon))
+        .then(expr)
+        .map(|((lambda, (items, _)), body)| {
+            let (_, args) = parameter_list(items, None);
+            let span = lambda.start..body.span.end;
+
+            let inner = Expr::Lambda {
+                args,
+                body: Box::new(body),
+            };
+
+            Spanned::new(inner, span)
+        })
+}
+
+fn compare_op() -> impl PyParser<InfixOp> {
+    use PyToken::{Bang, Equal, GreaterThan, In, Is, LessThan, Not};
+
+    adjacent(Equal, Equal)
+        .to(InfixOp::Eq)
+        .or(adjacent(Bang, Equal).to(InfixOp::NotEq))
+        .or(adjacent(LessThan, Equal).to(InfixOp::LtE))
+        .or(adjacent(GreaterThan, Equal).to(InfixOp::GtE))
+        .or(op(LessThan, InfixOp::Lt))
+        .or(op(GreaterThan, InfixOp::Gt))
+        .or(op(In, InfixOp::In))
+        .or(token(Not).then(token(In)).to(InfixOp::NotIn))
+        .or(token(Is).then(token(Not)).to(InfixOp::IsNot))
+        .or(op(Is, InfixOp::Is))
+}
+
+pub(super) fn expression() -> impl PyParser<Spanned<Expr>> {
+    recursive(|expr| {
+        // a parenthesized expression i.e. `(a + b)`, tuples are left to the atoms.
+        let group = token(PyToken::LParen)
+            .then(expr.clone())
+            .then(token(PyToken::RParen))
+            .map(|((lparen, inner), rparen): ((Span, Spanned<Expr>), Span)| {
+                Spanned::new(inner.inner, lparen.start..rparen.end)
+            });
+
+        let await_primary = token(PyToken::Await)
+            .repeated()
+            .then(primary(expr.clone()))
+            .map(|(awaits, primary)| match awaits.first() {
+                Some(await_) => Spanned::new(primary.inner, await_.start..primary.span.end),
+                None => primary,
+            })
+            .map(|primary| primary.replace_with(Expr::Primary));
+
+        let factor = recursive(move |factor| {
+            let power = group
+                .or(await_primary)
+                .then(
+                    adjacent(PyToken::Star, PyToken::Star)
+                        .ignore_then(factor)
+                        .or_not(),
+                )
+                .map(|(left, right)| match right {
+                    Some(right) => binop(left, InfixOp::Power, right),
+                    None => left,
+                });
+
+            let unary = op(PyToken::Plus, UnaryOp::Add)
+                .or(op(PyToken::Minus, UnaryOp::Sub))
+                .or(op(PyToken::Tilde, UnaryOp::Invert))
+                .map_with_span(|op, span: Span| (op, span));
+
+            unary.repeated().then(power).foldr(|(op, op_span), value| {
+                let span = op_span.start..value.span.end;
+
+                let inner = Expr::Unary {
+                    op,
+                    value: Box::new(value),
+                };
+
+                Spanned::new(inner, span)
+            })
+        })
+        .boxed();
+
+        let term_op = adjacent(PyToken::Div, PyToken::Div)
+            .to(InfixOp::FloorDiv)
+            .or(op(PyToken::Star, InfixOp::Mult))
+            .or(op(PyToken::Div, InfixOp::Div))
+            .or(op(PyToken::Modulo, InfixOp::Mod))
+            .or(op(PyToken::At, InfixOp::MatMult));
+
+        let term = factor
+            .clone()
+            .then(term_op.then(factor).repeated())
+            .foldl(|left, (op, right)| binop(left, op, right))
+            .boxed();
+
+        let sum_op = op(PyToken::Plus, InfixOp::Add).or(op(PyToken::Minus, InfixOp::Sub));
+
+        let sum = term
+            .clone()
+            .then(sum_op.then(term).repeated())
+            .foldl(|left, (op, right)| binop(left, op, right))
+            .boxed();
+
+        let shift_op = adjacent(PyToken::LessThan, PyToken::LessThan)
+            .to(InfixOp::LeftShift)
+            .or(adjacent(PyToken::GreaterThan, PyToken::GreaterThan).to(InfixOp::RightShift));
+
+        let shift = sum
+            .clone()
+            .then(shift_op.then(sum).repeated())
+            .foldl(|left, (op, right)| binop(left, op, right));
+
+        let bitwise_and = right_assoc(shift, PyToken::And, InfixOp::And);
+        let bitwise_xor = right_assoc(bitwise_and, PyToken::Caret, InfixOp::Xor);
+        let bitwise_or = right_assoc(bitwise_xor, PyToken::Pipe, InfixOp::Or).boxed();
+
+        let comparison = bitwise_or
+            .clone()
+            .then(compare_op().then(bitwise_or).repeated())
+            .map(|(left, mut ops)| match ops.len() {
+                0 => left,
+                // a lone comparison is just another binary operator.
+                1 => {
+                    let (op, right) = ops.pop().expect("one comparison is parsed.");
+                    binop(left, op, right)
+                }
+                _ => {
+                    let span = left.span.start..ops[ops.len() - 1].1.span.end;
+
+                    let inner = Expr::Compare {
+                        left: Box::new(left),
+                        ops,
+                    };
+
+                    Spanned::new(inner, span)
+                }
+            });
+
+        let inversion = token(PyToken::Not)
+            .repeated()
+            .then(comparison)
+            .foldr(|not, value| {
+                let span = not.start..value.span.end;
+
+                let inner = Expr::Unary {
+                    op: UnaryOp::Not,
+                    value: Box::new(value),
+                };
+
+                Spanned::new(inner, span)
+            });
+
+        let disjunction = right_assoc(inversion, PyToken::Or, InfixOp::Or).boxed();
+
+        let ternary = disjunction
+            .clone()
+            .then(
+                token(PyToken::If)
+                    .ignore_then(disjunction)
+                    .then_ignore(token(PyToken::Else))
+                    .then(expr.clone())
+                    .or_not(),
+            )
+            .map(|(body, tail)| match tail {
+                None => body,
+                Some((test, orelse)) => {
+                    let span = body.span.start..orelse.span.end;
+
+                    let inner = Expr::If {
+                        test: Box::new(test),
+                        body: Box::new(body),
+                        orelse: Box::new(orelse),
+                    };
+
+                    Spanned::new(inner, span)
+                }
+            });
+
+        lambda(expr).or(ternary)
     })
 }
 
@@ -54,25 +665,23 @@ mod test {
     use montyc_core::ModuleRef;
 
     use crate::{
-        prelude::{Atom, Spanned},
-        token::PyToken,
+        prelude::{Atom, Expr, InfixOp, Module, Parseable, ParsingError, Spanned, Statement},
+        span_interner::SpanInterner,
     };
 
-    fn lex(source: &str) -> Vec<(PyToken, montyc_core::Span)> {
-        let spanner = crate::span_interner::SpanInterner::new();
-        let bound = spanner.get(source, ModuleRef(0)).unwrap();
-        let tokens = crate::token_iter::TokenStreamIter {
-            bound,
-            lexer: <PyToken as logos::Logos>::lexer(source),
-        }
-        .map(|each| each.unwrap())
-        .collect::<Vec<_>>();
-        tokens
-    }
-
     #[test]
     pub fn atom_parser_works() {
-        let (out, errors) = (super::atom()).parse_recovery(lex("1"));
+        let parse = |source| {
+            let (tokens, errors) = super::super::lex(source, &SpanInterner::new(), ModuleRef(0));
+            assert!(errors.is_empty());
+
+            let expr = super::expression();
+            let atom = super::atom(expr.clone(), super::primary(expr));
+
+            atom.parse_recovery(super::super::stream(&tokens))
+        };
+
+        let (out, errors) = parse("1");
         assert!(errors.is_empty());
         assert!(out.is_some());
         match out {
@@ -83,15 +692,66 @@ mod test {
             _ => panic!("{:?}", out),
         }
 
-        let (out, errors) = (super::atom()).parse_recovery(lex("(1,2,3)"));
+        let (out, errors) = parse("(1,2,3)");
         assert!(errors.is_empty());
         assert!(out.is_some());
         match out {
             Some(Spanned {
-                inner: Atom::Int(_),
+                inner: Atom::Tuple(ref values),
                 ..
-            }) => (),
+            }) if values.len() == 3 => (),
             _ => panic!("{:?}", out),
         }
     }
+
+    #[test]
+    pub fn expression_parser_works() {
+        match Expr::parse_from("1 + 2 * 3").unwrap() {
+            Expr::BinOp {
+                op: InfixOp::Add,
+                right,
+                ..
+            } => assert!(matches!(
+                right.inner,
+                Expr::BinOp {
+                    op: InfixOp::Mult,
+                    ..
+                }
+            )),
+            expr => panic!("{:?}", expr),
+        }
+
+        match Expr::parse_from("a < b <= c").unwrap() {
+            Expr::Compare { ops, .. } => assert_eq!(
+                ops.iter().map(|(op, _)| *op).collect::<Vec<_>>(),
+                vec![InfixOp::Lt, InfixOp::LtE]
+            ),
+            expr => panic!("{:?}", expr),
+        }
+
+        assert!(matches!(
+            Expr::parse_from("x-1").unwrap(),
+            Expr::BinOp {
+                op: InfixOp::Sub,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    pub fn module_parser_recovers() {
+        let source = "x = = 1\ndef f():\n    return\n    y = (\n";
+
+        let errors = Module::parse_from(source).unwrap_err();
+
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(matches!(errors[0], ParsingError::Unexpected { span: ref s, .. } if s == &(4..5)));
+        assert_eq!(errors[0].message(source), "unexpected `=`.");
+        assert_eq!(errors[1].message(source), "unexpected end of line.");
+
+        let module = Module::parse_from("if x:\n    pass\n# trailing\n").unwrap();
+
+        assert!(matches!(module.body[0].inner, Statement::If(_)));
+        assert!(matches!(module.body[1].inner, Statement::Expr(_)));
+    }
 }
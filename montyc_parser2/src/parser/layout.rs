@@ -0,0 +1,219 @@
+//! Turns the raw token stream into logical lines for the grammar.
+//!
+//! Whitespace, comments at the end of lines and newlines inside of brackets
+//! carry no meaning to the grammar so they're dropped here. What's left is one
+//! `Newline` at the end of every logical line, and `Indent`/`Dedent` tokens
+//! whenever the indentation of a line changes.
+
+use crate::token::PyToken;
+
+use super::ParsingError;
+
+type Span = logos::Span;
+
+/// Lay out the tokens of a source that is `eoi` bytes long.
+pub(super) fn layout(
+    tokens: impl Iterator<Item = Result<(PyToken, Span), Span>>,
+    eoi: usize,
+) -> (Vec<(PyToken, Span)>, Vec<ParsingError>) {
+    let mut layout = Layout::default();
+
+    for token in tokens {
+        match token {
+            Ok((token, span)) => layout.push(token, span),
+            Err(span) => layout.errors.push(ParsingError::InvalidToken { span }),
+        }
+    }
+
+    layout.finish(eoi)
+}
+
+/// Split laid out tokens into top level statements.
+///
+/// Each statement can be parsed on its own, so that a syntax error in one of
+/// them does not stop the rest of the module from being parsed.
+pub(super) fn statements(tokens: &[(PyToken, Span)]) -> Vec<&[(PyToken, Span)]> {
+    let mut statements = vec![];
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut line_start = true;
+    let mut decorated = false;
+
+    for (ix, (token, _)) in tokens.iter().enumerate() {
+        if line_start && depth == 0 && !matches!(token, PyToken::Indent | PyToken::Dedent) {
+            // decorators and the clauses of compound statements belong with the line before them.
+            let continues = decorated
+                || matches!(
+                    token,
+                    PyToken::Elif | PyToken::Else | PyToken::Except | PyToken::Finally
+                );
+
+            if ix > start && !continues {
+                statements.push(&tokens[start..ix]);
+                start = ix;
+            }
+
+            decorated = matches!(token, PyToken::At);
+        }
+
+        match token {
+            PyToken::Indent => depth += 1,
+            PyToken::Dedent => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+
+        line_start = matches!(token, PyToken::Newline | PyToken::Dedent);
+    }
+
+    if start < tokens.len() {
+        statements.push(&tokens[start..]);
+    }
+
+    statements
+}
+
+#[derive(Debug, Default)]
+struct Layout {
+    tokens: Vec<(PyToken, Span)>,
+    errors: Vec<ParsingError>,
+
+    /// The columns of the enclosing indented blocks, the module itself is at column `0`.
+    ///
+    /// Levels that were only pushed to recover from an inconsistent dedent are
+    /// marked so that leaving them does not produce a `Dedent`.
+    indents: Vec<(usize, bool)>,
+
+    /// Comment-only lines seen since the last logical line, with their column.
+    comments: Vec<(usize, PyToken, Span)>,
+
+    /// The number of open brackets, newlines are insignificant inside of them.
+    depth: usize,
+
+    /// The column of the current line while its leading whitespace is being read.
+    column: usize,
+
+    /// Whether the tokens of a logical line are being read.
+    in_line: bool,
+
+    /// The span of a `\` that may be continuing the line.
+    escape: Option<Span>,
+}
+
+impl Layout {
+    fn top(&self) -> usize {
+        self.indents.last().map_or(0, |(column, _)| *column)
+    }
+
+    fn push(&mut self, token: PyToken, span: Span) {
+        if !self.in_line {
+            match token {
+                PyToken::Whitespace => {
+                    self.column += 1;
+                    return;
+                }
+
+                PyToken::FormFeed => return,
+
+                PyToken::Newline => {
+                    self.column = 0;
+                    return;
+                }
+
+                PyToken::CommentRef(_) => {
+                    self.comments.push((self.column, token, span));
+                    return;
+                }
+
+                _ => {
+                    self.indent(self.column, span.start);
+                    self.in_line = true;
+                }
+            }
+        }
+
+        if let Some(escape) = self.escape.take() {
+            match token {
+                PyToken::Newline => return,
+                _ => self.emit(PyToken::Escape, escape),
+            }
+        }
+
+        match token {
+            PyToken::Whitespace | PyToken::FormFeed | PyToken::CommentRef(_) => (),
+
+            PyToken::Escape => self.escape = Some(span),
+
+            PyToken::Newline if self.depth > 0 => (),
+
+            PyToken::Newline => {
+                self.emit(token, span);
+                self.in_line = false;
+                self.column = 0;
+            }
+
+            PyToken::LParen | PyToken::LBracket | PyToken::LBrace => {
+                self.depth += 1;
+                self.emit(token, span);
+            }
+
+            PyToken::RParen | PyToken::RBracket | PyToken::RBrace => {
+                self.depth = self.depth.saturating_sub(1);
+                self.emit(token, span);
+            }
+
+            _ => self.emit(token, span),
+        }
+    }
+
+    fn emit(&mut self, token: PyToken, span: Span) {
+        self.tokens.push((token, span));
+    }
+
+    /// Start a logical line at `column`, `at` is the offset of its first token.
+    fn indent(&mut self, column: usize, at: usize) {
+        if column > self.top() {
+            self.indents.push((column, false));
+            self.emit(PyToken::Indent, at.saturating_sub(column)..at);
+        }
+
+        // comments go in the innermost block that they're indented into.
+        for (comment_column, token, span) in std::mem::take(&mut self.comments) {
+            self.dedent(comment_column.max(column), span.start);
+
+            let end = span.end;
+            self.emit(token, span);
+            self.emit(PyToken::Newline, end..end);
+        }
+
+        self.dedent(column, at);
+
+        if column != self.top() {
+            self.errors
+                .push(ParsingError::InconsistentDedent { span: at..at });
+
+            self.indents.push((column, true));
+        }
+    }
+
+    fn dedent(&mut self, column: usize, at: usize) {
+        while self.top() > column {
+            if let Some((_, false)) = self.indents.pop() {
+                self.emit(PyToken::Dedent, at..at);
+            }
+        }
+    }
+
+    fn finish(mut self, eoi: usize) -> (Vec<(PyToken, Span)>, Vec<ParsingError>) {
+        if let Some(escape) = self.escape.take() {
+            self.emit(PyToken::Escape, escape);
+        }
+
+        if self.in_line {
+            self.emit(PyToken::Newline, eoi..eoi);
+        }
+
+        self.indent(0, eoi);
+
+        (self.tokens, self.errors)
+    }
+}
@@ -1,20 +1,304 @@
+//! Parsing of source code into the AST types.
+//!
+//! Source is lexed and laid out into logical lines (see `layout`) and then fed
+//! to a grammar made of `chumsky` combinators (see `comb` and `stmt`).
+
 mod comb;
+mod layout;
+mod stmt;
+
+use std::fmt;
+
+use chumsky::{prelude::*, Stream};
+use logos::Logos;
+use montyc_core::ModuleRef;
+
+use crate::{
+    ast::{Expr, Module},
+    span_interner::SpanInterner,
+    token::PyToken,
+    token_iter::TokenStreamIter,
+};
+
+type Span = logos::Span;
+
+/// An error encountered while parsing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsingError {
+    /// Input that the lexer does not recognize as any token.
+    InvalidToken { span: Span },
+
+    /// A line that was dedented to a column no enclosing block is indented to.
+    InconsistentDedent { span: Span },
+
+    /// A token, or the end of input if `found` is `None`, that the grammar did not expect.
+    Unexpected {
+        span: Span,
+        found: Option<PyToken>,
+        /// The tokens that would have been accepted, `None` is the end of input.
+        expected: Vec<Option<PyToken>>,
+        label: Option<&'static str>,
+    },
+}
+
+impl ParsingError {
+    /// The span of the offending input.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::InvalidToken { span }
+            | Self::InconsistentDedent { span }
+            | Self::Unexpected { span, .. } => span.clone(),
+        }
+    }
+
+    /// Descriptions of what would have been accepted instead, i.e. "`:`" or "an expression".
+    pub fn expected(&self) -> Vec<String> {
+        let expected = match self {
+            Self::Unexpected { expected, .. } => expected,
+            _ => return vec![],
+        };
+
+        // any expression would do, that reads better than every token one could start with.
+        let expression = expected
+            .iter()
+            .any(|token| matches!(token, Some(PyToken::RawIdent)))
+            && expected
+                .iter()
+                .any(|token| matches!(token, Some(PyToken::Digits(_))));
+
+        let mut descriptions = vec![];
+
+        if expression {
+            descriptions.push(String::from("an expression"));
+        }
+
+        for token in expected {
+            let description = match token {
+                Some(token) if expression && starts_expression(token) => continue,
+                Some(token) => token.to_string(),
+                None => String::from("end of input"),
+            };
+
+            if !descriptions.contains(&description) {
+                descriptions.push(description);
+            }
+        }
 
-pub enum ParsingError {}
+        descriptions
+    }
+
+    /// A message for this error, quoting the offending part of `source`.
+    pub fn message(&self, source: &str) -> String {
+        let quoted = || source.get(self.span()).unwrap_or("");
+
+        match self {
+            Self::InvalidToken { .. } => format!("invalid token {:?}.", quoted()),
+            Self::InconsistentDedent { .. } => {
+                String::from("unindent does not match any outer indentation level.")
+            }
+            Self::Unexpected { found, .. } => match found {
+                None => String::from("unexpected end of input."),
+                Some(PyToken::Newline) => String::from("unexpected end of line."),
+                Some(PyToken::Indent) => String::from("unexpected indent."),
+                Some(PyToken::Dedent) => String::from("unexpected dedent."),
+                Some(_) => format!("unexpected `{}`.", quoted()),
+            },
+        }
+    }
+}
+
+fn starts_expression(token: &PyToken) -> bool {
+    matches!(
+        token,
+        PyToken::RawIdent
+            | PyToken::Ident(_)
+            | PyToken::StringLiteral
+            | PyToken::StringRef(_)
+            | PyToken::Digits(_)
+            | PyToken::Float(_)
+            | PyToken::True
+            | PyToken::False
+            | PyToken::None
+            | PyToken::Ellipsis
+            | PyToken::LParen
+            | PyToken::LBracket
+            | PyToken::LBrace
+            | PyToken::Plus
+            | PyToken::Minus
+            | PyToken::Tilde
+            | PyToken::Not
+            | PyToken::Await
+            | PyToken::Lambda
+    )
+}
+
+impl fmt::Display for ParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidToken { .. } => write!(f, "invalid token")?,
+            Self::InconsistentDedent { .. } => {
+                write!(f, "unindent does not match any outer indentation level")?
+            }
+            Self::Unexpected { found, label, .. } => {
+                match found {
+                    Some(token) => write!(f, "found {}", token)?,
+                    None => write!(f, "found end of input")?,
+                }
+
+                if let Some(label) = label {
+                    write!(f, " while parsing {}", label)?;
+                }
+            }
+        }
+
+        match self.expected().as_slice() {
+            [] => Ok(()),
+            [one] => write!(f, ", expected {}", one),
+            many => write!(f, ", expected one of {}", many.join(", ")),
+        }
+    }
+}
+
+impl chumsky::Error<PyToken> for ParsingError {
+    type Span = Span;
+    type Label = &'static str;
+
+    fn expected_input_found<Iter: IntoIterator<Item = Option<PyToken>>>(
+        span: Self::Span,
+        expected: Iter,
+        found: Option<PyToken>,
+    ) -> Self {
+        let mut tokens = vec![];
+
+        for token in expected {
+            if !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+
+        Self::Unexpected {
+            span,
+            found,
+            expected: tokens,
+            label: None,
+        }
+    }
 
-pub type ParsingResult<T> = Result<T, ParsingError>;
+    fn with_label(mut self, new: Self::Label) -> Self {
+        if let Self::Unexpected { label, .. } = &mut self {
+            label.get_or_insert(new);
+        }
+
+        self
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        if let (
+            Self::Unexpected { expected, .. },
+            Self::Unexpected {
+                expected: other, ..
+            },
+        ) = (&mut self, other)
+        {
+            for token in other {
+                if !expected.contains(&token) {
+                    expected.push(token);
+                }
+            }
+        }
+
+        self
+    }
+}
+
+pub type ParsingResult<T> = Result<T, Vec<ParsingError>>;
 
 pub trait Parseable {
-    fn parse_from(string: &str) -> ParsingResult<Self>
+    /// Parse `source`, interning its names and strings into `interner` on behalf of `module`.
+    fn parse_with(source: &str, interner: &SpanInterner, module: ModuleRef) -> ParsingResult<Self>
     where
         Self: Sized;
-}
 
-impl Parseable for crate::ast::Module {
     fn parse_from(string: &str) -> ParsingResult<Self>
     where
         Self: Sized,
     {
-        todo!()
+        Self::parse_with(string, &SpanInterner::new(), ModuleRef(0))
+    }
+}
+
+/// Lex and lay out `source` into the tokens the grammar is written against.
+fn lex(
+    source: &str,
+    interner: &SpanInterner,
+    module: ModuleRef,
+) -> (Vec<(PyToken, Span)>, Vec<ParsingError>) {
+    let bound = interner
+        .get(source, module)
+        .expect("span interner is already borrowed.");
+
+    let tokens = TokenStreamIter {
+        bound,
+        lexer: PyToken::lexer(source),
+    };
+
+    layout::layout(tokens, source.len())
+}
+
+/// A stream of laid out tokens, the end of input is right after the last one.
+fn stream(
+    tokens: &[(PyToken, Span)],
+) -> Stream<'_, PyToken, Span, impl Iterator<Item = (PyToken, Span)> + '_> {
+    let eoi = tokens.last().map_or(0, |(_, span)| span.end);
+
+    Stream::from_iter(eoi..eoi, tokens.iter().cloned())
+}
+
+impl Parseable for Module {
+    fn parse_with(source: &str, interner: &SpanInterner, module: ModuleRef) -> ParsingResult<Self>
+    where
+        Self: Sized,
+    {
+        let (tokens, mut errors) = lex(source, interner, module);
+        let statement = stmt::statement().then_ignore(end());
+
+        let mut body = vec![];
+
+        for tokens in layout::statements(&tokens) {
+            match statement.parse(stream(tokens)) {
+                Ok(stmt) => body.push(stmt),
+                Err(errs) => errors.extend(errs),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Module { body })
+        } else {
+            errors.sort_by_key(|error| error.span().start);
+            Err(errors)
+        }
+    }
+}
+
+impl Parseable for Expr {
+    fn parse_with(source: &str, interner: &SpanInterner, module: ModuleRef) -> ParsingResult<Self>
+    where
+        Self: Sized,
+    {
+        let (tokens, mut errors) = lex(source, interner, module);
+
+        let expr = comb::expression()
+            .then_ignore(just(PyToken::Newline))
+            .then_ignore(end());
+
+        match expr.parse(stream(&tokens)) {
+            Ok(expr) if errors.is_empty() => Ok(expr.inner),
+            Ok(_) => Err(errors),
+            Err(errs) => {
+                errors.extend(errs);
+                errors.sort_by_key(|error| error.span().start);
+                Err(errors)
+            }
+        }
     }
 }
@@ -0,0 +1,473 @@
+//! Statements, simple and compound.
+//!
+//! Spans follow the ones `montyc_parser` produces for the same statements.
+
+use chumsky::prelude::*;
+
+use crate::{
+    ast::{
+        Annotation, Assign, Atom, ClassDef, ExceptHandler, Expr, For, FunctionDef, If, IfChain,
+        Import, Primary, Raise, Return, Spanned, Statement, Try, While, With, WithItem,
+    },
+    token::PyToken,
+};
+
+use super::{
+    comb::{
+        adjacent, expression, name, parameter_items, parameter_list, primary, target_list, token,
+        ParameterItem, PyParser,
+    },
+    ParsingError,
+};
+
+type Span = logos::Span;
+
+type Block = Vec<Spanned<Statement>>;
+
+/// A dotted name i.e. `a.b.c`, as in imports.
+fn dotted_name() -> impl PyParser<Spanned<Primary>> {
+    name()
+        .map(|atom| atom.replace_with(Primary::Atomic))
+        .then(token(PyToken::Dot).ignore_then(name()).repeated())
+        .foldl(|left, attr| {
+            let span = left.span.start..attr.span.end;
+
+            let inner = Primary::Attribute {
+                left: Box::new(left),
+                attr,
+            };
+
+            Spanned::new(inner, span)
+        })
+}
+
+fn import() -> impl PyParser<Spanned<Statement>> {
+    let names = dotted_name()
+        .separated_by(token(PyToken::Comma))
+        .at_least(1);
+
+    let import_names = token(PyToken::Import).then(names.clone()).map(
+        |(tok, names): (Span, Vec<Spanned<Primary>>)| {
+            let span = tok.start..names[names.len() - 1].span.end;
+            Spanned::new(Import::Names(names), span)
+        },
+    );
+
+    // `...` is lexed as a single ellipsis token so it counts for three levels.
+    let level = token(PyToken::Dot)
+        .to(1)
+        .or(token(PyToken::Ellipsis).to(3))
+        .repeated()
+        .at_least(1)
+        .map(|levels: Vec<usize>| levels.into_iter().sum());
+
+    // the module may only be left out of relative imports, as in `from . import x`
+    let module = level
+        .then(dotted_name().or_not())
+        .or(dotted_name().map(|module| (0, Some(module))));
+
+    let from_import = token(PyToken::From)
+        .then(module)
+        .then_ignore(token(PyToken::Import))
+        .then(names)
+        .map(|((tok, (level, module)), names)| {
+            let span = tok.start..names[names.len() - 1].span.end;
+
+            Spanned::new(
+                Import::From {
+                    module,
+                    names,
+                    level,
+                },
+                span,
+            )
+        });
+
+    import_names
+        .or(from_import)
+        .map(|import| import.map(Statement::Import))
+}
+
+fn simple_statement(expr: impl PyParser<Spanned<Expr>>) -> impl PyParser<Spanned<Statement>> {
+    // assignments and annotations start out like any other expression, the
+    // target is only checked once the `=` or `:` after it has been seen.
+    let tail = token(PyToken::Colon)
+        .then(expr.clone())
+        .or_not()
+        .then(token(PyToken::Equal).then(expr.clone()).or_not());
+
+    let expr_statement = expr
+        .clone()
+        .then(tail)
+        .try_map(|(target, (kind, value)), _| match (kind, value) {
+            (None, None) => Ok(target.map(Statement::Expr)),
+
+            (kind, Some((equal, value))) => match target.inner {
+                Expr::Primary(name) => {
+                    let kind = kind.map(|(_, kind)| kind);
+                    let span = name.span.start..value.span.end;
+                    Ok(Spanned::new(
+                        Statement::Asn(Assign { name, value, kind }),
+                        span,
+                    ))
+                }
+
+                _ => Err(ParsingError::expected_input_found(
+                    equal,
+                    Some(Some(PyToken::Newline)),
+                    Some(PyToken::Equal),
+                )),
+            },
+
+            (Some((colon, kind)), None) => match target.inner {
+                Expr::Primary(Spanned {
+                    inner: Primary::Atomic(name),
+                    ..
+                }) if matches!(name.inner, Atom::Name(_)) => {
+                    let span = name.span.start..kind.span.end;
+                    Ok(Spanned::new(
+                        Statement::Ann(Annotation { name, kind }),
+                        span,
+                    ))
+                }
+
+                // only a plain name may be annotated without also being assigned to.
+                _ => Err(ParsingError::expected_input_found(
+                    colon,
+                    Some(Some(PyToken::Equal)),
+                    Some(PyToken::Colon),
+                )),
+            },
+        });
+
+    let return_ = token(PyToken::Return)
+        .then(expr.clone().or_not())
+        .map(|(tok, value)| {
+            let end = value.as_ref().map_or(tok.end, |value| value.span.end);
+
+            let value = match value {
+                Some(value) => Ok(value),
+                None => Err(Spanned::new((), tok.clone())),
+            };
+
+            Spanned::new(Statement::Ret(Return { value }), tok.start..end)
+        });
+
+    let raise = token(PyToken::Raise)
+        .then(expr.clone().or_not())
+        .map(|(tok, exc)| {
+            let end = exc.as_ref().map_or(tok.end, |exc| exc.span.end);
+            Spanned::new(Statement::Raise(Raise { exc }), tok.start..end)
+        });
+
+    let keyword =
+        |tok, stmt: Statement| token(tok).map(move |span| Spanned::new(stmt.clone(), span));
+
+    let comment = filter_map(|span, tok| match tok {
+        PyToken::CommentRef(_) => Ok(Spanned::new(Atom::from(tok), span)),
+        _ => Err(ParsingError::expected_input_found(span, None, Some(tok))),
+    })
+    .map(|comment| {
+        comment
+            .replace_with(Primary::Atomic)
+            .replace_with(Expr::Primary)
+            .map(Statement::Expr)
+    });
+
+    expr_statement
+        .or(return_)
+        .or(raise)
+        .or(keyword(PyToken::Pass, Statement::Pass))
+        .or(keyword(PyToken::Break, Statement::Break))
+        .or(keyword(PyToken::Continue, Statement::Continue))
+        .or(import())
+        .or(comment)
+}
+
+/// A function definition without its decorators.
+fn function_def(
+    expr: impl PyParser<Spanned<Expr>>,
+    block: impl PyParser<Block>,
+) -> impl PyParser<(
+    Spanned<Atom>,
+    (Vec<ParameterItem>, bool),
+    Option<Spanned<Expr>>,
+    Block,
+)> {
+    let annotation = token(PyToken::Colon).ignore_then(expr.clone()).or_not();
+
+    let returns = adjacent(PyToken::Minus, PyToken::GreaterThan)
+        .ignore_then(expr.clone())
+        .or_not();
+
+    token(PyToken::FnDef)
+        .ignore_then(name())
+        .then(
+            parameter_items(expr, annotation)
+                .delimited_by(token(PyToken::LParen), token(PyToken::RParen)),
+        )
+        .then(returns)
+        .then_ignore(token(PyToken::Colon))
+        .then(block)
+        .map(|(((name, params), returns), body)| (name, params, returns, body))
+}
+
+fn if_statement(
+    expr: impl PyParser<Spanned<Expr>>,
+    block: impl PyParser<Block>,
+) -> impl PyParser<Spanned<Statement>> {
+    let branch = |keyword| {
+        token(keyword)
+            .then(expr.clone())
+            .then_ignore(token(PyToken::Colon))
+            .then(block.clone())
+            .map(|((tok, test), body)| Spanned::new(If { test, body }, tok))
+    };
+
+    let orelse = token(PyToken::Else)
+        .ignore_then(token(PyToken::Colon))
+        .ignore_then(block.clone());
+
+    branch(PyToken::If)
+        .then(branch(PyToken::Elif).repeated())
+        .then(orelse.or_not())
+        .map(|((first, elifs), orelse)| {
+            let mut branches = vec![first];
+            branches.extend(elifs);
+
+            let span = branches[0].span.start..branches[branches.len() - 1].span.end;
+
+            Spanned::new(Statement::If(IfChain { branches, orelse }), span)
+        })
+}
+
+fn try_statement(
+    expr: impl PyParser<Spanned<Expr>>,
+    block: impl PyParser<Block>,
+) -> impl PyParser<Spanned<Statement>> {
+    let handler = token(PyToken::Except)
+        .then(
+            expr.then(token(PyToken::As).ignore_then(name()).or_not())
+                .or_not(),
+        )
+        .then_ignore(token(PyToken::Colon))
+        .then(block.clone())
+        .map(|((tok, clause), body)| {
+            let (kind, name) = match clause {
+                Some((kind, name)) => (Some(kind), name),
+                None => (None, None),
+            };
+
+            let end = body.last().map_or(tok.end, |stmt| stmt.span.end);
+
+            Spanned::new(ExceptHandler { kind, name, body }, tok.start..end)
+        });
+
+    let clause = |keyword| {
+        token(keyword)
+            .ignore_then(token(PyToken::Colon))
+            .ignore_then(block.clone())
+    };
+
+    let handlers = handler
+        .repeated()
+        .at_least(1)
+        .then(clause(PyToken::Else).or_not())
+        .then(clause(PyToken::Finally).or_not())
+        .map(|((handlers, orelse), finalbody)| (handlers, orelse, finalbody));
+
+    let finally = clause(PyToken::Finally).map(|finalbody| (vec![], None, Some(finalbody)));
+
+    token(PyToken::Try)
+        .then_ignore(token(PyToken::Colon))
+        .then(block.clone())
+        .then(handlers.or(finally))
+        .map(|((tok, body), (handlers, orelse, finalbody))| {
+            let try_ = Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            };
+
+            Spanned::new(Statement::Try(try_), tok)
+        })
+}
+
+fn with_statement(
+    expr: impl PyParser<Spanned<Expr>>,
+    primary: impl PyParser<Spanned<Primary>>,
+    block: impl PyParser<Block>,
+) -> impl PyParser<Spanned<Statement>> {
+    let item = expr
+        .then(token(PyToken::As).ignore_then(primary).or_not())
+        .map(|(context_expr, target)| {
+            let end = target
+                .as_ref()
+                .map_or(context_expr.span.end, |target| target.span.end);
+
+            let span = context_expr.span.start..end;
+
+            Spanned::new(
+                WithItem {
+                    context_expr,
+                    target,
+                },
+                span,
+            )
+        });
+
+    token(PyToken::Async)
+        .or_not()
+        .then(token(PyToken::With))
+        .then(item.separated_by(token(PyToken::Comma)).at_least(1))
+        .then_ignore(token(PyToken::Colon))
+        .then(block)
+        .map(|(((is_async, tok), items), body)| {
+            let start = is_async.as_ref().map_or(tok.start, |tok| tok.start);
+
+            let with = With {
+                items,
+                body,
+                is_async: is_async.is_some(),
+            };
+
+            Spanned::new(Statement::With(with), start..tok.end)
+        })
+}
+
+pub(super) fn statement() -> impl PyParser<Spanned<Statement>> {
+    let expr = expression().boxed();
+    let primary = primary(expr.clone()).boxed();
+    let simple = simple_statement(expr.clone()).boxed();
+
+    recursive(move |statement| {
+        // either a simple statement on the same line, or an indented block of statements.
+        let block = simple
+            .clone()
+            .then_ignore(token(PyToken::Newline))
+            .map(|stmt| vec![stmt])
+            .or(token(PyToken::Newline).ignore_then(
+                statement
+                    .repeated()
+                    .at_least(1)
+                    .delimited_by(token(PyToken::Indent), token(PyToken::Dedent)),
+            ))
+            .boxed();
+
+        let decorators = token(PyToken::At)
+            .ignore_then(primary.clone())
+            .then_ignore(token(PyToken::Newline))
+            .repeated();
+
+        let function_def = function_def(expr.clone(), block.clone()).map(
+            |(name, (items, trailing), returns, body)| {
+                // a leading plain name followed by a comma is the receiver i.e. `self`.
+                let receiver = match items.first() {
+                    Some(ParameterItem::Named(name, None, None)) if items.len() > 1 || trailing => {
+                        Some(name.clone())
+                    }
+                    _ => None,
+                };
+
+                let items = items
+                    .into_iter()
+                    .skip(receiver.is_some() as usize)
+                    .collect();
+                let (receiver, args) = parameter_list(items, receiver);
+
+                (name, receiver, args, returns, body)
+            },
+        );
+
+        let class_def = token(PyToken::ClassDef)
+            .then(name())
+            .then(
+                expr.clone()
+                    .separated_by(token(PyToken::Comma))
+                    .allow_trailing()
+                    .delimited_by(token(PyToken::LParen), token(PyToken::RParen))
+                    .or_not(),
+            )
+            .then_ignore(token(PyToken::Colon))
+            .then(block.clone());
+
+        let decorated = decorators
+            .then(function_def.map(Ok).or(class_def.map(Err)))
+            .map(|(decorator_list, def)| match def {
+                Ok((name, receiver, args, returns, body)) => {
+                    let end = body.last().map_or(name.span.end, |stmt| stmt.span.end);
+                    let span = name.span.start..end;
+
+                    let funcdef = FunctionDef {
+                        reciever: receiver.map(|recv| recv.map(Atom::Name)),
+                        name,
+                        args: if args.is_empty() { None } else { Some(args) },
+                        body,
+                        decorator_list,
+                        returns,
+                    };
+
+                    Spanned::new(Statement::FnDef(funcdef), span)
+                }
+
+                Err((((tok, name), bases), body)) => {
+                    let start = decorator_list
+                        .first()
+                        .map_or(tok.start, |decorator| decorator.span.start);
+
+                    let end = body.last().map_or(name.span.end, |stmt| stmt.span.end);
+
+                    let klass = ClassDef {
+                        name,
+                        bases: bases.unwrap_or_default(),
+                        decorator_list,
+                        body,
+                    };
+
+                    Spanned::new(Statement::Class(klass), start..end)
+                }
+            });
+
+        let while_ = token(PyToken::While)
+            .then(expr.clone())
+            .then_ignore(token(PyToken::Colon))
+            .then(block.clone())
+            .map(|((tok, test), body)| Spanned::new(Statement::While(While { test, body }), tok));
+
+        let for_ = token(PyToken::For)
+            .then(target_list(primary.clone()))
+            .then_ignore(token(PyToken::In))
+            .then(expr.clone())
+            .then_ignore(token(PyToken::Colon))
+            .then(block.clone())
+            .then(
+                token(PyToken::Else)
+                    .ignore_then(token(PyToken::Colon))
+                    .ignore_then(block.clone())
+                    .or_not(),
+            )
+            .map(|((((tok, target), iter), body), orelse)| {
+                let for_ = For {
+                    target,
+                    iter,
+                    body,
+                    orelse,
+                };
+
+                Spanned::new(Statement::For(for_), tok)
+            });
+
+        let compound = decorated
+            .or(if_statement(expr.clone(), block.clone()))
+            .or(while_)
+            .or(for_)
+            .or(try_statement(expr.clone(), block.clone()))
+            .or(with_statement(expr.clone(), primary.clone(), block));
+
+        simple
+            .clone()
+            .then_ignore(token(PyToken::Newline))
+            .or(compound)
+    })
+}